@@ -29,8 +29,8 @@ export class ProbabilitySync {
     const data = accountInfo.data;
 
     // Read current probability from context
-    const probability = data.readBigUInt64LE(128);
-    const isResolved = data[160];
+    const probability = data.readUInt32LE(120);
+    const isResolved = data[144];
 
     // Update Percolator oracle authority price
     try {
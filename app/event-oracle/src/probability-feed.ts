@@ -28,6 +28,10 @@ export class ProbabilityFeed {
    * Fetch probability from sources, detect signals, and update on-chain
    */
   async updateProbability(): Promise<void> {
+    // The slot the reading is taken at; the matcher only accepts a sync whose
+    // slot is after the last one it stored, so a replay can't roll it back
+    const readSlot = await this.connection.getSlot("confirmed");
+
     // Aggregate probability from multiple sources
     const kalshiProb = await this.kalshi.getProbability();
     const polyProb = await this.polymarket.getProbability();
@@ -51,7 +55,7 @@ export class ProbabilityFeed {
     const signalSpread = this.computeSignalSpread(signal.severity);
 
     // Write to chain
-    await this.writeProbabilitySync(probability, signal.severity, signalSpread);
+    await this.writeProbabilitySync(probability, signal.severity, signalSpread, readSlot);
 
     console.log(
       `Probability updated: ${(probability / 10_000).toFixed(2)}% | ` +
@@ -95,12 +99,14 @@ export class ProbabilityFeed {
     probability: number,
     signalSeverity: SignalSeverity,
     signalSpread: number,
+    readSlot: number,
   ): Promise<void> {
-    const data = Buffer.alloc(25);
+    const data = Buffer.alloc(33);
     data[0] = 0x03; // Probability sync tag
     data.writeBigUInt64LE(BigInt(probability), 1);
     data.writeBigUInt64LE(BigInt(signalSeverity), 9);
     data.writeBigUInt64LE(BigInt(signalSpread), 17);
+    data.writeBigUInt64LE(BigInt(readSlot), 25);
 
     const ix = new TransactionInstruction({
       programId: this.matcherProgramId,
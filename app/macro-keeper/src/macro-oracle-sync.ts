@@ -58,11 +58,23 @@ export class MacroOracleSync {
    * Sends tag 0x03 (IndexSync) instruction.
    */
   async syncOracle(): Promise<void> {
+    // Stamp the sync with the slot the rates were read at, so a replay of it
+    // is rejected once a newer sync has landed
+    const readSlot = await this.connection.getSlot("confirmed");
     const { nominalBps, inflationBps, realRateBps } =
       await this.fredSource.fetchRealRate();
 
-    const markPrice = computeMarkPrice(realRateBps);
+    // The matcher requires the index to be exactly the mark of the packed
+    // components, so derive it from the same (clamped) values it will see
     const componentsPacked = packComponents(nominalBps, inflationBps);
+    const markPrice = computeMarkPrice(
+      Number(componentsPacked >> 32n) - Number(componentsPacked & 0xFFFFFFFFn),
+    );
+    if (markPrice === 0) {
+      // The matcher rejects this with InvalidIndexValue; mark 0 reads as unsynced
+      console.warn(`Real rate ${realRateBps}bps is at or below -${RATE_OFFSET}bps, skipping sync`);
+      return;
+    }
 
     // Signal intelligence: detect macro anomalies from rate data
     const signal = this.signalDetector.detect(nominalBps, inflationBps, realRateBps);
@@ -74,6 +86,7 @@ export class MacroOracleSync {
       componentsPacked,
       signalSeverity,
       signalAdjustedSpread,
+      readSlot,
     );
 
     const severityNames = ["NONE", "LOW", "HIGH", "CRITICAL"];
@@ -93,25 +106,36 @@ export class MacroOracleSync {
    *   [9..17] index_components_packed (u64 LE)
    *   [17..25] signal_severity (u64 LE)
    *   [25..33] signal_adjusted_spread (u64 LE)
+   *   [33..41] update_slot (u64 LE)
    */
   private async writeSyncInstruction(
     markPrice: number,
     componentsPacked: bigint,
     signalSeverity: number,
     signalAdjustedSpread: number,
+    readSlot: number,
   ): Promise<void> {
-    const data = Buffer.alloc(33);
+    const data = Buffer.alloc(41);
     data[0] = 0x03; // IndexSync tag
     data.writeBigUInt64LE(BigInt(markPrice), 1);
     data.writeBigUInt64LE(componentsPacked, 9);
     data.writeBigUInt64LE(BigInt(signalSeverity), 17);
     data.writeBigUInt64LE(BigInt(signalAdjustedSpread), 25);
+    data.writeBigUInt64LE(BigInt(readSlot), 33);
+
+    // Keys PDA holding the keeper authority; ignored when none is configured
+    const [keys] = PublicKey.findProgramAddressSync(
+      [Buffer.from("keys"), this.matcherContext.toBuffer()],
+      this.matcherProgramId,
+    );
 
     const ix = new TransactionInstruction({
       programId: this.matcherProgramId,
       keys: [
         { pubkey: this.matcherContext, isSigner: false, isWritable: true },
         { pubkey: this.macroOracle, isSigner: false, isWritable: false },
+        { pubkey: this.payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: keys, isSigner: false, isWritable: false },
       ],
       data,
     });
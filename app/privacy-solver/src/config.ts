@@ -3,6 +3,8 @@ import { PublicKey } from "@solana/web3.js";
 export interface SolverConfig {
   rpcUrl: string;
   solverKeypairPath: string;
+  encryptionKeypairPath: string; // NaCl box (X25519) secret key, JSON bytes; its public key is solver_encryption_pubkey
+  oracleSignerKeypairPath: string; // Ed25519 keypair stored in the oracle signers PDA; signs every oracle price
   matcherProgramId: PublicKey;
   matcherContextAccount: PublicKey;
   percolatorCliPath: string;
@@ -9,6 +9,8 @@ async function main() {
   const config: SolverConfig = {
     rpcUrl: process.env.RPC_URL || "https://api.devnet.solana.com",
     solverKeypairPath: process.env.SOLVER_KEYPAIR || "~/.config/solana/id.json",
+    encryptionKeypairPath: process.env.SOLVER_ENCRYPTION_KEYPAIR || "~/.config/solana/solver-encryption.json",
+    oracleSignerKeypairPath: process.env.ORACLE_SIGNER_KEYPAIR || "~/.config/solana/oracle-signer.json",
     matcherProgramId: new PublicKey(
       process.env.MATCHER_PROGRAM_ID || "Priv1111111111111111111111111111111111111111"
     ),
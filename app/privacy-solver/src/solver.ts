@@ -1,4 +1,4 @@
-import { Connection, Keypair, PublicKey, sendAndConfirmTransaction, Transaction, TransactionInstruction, SystemProgram } from "@solana/web3.js";
+import { Connection, Ed25519Program, Keypair, PublicKey, sendAndConfirmTransaction, SYSVAR_INSTRUCTIONS_PUBKEY, Transaction, TransactionInstruction, SystemProgram } from "@solana/web3.js";
 import { SolverConfig, EncryptedIntent, DecryptedIntent } from "./config";
 import { decrypt, deserializeIntent } from "./encryption";
 import { withRetry } from "../../shared/retry";
@@ -7,6 +7,10 @@ import * as fs from "fs";
 export class PrivacyPerpsSolver {
   private connection: Connection;
   private solverKeypair: Keypair;
+  private encryptionSecretKey: Uint8Array;
+  private oracleSignerKeypair: Keypair;
+  private oracleSigners: PublicKey;
+  private markOut: PublicKey;
   private config: SolverConfig;
   private running: boolean = false;
   private intentQueue: EncryptedIntent[] = [];
@@ -16,6 +20,17 @@ export class PrivacyPerpsSolver {
     this.connection = new Connection(config.rpcUrl, "confirmed");
     const keyData = JSON.parse(fs.readFileSync(config.solverKeypairPath, "utf-8"));
     this.solverKeypair = Keypair.fromSecretKey(Uint8Array.from(keyData));
+    this.encryptionSecretKey = Uint8Array.from(JSON.parse(fs.readFileSync(config.encryptionKeypairPath, "utf-8")));
+    const signerKeyData = JSON.parse(fs.readFileSync(config.oracleSignerKeypairPath, "utf-8"));
+    this.oracleSignerKeypair = Keypair.fromSecretKey(Uint8Array.from(signerKeyData));
+    [this.oracleSigners] = PublicKey.findProgramAddressSync(
+      [Buffer.from("oracle_signers"), config.matcherContextAccount.toBuffer()],
+      config.matcherProgramId,
+    );
+    [this.markOut] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mark_out"), config.matcherContextAccount.toBuffer()],
+      config.matcherProgramId,
+    );
   }
 
   /**
@@ -26,8 +41,12 @@ export class PrivacyPerpsSolver {
     console.log(`Privacy Perps Solver started`);
     console.log(`  Solver: ${this.solverKeypair.publicKey.toBase58()}`);
     console.log(`  Matcher: ${this.config.matcherProgramId.toBase58()}`);
+    console.log(`  Oracle signer: ${this.oracleSignerKeypair.publicKey.toBase58()}`);
     console.log(`  Poll interval: ${this.config.pollIntervalMs}ms`);
 
+    await this.ensureOracleSigner();
+    await this.ensureMarkOut();
+
     while (this.running) {
       try {
         await this.pollAndProcess();
@@ -87,7 +106,7 @@ export class PrivacyPerpsSolver {
         intent.encryptedPayload,
         intent.nonce,
         intent.userEphemeralPubkey,
-        this.solverKeypair.secretKey.slice(0, 32) // X25519 secret key
+        this.encryptionSecretKey
       );
       const parsed = deserializeIntent(decryptedBytes);
       return {
@@ -138,28 +157,121 @@ export class PrivacyPerpsSolver {
   }
 
   /**
-   * Update oracle price in matcher context (Tag 0x03)
+   * Store the oracle signer in the context's oracle signers PDA (Tag 0x07) if
+   * it isn't there yet, or rotate to it (Tag 0x0A) if the PDA holds another
+   * key. Rotation with co-signing on also needs the co-signer, so it's left
+   * to the operator.
+   */
+  private async ensureOracleSigner(): Promise<void> {
+    const existing = await this.connection.getAccountInfo(this.oracleSigners);
+    if (existing) {
+      const stored = new PublicKey(existing.data.subarray(16, 48));
+      if (stored.equals(this.oracleSignerKeypair.publicKey)) return;
+      if (existing.data[9] !== 0) {
+        console.warn(`Oracle signer is ${stored.toBase58()}; rotating needs the co-signer`);
+        return;
+      }
+      await this.rotateOracleSigner();
+      return;
+    }
+
+    const data = Buffer.alloc(33);
+    data[0] = 0x07; // Init oracle signer tag
+    this.oracleSignerKeypair.publicKey.toBuffer().copy(data, 1);
+
+    const ix = new TransactionInstruction({
+      programId: this.config.matcherProgramId,
+      keys: [
+        { pubkey: this.solverKeypair.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.config.matcherContextAccount, isSigner: false, isWritable: false },
+        { pubkey: this.oracleSigners, isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    });
+    const sig = await sendAndConfirmTransaction(this.connection, new Transaction().add(ix), [this.solverKeypair]);
+    console.log(`Oracle signer set: ${this.oracleSigners.toBase58()}, tx=${sig}`);
+  }
+
+  /**
+   * Replace the stored oracle signer with this solver's (Tag 0x0A), leaving
+   * co-signing off.
+   */
+  private async rotateOracleSigner(): Promise<void> {
+    const data = Buffer.alloc(33);
+    data[0] = 0x0a; // Rotate oracle signer tag
+    this.oracleSignerKeypair.publicKey.toBuffer().copy(data, 1);
+
+    const ix = new TransactionInstruction({
+      programId: this.config.matcherProgramId,
+      keys: [
+        { pubkey: this.solverKeypair.publicKey, isSigner: true, isWritable: false },
+        { pubkey: this.config.matcherContextAccount, isSigner: false, isWritable: false },
+        { pubkey: this.oracleSigners, isSigner: false, isWritable: true },
+      ],
+      data,
+    });
+    const sig = await sendAndConfirmTransaction(this.connection, new Transaction().add(ix), [this.solverKeypair]);
+    console.log(`Oracle signer rotated to ${this.oracleSignerKeypair.publicKey.toBase58()}, tx=${sig}`);
+  }
+
+  /**
+   * Create the context's mark-out PDA (Tag 0x0C) if it isn't there yet. Match
+   * and Oracle Update need it while the toxicity spread or slippage guard is on.
+   */
+  private async ensureMarkOut(): Promise<void> {
+    if (await this.connection.getAccountInfo(this.markOut)) return;
+
+    const ix = new TransactionInstruction({
+      programId: this.config.matcherProgramId,
+      keys: [
+        { pubkey: this.solverKeypair.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.config.matcherContextAccount, isSigner: false, isWritable: false },
+        { pubkey: this.markOut, isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data: Buffer.from([0x0c]), // Init mark-out tag
+    });
+    const sig = await sendAndConfirmTransaction(this.connection, new Transaction().add(ix), [this.solverKeypair]);
+    console.log(`Mark-out state created: ${this.markOut.toBase58()}, tx=${sig}`);
+  }
+
+  /**
+   * Update oracle price in matcher context (Tag 0x03), preceded by an Ed25519
+   * verify of the price and the slot it was read at, signed with the oracle
+   * signer. The matcher rejects a slot at or behind the last update's, so a
+   * replayed update fails even in a later slot.
    */
   private async updateOraclePrice(price: bigint): Promise<void> {
-    const data = Buffer.alloc(9);
+    const readSlot = await this.connection.getSlot("confirmed");
+    const data = Buffer.alloc(17);
     data[0] = 0x03; // Oracle update tag
     data.writeBigUInt64LE(price, 1);
+    data.writeBigUInt64LE(BigInt(readSlot), 9);
+
+    const verifyIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: this.oracleSignerKeypair.secretKey,
+      message: data.subarray(1, 17),
+    });
 
     const ix = new TransactionInstruction({
       programId: this.config.matcherProgramId,
       keys: [
         { pubkey: this.solverKeypair.publicKey, isSigner: true, isWritable: false },
         { pubkey: this.config.matcherContextAccount, isSigner: false, isWritable: true },
+        { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: this.oracleSigners, isSigner: false, isWritable: false },
+        { pubkey: this.markOut, isSigner: false, isWritable: true },
       ],
       data,
     });
 
-    const tx = new Transaction().add(ix);
+    const tx = new Transaction().add(verifyIx, ix);
     const sig = await withRetry(
       () => sendAndConfirmTransaction(this.connection, tx, [this.solverKeypair]),
       { onRetry: (err, attempt, delay) => console.log(`[SOLVER] retry ${attempt} in ${delay}ms: ${err}`) },
     );
-    console.log(`Oracle updated: price=${price}, tx=${sig}`);
+    console.log(`Oracle updated: price=${price}, slot=${readSlot}, tx=${sig}`);
   }
 
   /**
@@ -0,0 +1,46 @@
+/**
+ * Cross-matcher price consistency for matchers quoting the same underlying.
+ *
+ * divergence_bps = (max - min) * 10_000 / min
+ *
+ * An off-chain monitor reads each matcher's exec price (e6, u64 from the
+ * return buffer or context view) and flags the set when the spread between
+ * the highest and lowest quote exceeds the bound.
+ */
+
+export interface PriceConsistency {
+  consistent: boolean;
+  /** Spread between the highest and lowest price, in bps of the lowest (floored) */
+  divergenceBps: bigint;
+  minPrice: bigint;
+  maxPrice: bigint;
+}
+
+export function checkPriceConsistency(
+  prices: bigint[],
+  maxDivergenceBps: number,
+): PriceConsistency {
+  if (prices.length === 0) {
+    return { consistent: true, divergenceBps: 0n, minPrice: 0n, maxPrice: 0n };
+  }
+
+  let minPrice = prices[0];
+  let maxPrice = prices[0];
+  for (const p of prices) {
+    if (p < minPrice) minPrice = p;
+    if (p > maxPrice) maxPrice = p;
+  }
+
+  // A zero quote is an unset oracle, never consistent with a live one
+  if (minPrice === 0n) {
+    return { consistent: maxPrice === 0n, divergenceBps: 0n, minPrice, maxPrice };
+  }
+
+  const divergenceBps = ((maxPrice - minPrice) * 10_000n) / minPrice;
+  return {
+    consistent: divergenceBps <= BigInt(maxDivergenceBps),
+    divergenceBps,
+    minPrice,
+    maxPrice,
+  };
+}
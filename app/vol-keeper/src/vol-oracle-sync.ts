@@ -35,16 +35,18 @@ export class VolOracleSync {
    * Read Sigma oracle accounts and sync to vol-matcher context
    */
   async syncOracle(): Promise<void> {
-    // Read Sigma oracle accounts
-    const [vtInfo, viInfo] = await Promise.all([
-      this.connection.getAccountInfo(this.varianceTracker),
-      this.connection.getAccountInfo(this.volIndex),
-    ]);
+    // Read Sigma oracle accounts, with the slot they were read at: the matcher
+    // only accepts a sync read after the last one it stored, so a replayed
+    // sync can't roll the mark back
+    const {
+      context: { slot: readSlot },
+      value: [vtInfo, viInfo],
+    } = await this.connection.getMultipleAccountsInfoAndContext([this.varianceTracker, this.volIndex]);
 
     if (!vtInfo || !viInfo) {
       console.warn("Sigma oracle accounts not found — using fallback values");
       // Use fallback values for testing
-      await this.writeSyncInstruction(3000, 3_000_000_000, 2, 2800, 3200);
+      await this.writeSyncInstruction(3000, 3_000_000_000, 2, 2800, 3200, readSlot);
       return;
     }
 
@@ -66,6 +68,7 @@ export class VolOracleSync {
       regime,
       Number(vol7d),
       Number(vol30d),
+      readSlot,
     );
   }
 
@@ -75,15 +78,17 @@ export class VolOracleSync {
     regime: number,
     vol7dBps: number,
     vol30dBps: number,
+    readSlot: number,
   ): Promise<void> {
     // Build tag 0x03 instruction data
-    const data = Buffer.alloc(34);
+    const data = Buffer.alloc(42);
     data[0] = 0x03;
     data.writeBigUInt64LE(BigInt(currentVolBps), 1);
     data.writeBigUInt64LE(BigInt(volMarkPrice), 9);
     data[17] = regime;
     data.writeBigUInt64LE(BigInt(vol7dBps), 18);
     data.writeBigUInt64LE(BigInt(vol30dBps), 26);
+    data.writeBigUInt64LE(BigInt(readSlot), 34);
 
     const ix = new TransactionInstruction({
       programId: this.matcherProgramId,
@@ -47,7 +47,7 @@ program
 program
   .command("resolve")
   .description("Resolve an event market")
-  .requiredOption("--outcome <yes|no>", "Event outcome")
+  .requiredOption("--outcome <yes|no|void>", "Event outcome")
   .requiredOption("--context <pubkey>", "Matcher context account")
   .option("--rpc <url>", "RPC URL", process.env.RPC_URL || "https://api.devnet.solana.com")
   .action(async (opts) => {
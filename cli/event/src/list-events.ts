@@ -32,19 +32,31 @@ export async function listEvents(opts: { rpc: string }): Promise<void> {
     return;
   }
 
-  const probability = data.readBigUInt64LE(128);
-  const isResolved = data[160];
-  const outcome = data[161];
-  const signalSev = data.readBigUInt64LE(168);
-  const baseSpread = data.readUInt32LE(112);
-  const edgeSpread = data.readUInt32LE(116);
-  const maxSpread = data.readUInt32LE(120);
+  const version = data.readUInt32LE(72);
+  if (version !== 2) {
+    console.log(`Unsupported context version ${version} (expected 2).`);
+    return;
+  }
+
+  const probability = data.readUInt32LE(120);
+  const isResolved = data[144];
+  const outcome = data[145];
+  const signalSev = data[152];
+  const baseSpread = data.readUInt16LE(112);
+  const edgeSpread = data.readUInt16LE(114);
+  const maxSpread = data.readUInt16LE(116);
+  const totalTrades = data.readBigUInt64LE(288);
+  const totalVolume = data.readBigUInt64LE(296) + (data.readBigUInt64LE(304) << 64n);
 
   console.log(`  Context: ${contextPubkey}`);
   console.log(`  Probability: ${(Number(probability) / 10_000).toFixed(2)}%`);
-  console.log(`  Status: ${isResolved ? `RESOLVED (${outcome === 1 ? "YES" : "NO"})` : "ACTIVE"}`);
+  // Outcome byte: 0 = NO, 1 = YES, 2 = VOID, 3 = EMERGENCY
+  const outcomeName = ["NO", "YES", "VOID", "EMERGENCY"][outcome] ?? `UNKNOWN ${outcome}`;
+  console.log(`  Status: ${isResolved ? `RESOLVED (${outcomeName})` : "ACTIVE"}`);
   console.log(`  Signal severity: ${signalSev}`);
   console.log(`  Base spread: ${baseSpread} bps`);
   console.log(`  Edge spread: ${edgeSpread} bps`);
   console.log(`  Max spread: ${maxSpread} bps`);
+  console.log(`  Trades: ${totalTrades}`);
+  console.log(`  Volume: ${totalVolume}`);
 }
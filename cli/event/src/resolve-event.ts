@@ -16,7 +16,18 @@ export async function resolveEvent(opts: {
     process.env.MATCHER_PROGRAM_ID || "Evnt1111111111111111111111111111111111111111"
   );
 
-  const outcome = opts.outcome.toLowerCase() === "yes" ? 1 : 0;
+  // 0 = NO, 1 = YES, 2 = VOID (settles at the void probability set at init)
+  const outcomes: Record<string, number> = { no: 0, yes: 1, void: 2 };
+  const outcome = outcomes[opts.outcome.toLowerCase()];
+  if (outcome === undefined) {
+    throw new Error(`Unknown outcome "${opts.outcome}" (expected yes, no or void)`);
+  }
+  const context = new PublicKey(opts.context);
+  // Only read when the market has a resolver authority; harmless otherwise
+  const [authorities] = PublicKey.findProgramAddressSync(
+    [Buffer.from("authorities"), context.toBuffer()],
+    matcherProgramId
+  );
 
   const data = Buffer.alloc(2);
   data[0] = 0x04; // Resolve tag
@@ -25,8 +36,9 @@ export async function resolveEvent(opts: {
   const ix = new TransactionInstruction({
     programId: matcherProgramId,
     keys: [
-      { pubkey: new PublicKey(opts.context), isSigner: false, isWritable: true },
+      { pubkey: context, isSigner: false, isWritable: true },
       { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+      { pubkey: authorities, isSigner: false, isWritable: false },
     ],
     data,
   });
@@ -35,12 +47,12 @@ export async function resolveEvent(opts: {
   const sig = await sendAndConfirmTransaction(connection, tx, [payer]);
 
   console.log("Event resolved!");
-  console.log(`  Outcome: ${outcome === 1 ? "YES (100%)" : "NO (0%)"}`);
+  console.log(`  Outcome: ${["NO (0%)", "YES (100%)", "VOID (void probability)"][outcome]}`);
   console.log(`  Context: ${opts.context}`);
   console.log(`  Transaction: ${sig}`);
   console.log("");
   console.log("Next steps:");
   console.log("  1. Keeper will sync final probability to Percolator oracle");
-  console.log("  2. All positions settle at terminal price (0 or 1,000,000)");
+  console.log("  2. All positions settle at the terminal price (0, 1,000,000 or the void probability)");
   console.log("  3. Traders can close positions through normal Percolator mechanics");
 }
@@ -26,16 +26,28 @@ export async function macroStatus(opts: {
   const regimeNames = ["Expansion", "Stagnation", "Crisis", "Recovery"];
   const signalNames = ["None", "Low", "High", "Critical"];
   const mode = data[76];
+  const version = data.readUInt32LE(72);
+  if (version !== 1) {
+    console.error(`Unsupported context version ${version} (expected 1)`);
+    process.exit(1);
+  }
+  const readU128 = (offset: number) =>
+    data.readBigUInt64LE(offset) | (data.readBigUInt64LE(offset + 8) << 64n);
+  const baseSpread = data.readUInt32LE(112);
+  const regimeSpread = data.readUInt32LE(116);
+  const maxSpread = data.readUInt32LE(120);
   const currentIndex = data.readBigUInt64LE(128);
   const componentsPacked = data.readBigUInt64LE(136);
   const lastSlot = data.readBigUInt64LE(144);
   const regime = data[152];
+  const staticSkew = data.readInt16LE(154);
   const signalSeverity = data.readBigUInt64LE(160);
   const signalSpread = data.readBigUInt64LE(168);
-  const baseSpread = data.readUInt32LE(112);
-  const regimeSpread = data.readUInt32LE(116);
-  const maxSpread = data.readUInt32LE(120);
+  const liquidity = readU128(176);
   const totalTrades = data.readBigUInt64LE(256);
+  const maxBidSpread = data.readUInt16LE(276);
+  const maxAskSpread = data.readUInt16LE(278);
+  const consumedLiquidity = readU128(280);
 
   // Unpack components: nominal(high 32) | inflation(low 32)
   const nominalBps = Number(componentsPacked >> 32n);
@@ -58,6 +70,9 @@ export async function macroStatus(opts: {
   console.log(`    Base spread: ${baseSpread} bps`);
   console.log(`    Regime spread: ${regimeSpread} bps`);
   console.log(`    Max spread: ${maxSpread} bps`);
+  console.log(`    Max bid spread: ${maxBidSpread || maxSpread} bps`);
+  console.log(`    Max ask spread: ${maxAskSpread || maxSpread} bps`);
+  console.log(`    Static skew: ${staticSkew} bps`);
   console.log("");
   console.log("  Signal Intelligence:");
   console.log(`    Severity: ${signalNames[Number(signalSeverity)] || "Unknown"} (${signalSeverity})`);
@@ -65,4 +80,5 @@ export async function macroStatus(opts: {
   console.log("");
   console.log(`  Last update slot: ${lastSlot}`);
   console.log(`  Total trades: ${totalTrades}`);
+  console.log(`  Liquidity consumed: ${consumedLiquidity} / ${liquidity} (e6)`);
 }
@@ -22,8 +22,13 @@ export async function initPrivacyLp(opts: {
   // Generate context account
   const contextAccount = Keypair.generate();
 
-  // Generate solver encryption keypair
+  // Generate solver encryption keypair (X25519). The solver decrypts intents
+  // with the secret half, read from SOLVER_ENCRYPTION_KEYPAIR. Oracle prices
+  // are signed by a separate Ed25519 key the solver stores on its first start.
   const encryptionKeys = generateKeyPair();
+  const encryptionKeyPath =
+    process.env.SOLVER_ENCRYPTION_KEYPAIR || `${process.env.HOME}/.config/solana/solver-encryption.json`;
+  fs.writeFileSync(encryptionKeyPath, JSON.stringify(Array.from(encryptionKeys.secretKey)), { mode: 0o600 });
 
   // Build init instruction data
   const baseSpread = parseInt(opts.baseSpread);
@@ -68,5 +73,6 @@ export async function initPrivacyLp(opts: {
   console.log(`  Max spread: ${maxSpread} bps`);
   console.log(`  Solver fee: ${solverFee} bps`);
   console.log(`  Solver encryption pubkey: ${Buffer.from(encryptionKeys.publicKey).toString("hex")}`);
+  console.log(`  Solver encryption secret: ${encryptionKeyPath}`);
   console.log(`  Transaction: ${sig}`);
 }
@@ -23,6 +23,12 @@ export async function volStatus(opts: {
     process.exit(1);
   }
 
+  const version = data.readUInt32LE(72);
+  if (version !== 1) {
+    console.error(`Unsupported context version ${version} (expected 1)`);
+    process.exit(1);
+  }
+
   const regimeNames = ["VeryLow", "Low", "Normal", "High", "Extreme"];
   const mode = data[76];
   const currentVol = data.readBigUInt64LE(128);
@@ -34,6 +40,8 @@ export async function volStatus(opts: {
   const baseSpread = data.readUInt32LE(112);
   const vovSpread = data.readUInt32LE(116);
   const maxSpread = data.readUInt32LE(120);
+  const totalVolume = data.readBigUInt64LE(280) + (data.readBigUInt64LE(288) << 64n);
+  const totalTrades = data.readBigUInt64LE(296);
 
   console.log("Vol Matcher Status");
   console.log("===================");
@@ -48,4 +56,6 @@ export async function volStatus(opts: {
   console.log(`  Base spread: ${baseSpread} bps`);
   console.log(`  VoV spread: ${vovSpread} bps`);
   console.log(`  Max spread: ${maxSpread} bps`);
+  console.log(`  Trades: ${totalTrades}`);
+  console.log(`  Volume: ${totalVolume}`);
 }
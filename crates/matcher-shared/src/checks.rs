@@ -0,0 +1,83 @@
+use matcher_common::CTX_SIZE;
+use solana_program::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey};
+
+/// Reject a context account this program doesn't own. Magic bytes and the
+/// LP PDA are plain data any program could write; the owner can't be forged.
+pub fn verify_ctx_owner(ctx_account: &AccountInfo, program_id: &Pubkey, name: &str) -> Result<(), ProgramError> {
+    if ctx_account.owner != program_id {
+        msg!("{}: Context account owned by {}, expected this program", name, ctx_account.owner);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Reject an all-zero LP PDA at init. It is also the system program's id and
+/// no one can sign for it, so the matcher could never be matched.
+pub fn verify_lp_pda_key(lp_pda: &Pubkey, name: &str) -> Result<(), ProgramError> {
+    if *lp_pda == Pubkey::default() {
+        msg!("{}: LP PDA is the all-zero key", name);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// Reject a context account that isn't exactly CTX_SIZE bytes. The shared
+/// precondition only rejects short accounts; bytes past the layout would be
+/// data no field owns, and Percolator's CPI contract is exactly 320.
+pub fn verify_ctx_size(ctx_account: &AccountInfo, name: &str) -> Result<(), ProgramError> {
+    if ctx_account.data_len() != CTX_SIZE {
+        msg!("{}: Context account is {} bytes, expected {}", name, ctx_account.data_len(), CTX_SIZE);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAME: &str = "TEST-MATCHER";
+
+    /// Call `check` with a context account of `len` bytes owned by `owner`
+    fn with_ctx<R>(len: usize, owner: &Pubkey, check: impl FnOnce(&AccountInfo) -> R) -> R {
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let mut lamports = 0u64;
+        let mut data = vec![0u8; len];
+        let ctx = AccountInfo::new(&ctx_key, false, true, &mut lamports, &mut data, owner, false, 0);
+        check(&ctx)
+    }
+
+    #[test]
+    fn test_ctx_owner_must_be_this_program() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let other_program = Pubkey::new_from_array([8u8; 32]);
+        // A fresh context, but the account belongs to another program
+        assert_eq!(
+            with_ctx(CTX_SIZE, &other_program, |ctx| verify_ctx_owner(ctx, &program_id, NAME)),
+            Err(ProgramError::IncorrectProgramId)
+        );
+        assert_eq!(with_ctx(CTX_SIZE, &program_id, |ctx| verify_ctx_owner(ctx, &program_id, NAME)), Ok(()));
+    }
+
+    #[test]
+    fn test_ctx_size_must_be_exact() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        // Oversized: a `>=` check would let the tail past the layout ride along
+        assert_eq!(
+            with_ctx(384, &program_id, |ctx| verify_ctx_size(ctx, NAME)),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            with_ctx(CTX_SIZE - 1, &program_id, |ctx| verify_ctx_size(ctx, NAME)),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(with_ctx(CTX_SIZE, &program_id, |ctx| verify_ctx_size(ctx, NAME)), Ok(()));
+    }
+
+    #[test]
+    fn test_zero_lp_pda_rejected() {
+        // A fat-fingered client passing the default pubkey as the LP
+        assert_eq!(verify_lp_pda_key(&Pubkey::default(), NAME), Err(ProgramError::InvalidAccountData));
+        assert_eq!(verify_lp_pda_key(&Pubkey::new_from_array([1u8; 32]), NAME), Ok(()));
+    }
+}
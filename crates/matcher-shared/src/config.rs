@@ -0,0 +1,31 @@
+/// Whether an UpdateConfig at `slot` comes at least `min_interval_slots` after
+/// the previous one, so an operator (or a stolen key) can't flip parameters
+/// back and forth under traders. Slots are stored as their low 32 bits and
+/// compared with wrapping subtraction; a `last_config_slot` of 0 means the
+/// config has never been updated, and the first update is never held back.
+pub fn config_interval_elapsed(last_config_slot: u32, min_interval_slots: u16, slot: u64) -> bool {
+    if min_interval_slots == 0 || last_config_slot == 0 {
+        return true;
+    }
+    (slot as u32).wrapping_sub(last_config_slot) >= min_interval_slots as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_interval() {
+        // Too soon, then exactly the interval apart
+        assert!(!config_interval_elapsed(990, 20, 1_000));
+        assert!(config_interval_elapsed(980, 20, 1_000));
+
+        // Off, or no update yet
+        assert!(config_interval_elapsed(999, 0, 1_000));
+        assert!(config_interval_elapsed(0, 20, 1_000));
+
+        // Across the u32 wrap of the stored slot
+        assert!(!config_interval_elapsed(u32::MAX - 5, 20, 1u64 << 32 | 5));
+        assert!(config_interval_elapsed(u32::MAX - 5, 20, 1u64 << 32 | 14));
+    }
+}
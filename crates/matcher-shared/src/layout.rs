@@ -0,0 +1,85 @@
+//! Context layout constants for off-chain clients (the Percolator keeper, test
+//! harnesses), so they read the 320-byte header from one place instead of
+//! hardcoding it. The offsets are matcher-common's; the magics mirror each
+//! program's state.rs, whose tests check them against these.
+
+pub use matcher_common::{CTX_SIZE, LP_PDA_OFFSET, MAGIC_OFFSET, RETURN_DATA_OFFSET, RETURN_DATA_SIZE};
+
+/// "PRIVMATC" as u64 LE
+pub const PRIVACY_MATCHER_MAGIC: u64 = 0x5052_4956_4d41_5443;
+/// "VOLMATCH" as u64 LE
+pub const VOL_MATCHER_MAGIC: u64 = 0x564F_4c4d_4154_4348;
+/// "JPYMATCH" as u64 LE
+pub const JPY_MATCHER_MAGIC: u64 = 0x4A50_594D_4154_4348;
+/// "EVNTMATC" as u64 LE
+pub const EVENT_MATCHER_MAGIC: u64 = 0x4556_4e54_4d41_5443;
+/// "MACOMATC" as u64 LE
+pub const MACRO_MATCHER_MAGIC: u64 = 0x4d41_434f_4d41_5443;
+
+/// The matcher programs in this repo, one per context magic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatcherKind {
+    Privacy,
+    Vol,
+    Jpy,
+    Event,
+    Macro,
+}
+
+impl MatcherKind {
+    pub const ALL: [MatcherKind; 5] =
+        [MatcherKind::Privacy, MatcherKind::Vol, MatcherKind::Jpy, MatcherKind::Event, MatcherKind::Macro];
+}
+
+/// The magic `kind`'s Init writes at `MAGIC_OFFSET`
+pub const fn matcher_magic(kind: MatcherKind) -> u64 {
+    match kind {
+        MatcherKind::Privacy => PRIVACY_MATCHER_MAGIC,
+        MatcherKind::Vol => VOL_MATCHER_MAGIC,
+        MatcherKind::Jpy => JPY_MATCHER_MAGIC,
+        MatcherKind::Event => EVENT_MATCHER_MAGIC,
+        MatcherKind::Macro => MACRO_MATCHER_MAGIC,
+    }
+}
+
+/// Which matcher an arbitrary context account belongs to, by the magic at
+/// `MAGIC_OFFSET`, so a router can dispatch on it. `None` for a short buffer,
+/// an uninitialized context, or a magic no matcher here writes.
+pub fn identify(ctx_data: &[u8]) -> Option<MatcherKind> {
+    let magic = u64::from_le_bytes(ctx_data.get(MAGIC_OFFSET..MAGIC_OFFSET + 8)?.try_into().ok()?);
+    MatcherKind::ALL.into_iter().find(|&kind| matcher_magic(kind) == magic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matcher_common::write_header;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_magics_are_distinct_and_spell_their_matcher() {
+        let names: Vec<[u8; 8]> = MatcherKind::ALL.iter().map(|&kind| matcher_magic(kind).to_be_bytes()).collect();
+        assert_eq!(
+            names,
+            [*b"PRIVMATC", *b"VOLMATCH", *b"JPYMATCH", *b"EVNTMATC", *b"MACOMATC"]
+        );
+    }
+
+    #[test]
+    fn test_identify() {
+        for kind in MatcherKind::ALL {
+            let mut data = vec![0u8; CTX_SIZE];
+            write_header(&mut data, matcher_magic(kind), 0, &Pubkey::new_unique());
+            assert_eq!(identify(&data), Some(kind));
+        }
+
+        // Uninitialized, foreign, or too short to hold a magic
+        assert_eq!(identify(&[0u8; CTX_SIZE]), None);
+        let mut foreign = vec![0u8; CTX_SIZE];
+        write_header(&mut foreign, u64::from_be_bytes(*b"OTHRMATC"), 0, &Pubkey::new_unique());
+        assert_eq!(identify(&foreign), None);
+        let mut header = vec![0u8; CTX_SIZE];
+        write_header(&mut header, VOL_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(identify(&header[..MAGIC_OFFSET + 7]), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! Helpers shared by the matcher programs on top of matcher-common.
+//!
+//! matcher-common owns the Percolator CPI contract (header layout, LP PDA
+//! check, exec price return); the code here is what every matcher in this
+//! repo does the same way around it. `name` arguments are the log prefix
+//! ("VOL-MATCHER", ...), as in matcher-common's own checks.
+
+mod checks;
+mod config;
+pub mod layout;
+mod pda;
+mod pricing;
+mod stats;
+mod sweep;
+
+pub use checks::{verify_ctx_owner, verify_ctx_size, verify_lp_pda_key};
+pub use config::config_interval_elapsed;
+pub use layout::{identify, matcher_magic, MatcherKind};
+pub use pda::create_pda_account;
+pub use pricing::{side_price, trade_fee, SidePriceError, DIRECTION_BUY, DIRECTION_SELL};
+pub use stats::bump_stats;
+pub use sweep::process_sweep_excess;
+
+/// Informational log -- compiled out under the `no-logs` feature to save compute.
+/// Error paths log with plain `msg!` so failures stay diagnosable. Each program's
+/// own `no-logs` feature turns this one on.
+#[cfg(not(feature = "no-logs"))]
+#[macro_export]
+macro_rules! matcher_log {
+    ($($arg:tt)*) => { $crate::__private::msg!($($arg)*) };
+}
+
+#[cfg(feature = "no-logs")]
+#[macro_export]
+macro_rules! matcher_log {
+    // Still type-check the arguments so nothing goes unused
+    ($($arg:tt)*) => { let _ = format_args!($($arg)*); };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use solana_program::msg;
+}
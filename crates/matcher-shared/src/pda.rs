@@ -0,0 +1,50 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+/// Create a program-owned PDA of `size` bytes, paid by `payer`. `seeds` are the
+/// PDA's full signer seeds, bump included. `create_account` fails on an address
+/// that already holds lamports, and anyone can send some to a PDA ahead of its
+/// creation; in that case top it up to rent exemption and allocate + assign it
+/// instead.
+pub fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    pda: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    size: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent = Rent::get()?.minimum_balance(size);
+    if pda.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, pda.key, rent, size as u64, program_id),
+            &[payer.clone(), pda.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let top_up = rent.saturating_sub(pda.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, pda.key, top_up),
+            &[payer.clone(), pda.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(pda.key, size as u64),
+        &[pda.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(pda.key, program_id),
+        &[pda.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
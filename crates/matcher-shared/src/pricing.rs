@@ -0,0 +1,94 @@
+use matcher_common::compute_exec_price;
+
+/// Match data byte [9]: which side of the LP's book the taker hits
+pub const DIRECTION_BUY: u8 = 0;                     // taker buys from LP (ask side)
+pub const DIRECTION_SELL: u8 = 1;                    // taker sells to LP (bid side)
+
+/// Why `side_price` couldn't price a side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidePriceError {
+    /// The ask doesn't fit a u64
+    Overflow,
+    /// A bid spread of 10_000 bps or more would pay the taker nothing
+    SpreadTooWide,
+}
+
+/// Ask = mark * (1 + spread), bid = mark * (1 - spread). Bids always floor, in
+/// the LP's favor; `round_ask_up` rounds asks up instead of flooring them.
+pub fn side_price(mark: u64, spread_bps: u64, direction: u8, round_ask_up: bool) -> Result<u64, SidePriceError> {
+    if direction == DIRECTION_BUY {
+        if !round_ask_up {
+            return compute_exec_price(mark, spread_bps).map_err(|_| SidePriceError::Overflow);
+        }
+        let scaled = (mark as u128)
+            .checked_mul(10_000u128 + spread_bps as u128)
+            .ok_or(SidePriceError::Overflow)?;
+        return u64::try_from(scaled.div_ceil(10_000)).map_err(|_| SidePriceError::Overflow);
+    }
+    if spread_bps >= 10_000 {
+        return Err(SidePriceError::SpreadTooWide);
+    }
+    // mark * (10_000 - spread) / 10_000 <= mark, so the bid always fits
+    Ok(((mark as u128) * (10_000 - spread_bps) as u128 / 10_000) as u64)
+}
+
+/// Absolute spread charged on a fill: `oracle * size / 1e6` notional times
+/// `spread_bps / 10000`, divided once and rounded half away from zero (half
+/// up, as every term is unsigned). This is the figure accounting reconciles
+/// against, so it must not depend on where intermediate truncation falls.
+/// `None` on overflow.
+pub fn trade_fee(oracle_price: u64, spread_bps: u64, trade_size: u64) -> Option<u64> {
+    const DENOM: u128 = 1_000_000 * 10_000;
+    let scaled = (oracle_price as u128)
+        .checked_mul(trade_size as u128)?
+        .checked_mul(spread_bps as u128)?;
+    let fee = scaled / DENOM + u128::from(scaled % DENOM >= DENOM / 2);
+    u64::try_from(fee).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ask_and_bid_around_mark() {
+        // ask = 4_500_000_000 * 10040 / 10000, bid = 4_500_000_000 * 9940 / 10000
+        assert_eq!(side_price(4_500_000_000, 40, DIRECTION_BUY, false), Ok(4_518_000_000));
+        assert_eq!(side_price(4_500_000_000, 60, DIRECTION_SELL, false), Ok(4_473_000_000));
+        assert_eq!(side_price(4_500_000_000, 0, DIRECTION_SELL, false), Ok(4_500_000_000));
+    }
+
+    #[test]
+    fn test_round_ask_up() {
+        // 7_000_001 * 10_050 / 10_000 = 7_035_001.005: floor gives the taker the remainder
+        assert_eq!(side_price(7_000_001, 50, DIRECTION_BUY, false), Ok(7_035_001));
+        assert_eq!(side_price(7_000_001, 50, DIRECTION_BUY, true), Ok(7_035_002));
+        // 7_000_001 * 9_930 / 10_000 = 6_951_000.993: bids floor either way
+        assert_eq!(side_price(7_000_001, 70, DIRECTION_SELL, false), Ok(6_951_000));
+        assert_eq!(side_price(7_000_001, 70, DIRECTION_SELL, true), Ok(6_951_000));
+        // No remainder, no difference
+        assert_eq!(side_price(7_000_000, 50, DIRECTION_BUY, true), Ok(7_035_000));
+    }
+
+    #[test]
+    fn test_bid_spread_of_100_percent_rejected() {
+        // Saturating the multiplier used to turn these into a bid of 0
+        assert_eq!(side_price(7_000_000, 10_000, DIRECTION_SELL, false), Err(SidePriceError::SpreadTooWide));
+        assert_eq!(side_price(7_000_000, 65_535, DIRECTION_SELL, true), Err(SidePriceError::SpreadTooWide));
+        assert_eq!(side_price(7_000_000, 9_999, DIRECTION_SELL, false), Ok(700));
+        // The ask side has no such ceiling
+        assert_eq!(side_price(7_000_000, 10_000, DIRECTION_BUY, false), Ok(14_000_000));
+        assert_eq!(side_price(u64::MAX, 10_000, DIRECTION_BUY, true), Err(SidePriceError::Overflow));
+    }
+
+    #[test]
+    fn test_trade_fee_rounds_half_away_from_zero() {
+        // 20 bps on a 150.0 oracle: 0.3 e6 of fee per unit of size
+        assert_eq!(trade_fee(150_000_000, 20, 5), Some(2)); // 1.5
+        assert_eq!(trade_fee(150_000_000, 20, 15), Some(5)); // 4.5
+        assert_eq!(trade_fee(150_000_000, 20, 4), Some(1)); // 1.2
+        assert_eq!(trade_fee(150_000_000, 20, 1), Some(0)); // 0.3
+        assert_eq!(trade_fee(150_000_000, 20, 5_000_000), Some(1_500_000));
+        assert_eq!(trade_fee(u64::MAX, 10_000, u64::MAX), None);
+    }
+}
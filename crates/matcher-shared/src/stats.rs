@@ -0,0 +1,34 @@
+/// Add `trades` to the u64 trade count and `volume` to the u128 volume total
+/// stored at the given context offsets. Both saturate: a pinned counter is
+/// better than a match that fails on bookkeeping.
+pub fn bump_stats(ctx_data: &mut [u8], trades_offset: usize, volume_offset: usize, trades: u64, volume: u128) {
+    let mut count = [0u8; 8];
+    count.copy_from_slice(&ctx_data[trades_offset..trades_offset + 8]);
+    let count = u64::from_le_bytes(count).saturating_add(trades);
+    ctx_data[trades_offset..trades_offset + 8].copy_from_slice(&count.to_le_bytes());
+
+    let mut total = [0u8; 16];
+    total.copy_from_slice(&ctx_data[volume_offset..volume_offset + 16]);
+    let total = u128::from_le_bytes(total).saturating_add(volume);
+    ctx_data[volume_offset..volume_offset + 16].copy_from_slice(&total.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_accumulate_past_u64_volume_and_saturate() {
+        let mut ctx = [0u8; 32];
+        bump_stats(&mut ctx, 0, 8, 1, u64::MAX as u128);
+        bump_stats(&mut ctx, 0, 8, 1, u64::MAX as u128);
+        assert_eq!(u64::from_le_bytes(ctx[0..8].try_into().unwrap()), 2);
+        assert_eq!(u128::from_le_bytes(ctx[8..24].try_into().unwrap()), 2 * u64::MAX as u128);
+
+        ctx[0..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        ctx[8..24].copy_from_slice(&u128::MAX.to_le_bytes());
+        bump_stats(&mut ctx, 0, 8, 1, 1);
+        assert_eq!(u64::from_le_bytes(ctx[0..8].try_into().unwrap()), u64::MAX);
+        assert_eq!(u128::from_le_bytes(ctx[8..24].try_into().unwrap()), u128::MAX);
+    }
+}
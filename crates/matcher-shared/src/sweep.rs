@@ -0,0 +1,107 @@
+use matcher_common::{verify_lp_pda, CTX_SIZE};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey,
+    rent::Rent, sysvar::Sysvar,
+};
+
+use crate::verify_ctx_owner;
+
+/// Tag 0x0E: Sweep excess — move lamports above the rent-exempt minimum out
+/// of an over-funded context without closing it
+/// Accounts:
+///   [0] LP PDA (signer)
+///   [1] Matcher context account (writable)
+///   [2] Destination (writable)
+/// Data layout:
+///   [0] tag (0x0E)
+pub fn process_sweep_excess(program_id: &Pubkey, accounts: &[AccountInfo], magic: u64, name: &str) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+    let destination = &accounts[2];
+
+    verify_ctx_owner(ctx_account, program_id, name)?;
+    verify_lp_pda(lp_pda, ctx_account, magic, name)?;
+    if destination.key == ctx_account.key {
+        msg!("{}: Sweep destination is the context account", name);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let rent_exempt = Rent::get()?.minimum_balance(CTX_SIZE);
+    let excess = ctx_account.lamports().saturating_sub(rent_exempt);
+    if excess == 0 {
+        crate::matcher_log!("SWEEP_EXCESS: nothing above rent-exempt {}", rent_exempt);
+        return Ok(());
+    }
+
+    let credited = destination
+        .lamports()
+        .checked_add(excess)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **ctx_account.try_borrow_mut_lamports()? -= excess;
+    **destination.try_borrow_mut_lamports()? = credited;
+
+    crate::matcher_log!("SWEEP_EXCESS: amount={} destination={}", excess, destination.key);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+
+    const MAGIC: u64 = 0x5445_5354_4d41_5443;
+
+    /// Sweep Excess reads the rent sysvar; serve the default schedule off-chain
+    struct FixedRent;
+
+    impl SyscallStubs for FixedRent {
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+    }
+
+    /// Run Sweep Excess against `ctx` funded with `ctx_lamports`. Returns the
+    /// result and the context and destination balances afterwards.
+    fn run_sweep(ctx: &mut [u8], ctx_lamports: u64) -> (ProgramResult, u64, u64) {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let dest_key = Pubkey::new_from_array([9u8; 32]);
+        let system_program = Pubkey::default();
+        let (mut lp_lamports, mut ctx_lamports, mut dest_lamports) = (0u64, ctx_lamports, 1_000u64);
+        let (mut lp_data, mut dest_data) = (Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&dest_key, false, true, &mut dest_lamports, &mut dest_data, &system_program, false, 0),
+        ];
+        let result = process_sweep_excess(&program_id, &accounts, MAGIC, "TEST-MATCHER");
+        (result, accounts[1].lamports(), accounts[2].lamports())
+    }
+
+    #[test]
+    fn test_sweep_excess_leaves_context_rent_exempt() {
+        set_syscall_stubs(Box::new(FixedRent));
+        let rent_exempt = Rent::default().minimum_balance(CTX_SIZE);
+        let mut ctx = vec![0u8; CTX_SIZE];
+        matcher_common::write_header(&mut ctx, MAGIC, 0, &Pubkey::new_from_array([1u8; 32]));
+        let before = ctx.clone();
+
+        // Over-funded: swept down to exactly rent-exempt, data untouched
+        let (result, ctx_after, dest_after) = run_sweep(&mut ctx, rent_exempt + 2_500_000);
+        assert_eq!(result, Ok(()));
+        assert_eq!((ctx_after, dest_after), (rent_exempt, 2_501_000));
+        assert_eq!(ctx, before);
+
+        // Rent-exempt only: nothing moves
+        let (result, ctx_after, dest_after) = run_sweep(&mut ctx, rent_exempt);
+        assert_eq!(result, Ok(()));
+        assert_eq!((ctx_after, dest_after), (rent_exempt, 1_000));
+    }
+}
@@ -10,6 +10,17 @@ pub enum EventMatcherError {
     InvalidOutcome = 0x205,
     InvalidSignalSeverity = 0x206,
     ArithmeticOverflow = 0x207,
+    InvalidLiquidityConfig = 0x208,
+    InvalidResolutionTime = 0x209,
+    EmergencySettleLocked = 0x20A,
+    MatchReentered = 0x20B,
+    InvalidMode = 0x20C,
+    ProbabilityMoveTooLarge = 0x20D,
+    StaleUpdate = 0x20E,
+    SyncFrozen = 0x20F,
+    UnsupportedVersion = 0x210,
+    AuthorityMismatch = 0x211,
+    MarketNotResolved = 0x212,
 }
 
 impl From<EventMatcherError> for ProgramError {
@@ -11,15 +11,42 @@ pub enum EventMatcherInstruction {
     /// Initialize event matcher context
     #[account(0, name = "lp_pda", desc = "LP PDA to store")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes, writable)")]
+    #[account(2, optional, writable, signer, name = "payer", desc = "Pays for the authorities PDA (only with an authority)")]
+    #[account(3, optional, writable, name = "authorities", desc = "Authorities PDA, seeds [\"authorities\", context] (only with an authority)")]
+    #[account(4, optional, name = "system_program", desc = "System program (only with an authority)")]
     Init,
 
     /// Sync probability from oracle
     #[account(0, writable, name = "matcher_context", desc = "Matcher context account")]
     #[account(1, name = "event_oracle", desc = "Event oracle account")]
+    #[account(2, optional, signer, name = "lp_pda", desc = "LP PDA (signer, only to override the move limit)")]
     ProbabilitySync,
 
     /// Resolve event - set final probability
     #[account(0, writable, name = "matcher_context", desc = "Matcher context account")]
-    #[account(1, signer, name = "event_oracle", desc = "Event oracle (must be signer)")]
+    #[account(1, signer, name = "resolver", desc = "Resolver authority, or the event oracle when none is set (must be signer)")]
+    #[account(2, optional, name = "authorities", desc = "Authorities PDA (only with a resolver authority)")]
     Resolve,
+
+    /// Emergency settle - resolve at the configured emergency price after the delay
+    #[account(0, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(1, signer, name = "settlement_authority", desc = "Settlement authority from the authorities PDA (must be signer)")]
+    #[account(2, name = "authorities", desc = "Authorities PDA")]
+    EmergencySettle,
+
+    /// Advance settlement - move a resolved market's mark along its settlement ramp
+    #[account(0, writable, name = "matcher_context", desc = "Matcher context account")]
+    AdvanceSettlement,
+
+    /// Simulate resolve - write the settlement probability for an outcome without resolving
+    #[account(0, writable, name = "matcher_context", desc = "Matcher context account (return buffer only)")]
+    #[account(1, signer, name = "resolver", desc = "Resolver authority, or the event oracle when none is set (must be signer)")]
+    #[account(2, optional, name = "authorities", desc = "Authorities PDA (only with a resolver authority)")]
+    SimulateResolve,
+
+    /// Sweep excess - move lamports above the rent-exempt minimum to a destination
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "destination", desc = "Receives the excess lamports")]
+    SweepExcess,
 }
@@ -3,12 +3,18 @@ use solana_program::{
     program_error::ProgramError, pubkey::Pubkey,
 };
 
+#[macro_use]
+extern crate matcher_shared;
+
 mod errors;
 mod instructions;
 mod probability;
 mod state;
 
-use probability::{process_init, process_match, process_probability_sync, process_resolve};
+use probability::{
+    process_advance_settlement, process_emergency_settle, process_init, process_match, process_probability_sync,
+    process_resolve, process_simulate_resolve,
+};
 
 entrypoint!(process_instruction);
 
@@ -23,21 +29,37 @@ pub fn process_instruction(
 
     match instruction_data[0] {
         0x00 => {
-            msg!("EVENT-MATCHER: Match instruction");
+            matcher_log!("EVENT-MATCHER: Match instruction");
             process_match(program_id, accounts, instruction_data)
         }
         0x02 => {
-            msg!("EVENT-MATCHER: Init instruction");
+            matcher_log!("EVENT-MATCHER: Init instruction");
             process_init(program_id, accounts, instruction_data)
         }
         0x03 => {
-            msg!("EVENT-MATCHER: Probability sync instruction");
+            matcher_log!("EVENT-MATCHER: Probability sync instruction");
             process_probability_sync(program_id, accounts, instruction_data)
         }
         0x04 => {
-            msg!("EVENT-MATCHER: Resolve instruction");
+            matcher_log!("EVENT-MATCHER: Resolve instruction");
             process_resolve(program_id, accounts, instruction_data)
         }
+        0x05 => {
+            matcher_log!("EVENT-MATCHER: Emergency settle instruction");
+            process_emergency_settle(program_id, accounts, instruction_data)
+        }
+        0x06 => {
+            matcher_log!("EVENT-MATCHER: Advance settlement instruction");
+            process_advance_settlement(program_id, accounts, instruction_data)
+        }
+        0x0D => {
+            matcher_log!("EVENT-MATCHER: Simulate resolve instruction");
+            process_simulate_resolve(program_id, accounts, instruction_data)
+        }
+        0x0E => {
+            matcher_log!("EVENT-MATCHER: Sweep excess instruction");
+            matcher_shared::process_sweep_excess(program_id, accounts, state::EVENT_MATCHER_MAGIC, "EVENT-MATCHER")
+        }
         _ => {
             msg!("EVENT-MATCHER: Unknown instruction tag {}", instruction_data[0]);
             Err(ProgramError::InvalidInstructionData)
@@ -4,6 +4,7 @@ use solana_program::{
 };
 
 use matcher_common::{verify_lp_pda as verify_lp_pda_common, verify_init_preconditions, write_header, write_exec_price};
+use matcher_shared::{bump_stats, create_pda_account, verify_ctx_owner, verify_ctx_size, verify_lp_pda_key};
 
 use crate::errors::EventMatcherError;
 use crate::state::*;
@@ -12,6 +13,9 @@ use crate::state::*;
 /// Accounts:
 ///   [0] LP PDA (signer)
 ///   [1] Matcher context account (writable, 320 bytes)
+///   [2] Payer (signer, writable; only when an authority is supplied)
+///   [3] Authorities PDA (writable, ["authorities", context]; only when an authority is supplied)
+///   [4] System program (only when an authority is supplied)
 /// Data layout:
 ///   [0]    tag (0x02)
 ///   [1]    mode (u8: 0=Continuous, 1=BinarySettlement)
@@ -24,8 +28,20 @@ use crate::state::*;
 ///   [34..50] liquidity_notional_e6 (u128 LE)
 ///   [50..66] max_fill_abs (u128 LE)
 ///   [66..98] event_oracle pubkey (32 bytes)
+///   [98..102] min_probability_e6 (u32 LE, optional, 0 = no sync clamp)
+///   [102..104] twap_lookback_slots (u16 LE, optional, 0 = price on instantaneous probability)
+///   [104..108] emergency_price_e6 (u32 LE, optional, 0 = settles NO)
+///   [108..110] emergency_delay_days (u16 LE, optional, 0 = 30 days)
+///   [110..112] max_prob_move_bps (u16 LE, optional, 0 = no sync move limit)
+///   [112..116] freeze_secs_before_resolution (u32 LE, optional, 0 = syncs allowed up to resolution)
+///   [116..148] settlement_authority pubkey (optional, zero = no emergency settlement)
+///   [148..180] resolver_authority pubkey (optional, zero = the event oracle resolves)
+///   [180..182] settlement_ramp_slots (u16 LE, optional, 0 = the mark snaps to the settlement value)
+///   [182..186] void_probability_e6 (u32 LE, optional, 0 = 500_000)
+/// Spreads and impact_k must fit u16, liquidity and max fill u64: the context
+/// stores them narrower than the payload.
 pub fn process_init(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
@@ -39,7 +55,23 @@ pub fn process_init(
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
+    verify_ctx_owner(ctx_account, program_id, "EVENT-MATCHER")?;
     verify_init_preconditions(ctx_account, EVENT_MATCHER_MAGIC, "EVENT-MATCHER")?;
+    verify_ctx_size(ctx_account, "EVENT-MATCHER")?;
+    verify_lp_pda_key(lp_pda.key, "EVENT-MATCHER")?;
+
+    if data[1] > MAX_MODE {
+        msg!("EVENT-MATCHER: Invalid mode {}, expected 0..={}", data[1], MAX_MODE);
+        return Err(EventMatcherError::InvalidMode.into());
+    }
+
+    let base_spread = init_u16(data, 2, "base_spread_bps")?;
+    let edge_spread = init_u16(data, 6, "edge_spread_bps")?;
+    let max_spread = init_u16(data, 10, "max_spread_bps")?;
+    let impact_k = init_u16(data, 14, "impact_k_bps")?;
+    let liquidity = init_u64(data, 34, "liquidity_notional_e6")?;
+    let max_fill = init_u64(data, 50, "max_fill_abs")?;
+    validate_liquidity_config(impact_k, liquidity)?;
 
     let initial_probability = u64::from_le_bytes(data[18..26].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     if initial_probability > MAX_PROBABILITY {
@@ -47,50 +79,144 @@ pub fn process_init(
         return Err(EventMatcherError::InvalidProbability.into());
     }
 
+    let min_probability = if data.len() >= 102 {
+        u32::from_le_bytes(data[98..102].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    if (min_probability as u64).saturating_mul(2) >= MAX_PROBABILITY {
+        msg!("EVENT-MATCHER: min_probability {} leaves no tradable range", min_probability);
+        return Err(EventMatcherError::InvalidProbability.into());
+    }
+
+    let twap_lookback = if data.len() >= 104 {
+        u16::from_le_bytes(data[102..104].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+
+    let (emergency_price, emergency_delay_days) = if data.len() >= 110 {
+        (
+            u32::from_le_bytes(data[104..108].try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+            u16::from_le_bytes(data[108..110].try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+        )
+    } else {
+        (0, 0)
+    };
+    if emergency_price as u64 > MAX_PROBABILITY {
+        msg!("EVENT-MATCHER: Emergency price {} exceeds max {}", emergency_price, MAX_PROBABILITY);
+        return Err(EventMatcherError::InvalidProbability.into());
+    }
+
+    let max_prob_move = if data.len() >= 112 {
+        u16::from_le_bytes(data[110..112].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+
+    let freeze_secs_before_resolution = if data.len() >= 116 {
+        u32::from_le_bytes(data[112..116].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+
+    let settlement_authority = if data.len() >= 148 {
+        Pubkey::new_from_array(data[116..148].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        Pubkey::default()
+    };
+    let resolver_authority = if data.len() >= 180 {
+        Pubkey::new_from_array(data[148..180].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        Pubkey::default()
+    };
+
+    let settlement_ramp_slots = if data.len() >= 182 {
+        u16::from_le_bytes(data[180..182].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+
+    let void_probability = if data.len() >= 186 {
+        u32::from_le_bytes(data[182..186].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    if void_probability as u64 > MAX_PROBABILITY {
+        msg!("EVENT-MATCHER: Void probability {} exceeds max {}", void_probability, MAX_PROBABILITY);
+        return Err(EventMatcherError::InvalidProbability.into());
+    }
+    let void_probability = if void_probability == 0 { DEFAULT_VOID_PROBABILITY as u32 } else { void_probability };
+
+    let clock = Clock::get()?;
+    let resolution_timestamp = i64::from_le_bytes(data[26..34].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    validate_resolution_time(resolution_timestamp, clock.unix_timestamp)?;
+
+    let authorities = create_authorities(program_id, accounts, settlement_authority, resolver_authority)?;
+
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
 
     write_header(&mut ctx_data, EVENT_MATCHER_MAGIC, data[1], lp_pda.key);
+    ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&CTX_VERSION.to_le_bytes());
+    ctx_data[MAX_PROB_MOVE_OFFSET..MAX_PROB_MOVE_OFFSET + 2].copy_from_slice(&max_prob_move.to_le_bytes());
+    for (start, end) in RESERVED_RANGES {
+        ctx_data[start..end].fill(0);
+    }
 
     // Spread params
-    ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&data[2..6]);
-    ctx_data[EDGE_SPREAD_OFFSET..EDGE_SPREAD_OFFSET + 4].copy_from_slice(&data[6..10]);
-    ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&data[10..14]);
-    ctx_data[IMPACT_K_OFFSET..IMPACT_K_OFFSET + 4].copy_from_slice(&data[14..18]);
+    ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 2].copy_from_slice(&base_spread.to_le_bytes());
+    ctx_data[EDGE_SPREAD_OFFSET..EDGE_SPREAD_OFFSET + 2].copy_from_slice(&edge_spread.to_le_bytes());
+    ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 2].copy_from_slice(&max_spread.to_le_bytes());
+    ctx_data[IMPACT_K_OFFSET..IMPACT_K_OFFSET + 2].copy_from_slice(&impact_k.to_le_bytes());
 
-    // Probability
-    ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 8]
-        .copy_from_slice(&initial_probability.to_le_bytes());
-    ctx_data[PROBABILITY_MARK_OFFSET..PROBABILITY_MARK_OFFSET + 8]
-        .copy_from_slice(&initial_probability.to_le_bytes()); // mark = prob in e6
+    // Probability (mark = prob in e6)
+    write_probability(&mut ctx_data, initial_probability);
 
-    let clock = Clock::get()?;
     ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
         .copy_from_slice(&clock.slot.to_le_bytes());
 
     // Resolution
     ctx_data[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8]
-        .copy_from_slice(&data[26..34]);
+        .copy_from_slice(&resolution_timestamp.to_le_bytes());
     ctx_data[IS_RESOLVED_OFFSET] = 0;
     ctx_data[RESOLUTION_OUTCOME_OFFSET] = 0;
-    ctx_data[162..168].fill(0); // padding
+    ctx_data[EMERGENCY_DELAY_DAYS_OFFSET..EMERGENCY_DELAY_DAYS_OFFSET + 2]
+        .copy_from_slice(&emergency_delay_days.to_le_bytes());
+    ctx_data[EMERGENCY_PRICE_OFFSET..EMERGENCY_PRICE_OFFSET + 4]
+        .copy_from_slice(&emergency_price.to_le_bytes());
+    ctx_data[MIN_PROBABILITY_OFFSET..MIN_PROBABILITY_OFFSET + 4]
+        .copy_from_slice(&min_probability.to_le_bytes());
+    ctx_data[SETTLEMENT_RAMP_SLOTS_OFFSET..SETTLEMENT_RAMP_SLOTS_OFFSET + 2]
+        .copy_from_slice(&settlement_ramp_slots.to_le_bytes());
+    ctx_data[VOID_PROBABILITY_OFFSET..VOID_PROBABILITY_OFFSET + 4]
+        .copy_from_slice(&void_probability.to_le_bytes());
+    ctx_data[RAMP_START_MARK_OFFSET..RAMP_START_SLOT_OFFSET + 4].fill(0);
 
     // Signal (init to none)
-    ctx_data[SIGNAL_SEVERITY_OFFSET..SIGNAL_SEVERITY_OFFSET + 8]
-        .copy_from_slice(&SIGNAL_NONE.to_le_bytes());
-    ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8]
-        .copy_from_slice(&0u64.to_le_bytes());
+    ctx_data[SIGNAL_SEVERITY_OFFSET] = SIGNAL_NONE as u8;
+    ctx_data[AUTHORITIES_OFFSET] = authorities;
+    ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 2].copy_from_slice(&0u16.to_le_bytes());
+    ctx_data[FREEZE_SECS_BEFORE_RESOLUTION_OFFSET..FREEZE_SECS_BEFORE_RESOLUTION_OFFSET + 4]
+        .copy_from_slice(&freeze_secs_before_resolution.to_le_bytes());
 
     // Liquidity + max fill
-    ctx_data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&data[34..50]);
-    ctx_data[MAX_FILL_OFFSET..MAX_FILL_OFFSET + 16].copy_from_slice(&data[50..66]);
+    ctx_data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 8].copy_from_slice(&liquidity.to_le_bytes());
+    ctx_data[MAX_FILL_OFFSET..MAX_FILL_OFFSET + 8].copy_from_slice(&max_fill.to_le_bytes());
+
+    // Trade stats
+    ctx_data[TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    ctx_data[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16].copy_from_slice(&0u128.to_le_bytes());
 
     // Event oracle
     ctx_data[EVENT_ORACLE_OFFSET..EVENT_ORACLE_OFFSET + 32].copy_from_slice(&data[66..98]);
 
-    // Zero reserved
-    ctx_data[248..CTX_SIZE].fill(0);
+    // Probability TWAP: the initial probability seeds the ring
+    ctx_data[TWAP_LOOKBACK_SLOTS_OFFSET..TWAP_LOOKBACK_SLOTS_OFFSET + 2]
+        .copy_from_slice(&twap_lookback.to_le_bytes());
+    ctx_data[PROB_RING_HEAD_OFFSET..PROB_RING_OFFSET + PROB_RING_CAPACITY * PROB_SAMPLE_SIZE].fill(0);
+    push_probability_sample(&mut ctx_data, initial_probability, clock.slot);
 
-    msg!(
+    matcher_log!(
         "INIT: lp_pda={} mode={} probability={} resolution_ts={}",
         lp_pda.key,
         data[1],
@@ -101,14 +227,137 @@ pub fn process_init(
     Ok(())
 }
 
+/// Create the authorities PDA when Init supplies an authority, returning the
+/// AUTHORITIES flags to store. With none supplied nothing is created and the
+/// accounts past [1] aren't needed.
+fn create_authorities(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    settlement_authority: Pubkey,
+    resolver_authority: Pubkey,
+) -> Result<u8, ProgramError> {
+    let mut flags = 0;
+    if settlement_authority != Pubkey::default() {
+        flags |= AUTHORITY_SETTLEMENT;
+    }
+    if resolver_authority != Pubkey::default() {
+        flags |= AUTHORITY_RESOLVER;
+    }
+    if flags == 0 {
+        return Ok(0);
+    }
+    if accounts.len() < 5 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let ctx_account = &accounts[1];
+    let payer = &accounts[2];
+    let authorities = &accounts[3];
+    let system_program = &accounts[4];
+
+    if !payer.is_signer {
+        msg!("EVENT-MATCHER: Payer must be a signer to create the authorities");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected, bump) = Pubkey::find_program_address(&[AUTHORITIES_SEED, ctx_account.key.as_ref()], program_id);
+    if *authorities.key != expected {
+        msg!("EVENT-MATCHER: Authorities must be the PDA {}", expected);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !authorities.data_is_empty() {
+        msg!("EVENT-MATCHER: Authorities already set for {}", ctx_account.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        authorities,
+        system_program,
+        AUTHORITIES_SIZE,
+        &[AUTHORITIES_SEED, ctx_account.key.as_ref(), &[bump]],
+    )?;
+
+    let mut authorities_data = authorities.try_borrow_mut_data()?;
+    authorities_data[..AUTHORITIES_SIZE].fill(0);
+    authorities_data[AUTH_MAGIC_OFFSET..AUTH_MAGIC_OFFSET + 8].copy_from_slice(&AUTHORITIES_MAGIC.to_le_bytes());
+    authorities_data[AUTH_BUMP_OFFSET] = bump;
+    authorities_data[SETTLEMENT_AUTHORITY_OFFSET..SETTLEMENT_AUTHORITY_OFFSET + 32]
+        .copy_from_slice(settlement_authority.as_ref());
+    authorities_data[RESOLVER_AUTHORITY_OFFSET..RESOLVER_AUTHORITY_OFFSET + 32]
+        .copy_from_slice(resolver_authority.as_ref());
+
+    matcher_log!(
+        "INIT_AUTHORITIES: ctx={} settlement={} resolver={}",
+        ctx_account.key,
+        settlement_authority,
+        resolver_authority
+    );
+
+    Ok(flags)
+}
+
+/// A u32 init field that the context stores as u16
+fn init_u16(data: &[u8], offset: usize, name: &str) -> Result<u16, ProgramError> {
+    let value = u32::from_le_bytes(data[offset..offset + 4].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    u16::try_from(value).map_err(|_| {
+        msg!("EVENT-MATCHER: {} {} exceeds {}", name, value, u16::MAX);
+        ProgramError::InvalidInstructionData
+    })
+}
+
+/// A u128 init field that the context stores as u64
+fn init_u64(data: &[u8], offset: usize, name: &str) -> Result<u64, ProgramError> {
+    let value = u128::from_le_bytes(data[offset..offset + 16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    u64::try_from(value).map_err(|_| {
+        msg!("EVENT-MATCHER: {} {} exceeds {}", name, value, u64::MAX);
+        ProgramError::InvalidInstructionData
+    })
+}
+
+/// Impact pricing divides by liquidity, so an enabled impact model needs a nonzero pool.
+/// With both zero the impact term is simply off.
+fn validate_liquidity_config(impact_k_bps: u16, liquidity_notional_e6: u64) -> Result<(), ProgramError> {
+    if impact_k_bps > 0 && liquidity_notional_e6 == 0 {
+        msg!("EVENT-MATCHER: impact_k_bps={} requires nonzero liquidity", impact_k_bps);
+        return Err(EventMatcherError::InvalidLiquidityConfig.into());
+    }
+    Ok(())
+}
+
+/// A nonzero resolution time must lie in the future, within MAX_RESOLUTION_HORIZON_SECS.
+/// Zero means the market never expires.
+fn validate_resolution_time(resolution_timestamp: i64, now: i64) -> Result<(), ProgramError> {
+    if resolution_timestamp == 0 {
+        return Ok(());
+    }
+    if resolution_timestamp <= now {
+        msg!("EVENT-MATCHER: resolution_timestamp {} is not after now {}", resolution_timestamp, now);
+        return Err(EventMatcherError::InvalidResolutionTime.into());
+    }
+    if resolution_timestamp.saturating_sub(now) > MAX_RESOLUTION_HORIZON_SECS {
+        msg!(
+            "EVENT-MATCHER: resolution_timestamp {} is more than {}s ahead of now {}",
+            resolution_timestamp,
+            MAX_RESOLUTION_HORIZON_SECS,
+            now
+        );
+        return Err(EventMatcherError::InvalidResolutionTime.into());
+    }
+    Ok(())
+}
+
 /// Tag 0x00: Execute match -- probability-based pricing with edge spread
 /// Accounts:
 ///   [0] LP PDA (signer)
 ///   [1] Matcher context account (writable)
+/// Data layout:
+///   [0]    tag (0x00)
+///   [1..9] trade_size_abs (u64 LE, optional: added to total volume)
 pub fn process_match(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -118,7 +367,12 @@ pub fn process_match(
     let ctx_account = &accounts[1];
 
     // Verify LP PDA signature + context magic + PDA match
+    verify_ctx_owner(ctx_account, program_id, "EVENT-MATCHER")?;
     verify_lp_pda_common(lp_pda, ctx_account, EVENT_MATCHER_MAGIC, "EVENT-MATCHER")?;
+    check_ctx_version(&ctx_account.try_borrow_data()?)?;
+
+    // Reentrancy guard: held until this match completes
+    enter_match_guard(&mut ctx_account.try_borrow_mut_data()?)?;
 
     let ctx_data = ctx_account.try_borrow_data()?;
 
@@ -128,20 +382,18 @@ pub fn process_match(
         return Err(EventMatcherError::MarketResolved.into());
     }
 
-    let base_spread = u32::from_le_bytes(
-        ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    let base_spread = u16::from_le_bytes(
+        ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 2].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let edge_spread = u32::from_le_bytes(
-        ctx_data[EDGE_SPREAD_OFFSET..EDGE_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    let edge_spread = u16::from_le_bytes(
+        ctx_data[EDGE_SPREAD_OFFSET..EDGE_SPREAD_OFFSET + 2].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let max_spread = u32::from_le_bytes(
-        ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    let max_spread = u16::from_le_bytes(
+        ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 2].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let probability_e6 = u64::from_le_bytes(
-        ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
-    );
-    let signal_adj = u64::from_le_bytes(
-        ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8]
+    let probability_e6 = read_probability(&ctx_data)?;
+    let signal_adj = u16::from_le_bytes(
+        ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 2]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
@@ -152,30 +404,113 @@ pub fn process_match(
         return Err(EventMatcherError::ProbabilityNotSet.into());
     }
 
-    // Check oracle staleness (reject if > 200 slots old)
-    let last_update = u64::from_le_bytes(
-        ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
-    );
+    // Check oracle staleness (reject if > EVENT_MAX_STALENESS_SLOTS old). Inside
+    // the freeze window syncs are refused and the probability is held on
+    // purpose, so it can't go stale there: trading continues on it until resolution.
     let clock = Clock::get()?;
-    if clock.slot.saturating_sub(last_update) > 200 {
-        msg!("EVENT-MATCHER: Oracle stale -- last update slot {}, current {}", last_update, clock.slot);
-        return Err(EventMatcherError::OracleStale.into());
+    if !in_sync_freeze(&ctx_data, clock.unix_timestamp)? {
+        check_staleness(&ctx_data, clock.slot)?;
+    }
+
+    // Price against the TWAP when configured, damping single-slot spikes
+    let probability_e6 = twap_probability(&ctx_data, probability_e6, clock.slot)?;
+
+    let (total_spread, edge_factor) =
+        edge_adjusted_spread(probability_e6, base_spread, edge_spread, max_spread, signal_adj as u64)?;
+
+    // Mark price = probability * 1e6 (already in e6 format)
+    // Exec price = mark * (1 + spread/10000), rounded up so a small spread on a
+    // low probability isn't floored away, and never above 100%. Priced once at
+    // MARK_SCALE; the e6 price is derived from it so the two slots agree.
+    let exec_price_e9 = compute_exec_price_e9(probability_e6, total_spread)?;
+    let exec_price = exec_price_e6(exec_price_e9);
+
+    drop(ctx_data);
+
+    // Write execution price to return buffer, with a status byte so a reader
+    // can tell a written price from an unset buffer
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    write_exec_price(&mut ctx_data, exec_price);
+    ctx_data[MATCH_STATUS_RETURN_OFFSET] = MATCH_STATUS_OK;
+    ctx_data[EXEC_PRICE_E9_RETURN_OFFSET..EXEC_PRICE_E9_RETURN_OFFSET + 8]
+        .copy_from_slice(&exec_price_e9.to_le_bytes());
+    bump_stats(&mut ctx_data, TOTAL_TRADES_OFFSET, TOTAL_VOLUME_OFFSET, 1, parse_trade_size(data)? as u128);
+
+    // Release the reentrancy guard
+    ctx_data[MATCH_GUARD_OFFSET] = 0;
+
+    matcher_log!(
+        "MATCH: price={} spread={} probability={} edge_factor={}",
+        exec_price,
+        total_spread,
+        probability_e6,
+        edge_factor
+    );
+
+    Ok(())
+}
+
+/// Every instruction past Init rejects a context written under any other
+/// layout version: a v1 context holds these fields at other offsets and
+/// widths, so reading or writing it here would corrupt it
+fn check_ctx_version(ctx_data: &[u8]) -> ProgramResult {
+    let version = read_version(ctx_data)?;
+    if version != CTX_VERSION {
+        msg!("EVENT-MATCHER: Context version {}, expected {}", version, CTX_VERSION);
+        return Err(EventMatcherError::UnsupportedVersion.into());
+    }
+    Ok(())
+}
+
+/// Optional trade size after the tag; 0 when omitted. A partial size is
+/// rejected rather than read as no volume.
+fn parse_trade_size(data: &[u8]) -> Result<u64, ProgramError> {
+    match data.len() {
+        0 | 1 => Ok(0),
+        9.. => Ok(u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)),
+        len => {
+            msg!("EVENT-MATCHER: Truncated trade size ({} of 8 bytes)", len - 1);
+            Err(ProgramError::InvalidInstructionData)
+        }
     }
+}
+
+/// Take the reentrancy guard for the duration of a match. A nested match on
+/// the same context finds it held and is rejected before touching any stats.
+fn enter_match_guard(ctx_data: &mut [u8]) -> ProgramResult {
+    if ctx_data[MATCH_GUARD_OFFSET] != 0 {
+        msg!("EVENT-MATCHER: Re-entrant match rejected");
+        return Err(EventMatcherError::MatchReentered.into());
+    }
+    ctx_data[MATCH_GUARD_OFFSET] = 1;
+    Ok(())
+}
 
+/// Total spread for a probability: `(min(base + edge * edge_factor + signal, max), edge_factor)`.
+/// A probability past 100% only comes from a corrupt context and fails with
+/// ArithmeticOverflow rather than pricing off a wrapped `1 - p`.
+fn edge_adjusted_spread(
+    probability_e6: u64,
+    base_spread: u16,
+    edge_spread: u16,
+    max_spread: u16,
+    signal_adj: u64,
+) -> Result<(u64, u128), ProgramError> {
     // Edge spread calculation:
     // Edge factor = 1 / (p * (1-p) * 4)
     // At 50%: factor = 1.0 (no extra spread)
     // At 10%: factor ~2.78 (wider spread)
     // At 1%:  factor ~25.3 (much wider spread)
     let p = probability_e6 as u128;
-    let one_minus_p = MAX_PROBABILITY as u128 - p;
+    let one_minus_p = (MAX_PROBABILITY as u128)
+        .checked_sub(p)
+        .ok_or(EventMatcherError::ArithmeticOverflow)?;
 
     // p * (1-p) * 4 / 1e12 gives us the denominator scaled appropriately
     let edge_denominator = p
         .checked_mul(one_minus_p)
-        .unwrap_or(0)
-        .checked_mul(4)
-        .unwrap_or(0)
+        .and_then(|v| v.checked_mul(4))
+        .ok_or(EventMatcherError::ArithmeticOverflow)?
         / 1_000_000_000_000u128;
 
     let edge_factor = if edge_denominator > 0 {
@@ -186,49 +521,60 @@ pub fn process_match(
 
     let adjusted_edge = (edge_spread as u128)
         .checked_mul(edge_factor)
-        .unwrap_or(0)
+        .ok_or(EventMatcherError::ArithmeticOverflow)?
         / 1_000_000u128;
+    let adjusted_edge = u64::try_from(adjusted_edge).map_err(|_| EventMatcherError::ArithmeticOverflow)?;
 
-    // Total spread = base + edge_adjustment + signal_adjustment
+    // Total spread = base + edge_adjustment + signal_adjustment; saturating is
+    // safe here since the sum is capped at max_spread anyway
     let total_spread = std::cmp::min(
-        (base_spread as u64).saturating_add(adjusted_edge as u64).saturating_add(signal_adj),
+        (base_spread as u64).saturating_add(adjusted_edge).saturating_add(signal_adj),
         max_spread as u64,
     );
 
-    // Mark price = probability * 1e6 (already in e6 format)
-    // Exec price = mark * (1 + spread/10000)
-    let spread_mult = 10_000u64.saturating_add(total_spread);
-    let exec_price = ((probability_e6 as u128)
-        .checked_mul(spread_mult as u128)
-        .ok_or(EventMatcherError::ArithmeticOverflow)?
-        / 10_000u128) as u64;
-
-    drop(ctx_data);
+    Ok((total_spread, edge_factor))
+}
 
-    // Write execution price to return buffer
-    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    write_exec_price(&mut ctx_data, exec_price);
+/// `price * (10000 + spread_bps) / 10000`, optionally rounding the division up.
+/// Probabilities are small e6 integers, so flooring can erase a sub-unit spread
+/// entirely. Result is unclamped; `compute_exec_price_e9` applies the 100% cap.
+fn compute_exec_price_round(price: u64, spread_bps: u64, round_up: bool) -> Result<u128, ProgramError> {
+    let scaled = (price as u128)
+        .checked_mul(10_000u128 + spread_bps as u128)
+        .ok_or(EventMatcherError::ArithmeticOverflow)?;
+    if round_up {
+        Ok(scaled.div_ceil(10_000))
+    } else {
+        Ok(scaled / 10_000)
+    }
+}
 
-    msg!(
-        "MATCH: price={} spread={} probability={} edge_factor={}",
-        exec_price,
-        total_spread,
-        probability_e6,
-        edge_factor
-    );
+/// The exec price at MARK_SCALE precision, rounded up and never above 100%
+fn compute_exec_price_e9(price_e6: u64, spread_bps: u64) -> Result<u64, ProgramError> {
+    let price_e9 = price_e6.checked_mul(MARK_SCALE).ok_or(EventMatcherError::ArithmeticOverflow)?;
+    let exec_e9 = compute_exec_price_round(price_e9, spread_bps, true)?;
+    Ok(std::cmp::min(exec_e9, (MAX_PROBABILITY * MARK_SCALE) as u128) as u64)
+}
 
-    Ok(())
+/// The e6 exec price Match writes: the e9 price rounded up. A binary outcome
+/// pays at most 1.0, and the e9 clamp keeps this at or below MAX_PROBABILITY.
+fn exec_price_e6(exec_price_e9: u64) -> u64 {
+    exec_price_e9.div_ceil(MARK_SCALE)
 }
 
 /// Tag 0x03: Sync probability from oracle
 /// Accounts:
 ///   [0] Matcher context account (writable)
 ///   [1] Event oracle account (read -- must match stored oracle)
+///   [2] LP PDA (signer, only with the move override)
 /// Data:
 ///   [0]    tag (0x03)
-///   [1..9] new_probability_e6 (u64 LE, 0-1_000_000)
+///   [1..9] new_probability_e6 (u64 LE, 0-1_000_000; clamped away from the tails by min_probability)
 ///   [9..17] signal_severity (u64 LE, 0-3)
-///   [17..25] signal_adjusted_spread (u64 LE)
+///   [17..25] signal_adjusted_spread (u64 LE, must fit u16)
+///   [25..33] update_slot (u64 LE): the slot the probability was read at; must be
+///            after the stored last_update_slot and not past the current slot
+///   [33]   move_override (u8, optional: 1 = LP-signed, skips the max_prob_move limit)
 pub fn process_probability_sync(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -237,7 +583,7 @@ pub fn process_probability_sync(
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 25 {
+    if data.len() < 33 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
@@ -254,6 +600,7 @@ pub fn process_probability_sync(
         if !verify_magic(&ctx_data) {
             return Err(ProgramError::UninitializedAccount);
         }
+        check_ctx_version(&ctx_data)?;
 
         // Check market not resolved
         if ctx_data[IS_RESOLVED_OFFSET] == 1 {
@@ -268,6 +615,12 @@ pub fn process_probability_sync(
         }
     }
 
+    let move_override = data.get(33) == Some(&1);
+    if move_override {
+        let lp_pda = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        verify_lp_pda_common(lp_pda, ctx_account, EVENT_MATCHER_MAGIC, "EVENT-MATCHER")?;
+    }
+
     let new_probability = u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     if new_probability > MAX_PROBABILITY {
         return Err(EventMatcherError::InvalidProbability.into());
@@ -279,27 +632,39 @@ pub fn process_probability_sync(
     }
 
     let signal_spread = u64::from_le_bytes(data[17..25].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let signal_spread = u16::try_from(signal_spread).map_err(|_| {
+        msg!("EVENT-MATCHER: Signal spread {} exceeds {}", signal_spread, u16::MAX);
+        ProgramError::InvalidInstructionData
+    })?;
+    let update_slot = u64::from_le_bytes(data[25..33].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     let clock = Clock::get()?;
 
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    let old_probability = u64::from_le_bytes(
-        ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 8]
+    let old_probability = read_probability(&ctx_data)?;
+    let last_update = u64::from_le_bytes(
+        ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    check_sync_slot(last_update, update_slot, clock.slot)?;
+    check_sync_freeze(&ctx_data, clock.unix_timestamp)?;
+    let min_probability = u32::from_le_bytes(
+        ctx_data[MIN_PROBABILITY_OFFSET..MIN_PROBABILITY_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let new_probability = clamp_probability(new_probability, min_probability);
+    check_probability_move(&ctx_data, old_probability, new_probability, move_override)?;
 
-    ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 8]
-        .copy_from_slice(&new_probability.to_le_bytes());
-    ctx_data[PROBABILITY_MARK_OFFSET..PROBABILITY_MARK_OFFSET + 8]
-        .copy_from_slice(&new_probability.to_le_bytes());
+    write_probability(&mut ctx_data, new_probability);
     ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
-        .copy_from_slice(&clock.slot.to_le_bytes());
-    ctx_data[SIGNAL_SEVERITY_OFFSET..SIGNAL_SEVERITY_OFFSET + 8]
-        .copy_from_slice(&signal_severity.to_le_bytes());
-    ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8]
+        .copy_from_slice(&update_slot.to_le_bytes());
+    ctx_data[SIGNAL_SEVERITY_OFFSET] = signal_severity as u8;
+    ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 2]
         .copy_from_slice(&signal_spread.to_le_bytes());
+    push_probability_sample(&mut ctx_data, new_probability, update_slot);
 
-    msg!(
+    matcher_log!(
         "ORACLE_SYNC: old_prob={} new_prob={} signal={}",
         old_probability,
         new_probability,
@@ -309,15 +674,186 @@ pub fn process_probability_sync(
     Ok(())
 }
 
-/// Tag 0x04: Resolve event -- sets final probability to 0 or 1_000_000
+/// A sync must carry a later reading slot than the stored one, so a replay is
+/// rejected in whatever slot it lands, and no slot from the future. Init
+/// stamps its own slot, so the first sync reads no earlier than the next one.
+fn check_sync_slot(last_update_slot: u64, update_slot: u64, slot: u64) -> ProgramResult {
+    if update_slot <= last_update_slot {
+        msg!("EVENT-MATCHER: Stale probability sync for slot {} (last update for {})", update_slot, last_update_slot);
+        return Err(EventMatcherError::StaleUpdate.into());
+    }
+    if update_slot > slot {
+        msg!("EVENT-MATCHER: Probability sync for slot {} is past the current slot {}", update_slot, slot);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+/// Once the event is within `freeze_secs_before_resolution` of its resolution
+/// time the probability is left where it stands, so a last-minute print can't
+/// move the mark positions are about to settle against.
+fn check_sync_freeze(ctx_data: &[u8], now: i64) -> ProgramResult {
+    if in_sync_freeze(ctx_data, now)? {
+        msg!("EVENT-MATCHER: Probability sync frozen at {} ahead of resolution", now);
+        return Err(EventMatcherError::SyncFrozen.into());
+    }
+    Ok(())
+}
+
+/// Whether `now` falls in the freeze window before resolution_timestamp. The
+/// window is in seconds, not slots, because the deadline it counts down to is
+/// a unix timestamp and slot times drift. No expiry, no freeze.
+fn in_sync_freeze(ctx_data: &[u8], now: i64) -> Result<bool, ProgramError> {
+    let resolution_ts = i64::from_le_bytes(
+        ctx_data[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let freeze_secs = u32::from_le_bytes(
+        ctx_data[FREEZE_SECS_BEFORE_RESOLUTION_OFFSET..FREEZE_SECS_BEFORE_RESOLUTION_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if resolution_ts == 0 || freeze_secs == 0 {
+        return Ok(false);
+    }
+    Ok(now >= resolution_ts.saturating_sub(freeze_secs as i64))
+}
+
+/// Circuit breaker against a single sync dragging the mark across the book
+/// (10% -> 90%) ahead of a large fill. The first sync after init moves off the
+/// seeded probability freely; later ones need the LP's co-signature to exceed
+/// the limit.
+fn check_probability_move(
+    ctx_data: &[u8],
+    old_probability: u64,
+    new_probability: u64,
+    lp_override: bool,
+) -> ProgramResult {
+    let max_move_bps = u16::from_le_bytes(
+        ctx_data[MAX_PROB_MOVE_OFFSET..MAX_PROB_MOVE_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    // Init seeds the ring with one sample, so a single sample means no sync yet
+    if max_move_bps == 0 || lp_override || ctx_data[PROB_RING_LEN_OFFSET] <= 1 {
+        return Ok(());
+    }
+    let max_move = max_move_bps as u64 * PROBABILITY_E6_PER_BPS;
+    let moved = old_probability.abs_diff(new_probability);
+    if moved > max_move {
+        msg!(
+            "EVENT-MATCHER: Probability move {} -> {} exceeds max {}",
+            old_probability,
+            new_probability,
+            max_move
+        );
+        return Err(EventMatcherError::ProbabilityMoveTooLarge.into());
+    }
+    Ok(())
+}
+
+/// Keep an unresolved market out of the degenerate 0% / 100% tails.
+/// Only syncs are clamped; resolution sets the extremes on purpose.
+fn clamp_probability(probability_e6: u64, min_probability: u32) -> u64 {
+    let min = min_probability as u64;
+    probability_e6.clamp(min, MAX_PROBABILITY.saturating_sub(min))
+}
+
+/// Record a probability in the TWAP ring, overwriting the oldest sample once full.
+/// Probabilities are <= MAX_PROBABILITY so u32 holds them; only the slot's low
+/// 32 bits are kept since ages are taken with wrapping subtraction.
+fn push_probability_sample(ctx_data: &mut [u8], probability_e6: u64, slot: u64) {
+    let head = ctx_data[PROB_RING_HEAD_OFFSET] as usize % PROB_RING_CAPACITY;
+    let at = PROB_RING_OFFSET + head * PROB_SAMPLE_SIZE;
+    ctx_data[at..at + 4].copy_from_slice(&(probability_e6 as u32).to_le_bytes());
+    ctx_data[at + 4..at + 8].copy_from_slice(&(slot as u32).to_le_bytes());
+    ctx_data[PROB_RING_HEAD_OFFSET] = ((head + 1) % PROB_RING_CAPACITY) as u8;
+    let len = ctx_data[PROB_RING_LEN_OFFSET] as usize;
+    ctx_data[PROB_RING_LEN_OFFSET] = (len + 1).min(PROB_RING_CAPACITY) as u8;
+}
+
+/// Time-weighted probability over the last `twap_lookback_slots`. Each sample
+/// holds until the next one, the newest until `slot`. A sample older than the
+/// window covers the window's start, so when every sample predates the window
+/// the result is the newest value. Falls back to `current` when TWAP is off or
+/// no time has elapsed (cold start within the sync slot).
+fn twap_probability(ctx_data: &[u8], current: u64, slot: u64) -> Result<u64, ProgramError> {
+    let lookback = u16::from_le_bytes(
+        ctx_data[TWAP_LOOKBACK_SLOTS_OFFSET..TWAP_LOOKBACK_SLOTS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as u64;
+    if lookback == 0 {
+        return Ok(current);
+    }
+    let head = ctx_data[PROB_RING_HEAD_OFFSET] as usize % PROB_RING_CAPACITY;
+    let len = (ctx_data[PROB_RING_LEN_OFFSET] as usize).min(PROB_RING_CAPACITY);
+
+    // Walk newest -> oldest, weighting each sample by the slots it was live
+    let mut weighted = 0u128;
+    let mut covered = 0u64;
+    for i in 0..len {
+        let idx = (head + PROB_RING_CAPACITY - 1 - i) % PROB_RING_CAPACITY;
+        let at = PROB_RING_OFFSET + idx * PROB_SAMPLE_SIZE;
+        let probability = u32::from_le_bytes(
+            ctx_data[at..at + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let sample_slot = u32::from_le_bytes(
+            ctx_data[at + 4..at + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let age = ((slot as u32).wrapping_sub(sample_slot) as u64).min(lookback);
+        if age > covered {
+            weighted += probability as u128 * (age - covered) as u128;
+            covered = age;
+        }
+        if covered >= lookback {
+            break;
+        }
+    }
+    if covered == 0 {
+        return Ok(current);
+    }
+    Ok((weighted / covered as u128) as u64)
+}
+
+/// Final settlement probability for a resolved outcome (0=NO, 1=YES)
+fn resolution_probability(outcome: u8) -> u64 {
+    if outcome == 1 {
+        MAX_PROBABILITY // YES -> 100%
+    } else {
+        0u64 // NO -> 0%
+    }
+}
+
+/// Settlement probability for a Resolve outcome: 0 or 1_000_000 for NO and
+/// YES, the void probability Init stored for VOID
+fn outcome_probability(ctx_data: &[u8], outcome: u8) -> Result<u64, ProgramError> {
+    match outcome {
+        0 | 1 => Ok(resolution_probability(outcome)),
+        OUTCOME_VOID => Ok(u32::from_le_bytes(
+            ctx_data[VOID_PROBABILITY_OFFSET..VOID_PROBABILITY_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        ) as u64),
+        _ => {
+            msg!("EVENT-MATCHER: Invalid outcome: {} (must be 0, 1 or 2)", outcome);
+            Err(EventMatcherError::InvalidOutcome.into())
+        }
+    }
+}
+
+/// Tag 0x04: Resolve event -- sets final probability to 0 or 1_000_000,
+/// or voids the market at the void probability stored at init
 /// Accounts:
 ///   [0] Matcher context account (writable)
-///   [1] Event oracle account (signer -- must be authorized oracle)
+///   [1] Resolver (signer -- the resolver authority, or the event oracle when none is stored)
+///   [2] Authorities PDA (only when a resolver authority is stored)
 /// Data:
 ///   [0] tag (0x04)
-///   [1] outcome (u8: 0=NO -> prob=0, 1=YES -> prob=1_000_000)
+///   [1] outcome (u8: 0=NO -> prob=0, 1=YES -> prob=1_000_000, 2=VOID -> void probability)
 pub fn process_resolve(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
@@ -329,107 +865,354 @@ pub fn process_resolve(
     }
 
     let ctx_account = &accounts[0];
-    let oracle = &accounts[1];
-
-    // Oracle must be signer
-    if !oracle.is_signer {
-        msg!("EVENT-MATCHER: Oracle must be signer for resolution");
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    // Verify context + oracle
-    {
-        let ctx_data = ctx_account.try_borrow_data()?;
-        if !verify_magic(&ctx_data) {
-            return Err(ProgramError::UninitializedAccount);
-        }
-
-        if ctx_data[IS_RESOLVED_OFFSET] == 1 {
-            msg!("EVENT-MATCHER: Already resolved");
-            return Err(EventMatcherError::MarketResolved.into());
-        }
-
-        let stored_oracle = read_event_oracle(&ctx_data)?;
-        if *oracle.key != stored_oracle {
-            msg!("EVENT-MATCHER: Oracle mismatch");
-            return Err(EventMatcherError::OracleMismatch.into());
-        }
-    }
+    verify_resolver(program_id, accounts)?;
 
     let outcome = data[1];
-    if outcome > 1 {
-        msg!("EVENT-MATCHER: Invalid outcome: {} (must be 0 or 1)", outcome);
-        return Err(EventMatcherError::InvalidOutcome.into());
-    }
-
-    let final_probability = if outcome == 1 {
-        MAX_PROBABILITY // YES -> 100%
-    } else {
-        0u64 // NO -> 0%
-    };
-
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    ctx_data[IS_RESOLVED_OFFSET] = 1;
-    ctx_data[RESOLUTION_OUTCOME_OFFSET] = outcome;
-    ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 8]
-        .copy_from_slice(&final_probability.to_le_bytes());
-    ctx_data[PROBABILITY_MARK_OFFSET..PROBABILITY_MARK_OFFSET + 8]
-        .copy_from_slice(&final_probability.to_le_bytes());
+    let final_probability = outcome_probability(&ctx_data, outcome)?;
+    write_resolution(&mut ctx_data, outcome, final_probability, Clock::get()?.slot)?;
 
-    msg!(
+    matcher_log!(
         "RESOLVE: outcome={} final_price={}",
-        if outcome == 1 { "YES" } else { "NO" },
+        match outcome {
+            1 => "YES",
+            OUTCOME_VOID => "VOID",
+            _ => "NO",
+        },
         final_probability
     );
 
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::state::*;
-
-    /// Replicates the edge spread calculation from process_match, purely arithmetic.
-    /// Returns (exec_price, total_spread, edge_factor).
-    fn compute_exec_price_edge(
-        probability_e6: u64,
-        base_spread: u32,
-        edge_spread: u32,
-        max_spread: u32,
-        signal_adj: u64,
-    ) -> (u64, u64, u128) {
-        let p = probability_e6 as u128;
-        let one_minus_p = MAX_PROBABILITY as u128 - p;
-
-        let edge_denominator = p
-            .checked_mul(one_minus_p)
-            .unwrap_or(0)
-            .checked_mul(4)
-            .unwrap_or(0)
-            / 1_000_000_000_000u128;
-
-        let edge_factor = if edge_denominator > 0 {
-            std::cmp::min(1_000_000u128 / edge_denominator, 10_000_000u128)
-        } else {
-            10_000_000u128
-        };
-
-        let adjusted_edge = (edge_spread as u128)
-            .checked_mul(edge_factor)
-            .unwrap_or(0)
-            / 1_000_000u128;
-
-        let total_spread = std::cmp::min(
-            (base_spread as u64)
-                .saturating_add(adjusted_edge as u64)
-                .saturating_add(signal_adj),
-            max_spread as u64,
-        );
+/// Mark a context resolved at `final_probability`. The live probability takes
+/// the settlement value at once; the mark does too unless a settlement ramp is
+/// configured, in which case it stays where it stood and Advance Settlement
+/// walks it over from `slot`.
+fn write_resolution(ctx_data: &mut [u8], outcome: u8, final_probability: u64, slot: u64) -> ProgramResult {
+    ctx_data[IS_RESOLVED_OFFSET] = 1;
+    ctx_data[RESOLUTION_OUTCOME_OFFSET] = outcome;
+    let ramp_slots = u16::from_le_bytes(
+        ctx_data[SETTLEMENT_RAMP_SLOTS_OFFSET..SETTLEMENT_RAMP_SLOTS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if ramp_slots == 0 {
+        write_probability(ctx_data, final_probability);
+        return Ok(());
+    }
+    let start_mark: [u8; 4] = ctx_data[PROBABILITY_MARK_OFFSET..PROBABILITY_MARK_OFFSET + 4]
+        .try_into()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    ctx_data[RAMP_START_MARK_OFFSET..RAMP_START_MARK_OFFSET + 4].copy_from_slice(&start_mark);
+    ctx_data[RAMP_START_SLOT_OFFSET..RAMP_START_SLOT_OFFSET + 4].copy_from_slice(&(slot as u32).to_le_bytes());
+    let settled = final_probability.min(MAX_PROBABILITY) as u32;
+    ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 4].copy_from_slice(&settled.to_le_bytes());
+    Ok(())
+}
 
-        let spread_mult = 10_000u64.saturating_add(total_spread);
-        let exec_price = ((probability_e6 as u128)
-            .checked_mul(spread_mult as u128)
-            .unwrap()
+/// Mark of a resolved context at `slot`: linear from the ramp's start mark to
+/// the settlement value over `settlement_ramp_slots`, then held there. Without
+/// a ramp it is the settlement value.
+fn settlement_mark(ctx_data: &[u8], slot: u64) -> Result<u64, ProgramError> {
+    let settled = read_probability(ctx_data)?;
+    let ramp_slots = u16::from_le_bytes(
+        ctx_data[SETTLEMENT_RAMP_SLOTS_OFFSET..SETTLEMENT_RAMP_SLOTS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as u64;
+    if ramp_slots == 0 {
+        return Ok(settled);
+    }
+    let start_mark = u32::from_le_bytes(
+        ctx_data[RAMP_START_MARK_OFFSET..RAMP_START_MARK_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as u64;
+    let start_slot = u32::from_le_bytes(
+        ctx_data[RAMP_START_SLOT_OFFSET..RAMP_START_SLOT_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let elapsed = ((slot as u32).wrapping_sub(start_slot) as u64).min(ramp_slots);
+    // Both ends are <= MAX_PROBABILITY and elapsed <= u16::MAX: no overflow
+    let moved = start_mark.abs_diff(settled) * elapsed / ramp_slots;
+    Ok(if settled >= start_mark { start_mark + moved } else { start_mark - moved })
+}
+
+/// Tag 0x06: Advance settlement -- move a resolved market's mark along its
+/// settlement ramp to the current slot. Anyone may crank it; the mark only
+/// ever follows the ramp.
+/// Accounts:
+///   [0] Matcher context account (writable)
+/// Data:
+///   [0] tag (0x06)
+pub fn process_advance_settlement(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let ctx_account = &accounts[0];
+    verify_ctx_owner(ctx_account, program_id, "EVENT-MATCHER")?;
+    if !ctx_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_ctx_version(&ctx_data)?;
+    if ctx_data[IS_RESOLVED_OFFSET] != 1 {
+        msg!("EVENT-MATCHER: Market not resolved -- nothing to settle");
+        return Err(EventMatcherError::MarketNotResolved.into());
+    }
+
+    let mark = settlement_mark(&ctx_data, Clock::get()?.slot)?;
+    ctx_data[PROBABILITY_MARK_OFFSET..PROBABILITY_MARK_OFFSET + 4].copy_from_slice(&(mark as u32).to_le_bytes());
+
+    matcher_log!("ADVANCE_SETTLEMENT: mark={} settlement={}", mark, read_probability(&ctx_data)?);
+
+    Ok(())
+}
+
+/// Resolver signer over an initialized, unresolved context -- the gate for
+/// Resolve and its simulation. The resolver is the stored resolver authority
+/// when Init set one, otherwise the event oracle.
+fn verify_resolver(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let ctx_account = &accounts[0];
+    let resolver = &accounts[1];
+    if !resolver.is_signer {
+        msg!("EVENT-MATCHER: Resolver must be signer for resolution");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_ctx_version(&ctx_data)?;
+
+    if ctx_data[IS_RESOLVED_OFFSET] == 1 {
+        msg!("EVENT-MATCHER: Already resolved");
+        return Err(EventMatcherError::MarketResolved.into());
+    }
+
+    if ctx_data[AUTHORITIES_OFFSET] & AUTHORITY_RESOLVER == 0 {
+        let stored_oracle = read_event_oracle(&ctx_data)?;
+        if *resolver.key != stored_oracle {
+            msg!("EVENT-MATCHER: Oracle mismatch");
+            return Err(EventMatcherError::OracleMismatch.into());
+        }
+        return Ok(());
+    }
+
+    let authorities = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let authorities_data = authorities.try_borrow_data()?;
+    verify_authorities(authorities, &authorities_data, ctx_account.key, program_id)?;
+    let stored_resolver = read_authority(&authorities_data, RESOLVER_AUTHORITY_OFFSET)?;
+    if *resolver.key != stored_resolver {
+        msg!("EVENT-MATCHER: Unauthorized resolver: expected {}, got {}", stored_resolver, resolver.key);
+        return Err(EventMatcherError::AuthorityMismatch.into());
+    }
+    Ok(())
+}
+
+/// Tag 0x0D: Simulate resolve -- the probability a Resolve with this data
+/// would settle at, written to return bytes 0..8. Nothing outside the return
+/// buffer changes; the match status byte is cleared so the value can't pass
+/// for a match price.
+/// Accounts:
+///   [0] Matcher context account (writable -- return buffer only)
+///   [1] Resolver (signer -- as for Resolve)
+///   [2] Authorities PDA (only when a resolver authority is stored)
+/// Data:
+///   Same as Resolve (tag 0x0D)
+pub fn process_simulate_resolve(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let ctx_account = &accounts[0];
+    verify_resolver(program_id, accounts)?;
+
+    let outcome = data[1];
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    let final_probability = outcome_probability(&ctx_data, outcome)?;
+    write_exec_price(&mut ctx_data, final_probability);
+    ctx_data[MATCH_STATUS_RETURN_OFFSET] = 0;
+    ctx_data[EXEC_PRICE_E9_RETURN_OFFSET..EXEC_PRICE_E9_RETURN_OFFSET + 8]
+        .copy_from_slice(&(final_probability * MARK_SCALE).to_le_bytes());
+
+    matcher_log!("SIMULATE_RESOLVE: outcome={} final_price={}", outcome, final_probability);
+
+    Ok(())
+}
+
+/// Tag 0x05: Emergency settle -- break-glass resolution at the configured price
+/// once the oracle has missed resolution by the emergency delay
+/// Accounts:
+///   [0] Matcher context account (writable)
+///   [1] Settlement authority (signer -- stored in the authorities PDA at init)
+///   [2] Authorities PDA
+/// Data:
+///   [0] tag (0x05)
+pub fn process_emergency_settle(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let ctx_account = &accounts[0];
+    let settlement_authority = &accounts[1];
+
+    if !settlement_authority.is_signer {
+        msg!("EVENT-MATCHER: Settlement authority must be signer for emergency settlement");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let clock = Clock::get()?;
+    let emergency_price = {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if !verify_magic(&ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        check_ctx_version(&ctx_data)?;
+
+        if ctx_data[IS_RESOLVED_OFFSET] == 1 {
+            msg!("EVENT-MATCHER: Already resolved");
+            return Err(EventMatcherError::MarketResolved.into());
+        }
+
+        // The price can be anything in [0, 1_000_000], so whether emergency
+        // settlement is on is recorded by the authority, not the price
+        if ctx_data[AUTHORITIES_OFFSET] & AUTHORITY_SETTLEMENT == 0 {
+            msg!("EVENT-MATCHER: No settlement authority configured");
+            return Err(EventMatcherError::EmergencySettleLocked.into());
+        }
+        let authorities = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let authorities_data = authorities.try_borrow_data()?;
+        verify_authorities(authorities, &authorities_data, ctx_account.key, program_id)?;
+        let stored_authority = read_authority(&authorities_data, SETTLEMENT_AUTHORITY_OFFSET)?;
+        if *settlement_authority.key != stored_authority {
+            msg!(
+                "EVENT-MATCHER: Unauthorized settlement authority: expected {}, got {}",
+                stored_authority,
+                settlement_authority.key
+            );
+            return Err(EventMatcherError::AuthorityMismatch.into());
+        }
+
+        emergency_settle_price(&ctx_data, clock.unix_timestamp)?
+    };
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    write_resolution(&mut ctx_data, OUTCOME_EMERGENCY, emergency_price, clock.slot)?;
+
+    matcher_log!("EMERGENCY_SETTLE: final_price={}", emergency_price);
+
+    Ok(())
+}
+
+/// Reject a match when the last probability sync is more than
+/// EVENT_MAX_STALENESS_SLOTS behind `slot`
+fn check_staleness(ctx_data: &[u8], slot: u64) -> Result<(), ProgramError> {
+    let last_update = u64::from_le_bytes(
+        ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if slot.saturating_sub(last_update) > EVENT_MAX_STALENESS_SLOTS {
+        msg!("EVENT-MATCHER: Oracle stale -- last update slot {}, current {}", last_update, slot);
+        return Err(EventMatcherError::OracleStale.into());
+    }
+    Ok(())
+}
+
+/// Configured emergency price, if `now` is at least the emergency delay past
+/// resolution_timestamp. A market without an expiry can't be settled this way.
+fn emergency_settle_price(ctx_data: &[u8], now: i64) -> Result<u64, ProgramError> {
+    let price = u32::from_le_bytes(
+        ctx_data[EMERGENCY_PRICE_OFFSET..EMERGENCY_PRICE_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let resolution_timestamp = i64::from_le_bytes(
+        ctx_data[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if resolution_timestamp == 0 {
+        msg!("EVENT-MATCHER: Emergency settlement needs a resolution time");
+        return Err(EventMatcherError::EmergencySettleLocked.into());
+    }
+    let delay_days = match u16::from_le_bytes(
+        ctx_data[EMERGENCY_DELAY_DAYS_OFFSET..EMERGENCY_DELAY_DAYS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) {
+        0 => DEFAULT_EMERGENCY_DELAY_DAYS,
+        days => days,
+    };
+    let unlock_at = resolution_timestamp.saturating_add(delay_days as i64 * 86_400);
+    if now < unlock_at {
+        msg!("EVENT-MATCHER: Emergency settlement locked until {} (now {})", unlock_at, now);
+        return Err(EventMatcherError::EmergencySettleLocked.into());
+    }
+    Ok(price as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_staleness, check_sync_slot, clamp_probability, compute_exec_price_e9,
+        compute_exec_price_round, exec_price_e6,
+        edge_adjusted_spread, emergency_settle_price, enter_match_guard, process_advance_settlement,
+        process_emergency_settle, process_init, process_match,
+        process_probability_sync, process_resolve, process_simulate_resolve, push_probability_sample, outcome_probability, resolution_probability, settlement_mark, twap_probability, validate_liquidity_config,
+        validate_resolution_time,
+    };
+    use crate::errors::EventMatcherError;
+    use crate::state::*;
+    use solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        instruction::Instruction,
+        program_error::ProgramError,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+
+    /// Edge spread from process_match plus a floored exec price, purely arithmetic.
+    /// Returns (exec_price, total_spread, edge_factor).
+    fn compute_exec_price_edge(
+        probability_e6: u64,
+        base_spread: u16,
+        edge_spread: u16,
+        max_spread: u16,
+        signal_adj: u64,
+    ) -> (u64, u64, u128) {
+        let (total_spread, edge_factor) =
+            edge_adjusted_spread(probability_e6, base_spread, edge_spread, max_spread, signal_adj).unwrap();
+
+        let spread_mult = 10_000u64.saturating_add(total_spread);
+        let exec_price = ((probability_e6 as u128)
+            .checked_mul(spread_mult as u128)
+            .unwrap()
             / 10_000u128) as u64;
 
         (exec_price, total_spread, edge_factor)
@@ -549,6 +1332,66 @@ mod tests {
         assert_eq!(price2, 10_000 * 10_500 / 10_000);
     }
 
+    #[test]
+    fn test_exec_price_clamped_at_high_probability() {
+        // 99% + 320 bps would quote 1_021_680 (> 100%)
+        let (_, spread, _) = compute_exec_price_edge(990_000, 20, 30, 500, 0);
+        assert_eq!(exec_price_e6(compute_exec_price_e9(990_000, spread).unwrap()), MAX_PROBABILITY);
+
+        // 99.99% with a critical signal on top still stays at 100%
+        let (_, spread, _) = compute_exec_price_edge(999_900, 20, 30, 1000, 500);
+        assert_eq!(exec_price_e6(compute_exec_price_e9(999_900, spread).unwrap()), MAX_PROBABILITY);
+    }
+
+    #[test]
+    fn test_exec_price_unclamped_at_low_probability() {
+        // 1% + 320 bps = 10_320, well inside [0, MAX_PROBABILITY]
+        let (price, spread, _) = compute_exec_price_edge(10_000, 20, 30, 500, 0);
+        let exec = exec_price_e6(compute_exec_price_e9(10_000, spread).unwrap());
+        assert_eq!((exec, price), (10_320, 10_320));
+
+        // 50% is unaffected
+        let (_, spread, _) = compute_exec_price_edge(500_000, 20, 300, 500, 50);
+        assert_eq!(exec_price_e6(compute_exec_price_e9(500_000, spread).unwrap()), 518_500);
+    }
+
+    #[test]
+    fn test_exec_price_round_up_keeps_sub_unit_spread() {
+        // 5_000 * 1.0001 = 5_000.5 exactly: floor drops the half unit, round-up keeps it
+        assert_eq!(compute_exec_price_round(5_000, 1, false).unwrap(), 5_000);
+        assert_eq!(compute_exec_price_round(5_000, 1, true).unwrap(), 5_001);
+
+        // 10 bps on a 0.005% probability: 50.05 floors back to the mark
+        assert_eq!(compute_exec_price_round(50, 10, false).unwrap(), 50);
+        assert_eq!(compute_exec_price_round(50, 10, true).unwrap(), 51);
+
+        // Exact results are identical either way
+        assert_eq!(compute_exec_price_round(10_000, 10, true).unwrap(), 10_010);
+        assert_eq!(compute_exec_price_round(10_000, 10, false).unwrap(), 10_010);
+    }
+
+    #[test]
+    fn test_exec_price_e9_keeps_fractional_bps_in_the_tail() {
+        // 3 bps on a 0.5% mark: 5_000 * 1.0003 = 5_001.5 e6
+        assert_eq!(compute_exec_price_round(5_000, 3, false).unwrap(), 5_001);
+        assert_eq!(compute_exec_price_round(5_000, 3, true).unwrap(), 5_002);
+        // e9 holds it exactly; the e6 price is its round-up
+        let e9 = compute_exec_price_e9(5_000, 3).unwrap();
+        assert_eq!(e9, 5_001_500);
+        assert_eq!(exec_price_e6(e9), 5_002);
+
+        // Clamped at 100% like the e6 price
+        assert_eq!(compute_exec_price_e9(999_000, 200).unwrap(), MAX_PROBABILITY * MARK_SCALE);
+
+        // Match returns both
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        run_match(&mut ctx).unwrap();
+        let e6 = u64::from_le_bytes(ctx[..8].try_into().unwrap());
+        let e9 = u64::from_le_bytes(ctx[EXEC_PRICE_E9_RETURN_OFFSET..EXEC_PRICE_E9_RETURN_OFFSET + 8].try_into().unwrap());
+        assert_eq!(e9.div_ceil(MARK_SCALE), e6);
+    }
+
     #[test]
     fn test_max_probability_constant() {
         assert_eq!(MAX_PROBABILITY, 1_000_000);
@@ -561,4 +1404,804 @@ mod tests {
         assert_eq!(SIGNAL_HIGH, 2);
         assert_eq!(SIGNAL_CRITICAL, 3);
     }
+
+    #[test]
+    fn test_impact_without_liquidity_rejected() {
+        assert_eq!(
+            validate_liquidity_config(10, 0),
+            Err(ProgramError::Custom(EventMatcherError::InvalidLiquidityConfig as u32))
+        );
+        assert!(validate_liquidity_config(10, 1_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_zero_impact_zero_liquidity_accepted() {
+        assert!(validate_liquidity_config(0, 0).is_ok());
+    }
+
+    #[test]
+    fn test_sync_clamps_zero_up_to_min_probability() {
+        // min_prob = 1000 (0.1%)
+        assert_eq!(clamp_probability(0, 1000), 1000);
+        assert_eq!(clamp_probability(MAX_PROBABILITY, 1000), 999_000);
+        assert_eq!(clamp_probability(500_000, 1000), 500_000);
+        // min_prob = 0 disables the clamp
+        assert_eq!(clamp_probability(0, 0), 0);
+        assert_eq!(clamp_probability(MAX_PROBABILITY, 0), MAX_PROBABILITY);
+    }
+
+    #[test]
+    fn test_resolve_bypasses_probability_clamp() {
+        assert_eq!(resolution_probability(1), MAX_PROBABILITY);
+        assert_eq!(resolution_probability(0), 0);
+    }
+
+    const NOW: i64 = 1_750_000_000;
+
+    #[test]
+    fn test_resolution_time_in_past_rejected() {
+        let err = Err(ProgramError::Custom(EventMatcherError::InvalidResolutionTime as u32));
+        assert_eq!(validate_resolution_time(NOW - 3_600, NOW), err);
+        assert_eq!(validate_resolution_time(NOW, NOW), err);
+    }
+
+    #[test]
+    fn test_resolution_time_future_and_zero_accepted() {
+        assert!(validate_resolution_time(NOW + 30 * 24 * 3_600, NOW).is_ok());
+        assert!(validate_resolution_time(0, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_resolution_time_beyond_horizon_rejected() {
+        // Milliseconds passed as seconds
+        assert_eq!(
+            validate_resolution_time((NOW + 3_600) * 1_000, NOW),
+            Err(ProgramError::Custom(EventMatcherError::InvalidResolutionTime as u32))
+        );
+        assert!(validate_resolution_time(NOW + MAX_RESOLUTION_HORIZON_SECS, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_staleness_boundary_is_inclusive() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        assert!(check_staleness(&ctx, 1_000 + EVENT_MAX_STALENESS_SLOTS).is_ok());
+        assert_eq!(
+            check_staleness(&ctx, 1_000 + EVENT_MAX_STALENESS_SLOTS + 1),
+            Err(ProgramError::Custom(EventMatcherError::OracleStale as u32))
+        );
+        assert_eq!(EVENT_MAX_STALENESS_SLOTS, 200);
+    }
+
+    fn emergency_ctx(resolution_ts: i64, price: u32, delay_days: u16) -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8].copy_from_slice(&resolution_ts.to_le_bytes());
+        ctx[EMERGENCY_PRICE_OFFSET..EMERGENCY_PRICE_OFFSET + 4].copy_from_slice(&price.to_le_bytes());
+        ctx[EMERGENCY_DELAY_DAYS_OFFSET..EMERGENCY_DELAY_DAYS_OFFSET + 2].copy_from_slice(&delay_days.to_le_bytes());
+        ctx
+    }
+
+    #[test]
+    fn test_emergency_settle_rejected_before_timeout() {
+        let locked = Err(ProgramError::Custom(EventMatcherError::EmergencySettleLocked as u32));
+        let ctx = emergency_ctx(NOW, 500_000, 7);
+        assert_eq!(emergency_settle_price(&ctx, NOW - 1), locked);
+        assert_eq!(emergency_settle_price(&ctx, NOW + 7 * 86_400 - 1), locked);
+        // Default delay when unset is 30 days
+        let ctx = emergency_ctx(NOW, 500_000, 0);
+        assert_eq!(emergency_settle_price(&ctx, NOW + 29 * 86_400), locked);
+    }
+
+    #[test]
+    fn test_emergency_settle_accepted_after_timeout() {
+        let ctx = emergency_ctx(NOW, 500_000, 7);
+        assert_eq!(emergency_settle_price(&ctx, NOW + 7 * 86_400).unwrap(), 500_000);
+        let ctx = emergency_ctx(NOW, 500_000, 0);
+        assert_eq!(emergency_settle_price(&ctx, NOW + 30 * 86_400).unwrap(), 500_000);
+    }
+
+    #[test]
+    fn test_emergency_settle_requires_configuration() {
+        let locked = Err(ProgramError::Custom(EventMatcherError::EmergencySettleLocked as u32));
+        let far_future = NOW + 10 * 365 * 86_400;
+        // A market that never expires has no deadline to miss
+        assert_eq!(emergency_settle_price(&emergency_ctx(0, 500_000, 7), far_future), locked);
+        // A zero price is a NO settlement, not a disabled one
+        assert_eq!(emergency_settle_price(&emergency_ctx(NOW, 0, 7), far_future), Ok(0));
+    }
+
+    fn twap_ctx(lookback: u16) -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[TWAP_LOOKBACK_SLOTS_OFFSET..TWAP_LOOKBACK_SLOTS_OFFSET + 2].copy_from_slice(&lookback.to_le_bytes());
+        ctx
+    }
+
+    #[test]
+    fn test_twap_over_three_samples() {
+        let mut ctx = twap_ctx(300);
+        push_probability_sample(&mut ctx, 400_000, 1_000);
+        push_probability_sample(&mut ctx, 600_000, 1_100);
+        push_probability_sample(&mut ctx, 900_000, 1_250); // single-slot spike
+        // At 1_300: 900k live 50 slots, 600k 150, 400k 100 (clipped at window start 1_000)
+        let twap = twap_probability(&ctx, 900_000, 1_300).unwrap();
+        assert_eq!(twap, (900_000 * 50 + 600_000 * 150 + 400_000 * 100) / 300);
+        assert!(twap < 900_000);
+
+        // TWAP off: instantaneous value passes through
+        let mut off = ctx.clone();
+        off[TWAP_LOOKBACK_SLOTS_OFFSET..TWAP_LOOKBACK_SLOTS_OFFSET + 2].fill(0);
+        assert_eq!(twap_probability(&off, 900_000, 1_300).unwrap(), 900_000);
+    }
+
+    #[test]
+    fn test_twap_cold_start_single_sample() {
+        let mut ctx = twap_ctx(300);
+        push_probability_sample(&mut ctx, 500_000, 1_000);
+        // Same slot as the seed: nothing elapsed, use the instantaneous value
+        assert_eq!(twap_probability(&ctx, 500_000, 1_000).unwrap(), 500_000);
+        // Later: the lone sample covers all elapsed time
+        assert_eq!(twap_probability(&ctx, 500_000, 1_040).unwrap(), 500_000);
+    }
+
+    #[test]
+    fn test_twap_all_samples_before_window() {
+        let mut ctx = twap_ctx(100);
+        push_probability_sample(&mut ctx, 300_000, 1_000);
+        push_probability_sample(&mut ctx, 700_000, 1_010);
+        // Both predate the window; the newest held throughout it
+        assert_eq!(twap_probability(&ctx, 700_000, 2_000).unwrap(), 700_000);
+    }
+
+    #[test]
+    fn test_probability_ring_wraps_at_capacity() {
+        let mut ctx = twap_ctx(1_000);
+        for i in 0..(PROB_RING_CAPACITY as u64 + 3) {
+            push_probability_sample(&mut ctx, 100_000 + i, 1_000 + i);
+        }
+        assert_eq!(ctx[PROB_RING_LEN_OFFSET] as usize, PROB_RING_CAPACITY);
+        assert_eq!(ctx[PROB_RING_HEAD_OFFSET], 3);
+        // Oldest survivor is sample #3 at slot 1_003
+        let at = PROB_RING_OFFSET + 3 * PROB_SAMPLE_SIZE;
+        assert_eq!(u32::from_le_bytes(ctx[at + 4..at + 8].try_into().unwrap()), 1_003);
+    }
+
+    /// Init reads the clock for last_update_slot; serve a fixed one off-chain
+    struct FixedClock;
+
+    impl SyscallStubs for FixedClock {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Clock) = Clock { slot: 1_000, ..Clock::default() } };
+            0
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+
+        /// Stands in for the System Program's CreateAccount: moves the
+        /// lamports and sizes the new account
+        fn sol_invoke_signed(&self, ix: &Instruction, infos: &[AccountInfo], _seeds: &[&[&[u8]]]) -> ProgramResult {
+            let info = |n: usize| infos.iter().find(|a| *a.key == ix.accounts[n].pubkey).unwrap();
+            assert_eq!(ix.data[..4], 0u32.to_le_bytes(), "only CreateAccount is stubbed");
+            let lamports = u64::from_le_bytes(ix.data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(ix.data[12..20].try_into().unwrap()) as usize;
+            **info(0).try_borrow_mut_lamports()? -= lamports;
+            **info(1).try_borrow_mut_lamports()? += lamports;
+            *info(1).data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+            Ok(())
+        }
+    }
+
+    /// Init payload with every optional trailing group present
+    fn full_init_data() -> Vec<u8> {
+        let mut data = vec![0x02, 0];
+        data.extend_from_slice(&20u32.to_le_bytes()); // base spread
+        data.extend_from_slice(&50u32.to_le_bytes()); // edge spread
+        data.extend_from_slice(&200u32.to_le_bytes()); // max spread
+        data.extend_from_slice(&10u32.to_le_bytes()); // impact k
+        data.extend_from_slice(&500_000u64.to_le_bytes()); // initial probability
+        data.extend_from_slice(&0i64.to_le_bytes()); // resolution timestamp
+        data.extend_from_slice(&10_000_000_000u128.to_le_bytes()); // liquidity
+        data.extend_from_slice(&1_000_000_000u128.to_le_bytes()); // max fill
+        data.extend_from_slice(&[5u8; 32]); // event oracle
+        data.extend_from_slice(&10_000u32.to_le_bytes()); // min probability
+        data.extend_from_slice(&300u16.to_le_bytes()); // twap lookback
+        data.extend_from_slice(&500_000u32.to_le_bytes()); // emergency price
+        data.extend_from_slice(&7u16.to_le_bytes()); // emergency delay days
+        data.extend_from_slice(&1_000u16.to_le_bytes()); // max prob move (10%)
+        data.extend_from_slice(&3_600u32.to_le_bytes()); // freeze secs before resolution (1h)
+        data.extend_from_slice(&[8u8; 32]); // settlement authority
+        data.extend_from_slice(&[0u8; 32]); // resolver authority: the event oracle
+        data.extend_from_slice(&0u16.to_le_bytes()); // settlement ramp: snap
+        data.extend_from_slice(&400_000u32.to_le_bytes()); // void probability
+        data
+    }
+
+    fn authorities_key() -> Pubkey {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        Pubkey::find_program_address(&[AUTHORITIES_SEED, ctx_key.as_ref()], &program_id).0
+    }
+
+    fn init_over(fill: u8, data: &[u8]) -> Vec<u8> {
+        try_init_over(fill, data).unwrap()
+    }
+
+    /// Run process_init over a context buffer pre-filled with `fill`
+    fn try_init_over(fill: u8, data: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        try_init_with_authorities(fill, data).map(|(ctx, _)| ctx)
+    }
+
+    /// Run process_init with the accounts an authorities PDA needs. Returns the
+    /// context and the PDA's data, empty when Init created none.
+    fn try_init_with_authorities(fill: u8, data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let payer_key = Pubkey::new_from_array([4u8; 32]);
+        let authorities_key = authorities_key();
+        let system_key = Pubkey::default();
+        let (mut lp_lamports, mut ctx_lamports, mut payer_lamports) = (0u64, 0u64, 1_000_000_000u64);
+        let (mut authorities_lamports, mut system_lamports) = (0u64, 0u64);
+        let (mut lp_data, mut ctx_data) = (Vec::new(), vec![fill; CTX_SIZE]);
+        let (mut payer_data, mut authorities_data, mut system_data) = (Vec::new(), Vec::new(), Vec::new());
+        let authorities = {
+            let accounts = [
+                AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut ctx_data, &program_id, false, 0),
+                AccountInfo::new(&payer_key, true, true, &mut payer_lamports, &mut payer_data, &system_key, false, 0),
+                AccountInfo::new(&authorities_key, false, true, &mut authorities_lamports, &mut authorities_data, &program_id, false, 0),
+                AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+            ];
+            process_init(&program_id, &accounts, data)?;
+            let created = accounts[3].data.borrow().to_vec();
+            created
+        };
+        Ok((ctx_data, authorities))
+    }
+
+    #[test]
+    fn test_init_overwrites_every_byte() {
+        // Any byte that differs between a zero- and 0xFF-prefilled account is
+        // one the init path never wrote -- stale data leaking into a new market
+        set_syscall_stubs(Box::new(FixedClock));
+        let data = full_init_data();
+        for len in [98, 102, 104, 110, 112, 116, 148, 180, 182, 186] {
+            let (zeros, ones) = (init_over(0x00, &data[..len]), init_over(0xFF, &data[..len]));
+            let leaked: Vec<usize> = (0..CTX_SIZE).filter(|&i| zeros[i] != ones[i]).collect();
+            assert!(leaked.is_empty(), "len {}: init left bytes {:?} unwritten", len, leaked);
+        }
+    }
+
+    #[test]
+    fn test_init_rejects_unknown_mode() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut data = full_init_data();
+        for mode in 0..=MAX_MODE {
+            data[1] = mode;
+            assert_eq!(init_over(0, &data)[MODE_OFFSET], mode);
+        }
+        // An unknown mode would otherwise be stored and silently priced as mode 0
+        data[1] = 200;
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(EventMatcherError::InvalidMode as u32))
+        );
+    }
+
+    #[test]
+    fn test_init_rejects_values_too_wide_for_the_context() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // Spreads and impact_k narrow to u16, liquidity and max fill to u64
+        for (at, width) in [(2, 4), (6, 4), (10, 4), (14, 4), (34, 16), (50, 16)] {
+            let mut data = full_init_data();
+            data[at..at + width].fill(0);
+            data[at + width / 2] = 1;
+            assert_eq!(try_init_over(0, &data), Err(ProgramError::InvalidInstructionData), "offset {}", at);
+        }
+    }
+
+    #[test]
+    fn test_void_resolution_settles_at_void_probability() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // Not configured: par
+        let ctx = init_over(0, &full_init_data()[..182]);
+        assert_eq!(outcome_probability(&ctx, OUTCOME_VOID).unwrap(), DEFAULT_VOID_PROBABILITY);
+        // Emergency is recorded by Emergency Settle, never requested through Resolve
+        assert_eq!(
+            outcome_probability(&ctx, OUTCOME_EMERGENCY),
+            Err(ProgramError::Custom(EventMatcherError::InvalidOutcome as u32))
+        );
+        let mut data = full_init_data();
+        data[182..186].copy_from_slice(&1_000_001u32.to_le_bytes());
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(EventMatcherError::InvalidProbability as u32))
+        );
+
+        // Configured at 40%; the outcome is stored as given
+        let mut ctx = init_over(0, &full_init_data());
+        run_resolve(&mut ctx, &mut Vec::new(), [5u8; 32], &[0x04, OUTCOME_VOID]).unwrap();
+        assert_eq!(ctx[IS_RESOLVED_OFFSET], 1);
+        assert_eq!(ctx[RESOLUTION_OUTCOME_OFFSET], 2);
+        assert_eq!(mark(&ctx), 400_000);
+
+        // Voided like any resolution: no further trading
+        assert_eq!(
+            run_match(&mut ctx),
+            Err(ProgramError::Custom(EventMatcherError::MarketResolved as u32))
+        );
+    }
+
+    #[test]
+    fn test_simulate_resolve_leaves_market_open() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        run_match(&mut ctx).unwrap();
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let oracle_key = Pubkey::new_from_array([5u8; 32]);
+        let simulate = |ctx: &mut Vec<u8>, data: &[u8]| {
+            let before = ctx.clone();
+            let (mut lamports, mut signer_lamports) = (0u64, 0u64);
+            let mut signer_data = Vec::new();
+            {
+                let accounts = [
+                    AccountInfo::new(&ctx_key, false, true, &mut lamports, ctx, &program_id, false, 0),
+                    AccountInfo::new(&oracle_key, true, false, &mut signer_lamports, &mut signer_data, &program_id, false, 0),
+                ];
+                process_simulate_resolve(&program_id, &accounts, data).unwrap();
+            }
+            // Only the return buffer moves
+            assert_eq!(ctx[64..], before[64..]);
+            assert_eq!(ctx[MATCH_STATUS_RETURN_OFFSET], 0);
+            u64::from_le_bytes(ctx[..8].try_into().unwrap())
+        };
+
+        assert_eq!(simulate(&mut ctx, &[0x0D, 1]), MAX_PROBABILITY);
+        assert_eq!(simulate(&mut ctx, &[0x0D, 0]), 0);
+        assert_eq!(simulate(&mut ctx, &[0x0D, OUTCOME_VOID]), 400_000);
+        assert_eq!(ctx[IS_RESOLVED_OFFSET], 0);
+
+        // Still tradeable afterwards
+        run_match(&mut ctx).unwrap();
+        assert_eq!(ctx[MATCH_STATUS_RETURN_OFFSET], MATCH_STATUS_OK);
+    }
+
+    /// Run EmergencySettle against `ctx` signed by `signer`, passing `authorities`
+    /// as the authorities PDA
+    fn run_emergency_settle(ctx: &mut [u8], authorities: &mut [u8], signer: [u8; 32]) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let signer_key = Pubkey::new_from_array(signer);
+        let authorities_key = authorities_key();
+        let (mut ctx_lamports, mut signer_lamports, mut authorities_lamports) = (0u64, 0u64, 0u64);
+        let mut signer_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&signer_key, true, false, &mut signer_lamports, &mut signer_data, &program_id, false, 0),
+            AccountInfo::new(&authorities_key, false, false, &mut authorities_lamports, authorities, &program_id, false, 0),
+        ];
+        process_emergency_settle(&program_id, &accounts, &[0x05])
+    }
+
+    #[test]
+    fn test_emergency_settle_needs_the_settlement_authority() {
+        // FixedClock reads unix_timestamp 0; backdate resolution by the 7 day delay
+        set_syscall_stubs(Box::new(FixedClock));
+        let locked = Err(ProgramError::Custom(EventMatcherError::EmergencySettleLocked as u32));
+        let backdate = |ctx: &mut Vec<u8>| {
+            ctx[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8]
+                .copy_from_slice(&(-7 * 86_400i64).to_le_bytes())
+        };
+        let (mut ctx, mut authorities) = try_init_with_authorities(0, &full_init_data()).unwrap();
+        assert_eq!(ctx[AUTHORITIES_OFFSET], AUTHORITY_SETTLEMENT);
+        backdate(&mut ctx);
+
+        // The event oracle alone can't break the glass
+        assert_eq!(
+            run_emergency_settle(&mut ctx.clone(), &mut authorities.clone(), [5u8; 32]),
+            Err(ProgramError::Custom(EventMatcherError::AuthorityMismatch as u32))
+        );
+        // Nor can a forged authorities account
+        let mut forged = authorities.clone();
+        forged[AUTH_BUMP_OFFSET] = forged[AUTH_BUMP_OFFSET].wrapping_add(1);
+        assert!(run_emergency_settle(&mut ctx.clone(), &mut forged, [8u8; 32]).is_err());
+
+        // A zero emergency price settles NO
+        ctx[EMERGENCY_PRICE_OFFSET..EMERGENCY_PRICE_OFFSET + 4].copy_from_slice(&0u32.to_le_bytes());
+        run_emergency_settle(&mut ctx, &mut authorities, [8u8; 32]).unwrap();
+        assert_eq!(ctx[IS_RESOLVED_OFFSET], 1);
+        assert_eq!(ctx[RESOLUTION_OUTCOME_OFFSET], OUTCOME_EMERGENCY);
+        assert_eq!(read_probability(&ctx).unwrap(), 0);
+
+        // No settlement authority at init: no PDA, and emergency settlement stays off
+        let mut data = full_init_data();
+        data[116..148].fill(0);
+        let (mut ctx, mut authorities) = try_init_with_authorities(0, &data).unwrap();
+        assert_eq!(ctx[AUTHORITIES_OFFSET], 0);
+        assert!(authorities.is_empty());
+        backdate(&mut ctx);
+        assert_eq!(run_emergency_settle(&mut ctx, &mut authorities, [8u8; 32]), locked);
+    }
+
+    /// Run Resolve against `ctx` signed by `signer`, passing `authorities` as
+    /// the authorities PDA
+    fn run_resolve(ctx: &mut [u8], authorities: &mut [u8], signer: [u8; 32], data: &[u8]) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let signer_key = Pubkey::new_from_array(signer);
+        let authorities_key = authorities_key();
+        let (mut ctx_lamports, mut signer_lamports, mut authorities_lamports) = (0u64, 0u64, 0u64);
+        let mut signer_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&signer_key, true, false, &mut signer_lamports, &mut signer_data, &program_id, false, 0),
+            AccountInfo::new(&authorities_key, false, false, &mut authorities_lamports, authorities, &program_id, false, 0),
+        ];
+        process_resolve(&program_id, &accounts, data)
+    }
+
+    #[test]
+    fn test_resolver_authority_resolves_in_place_of_the_oracle() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut data = full_init_data();
+        data[148..180].copy_from_slice(&[9u8; 32]);
+        let (ctx, mut authorities) = try_init_with_authorities(0, &data).unwrap();
+        assert_eq!(ctx[AUTHORITIES_OFFSET], AUTHORITY_SETTLEMENT | AUTHORITY_RESOLVER);
+
+        // The oracle still feeds the probability but can no longer settle
+        let mut oracle_try = ctx.clone();
+        assert_eq!(
+            run_resolve(&mut oracle_try, &mut authorities.clone(), [5u8; 32], &[0x04, 1]),
+            Err(ProgramError::Custom(EventMatcherError::AuthorityMismatch as u32))
+        );
+        assert_eq!(oracle_try[IS_RESOLVED_OFFSET], 0);
+        run_sync(&mut oracle_try, 600_000, false, false).unwrap();
+        // Leaving the authorities PDA out doesn't fall back to the oracle
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let oracle_key = Pubkey::new_from_array([5u8; 32]);
+        let (mut ctx_lamports, mut oracle_lamports) = (0u64, 0u64);
+        let mut oracle_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut oracle_try, &program_id, false, 0),
+            AccountInfo::new(&oracle_key, true, false, &mut oracle_lamports, &mut oracle_data, &program_id, false, 0),
+        ];
+        assert_eq!(process_resolve(&program_id, &accounts, &[0x04, 1]), Err(ProgramError::NotEnoughAccountKeys));
+
+        let mut ctx = ctx;
+        run_resolve(&mut ctx, &mut authorities, [9u8; 32], &[0x04, 1]).unwrap();
+        assert_eq!(ctx[IS_RESOLVED_OFFSET], 1);
+        assert_eq!(read_probability(&ctx).unwrap(), MAX_PROBABILITY);
+
+        // Default: no resolver authority, the event oracle resolves
+        let (mut ctx, mut authorities) = try_init_with_authorities(0, &full_init_data()).unwrap();
+        assert_eq!(ctx[AUTHORITIES_OFFSET] & AUTHORITY_RESOLVER, 0);
+        assert_eq!(
+            run_resolve(&mut ctx.clone(), &mut authorities.clone(), [9u8; 32], &[0x04, 1]),
+            Err(ProgramError::Custom(EventMatcherError::OracleMismatch as u32))
+        );
+        run_resolve(&mut ctx, &mut authorities, [5u8; 32], &[0x04, 0]).unwrap();
+        assert_eq!(ctx[IS_RESOLVED_OFFSET], 1);
+        assert_eq!(read_probability(&ctx).unwrap(), 0);
+    }
+
+    fn mark(ctx: &[u8]) -> u64 {
+        u32::from_le_bytes(ctx[PROBABILITY_MARK_OFFSET..PROBABILITY_MARK_OFFSET + 4].try_into().unwrap()) as u64
+    }
+
+    fn run_advance_settlement(ctx: &mut [u8]) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let mut ctx_lamports = 0u64;
+        let accounts = [AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0)];
+        process_advance_settlement(&program_id, &accounts, &[0x06])
+    }
+
+    #[test]
+    fn test_settlement_ramp_interpolates_to_the_settlement_value() {
+        // 100-slot ramp from a 60% mark; FixedClock resolves at slot 1_000
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut data = full_init_data();
+        data[180..182].copy_from_slice(&100u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        run_sync(&mut ctx, 600_000, false, false).unwrap();
+        assert_eq!(
+            run_advance_settlement(&mut ctx.clone()),
+            Err(ProgramError::Custom(EventMatcherError::MarketNotResolved as u32))
+        );
+
+        let mut yes = ctx.clone();
+        run_resolve(&mut yes, &mut Vec::new(), [5u8; 32], &[0x04, 1]).unwrap();
+        // Settled at once, but the mark hasn't moved yet
+        assert_eq!(read_probability(&yes).unwrap(), MAX_PROBABILITY);
+        assert_eq!(mark(&yes), 600_000);
+        for (slot, expected) in [(1_000, 600_000), (1_025, 700_000), (1_050, 800_000), (1_099, 996_000)] {
+            assert_eq!(settlement_mark(&yes, slot).unwrap(), expected, "slot {}", slot);
+        }
+        // Held at the settlement value once the ramp is over
+        assert_eq!(settlement_mark(&yes, 1_100).unwrap(), MAX_PROBABILITY);
+        assert_eq!(settlement_mark(&yes, 5_000).unwrap(), MAX_PROBABILITY);
+
+        // Ramping down toward NO
+        let mut no = ctx.clone();
+        run_resolve(&mut no, &mut Vec::new(), [5u8; 32], &[0x04, 0]).unwrap();
+        assert_eq!(settlement_mark(&no, 1_050).unwrap(), 300_000);
+        assert_eq!(settlement_mark(&no, 1_100).unwrap(), 0);
+
+        // The crank writes the mark for the current slot: 50 slots in after
+        // backdating the ramp's start
+        yes[RAMP_START_SLOT_OFFSET..RAMP_START_SLOT_OFFSET + 4].copy_from_slice(&950u32.to_le_bytes());
+        run_advance_settlement(&mut yes).unwrap();
+        assert_eq!(mark(&yes), 800_000);
+        assert_eq!(read_probability(&yes).unwrap(), MAX_PROBABILITY);
+
+        // No ramp: the mark snaps
+        let mut snap = init_over(0, &full_init_data());
+        run_sync(&mut snap, 600_000, false, false).unwrap();
+        run_resolve(&mut snap, &mut Vec::new(), [5u8; 32], &[0x04, 1]).unwrap();
+        assert_eq!(mark(&snap), MAX_PROBABILITY);
+        run_advance_settlement(&mut snap).unwrap();
+        assert_eq!(mark(&snap), MAX_PROBABILITY);
+    }
+
+    /// Run Match against `ctx` signed by the LP PDA `init_over` stores
+    fn run_match(ctx: &mut [u8]) -> ProgramResult {
+        run_match_data(ctx, &[0x00])
+    }
+
+    fn run_match_data(ctx: &mut [u8], data: &[u8]) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+        ];
+        process_match(&program_id, &accounts, data)
+    }
+
+    #[test]
+    fn test_match_stamps_success_status() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // Init zeroes the return region over whatever the account held: unset
+        let mut ctx = init_over(0xFF, &full_init_data());
+        assert_eq!(ctx[MATCH_STATUS_RETURN_OFFSET], 0);
+        assert_eq!(&ctx[..8], &[0u8; 8]);
+
+        run_match(&mut ctx).unwrap();
+        assert_eq!(ctx[MATCH_STATUS_RETURN_OFFSET], MATCH_STATUS_OK);
+        assert_ne!(u64::from_le_bytes(ctx[..8].try_into().unwrap()), 0);
+    }
+
+    #[test]
+    fn test_match_accumulates_trade_stats() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0xFF, &full_init_data());
+        let mut data = vec![0x00];
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        run_match_data(&mut ctx, &data).unwrap();
+        run_match_data(&mut ctx, &data).unwrap();
+        // Size omitted: counted as a trade with no volume
+        run_match(&mut ctx).unwrap();
+
+        let trades = u64::from_le_bytes(ctx[TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8].try_into().unwrap());
+        let volume = u128::from_le_bytes(ctx[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16].try_into().unwrap());
+        assert_eq!(trades, 3);
+        assert_eq!(volume, 2 * u64::MAX as u128);
+
+        // A truncated size is malformed, not zero
+        assert_eq!(run_match_data(&mut ctx, &[0x00, 1, 2, 3]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_every_instruction_rejects_other_layout_version() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let unsupported = Err(ProgramError::Custom(EventMatcherError::UnsupportedVersion as u32));
+        let (mut ctx, mut authorities) = try_init_with_authorities(0, &full_init_data()).unwrap();
+        assert_eq!(read_version(&ctx).unwrap(), CTX_VERSION);
+        ctx[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&1u32.to_le_bytes());
+        let v1 = ctx.clone();
+
+        assert_eq!(run_match(&mut ctx), unsupported);
+        assert_eq!(run_sync(&mut ctx, 600_000, false, false), unsupported);
+        assert_eq!(run_resolve(&mut ctx, &mut authorities, [5u8; 32], &[0x04, 1]), unsupported);
+        assert_eq!(run_emergency_settle(&mut ctx, &mut authorities, [8u8; 32]), unsupported);
+        // Advance Settlement would otherwise read a v1 mark as a ramp
+        ctx[IS_RESOLVED_OFFSET] = 1;
+        assert_eq!(run_advance_settlement(&mut ctx), unsupported);
+        ctx[IS_RESOLVED_OFFSET] = v1[IS_RESOLVED_OFFSET];
+        // Nothing past the sync's slot bookkeeping was written
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
+            .copy_from_slice(&v1[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]);
+        assert_eq!(ctx, v1);
+    }
+
+    #[test]
+    fn test_match_guard_rejects_nested_match() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        enter_match_guard(&mut ctx).unwrap();
+        assert_eq!(ctx[MATCH_GUARD_OFFSET], 1);
+        // A re-entrant match lands while the outer one still holds the guard
+        assert_eq!(
+            enter_match_guard(&mut ctx),
+            Err(ProgramError::Custom(EventMatcherError::MatchReentered as u32))
+        );
+        ctx[MATCH_GUARD_OFFSET] = 0;
+        assert!(enter_match_guard(&mut ctx).is_ok());
+    }
+
+    #[test]
+    fn test_corrupt_probability_fails_instead_of_zero_edge() {
+        let overflow = Err(ProgramError::Custom(EventMatcherError::ArithmeticOverflow as u32));
+        // p > 100% would wrap `1 - p`, overflow the denominator and price at a bogus edge factor
+        assert_eq!(edge_adjusted_spread(MAX_PROBABILITY + 1, 20, 30, 500, 0), overflow);
+        assert_eq!(edge_adjusted_spread(u64::MAX, 20, 30, 500, 0), overflow);
+        // An extreme edge spread widens to the cap, never collapses to zero
+        assert_eq!(edge_adjusted_spread(10_000, 20, u16::MAX, 500, 0), Ok((500, 10_000_000)));
+        assert_eq!(
+            edge_adjusted_spread(10_000, 20, u16::MAX, u16::MAX, u64::MAX),
+            Ok((u16::MAX as u64, 10_000_000))
+        );
+    }
+
+    /// Run ProbabilitySync against `ctx` from the stored oracle; `lp_signed`
+    /// adds the LP PDA as the override co-signer. The clock stub never advances,
+    /// so the last update is backdated a slot to let each sync, read at the
+    /// current slot, count as newer.
+    fn run_sync(ctx: &mut [u8], probability: u64, move_override: bool, lp_signed: bool) -> ProgramResult {
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&999u64.to_le_bytes());
+        run_sync_at(ctx, probability, 1_000, move_override, lp_signed)
+    }
+
+    /// Run ProbabilitySync for a reading taken at `update_slot`
+    fn run_sync_at(ctx: &mut [u8], probability: u64, update_slot: u64, move_override: bool, lp_signed: bool) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let oracle_key = Pubkey::new_from_array([5u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let (mut ctx_lamports, mut oracle_lamports, mut lp_lamports) = (0u64, 0u64, 0u64);
+        let (mut oracle_data, mut lp_data) = (Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&oracle_key, false, false, &mut oracle_lamports, &mut oracle_data, &program_id, false, 0),
+            AccountInfo::new(&lp_key, lp_signed, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+        ];
+        let mut data = vec![0x03];
+        data.extend_from_slice(&probability.to_le_bytes());
+        data.extend_from_slice(&SIGNAL_NONE.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&update_slot.to_le_bytes());
+        data.push(move_override as u8);
+        process_probability_sync(&program_id, &accounts, &data)
+    }
+
+    fn current_probability(ctx: &[u8]) -> u64 {
+        read_probability(ctx).unwrap()
+    }
+
+    #[test]
+    fn test_probability_move_limit() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // 10% limit; the first sync off the 50% seed is exempt
+        let mut ctx = init_over(0, &full_init_data());
+        run_sync(&mut ctx, 900_000, false, false).unwrap();
+        assert_eq!(current_probability(&ctx), 900_000);
+
+        // Within the limit, and exactly at it
+        run_sync(&mut ctx, 850_000, false, false).unwrap();
+        run_sync(&mut ctx, 750_000, false, false).unwrap();
+        assert_eq!(current_probability(&ctx), 750_000);
+
+        // 75% -> 10% in one sync trips the breaker
+        assert_eq!(
+            run_sync(&mut ctx.clone(), 100_000, false, false),
+            Err(ProgramError::Custom(EventMatcherError::ProbabilityMoveTooLarge as u32))
+        );
+        // The override needs the LP's signature, not just the flag
+        assert_eq!(run_sync(&mut ctx.clone(), 100_000, true, false), Err(ProgramError::MissingRequiredSignature));
+
+        run_sync(&mut ctx, 100_000, true, true).unwrap();
+        assert_eq!(current_probability(&ctx), 100_000);
+    }
+
+    #[test]
+    fn test_sync_must_advance_slot() {
+        let stale = Err(ProgramError::Custom(EventMatcherError::StaleUpdate as u32));
+        assert_eq!(check_sync_slot(1_000, 1_001, 1_001), Ok(()));
+        assert_eq!(check_sync_slot(1_000, 1_000, 1_001), stale);
+        assert_eq!(check_sync_slot(1_000, 999, 1_001), stale);
+        assert_eq!(check_sync_slot(1_000, 1_002, 1_001), Err(ProgramError::InvalidInstructionData));
+
+        // Init stamps the clock's slot: a reading from that same slot isn't newer
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        assert_eq!(run_sync_at(&mut ctx, 600_000, 1_000, false, false), stale);
+        // Without the slot there is nothing to order by
+        let mut short = ctx.clone();
+        short[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let oracle_key = Pubkey::new_from_array([5u8; 32]);
+        let (mut ctx_lamports, mut oracle_lamports) = (0u64, 0u64);
+        let mut oracle_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut short, &program_id, false, 0),
+            AccountInfo::new(&oracle_key, false, false, &mut oracle_lamports, &mut oracle_data, &program_id, false, 0),
+        ];
+        let mut data = vec![0x03];
+        data.extend_from_slice(&600_000u64.to_le_bytes());
+        data.extend_from_slice(&[0u8; 16]);
+        assert_eq!(process_probability_sync(&program_id, &accounts, &data), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_sync_replay_in_later_slot_rejected() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let stale = Err(ProgramError::Custom(EventMatcherError::StaleUpdate as u32));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&900u64.to_le_bytes());
+
+        // Readings at 950 then 960 land; the stored slot is the reading's, not the clock's
+        run_sync_at(&mut ctx, 550_000, 950, false, false).unwrap();
+        run_sync_at(&mut ctx, 560_000, 960, false, false).unwrap();
+        assert_eq!(&ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8], &960u64.to_le_bytes());
+
+        // The 950 sync replayed now, 40 slots after the newer one was read:
+        // still rejected, probability untouched
+        assert_eq!(run_sync_at(&mut ctx, 550_000, 950, false, false), stale);
+        assert_eq!(current_probability(&ctx), 560_000);
+    }
+
+    #[test]
+    fn test_sync_frozen_near_resolution() {
+        // FixedClock reads unix_timestamp 0; full_init_data freezes the last hour
+        set_syscall_stubs(Box::new(FixedClock));
+        let frozen = Err(ProgramError::Custom(EventMatcherError::SyncFrozen as u32));
+        let mut ctx = init_over(0, &full_init_data());
+        let set_resolution = |ctx: &mut Vec<u8>, ts: i64| {
+            ctx[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8].copy_from_slice(&ts.to_le_bytes())
+        };
+
+        // No expiry: never frozen
+        run_sync(&mut ctx, 550_000, false, false).unwrap();
+
+        // One second outside the window
+        set_resolution(&mut ctx, 3_601);
+        run_sync(&mut ctx, 560_000, false, false).unwrap();
+        assert_eq!(current_probability(&ctx), 560_000);
+
+        // Inside it: rejected, probability untouched
+        set_resolution(&mut ctx, 3_600);
+        assert_eq!(run_sync(&mut ctx.clone(), 570_000, false, false), frozen);
+        assert_eq!(current_probability(&ctx), 560_000);
+
+        // Window off
+        ctx[FREEZE_SECS_BEFORE_RESOLUTION_OFFSET..FREEZE_SECS_BEFORE_RESOLUTION_OFFSET + 4].copy_from_slice(&0u32.to_le_bytes());
+        run_sync(&mut ctx, 570_000, false, false).unwrap();
+    }
+
+    #[test]
+    fn test_frozen_market_trades_past_the_staleness_limit() {
+        // FixedClock sits at slot 1_000, unix_timestamp 0; full_init_data freezes the last hour
+        set_syscall_stubs(Box::new(FixedClock));
+        let stale = Err(ProgramError::Custom(EventMatcherError::OracleStale as u32));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
+            .copy_from_slice(&(999 - EVENT_MAX_STALENESS_SLOTS).to_le_bytes());
+
+        // Outside the window a stale probability still blocks matching
+        ctx[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8].copy_from_slice(&3_601i64.to_le_bytes());
+        assert_eq!(run_match(&mut ctx.clone()), stale);
+
+        // Inside it no sync can land, so the held probability isn't stale
+        ctx[RESOLUTION_TIMESTAMP_OFFSET..RESOLUTION_TIMESTAMP_OFFSET + 8].copy_from_slice(&3_600i64.to_le_bytes());
+        run_match(&mut ctx).unwrap();
+        assert_eq!(ctx[MATCH_STATUS_RETURN_OFFSET], MATCH_STATUS_OK);
+    }
+
 }
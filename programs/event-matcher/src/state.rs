@@ -1,4 +1,4 @@
-use solana_program::{pubkey::Pubkey, program_error::ProgramError};
+use solana_program::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey};
 
 // Re-export shared constants and functions from matcher-common
 pub use matcher_common::{CTX_SIZE, verify_magic as verify_magic_generic};
@@ -7,29 +7,110 @@ pub use matcher_common::{CTX_SIZE, verify_magic as verify_magic_generic};
 pub const EVENT_MATCHER_MAGIC: u64 = 0x4556_4e54_4d41_5443;
 
 // Field offsets (event-matcher-specific)
-#[allow(dead_code)]
 pub const VERSION_OFFSET: usize = 72;               // u32
 #[allow(dead_code)]
 pub const MODE_OFFSET: usize = 76;                  // u8: 0=Continuous, 1=BinarySettlement
-pub const BASE_SPREAD_OFFSET: usize = 112;          // u32
-pub const EDGE_SPREAD_OFFSET: usize = 116;          // u32: extra spread near 0%/100%
-pub const MAX_SPREAD_OFFSET: usize = 120;           // u32
-pub const IMPACT_K_OFFSET: usize = 124;             // u32
-pub const CURRENT_PROBABILITY_OFFSET: usize = 128;  // u64 (0 - 1_000_000)
-pub const PROBABILITY_MARK_OFFSET: usize = 136;     // u64: mark price = prob * 1e6
-pub const LAST_UPDATE_SLOT_OFFSET: usize = 144;     // u64
-pub const RESOLUTION_TIMESTAMP_OFFSET: usize = 152; // i64 (0 = no expiry)
-pub const IS_RESOLVED_OFFSET: usize = 160;          // u8
-pub const RESOLUTION_OUTCOME_OFFSET: usize = 161;   // u8: 0=NO, 1=YES
-pub const SIGNAL_SEVERITY_OFFSET: usize = 168;      // u64 (0-3)
-pub const SIGNAL_ADJUSTED_SPREAD_OFFSET: usize = 176; // u64
-pub const LIQUIDITY_OFFSET: usize = 184;            // u128 (16 bytes)
-pub const MAX_FILL_OFFSET: usize = 200;             // u128 (16 bytes)
-pub const EVENT_ORACLE_OFFSET: usize = 216;         // Pubkey (32)
-// 248..320 = reserved
+pub const MATCH_GUARD_OFFSET: usize = 77;           // u8: 1 while a match is in flight (reentrancy guard)
+pub const MAX_PROB_MOVE_OFFSET: usize = 78;         // u16: largest sync move in bps of probability (0 = off)
+pub const BASE_SPREAD_OFFSET: usize = 112;          // u16
+pub const EDGE_SPREAD_OFFSET: usize = 114;          // u16: extra spread near 0%/100%
+pub const MAX_SPREAD_OFFSET: usize = 116;           // u16
+pub const IMPACT_K_OFFSET: usize = 118;             // u16
+pub const CURRENT_PROBABILITY_OFFSET: usize = 120;  // u32 (0 - 1_000_000)
+pub const PROBABILITY_MARK_OFFSET: usize = 124;     // u32: mark price = prob in e6
+pub const LAST_UPDATE_SLOT_OFFSET: usize = 128;     // u64
+pub const RESOLUTION_TIMESTAMP_OFFSET: usize = 136; // i64 (0 = no expiry)
+pub const IS_RESOLVED_OFFSET: usize = 144;          // u8
+pub const RESOLUTION_OUTCOME_OFFSET: usize = 145;   // u8: 0=NO, 1=YES, 2=VOID, 3=EMERGENCY
+pub const EMERGENCY_DELAY_DAYS_OFFSET: usize = 146; // u16: days past resolution before emergency settle (0 = default)
+pub const MIN_PROBABILITY_OFFSET: usize = 148;      // u32: sync clamp into [min, MAX - min] (0 = off)
+pub const SIGNAL_SEVERITY_OFFSET: usize = 152;      // u8 (0-3)
+pub const AUTHORITIES_OFFSET: usize = 153;          // u8: AUTHORITY_* flags set in the authorities PDA
+pub const SIGNAL_ADJUSTED_SPREAD_OFFSET: usize = 154; // u16
+pub const FREEZE_SECS_BEFORE_RESOLUTION_OFFSET: usize = 156; // u32: seconds before resolution syncs freeze (0 = off)
+pub const LIQUIDITY_OFFSET: usize = 160;            // u64
+pub const MAX_FILL_OFFSET: usize = 168;             // u64
+pub const EVENT_ORACLE_OFFSET: usize = 176;         // Pubkey (32)
+pub const TWAP_LOOKBACK_SLOTS_OFFSET: usize = 208;  // u16 (0 = price on the instantaneous probability)
+pub const PROB_RING_HEAD_OFFSET: usize = 210;       // u8: ring index the next sync writes
+pub const PROB_RING_LEN_OFFSET: usize = 211;        // u8: samples held (<= PROB_RING_CAPACITY)
+pub const PROB_RING_OFFSET: usize = 212;            // [(u32 probability_e6, u32 slot low bits); 8]
+pub const EMERGENCY_PRICE_OFFSET: usize = 276;      // u32: break-glass settlement probability (0 = settles NO)
+pub const VOID_PROBABILITY_OFFSET: usize = 280;     // u32: probability a void Resolve settles at
+pub const SETTLEMENT_RAMP_SLOTS_OFFSET: usize = 284; // u16: slots the mark takes to reach the settlement value (0 = snap)
+pub const TOTAL_TRADES_OFFSET: usize = 288;         // u64: matches priced
+pub const TOTAL_VOLUME_OFFSET: usize = 296;         // u128: summed trade sizes
+pub const RAMP_START_MARK_OFFSET: usize = 312;      // u32: mark when the settlement ramp began
+pub const RAMP_START_SLOT_OFFSET: usize = 316;      // u32: low bits of the slot the settlement ramp began
+
+/// Unused bytes, zeroed at init so a later layout can claim them
+pub const RESERVED_RANGES: [(usize, usize); 1] = [(286, 288)];
+
+/// Return region status byte stamped by a successful Match. Init zeroes the
+/// return region, so 0 here means no price has been written yet.
+pub const MATCH_STATUS_RETURN_OFFSET: usize = 8;    // u8
+pub const MATCH_STATUS_OK: u8 = 1;
+/// Return region slot for the exec price at MARK_SCALE precision (e9)
+pub const EXEC_PRICE_E9_RETURN_OFFSET: usize = 16; // u64
+/// Extra precision Match prices at before rounding to e6. A 1% mark is only
+/// 10_000 units, so a few bps of spread lands in the fraction.
+pub const MARK_SCALE: u64 = 1_000;
+
+// The settlement ramp start ends exactly at CTX_SIZE
+const _: () = assert!(RAMP_START_SLOT_OFFSET + 4 == CTX_SIZE);
+
+/// Probability history kept for TWAP pricing
+pub const PROB_RING_CAPACITY: usize = 8;
+pub const PROB_SAMPLE_SIZE: usize = 8;
+
+/// Maximum staleness before rejecting a match (in slots)
+pub const EVENT_MAX_STALENESS_SLOTS: u64 = 200;
 
 /// Maximum probability value (100% = 1_000_000)
 pub const MAX_PROBABILITY: u64 = 1_000_000;
+/// One bps of probability in e6 units; the sync move limit is stored in bps to fit its u16 slot
+pub const PROBABILITY_E6_PER_BPS: u64 = 100;
+
+/// Resolve outcome that voids the market at the configured void probability;
+/// recorded as given, like NO (0) and YES (1)
+pub const OUTCOME_VOID: u8 = 2;
+/// Resolution outcome recorded by EmergencySettle (never a Resolve input)
+pub const OUTCOME_EMERGENCY: u8 = 3;
+/// Void settlement probability when Init doesn't configure one: par
+pub const DEFAULT_VOID_PROBABILITY: u64 = 500_000;
+
+/// Authorities: one PDA per context holding the keys allowed to settle it
+/// besides the event oracle, created by Init when any is supplied. The
+/// context's AUTHORITIES byte records which are set, so an instruction can
+/// tell a missing PDA from one that was never configured.
+pub const AUTHORITIES_SEED: &[u8] = b"authorities";
+/// Magic bytes: "EVNTAUTH" as u64 LE
+pub const AUTHORITIES_MAGIC: u64 = 0x4556_4e54_4155_5448;
+pub const AUTHORITIES_SIZE: usize = 80;
+pub const AUTH_MAGIC_OFFSET: usize = 0;            // u64
+pub const AUTH_BUMP_OFFSET: usize = 8;             // u8: PDA bump, so readers skip the seed search
+pub const SETTLEMENT_AUTHORITY_OFFSET: usize = 16; // Pubkey: signs Emergency Settle
+pub const RESOLVER_AUTHORITY_OFFSET: usize = 48;   // Pubkey: signs Resolve and Simulate Resolve
+
+/// AUTHORITIES flag: a settlement authority is stored, enabling Emergency Settle
+pub const AUTHORITY_SETTLEMENT: u8 = 1;
+/// AUTHORITIES flag: a resolver authority is stored and signs Resolve in place of the event oracle
+pub const AUTHORITY_RESOLVER: u8 = 2;
+
+/// Context layout version written by init; every other instruction rejects
+/// any other. v2 narrowed the spread, probability and liquidity fields to make
+/// room for trade stats, so a v1 context can't be read as this layout.
+pub const CTX_VERSION: u32 = 2;
+
+/// Highest valid mode byte (0=Continuous, 1=BinarySettlement)
+pub const MAX_MODE: u8 = 1;
+
+/// Emergency settlement waits this long past resolution_timestamp when no delay is configured
+pub const DEFAULT_EMERGENCY_DELAY_DAYS: u16 = 30;
+
+/// Furthest a resolution time may sit in the future (~5 years, in seconds).
+/// Catches millisecond timestamps passed where seconds are expected.
+pub const MAX_RESOLUTION_HORIZON_SECS: i64 = 5 * 365 * 24 * 60 * 60;
 
 /// Signal severity levels (from Kalshify)
 pub const SIGNAL_NONE: u64 = 0;
@@ -43,6 +124,79 @@ pub fn verify_magic(ctx_data: &[u8]) -> bool {
     verify_magic_generic(ctx_data, EVENT_MATCHER_MAGIC)
 }
 
+/// Read the context layout version stamped at init
+pub fn read_version(ctx_data: &[u8]) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Read the live probability (e6); stored as u32 since it never exceeds MAX_PROBABILITY
+pub fn read_probability(ctx_data: &[u8]) -> Result<u64, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as u64)
+}
+
+/// Write a probability (e6, at most MAX_PROBABILITY) to both the live value and the mark
+pub fn write_probability(ctx_data: &mut [u8], probability_e6: u64) {
+    let stored = probability_e6.min(MAX_PROBABILITY) as u32;
+    ctx_data[CURRENT_PROBABILITY_OFFSET..CURRENT_PROBABILITY_OFFSET + 4].copy_from_slice(&stored.to_le_bytes());
+    ctx_data[PROBABILITY_MARK_OFFSET..PROBABILITY_MARK_OFFSET + 4].copy_from_slice(&stored.to_le_bytes());
+}
+
+/// Reject an authorities account that isn't this context's PDA. Owner and
+/// magic alone would accept any program-owned account that happens to start
+/// with the magic; the PDA can only hold what Init wrote.
+pub fn verify_authorities(
+    authorities: &AccountInfo,
+    authorities_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if authorities.owner != program_id
+        || authorities_data.len() < AUTHORITIES_SIZE
+        || authorities_data[AUTH_MAGIC_OFFSET..AUTH_MAGIC_OFFSET + 8] != AUTHORITIES_MAGIC.to_le_bytes()
+    {
+        msg!("EVENT-MATCHER: Authorities for {} not initialized", ctx_key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let seeds: &[&[u8]] = &[AUTHORITIES_SEED, ctx_key.as_ref(), &[authorities_data[AUTH_BUMP_OFFSET]]];
+    let expected = Pubkey::create_program_address(seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)?;
+    if *authorities.key != expected {
+        msg!("EVENT-MATCHER: {} is not the authorities PDA of {}", authorities.key, ctx_key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// Read a Pubkey stored in the authorities PDA at `offset`
+pub fn read_authority(authorities_data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    Ok(Pubkey::new_from_array(
+        authorities_data[offset..offset + 32]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
 pub fn read_event_oracle(ctx_data: &[u8]) -> Result<Pubkey, ProgramError> {
     Ok(Pubkey::new_from_array(ctx_data[EVENT_ORACLE_OFFSET..EVENT_ORACLE_OFFSET + 32].try_into().map_err(|_| ProgramError::InvalidAccountData)?))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_matches_shared_layout() {
+        assert_eq!(matcher_shared::matcher_magic(matcher_shared::MatcherKind::Event), EVENT_MATCHER_MAGIC);
+        // A context this program initialized is identified as its own
+        let mut data = vec![0u8; CTX_SIZE];
+        matcher_common::write_header(&mut data, EVENT_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(matcher_shared::identify(&data), Some(matcher_shared::MatcherKind::Event));
+    }
+}
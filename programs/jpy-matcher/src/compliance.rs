@@ -1,9 +1,10 @@
 use solana_program::{
     account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
-    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+    program::set_return_data, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
 
 use matcher_common::{verify_lp_pda as verify_lp_pda_common, write_exec_price, compute_exec_price};
+use matcher_shared::verify_ctx_owner;
 use crate::errors::JpyMatcherError;
 use crate::state::*;
 
@@ -14,7 +15,7 @@ use crate::state::*;
 ///   [2] User's WhitelistEntry PDA (read, optional for compliance bypass)
 ///   [3] LP owner's WhitelistEntry PDA (read, optional)
 pub fn process_match_with_compliance(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
@@ -25,9 +26,93 @@ pub fn process_match_with_compliance(
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
+    verify_ctx_owner(ctx_account, program_id, "JPY-MATCHER")?;
     verify_lp_pda_common(lp_pda, ctx_account, JPY_MATCHER_MAGIC, "JPY-MATCHER")?;
 
-    let ctx_data = ctx_account.try_borrow_data()?;
+    // Reentrancy guard: held until this match completes
+    enter_match_guard(&mut ctx_account.try_borrow_mut_data()?)?;
+
+    let clock = Clock::get()?;
+    let (exec_price, capped_spread, user_kyc_level) =
+        compliant_exec_price(accounts, &ctx_account.try_borrow_data()?, data, clock.unix_timestamp)?;
+
+    // Write execution price and the fee charged on it to return buffer
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    write_exec_price(&mut ctx_data, exec_price);
+    let trade_size = parse_trade_size(data);
+    let trade_fee = write_trade_fee(&mut ctx_data, capped_spread, trade_size)?;
+    ctx_data[LAST_FEE_OFFSET..LAST_FEE_OFFSET + 8].copy_from_slice(&trade_fee.to_le_bytes());
+
+    // Update daily volume
+    if data.len() >= 9 {
+        let current_volume = u64::from_le_bytes(
+            ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let day_reset = i64::from_le_bytes(
+            ctx_data[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        if let Some(day_start) = day_rollover(&ctx_data, day_reset, clock.unix_timestamp) {
+            // New day — reset volume and update timestamp
+            ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
+                .copy_from_slice(&trade_size.to_le_bytes());
+            ctx_data[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8]
+                .copy_from_slice(&day_start.to_le_bytes());
+        } else {
+            let new_volume = current_volume.saturating_add(trade_size);
+            ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
+                .copy_from_slice(&new_volume.to_le_bytes());
+        }
+    }
+
+    // Release the reentrancy guard
+    ctx_data[MATCH_GUARD_OFFSET] = 0;
+
+    matcher_log!(
+        "MATCH: price={} spread={} kyc_level={}",
+        exec_price,
+        capped_spread,
+        user_kyc_level
+    );
+
+    Ok(())
+}
+
+/// Start of a new daily-cap window if `now` has left the one opened at
+/// `day_reset`, else `None`. Rolling windows run 24h from the first trade of
+/// the day; aligned windows open at `reset_hour_utc` on the compliance calendar.
+fn day_rollover(ctx_data: &[u8], day_reset: i64, now: i64) -> Option<i64> {
+    if ctx_data[DAY_ALIGNED_OFFSET] == 0 {
+        return (now > day_reset + SECONDS_PER_DAY).then_some(now);
+    }
+    let offset = ctx_data[RESET_HOUR_UTC_OFFSET] as i64 * 3_600;
+    let day_start = (now - offset).div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY + offset;
+    (day_start > day_reset).then_some(day_start)
+}
+
+/// Take the reentrancy guard for the duration of a match. A nested match on
+/// the same context finds it held and is rejected before touching any stats.
+fn enter_match_guard(ctx_data: &mut [u8]) -> ProgramResult {
+    if ctx_data[MATCH_GUARD_OFFSET] != 0 {
+        msg!("JPY-MATCHER: Re-entrant match rejected");
+        return Err(JpyMatcherError::MatchReentered.into());
+    }
+    ctx_data[MATCH_GUARD_OFFSET] = 1;
+    Ok(())
+}
+
+/// Compliance checks and pricing shared by Match and QuoteMatch:
+/// `(exec_price, spread_bps, user_kyc_level)`
+fn compliant_exec_price(
+    accounts: &[AccountInfo],
+    ctx_data: &[u8],
+    data: &[u8],
+    now: i64,
+) -> Result<(u64, u32, u8), ProgramError> {
     let min_kyc = ctx_data[MIN_KYC_LEVEL_OFFSET];
     let blocked_jurisdictions = ctx_data[BLOCKED_JURISDICTIONS_OFFSET];
     let oracle_price = u64::from_le_bytes(
@@ -63,16 +148,18 @@ pub fn process_match_with_compliance(
                 .try_into()
                 .map_err(|_| ProgramError::InvalidAccountData)?,
         );
-        let clock = Clock::get()?;
-        if clock.unix_timestamp > user_expiry {
+        if now > user_expiry {
             msg!(
                 "JPY-MATCHER: KYC expired: now={} > expiry={}",
-                clock.unix_timestamp,
+                now,
                 user_expiry
             );
             return Err(JpyMatcherError::KycExpired.into());
         }
 
+        // 2b. Check the provider re-verified the entry recently enough
+        check_whitelist_freshness(ctx_data, &user_wl_data, now)?;
+
         // 3. Check jurisdiction not blocked
         let user_jurisdiction = user_wl_data[WHITELIST_JURISDICTION_OFFSET];
         if user_jurisdiction < 8 && (blocked_jurisdictions >> user_jurisdiction) & 1 == 1 {
@@ -85,37 +172,16 @@ pub fn process_match_with_compliance(
         }
 
         // 4. Check daily volume cap
-        let daily_cap = u64::from_le_bytes(
-            ctx_data[DAILY_VOLUME_CAP_OFFSET..DAILY_VOLUME_CAP_OFFSET + 8]
-                .try_into()
-                .map_err(|_| ProgramError::InvalidAccountData)?,
-        );
+        let (daily_cap, current_volume, day_reset) = read_daily_volume(ctx_data)?;
         if daily_cap > 0 {
-            let current_volume = u64::from_le_bytes(
-                ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
-                    .try_into()
-                    .map_err(|_| ProgramError::InvalidAccountData)?,
-            );
-            let day_reset = i64::from_le_bytes(
-                ctx_data[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8]
-                    .try_into()
-                    .map_err(|_| ProgramError::InvalidAccountData)?,
-            );
-            let clock = Clock::get()?;
-
-            // Reset volume if new day (86400 seconds per day)
-            let effective_volume = if clock.unix_timestamp > day_reset + 86400 {
+            // Reset volume if new day
+            let effective_volume = if day_rollover(ctx_data, day_reset, now).is_some() {
                 0u64 // Volume resets
             } else {
                 current_volume
             };
 
-            // Parse trade size from instruction data if available
-            let trade_size = if data.len() >= 9 {
-                u64::from_le_bytes(data[1..9].try_into().unwrap_or([0u8; 8]))
-            } else {
-                0
-            };
+            let trade_size = parse_trade_size(data);
 
             if effective_volume.saturating_add(trade_size) > daily_cap {
                 msg!(
@@ -144,9 +210,10 @@ pub fn process_match_with_compliance(
             }
         }
     } else if min_kyc > 0 {
-        // KYC required but no whitelist account provided
+        // KYC required but no whitelist account provided: not a level problem,
+        // the client left the user's WhitelistEntry out
         msg!("JPY-MATCHER: KYC required but no WhitelistEntry provided");
-        return Err(JpyMatcherError::InsufficientKycLevel.into());
+        return Err(JpyMatcherError::MissingWhitelistAccount.into());
     }
 
     // === PRICING ===
@@ -167,46 +234,71 @@ pub fn process_match_with_compliance(
     let max_spread = u32::from_le_bytes(
         ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let capped_spread = std::cmp::min(effective_spread, max_spread);
+    let mut capped_spread = std::cmp::min(effective_spread, max_spread);
 
-    let exec_price = compute_exec_price(oracle_price, capped_spread as u64)?;
+    // Promotional window overrides the computed spread
+    if let Some(promo_spread) = active_promo_spread(ctx_data, now)? {
+        matcher_log!("JPY-MATCHER: Promo spread {} active (normal {})", promo_spread, capped_spread);
+        capped_spread = promo_spread;
+    }
+    let capped_spread = floor_spread(ctx_data, capped_spread)?;
 
-    drop(ctx_data);
+    let exec_price = compute_exec_price(oracle_price, capped_spread as u64)?;
 
-    // Write execution price to return buffer
-    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    write_exec_price(&mut ctx_data, exec_price);
+    Ok((exec_price, capped_spread, user_kyc_level))
+}
 
-    // Update daily volume
+/// Trade size at `[1..9]`, 0 when omitted
+fn parse_trade_size(data: &[u8]) -> u64 {
     if data.len() >= 9 {
-        let trade_size = u64::from_le_bytes(data[1..9].try_into().unwrap_or([0u8; 8]));
-        let current_volume = u64::from_le_bytes(
-            ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
-                .try_into()
-                .map_err(|_| ProgramError::InvalidAccountData)?,
-        );
-        let day_reset = i64::from_le_bytes(
-            ctx_data[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8]
-                .try_into()
-                .map_err(|_| ProgramError::InvalidAccountData)?,
-        );
-        let clock = Clock::get()?;
+        u64::from_le_bytes(data[1..9].try_into().unwrap_or([0u8; 8]))
+    } else {
+        0
+    }
+}
 
-        if clock.unix_timestamp > day_reset + 86400 {
-            // New day — reset volume and update timestamp
-            ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
-                .copy_from_slice(&trade_size.to_le_bytes());
-            ctx_data[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8]
-                .copy_from_slice(&clock.unix_timestamp.to_le_bytes());
-        } else {
-            let new_volume = current_volume.saturating_add(trade_size);
-            ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
-                .copy_from_slice(&new_volume.to_le_bytes());
-        }
+/// Write the fee on `trade_size` at `spread` to the return buffer, so the
+/// slot never holds a previous fill's fee, and return it
+fn write_trade_fee(ctx_data: &mut [u8], spread: u32, trade_size: u64) -> Result<u64, ProgramError> {
+    let oracle_price = u64::from_le_bytes(
+        ctx_data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let trade_fee = matcher_shared::trade_fee(oracle_price, spread as u64, trade_size)
+        .ok_or(JpyMatcherError::ArithmeticOverflow)?;
+    ctx_data[FEE_RETURN_OFFSET..FEE_RETURN_OFFSET + 8].copy_from_slice(&trade_fee.to_le_bytes());
+    Ok(trade_fee)
+}
+
+/// Tag 0x0B: Quote match -- runs Match's compliance checks and pricing and
+/// writes the exec price to the return buffer without recording daily volume.
+/// Accounts and data are the same as Match.
+pub fn process_quote_match(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
     }
 
-    msg!(
-        "MATCH: price={} spread={} kyc_level={}",
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    verify_ctx_owner(ctx_account, program_id, "JPY-MATCHER")?;
+    verify_lp_pda_common(lp_pda, ctx_account, JPY_MATCHER_MAGIC, "JPY-MATCHER")?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (exec_price, capped_spread, user_kyc_level) =
+        compliant_exec_price(accounts, &ctx_account.try_borrow_data()?, data, now)?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    write_exec_price(&mut ctx_data, exec_price);
+    write_trade_fee(&mut ctx_data, capped_spread, parse_trade_size(data))?;
+
+    matcher_log!(
+        "QUOTE: price={} spread={} kyc_level={}",
         exec_price,
         capped_spread,
         user_kyc_level
@@ -215,10 +307,142 @@ pub fn process_match_with_compliance(
     Ok(())
 }
 
+/// Tag 0x04: Reset daily volume -- compliance override that zeroes the day's
+/// volume and restarts the day window now, e.g. after a false-positive block.
+/// Accounts:
+///   [0] LP PDA (signer)
+///   [1] Matcher context account (writable)
+pub fn process_reset_daily_volume(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    verify_ctx_owner(ctx_account, program_id, "JPY-MATCHER")?;
+    verify_lp_pda_common(lp_pda, ctx_account, JPY_MATCHER_MAGIC, "JPY-MATCHER")?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    let (_, cleared, _) = read_daily_volume(&ctx_data)?;
+    ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    ctx_data[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8].copy_from_slice(&now.to_le_bytes());
+
+    matcher_log!("RESET_DAILY_VOLUME: cleared={} reset_ts={}", cleared, now);
+
+    Ok(())
+}
+
+/// Tag 0x0D: Query last fee -- the fee (e6) recorded by the last Match, for
+/// auditors reconciling fills. Read-only and signer-free.
+/// Accounts:
+///   [0] Matcher context account
+/// Return data (`set_return_data`): last_fee_e6 as u64 LE
+pub fn process_query_last_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let ctx_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    verify_ctx_owner(ctx_account, program_id, "JPY-MATCHER")?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let last_fee = read_last_fee(&ctx_data)?;
+    set_return_data(&last_fee.to_le_bytes());
+
+    matcher_log!("QUERY_LAST_FEE: fee={}", last_fee);
+
+    Ok(())
+}
+
+/// Reject a whitelist entry the KYC provider hasn't re-verified within the
+/// configured max age, reading `last_verified` at the configured offset.
+/// Independent of the entry's own KYC expiry.
+fn check_whitelist_freshness(ctx_data: &[u8], wl_data: &[u8], now: i64) -> Result<(), ProgramError> {
+    let max_age = u32::from_le_bytes(
+        ctx_data[WHITELIST_MAX_AGE_OFFSET..WHITELIST_MAX_AGE_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if max_age == 0 {
+        return Ok(());
+    }
+    let offset = read_last_verified_offset(ctx_data)?;
+    let last_verified = i64::from_le_bytes(
+        wl_data
+            .get(offset..offset + 8)
+            .ok_or(JpyMatcherError::InvalidComplianceData)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if now.saturating_sub(last_verified) > max_age as i64 {
+        msg!(
+            "JPY-MATCHER: Whitelist stale: last_verified={} now={} max_age={}",
+            last_verified,
+            now,
+            max_age
+        );
+        return Err(JpyMatcherError::WhitelistStale.into());
+    }
+    Ok(())
+}
+
+/// Raise the final spread to `min_spread_bps`, after the discount, cap and any
+/// promo, so the LP never quotes at the bare oracle price
+fn floor_spread(ctx_data: &[u8], spread: u32) -> Result<u32, ProgramError> {
+    let min_spread = u32::from_le_bytes(
+        ctx_data[MIN_SPREAD_OFFSET..MIN_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok(spread.max(min_spread))
+}
+
+/// Promo spread if `now` falls inside `[promo_start_ts, promo_end_ts)`, else None
+fn active_promo_spread(ctx_data: &[u8], now: i64) -> Result<Option<u32>, ProgramError> {
+    let start = i64::from_le_bytes(
+        ctx_data[PROMO_START_TS_OFFSET..PROMO_START_TS_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let end = i64::from_le_bytes(
+        ctx_data[PROMO_END_TS_OFFSET..PROMO_END_TS_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if end == 0 || now < start || now >= end {
+        return Ok(None);
+    }
+    Ok(Some(u32::from_le_bytes(
+        ctx_data[PROMO_SPREAD_OFFSET..PROMO_SPREAD_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    )))
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use super::{
+        active_promo_spread, check_whitelist_freshness, day_rollover, floor_spread,
+        process_match_with_compliance, process_query_last_fee, process_quote_match,
+        process_reset_daily_volume,
+    };
+    use std::cell::RefCell;
+    use crate::errors::JpyMatcherError;
+    use crate::pricing::process_oracle_update;
     use crate::state::*;
     use matcher_common::compute_exec_price;
+    use solana_program::{
+        account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult,
+        program::get_return_data, program_error::ProgramError,
+        program_stubs::{set_syscall_stubs, SyscallStubs}, pubkey::Pubkey, rent::Rent,
+    };
 
     /// Helper: replicates the pricing logic from process_match_with_compliance
     /// without requiring any Solana runtime state.
@@ -281,6 +505,20 @@ mod tests {
         assert_eq!(price, oracle);
     }
 
+    #[test]
+    fn test_min_spread_floor_holds_full_discount() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[MIN_SPREAD_OFFSET..MIN_SPREAD_OFFSET + 4].copy_from_slice(&5u32.to_le_bytes());
+        // base=15, discount=20 saturates to 0; the floor holds it at 5
+        let spread = floor_spread(&ctx, 15u32.saturating_sub(20)).unwrap();
+        assert_eq!(spread, 5);
+        assert_eq!(compute_exec_price(150_000_000, spread as u64).unwrap(), 150_075_000);
+        // Spreads already above the floor pass through
+        assert_eq!(floor_spread(&ctx, 30).unwrap(), 30);
+        // Unset floor keeps the zero spread
+        assert_eq!(floor_spread(&vec![0u8; CTX_SIZE], 0).unwrap(), 0);
+    }
+
     // ---------------------------------------------------------------
     // 5. Jurisdiction bitmask logic
     // ---------------------------------------------------------------
@@ -312,4 +550,412 @@ mod tests {
         assert_eq!(KYC_ENHANCED, 2);
         assert_eq!(KYC_INSTITUTIONAL, 3);
     }
+
+    // ---------------------------------------------------------------
+    // 7. Promotional spread window
+    // ---------------------------------------------------------------
+    fn promo_ctx(start: i64, end: i64, spread: u32) -> Vec<u8> {
+        let mut data = vec![0u8; CTX_SIZE];
+        data[PROMO_START_TS_OFFSET..PROMO_START_TS_OFFSET + 8].copy_from_slice(&start.to_le_bytes());
+        data[PROMO_END_TS_OFFSET..PROMO_END_TS_OFFSET + 8].copy_from_slice(&end.to_le_bytes());
+        data[PROMO_SPREAD_OFFSET..PROMO_SPREAD_OFFSET + 4].copy_from_slice(&spread.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_promo_inside_window_uses_promo_spread() {
+        let data = promo_ctx(1_000, 2_000, 0);
+        let promo = active_promo_spread(&data, 1_500).unwrap();
+        assert_eq!(promo, Some(0));
+        // Zero-spread promo -> price == oracle
+        let price = compute_exec_price(150_000_000, promo.unwrap() as u64).unwrap();
+        assert_eq!(price, 150_000_000);
+    }
+
+    #[test]
+    fn test_promo_outside_window_uses_normal_pricing() {
+        let data = promo_ctx(1_000, 2_000, 0);
+        assert_eq!(active_promo_spread(&data, 999).unwrap(), None);
+        assert_eq!(active_promo_spread(&data, 2_000).unwrap(), None); // end is exclusive
+        assert_eq!(calc_price(150_000_000, 30, 0, 100), 150_450_000);
+    }
+
+    #[test]
+    fn test_promo_unset_is_inactive() {
+        let data = vec![0u8; CTX_SIZE];
+        assert_eq!(active_promo_spread(&data, 0).unwrap(), None);
+    }
+
+    const DAY: i64 = SECONDS_PER_DAY;
+
+    fn freshness_ctx(max_age_secs: u32) -> Vec<u8> {
+        let mut data = vec![0u8; CTX_SIZE];
+        data[WHITELIST_MAX_AGE_OFFSET..WHITELIST_MAX_AGE_OFFSET + 4].copy_from_slice(&max_age_secs.to_le_bytes());
+        data
+    }
+
+    fn whitelist_verified_at(ts: i64) -> Vec<u8> {
+        let mut wl = vec![0u8; DEFAULT_WHITELIST_LAST_VERIFIED_OFFSET + 8];
+        wl[DEFAULT_WHITELIST_LAST_VERIFIED_OFFSET..DEFAULT_WHITELIST_LAST_VERIFIED_OFFSET + 8].copy_from_slice(&ts.to_le_bytes());
+        wl
+    }
+
+    #[test]
+    fn test_recently_verified_whitelist_accepted() {
+        let ctx = freshness_ctx(30 * DAY as u32);
+        let now = 1_750_000_000;
+        assert!(check_whitelist_freshness(&ctx, &whitelist_verified_at(now - 2 * DAY), now).is_ok());
+    }
+
+    #[test]
+    fn test_long_unverified_whitelist_rejected() {
+        let ctx = freshness_ctx(30 * DAY as u32);
+        let now = 1_750_000_000;
+        assert_eq!(
+            check_whitelist_freshness(&ctx, &whitelist_verified_at(now - 200 * DAY), now),
+            Err(ProgramError::Custom(JpyMatcherError::WhitelistStale as u32))
+        );
+        // Freshness check off: age doesn't matter
+        assert!(check_whitelist_freshness(&freshness_ctx(0), &whitelist_verified_at(0), now).is_ok());
+    }
+
+    #[test]
+    fn test_freshness_reads_configured_offset() {
+        let mut ctx = freshness_ctx(30 * DAY as u32);
+        ctx[LAST_VERIFIED_AT_OFFSET..LAST_VERIFIED_AT_OFFSET + 2].copy_from_slice(&96u16.to_le_bytes());
+        let now = 1_750_000_000;
+        // Stale at the default offset, fresh at the configured one: the configured one decides
+        let mut wl = whitelist_verified_at(now - 200 * DAY);
+        wl.resize(104, 0);
+        wl[96..104].copy_from_slice(&(now - DAY).to_le_bytes());
+        assert!(check_whitelist_freshness(&ctx, &wl, now).is_ok());
+        wl[96..104].copy_from_slice(&(now - 200 * DAY).to_le_bytes());
+        assert_eq!(
+            check_whitelist_freshness(&ctx, &wl, now),
+            Err(ProgramError::Custom(JpyMatcherError::WhitelistStale as u32))
+        );
+        // An entry too short to hold the stamp there is not read as fresh
+        assert_eq!(
+            check_whitelist_freshness(&ctx, &wl[..100], now),
+            Err(ProgramError::Custom(JpyMatcherError::InvalidComplianceData as u32))
+        );
+    }
+
+    const QUOTE_NOW: i64 = 1_750_000_000;
+
+    thread_local! {
+        static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Compliance checks read the clock; serve a fixed one off-chain. Also keeps
+    /// this thread's `set_return_data` so Query tests can read it back. Shared
+    /// with the pricing tests, as swapping stubs mid-test would race them.
+    pub(crate) struct FixedClock;
+
+    impl SyscallStubs for FixedClock {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Clock) = Clock { slot: 1_000, unix_timestamp: QUOTE_NOW, ..Clock::default() } };
+            0
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+
+        fn sol_set_return_data(&self, data: &[u8]) {
+            RETURN_DATA.with(|r| *r.borrow_mut() = data.to_vec());
+        }
+
+        fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+            Some((Pubkey::default(), RETURN_DATA.with(|r| r.borrow().clone())))
+        }
+    }
+
+    /// Initialized context: LP PDA [1; 32], Standard KYC minimum, daily cap on
+    fn quote_ctx() -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[64..72].copy_from_slice(&JPY_MATCHER_MAGIC.to_le_bytes());
+        ctx[80..112].copy_from_slice(&[1u8; 32]);
+        ctx[MIN_KYC_LEVEL_OFFSET] = KYC_STANDARD;
+        ctx[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&20u32.to_le_bytes());
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&100u32.to_le_bytes());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&150_000_000u64.to_le_bytes());
+        ctx[DAILY_VOLUME_CAP_OFFSET..DAILY_VOLUME_CAP_OFFSET + 8].copy_from_slice(&1_000_000_000u64.to_le_bytes());
+        ctx
+    }
+
+    /// Run an LP-signed instruction with a Standard-KYC user whitelist entry
+    fn run_lp_ix(
+        ix: fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult,
+        ctx: &mut [u8],
+        data: &[u8],
+    ) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key, wl_key) =
+            (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]), Pubkey::new_from_array([3u8; 32]));
+        let (mut lp_lamports, mut ctx_lamports, mut wl_lamports) = (0u64, 0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let mut wl_data = whitelist_verified_at(QUOTE_NOW);
+        wl_data[WHITELIST_KYC_LEVEL_OFFSET] = KYC_STANDARD;
+        wl_data[WHITELIST_EXPIRY_OFFSET..WHITELIST_EXPIRY_OFFSET + 8].copy_from_slice(&(QUOTE_NOW + DAY).to_le_bytes());
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&wl_key, false, false, &mut wl_lamports, &mut wl_data, &program_id, false, 0),
+        ];
+        ix(&program_id, &accounts, data)
+    }
+
+    #[test]
+    fn test_quote_leaves_daily_volume_untouched_match_records_it() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        let mut trade = vec![0x0B];
+        trade.extend_from_slice(&5_000_000u64.to_le_bytes());
+
+        let before = ctx.clone();
+        run_lp_ix(process_quote_match, &mut ctx, &trade).unwrap();
+        // exec = 150_000_000 * 10_020 / 10_000; nothing past the return buffer moves
+        assert_eq!(u64::from_le_bytes(ctx[0..8].try_into().unwrap()), 150_300_000);
+        assert_eq!(ctx[64..], before[64..]);
+
+        trade[0] = 0x00;
+        run_lp_ix(process_match_with_compliance, &mut ctx, &trade).unwrap();
+        assert_eq!(u64::from_le_bytes(ctx[0..8].try_into().unwrap()), 150_300_000);
+        // fee = 20 bps of 150_000_000 * 5_000_000 / 1e6 notional
+        let fee = &ctx[FEE_RETURN_OFFSET..FEE_RETURN_OFFSET + 8];
+        assert_eq!(u64::from_le_bytes(fee.try_into().unwrap()), 1_500_000);
+        let day_volume = &ctx[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8];
+        assert_eq!(u64::from_le_bytes(day_volume.try_into().unwrap()), 5_000_000);
+        let day_reset = &ctx[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8];
+        assert_eq!(i64::from_le_bytes(day_reset.try_into().unwrap()), QUOTE_NOW);
+
+        // A quote returns its own fee, never the last match's: half the size
+        // halves it, and no size quotes none
+        trade[0] = 0x0B;
+        trade[1..9].copy_from_slice(&2_500_000u64.to_le_bytes());
+        run_lp_ix(process_quote_match, &mut ctx, &trade).unwrap();
+        let fee = &ctx[FEE_RETURN_OFFSET..FEE_RETURN_OFFSET + 8];
+        assert_eq!(u64::from_le_bytes(fee.try_into().unwrap()), 750_000);
+        run_lp_ix(process_quote_match, &mut ctx, &[0x0B]).unwrap();
+        assert_eq!(ctx[FEE_RETURN_OFFSET..FEE_RETURN_OFFSET + 8], [0u8; 8]);
+        let stored = &ctx[LAST_FEE_OFFSET..LAST_FEE_OFFSET + 8];
+        assert_eq!(u64::from_le_bytes(stored.try_into().unwrap()), 1_500_000);
+    }
+
+    #[test]
+    fn test_fee_rounds_half_away_from_zero_and_is_stored() {
+        // 20 bps on a 150.0 oracle, size 5: 1.5 e6 of fee rounds up to 2
+        // Match returns the same figure it stores
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        let mut trade = vec![0x00];
+        trade.extend_from_slice(&5u64.to_le_bytes());
+        run_lp_ix(process_match_with_compliance, &mut ctx, &trade).unwrap();
+        let returned = u64::from_le_bytes(ctx[FEE_RETURN_OFFSET..FEE_RETURN_OFFSET + 8].try_into().unwrap());
+        let stored = u64::from_le_bytes(ctx[LAST_FEE_OFFSET..LAST_FEE_OFFSET + 8].try_into().unwrap());
+        assert_eq!((returned, stored), (2, 2));
+
+        // Query hands auditors the stored figure without a signer
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let mut lamports = 0u64;
+        let accounts = [AccountInfo::new(&ctx_key, false, false, &mut lamports, &mut ctx, &program_id, false, 0)];
+        process_query_last_fee(&program_id, &accounts, &[0x0D]).unwrap();
+        let (_, data) = get_return_data().unwrap();
+        assert_eq!(data, 2u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_quote_runs_compliance_checks() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        ctx[MIN_KYC_LEVEL_OFFSET] = KYC_INSTITUTIONAL;
+        assert_eq!(
+            run_lp_ix(process_quote_match, &mut ctx, &[0x0B]),
+            Err(ProgramError::Custom(JpyMatcherError::InsufficientKycLevel as u32))
+        );
+    }
+
+    #[test]
+    fn test_missing_whitelist_distinct_from_low_kyc() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let (mut lp_data, mut ctx) = (Vec::new(), quote_ctx());
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut ctx, &program_id, false, 0),
+        ];
+        // KYC required, no WhitelistEntry passed
+        assert_eq!(
+            process_match_with_compliance(&program_id, &accounts, &[0x00]),
+            Err(ProgramError::Custom(JpyMatcherError::MissingWhitelistAccount as u32))
+        );
+
+        // Entry passed, but Standard is below the Institutional minimum
+        let mut ctx = quote_ctx();
+        ctx[MIN_KYC_LEVEL_OFFSET] = KYC_INSTITUTIONAL;
+        assert_eq!(
+            run_lp_ix(process_match_with_compliance, &mut ctx, &[0x00]),
+            Err(ProgramError::Custom(JpyMatcherError::InsufficientKycLevel as u32))
+        );
+    }
+
+    #[test]
+    fn test_reentered_match_rejected() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        ctx[MATCH_GUARD_OFFSET] = 1;
+        let before = ctx.clone();
+        assert_eq!(
+            run_lp_ix(process_match_with_compliance, &mut ctx, &[0x00]),
+            Err(ProgramError::Custom(JpyMatcherError::MatchReentered as u32))
+        );
+        assert_eq!(ctx, before);
+
+        ctx[MATCH_GUARD_OFFSET] = 0;
+        run_lp_ix(process_match_with_compliance, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(ctx[MATCH_GUARD_OFFSET], 0);
+    }
+
+    #[test]
+    fn test_reset_daily_volume_unblocks_capped_trade() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        let trade = |size: u64| [&[0x00u8][..], &size.to_le_bytes()].concat();
+
+        run_lp_ix(process_match_with_compliance, &mut ctx, &trade(900_000_000)).unwrap();
+        assert_eq!(read_daily_volume(&ctx).unwrap(), (1_000_000_000, 900_000_000, QUOTE_NOW));
+        // Run the rejected attempt on a copy: on-chain a failed instruction's writes roll back
+        assert_eq!(
+            run_lp_ix(process_match_with_compliance, &mut ctx.clone(), &trade(200_000_000)),
+            Err(ProgramError::Custom(JpyMatcherError::DailyVolumeLimitExceeded as u32))
+        );
+
+        run_lp_ix(process_reset_daily_volume, &mut ctx, &[0x04]).unwrap();
+        assert_eq!(read_daily_volume(&ctx).unwrap(), (1_000_000_000, 0, QUOTE_NOW));
+
+        run_lp_ix(process_match_with_compliance, &mut ctx, &trade(200_000_000)).unwrap();
+        assert_eq!(read_daily_volume(&ctx).unwrap(), (1_000_000_000, 200_000_000, QUOTE_NOW));
+    }
+
+    fn aligned_ctx(reset_hour_utc: u8) -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[DAY_ALIGNED_OFFSET] = 1;
+        ctx[RESET_HOUR_UTC_OFFSET] = reset_hour_utc;
+        ctx
+    }
+
+    #[test]
+    fn test_aligned_day_rolls_at_reset_hour() {
+        // 09:00 UTC boundary; last window opened at yesterday's boundary
+        let ctx = aligned_ctx(9);
+        let boundary = 20_000 * DAY + 9 * 3_600;
+        let day_reset = boundary - DAY;
+        assert_eq!(day_rollover(&ctx, day_reset, boundary - 1), None);
+        assert_eq!(day_rollover(&ctx, day_reset, boundary), Some(boundary));
+        // A window opened mid-day still closes at the boundary, not 24h later
+        assert_eq!(day_rollover(&ctx, boundary + 3_600, boundary + DAY - 1), None);
+        assert_eq!(day_rollover(&ctx, boundary + 3_600, boundary + DAY), Some(boundary + DAY));
+        // Rolling windows ignore the hour
+        assert_eq!(day_rollover(&vec![0u8; CTX_SIZE], day_reset, boundary), None);
+    }
+
+    #[test]
+    fn test_aligned_reset_unblocks_trade_after_boundary() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // QUOTE_NOW is 15:06:40 UTC; a window opened at 16:00 yesterday is 900M into a 1B cap
+        let today_15h = QUOTE_NOW - (6 * 60 + 40);
+        let mut ctx = quote_ctx();
+        ctx[DAY_ALIGNED_OFFSET] = 1;
+        ctx[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8].copy_from_slice(&900_000_000u64.to_le_bytes());
+        ctx[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&(today_15h - DAY + 3_600).to_le_bytes());
+        let trade = [&[0x00u8][..], &200_000_000u64.to_le_bytes()].concat();
+
+        // 16:00 reset: the trade lands just before the boundary and counts against the old day
+        ctx[RESET_HOUR_UTC_OFFSET] = 16;
+        assert_eq!(
+            run_lp_ix(process_match_with_compliance, &mut ctx.clone(), &trade),
+            Err(ProgramError::Custom(JpyMatcherError::DailyVolumeLimitExceeded as u32))
+        );
+
+        // 15:00 reset: just after the boundary, a fresh day stamped at the boundary itself
+        ctx[RESET_HOUR_UTC_OFFSET] = 15;
+        run_lp_ix(process_match_with_compliance, &mut ctx, &trade).unwrap();
+        assert_eq!(read_daily_volume(&ctx).unwrap(), (1_000_000_000, 200_000_000, today_15h));
+    }
+
+    fn oracle_update(price: u64, update_slot: u64) -> Vec<u8> {
+        [&[0x03u8][..], &price.to_le_bytes(), &update_slot.to_le_bytes()].concat()
+    }
+
+    #[test]
+    fn test_oracle_update_must_advance_slot() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        let last_oracle_slot = |ctx: &[u8]| {
+            u64::from_le_bytes(ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].try_into().unwrap())
+        };
+
+        // Stored as the update's own slot, not the slot it landed in
+        let update = oracle_update(151_000_000, 990);
+        run_lp_ix(process_oracle_update, &mut ctx, &update).unwrap();
+        assert_eq!(last_oracle_slot(&ctx), 990);
+        let price = &ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8];
+        assert_eq!(u64::from_le_bytes(price.try_into().unwrap()), 151_000_000);
+
+        // Replayed (its slot is still 990 whatever slot it lands in), or older
+        let stale = Err(ProgramError::Custom(JpyMatcherError::StaleUpdate as u32));
+        assert_eq!(run_lp_ix(process_oracle_update, &mut ctx.clone(), &update), stale);
+        assert_eq!(run_lp_ix(process_oracle_update, &mut ctx.clone(), &oracle_update(150_000_000, 989)), stale);
+
+        // A slot past the current one is no reading
+        assert_eq!(
+            run_lp_ix(process_oracle_update, &mut ctx.clone(), &oracle_update(150_000_000, 1_001)),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        // Without the slot there is nothing to order by
+        assert_eq!(
+            run_lp_ix(process_oracle_update, &mut ctx.clone(), &update[..9]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        // A newer reading goes through, up to the current slot
+        run_lp_ix(process_oracle_update, &mut ctx, &oracle_update(152_000_000, 1_000)).unwrap();
+        assert_eq!(last_oracle_slot(&ctx), 1_000);
+    }
+
+    #[test]
+    fn test_oracle_update_replay_in_later_slot_rejected() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        let update = oracle_update(151_000_000, 995);
+        run_lp_ix(process_oracle_update, &mut ctx, &update).unwrap();
+        // A newer price lands, then the first update is replayed in a later
+        // slot: it must not overwrite the newer price
+        run_lp_ix(process_oracle_update, &mut ctx, &oracle_update(152_000_000, 998)).unwrap();
+        assert_eq!(
+            run_lp_ix(process_oracle_update, &mut ctx, &update),
+            Err(ProgramError::Custom(JpyMatcherError::StaleUpdate as u32))
+        );
+        let price = &ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8];
+        assert_eq!(u64::from_le_bytes(price.try_into().unwrap()), 152_000_000);
+    }
+
+    #[test]
+    fn test_created_slot_survives_matches_and_updates() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = quote_ctx();
+        ctx[CREATED_SLOT_OFFSET..CREATED_SLOT_OFFSET + 8].copy_from_slice(&42u64.to_le_bytes());
+
+        run_lp_ix(process_match_with_compliance, &mut ctx, &[0x00]).unwrap();
+        run_lp_ix(process_oracle_update, &mut ctx, &oracle_update(151_000_000, 1_000)).unwrap();
+        run_lp_ix(process_reset_daily_volume, &mut ctx, &[0x04]).unwrap();
+        assert_eq!(read_created_slot(&ctx).unwrap(), 42);
+    }
+
 }
@@ -10,6 +10,13 @@ pub enum JpyMatcherError {
     OraclePriceNotSet = 0x105,
     ArithmeticOverflow = 0x106,
     InvalidComplianceData = 0x107,
+    InvalidLiquidityConfig = 0x108,
+    WhitelistStale = 0x109,
+    MatchReentered = 0x10A,
+    InvalidMode = 0x10B,
+    InvalidSpreadConfig = 0x10C,
+    StaleUpdate = 0x10D,
+    MissingWhitelistAccount = 0x10E,
 }
 
 impl From<JpyMatcherError> for ProgramError {
@@ -19,4 +19,26 @@ pub enum JpyMatcherInstruction {
     #[account(0, signer, name = "authority", desc = "Oracle update authority")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
     OracleUpdate,
+
+    /// Quote match - Match's compliance checks and pricing without recording volume
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account (return buffer only)")]
+    #[account(2, optional, name = "user_whitelist", desc = "User WhitelistEntry PDA")]
+    #[account(3, optional, name = "lp_whitelist", desc = "LP owner WhitelistEntry PDA")]
+    QuoteMatch,
+
+    /// Reset daily volume - zero the day's volume and restart the day window
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    ResetDailyVolume,
+
+    /// Query last fee - the fee (e6) charged by the last Match via return data, read-only and signer-free
+    #[account(0, name = "matcher_context", desc = "Matcher context account")]
+    QueryLastFee,
+
+    /// Sweep excess - move lamports above the rent-exempt minimum to a destination
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "destination", desc = "Receives the excess lamports")]
+    SweepExcess,
 }
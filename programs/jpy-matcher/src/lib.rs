@@ -3,13 +3,19 @@ use solana_program::{
     program_error::ProgramError, pubkey::Pubkey,
 };
 
+#[macro_use]
+extern crate matcher_shared;
+
 mod compliance;
 mod errors;
 mod instructions;
 mod pricing;
-mod state;
+pub mod state;
 
-use compliance::process_match_with_compliance;
+use compliance::{
+    process_match_with_compliance, process_query_last_fee, process_quote_match,
+    process_reset_daily_volume,
+};
 use pricing::process_init;
 
 entrypoint!(process_instruction);
@@ -25,17 +31,33 @@ pub fn process_instruction(
 
     match instruction_data[0] {
         0x00 => {
-            msg!("JPY-MATCHER: Match instruction");
+            matcher_log!("JPY-MATCHER: Match instruction");
             process_match_with_compliance(program_id, accounts, instruction_data)
         }
         0x02 => {
-            msg!("JPY-MATCHER: Init instruction");
+            matcher_log!("JPY-MATCHER: Init instruction");
             process_init(program_id, accounts, instruction_data)
         }
         0x03 => {
-            msg!("JPY-MATCHER: Oracle update instruction");
+            matcher_log!("JPY-MATCHER: Oracle update instruction");
             pricing::process_oracle_update(program_id, accounts, instruction_data)
         }
+        0x04 => {
+            matcher_log!("JPY-MATCHER: Reset daily volume instruction");
+            process_reset_daily_volume(program_id, accounts, instruction_data)
+        }
+        0x0B => {
+            matcher_log!("JPY-MATCHER: Quote match instruction");
+            process_quote_match(program_id, accounts, instruction_data)
+        }
+        0x0D => {
+            matcher_log!("JPY-MATCHER: Query last fee instruction");
+            process_query_last_fee(program_id, accounts, instruction_data)
+        }
+        0x0E => {
+            matcher_log!("JPY-MATCHER: Sweep excess instruction");
+            matcher_shared::process_sweep_excess(program_id, accounts, state::JPY_MATCHER_MAGIC, "JPY-MATCHER")
+        }
         _ => {
             msg!("JPY-MATCHER: Unknown instruction tag {}", instruction_data[0]);
             Err(ProgramError::InvalidInstructionData)
@@ -1,9 +1,10 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg,
-    program_error::ProgramError, pubkey::Pubkey,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
 
 use matcher_common::{verify_init_preconditions, write_header};
+use matcher_shared::{verify_ctx_owner, verify_ctx_size, verify_lp_pda_key};
 use crate::errors::JpyMatcherError;
 use crate::state::*;
 
@@ -25,23 +26,74 @@ use crate::state::*;
 ///   [57..61] impact_k_bps (u32 LE)
 ///   [61..77] liquidity_notional_e6 (u128 LE)
 ///   [77..93] max_fill_abs (u128 LE)
+///   [93..101]  promo_start_ts (i64 LE, optional)
+///   [101..109] promo_end_ts (i64 LE, optional, 0 = no promo)
+///   [109..113] promo_spread_bps (u32 LE, optional, must not exceed max_spread_bps)
+///   [113..117] whitelist_max_age_secs (u32 LE, optional, 0 = no freshness check)
+///   [117..121] min_spread_bps (u32 LE, optional, 0 = no floor; must not exceed max_spread_bps)
+///   [121]      reset_hour_utc (u8, optional, 0..=23)
+///   [122..124] whitelist_last_verified_offset (u16 LE, optional, 0 = DEFAULT_WHITELIST_LAST_VERIFIED_OFFSET;
+///              must not overlap the discriminator or the KYC fields)
 pub fn process_init(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 93 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
+    check_init_len(data.len())?;
 
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
+    verify_ctx_owner(ctx_account, program_id, "JPY-MATCHER")?;
     verify_init_preconditions(ctx_account, JPY_MATCHER_MAGIC, "JPY-MATCHER")?;
+    verify_ctx_size(ctx_account, "JPY-MATCHER")?;
+    verify_lp_pda_key(lp_pda.key, "JPY-MATCHER")?;
+
+    if data[1] > MAX_MODE {
+        msg!("JPY-MATCHER: Invalid mode {}, expected 0..={}", data[1], MAX_MODE);
+        return Err(JpyMatcherError::InvalidMode.into());
+    }
+
+    let impact_k = u32::from_le_bytes(data[57..61].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let liquidity = u128::from_le_bytes(data[61..77].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    validate_liquidity_config(impact_k, liquidity)?;
 
+    let max_spread = u32::from_le_bytes(data[44..48].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let min_spread = if data.len() >= 121 {
+        u32::from_le_bytes(data[117..121].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    if min_spread > max_spread {
+        msg!("JPY-MATCHER: min_spread_bps {} exceeds max_spread_bps {}", min_spread, max_spread);
+        return Err(JpyMatcherError::InvalidSpreadConfig.into());
+    }
+    // The promo replaces the capped spread outright, so it must respect the cap itself
+    let promo_spread = if data.len() >= 113 {
+        u32::from_le_bytes(data[109..113].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    if promo_spread > max_spread {
+        msg!("JPY-MATCHER: promo_spread_bps {} exceeds max_spread_bps {}", promo_spread, max_spread);
+        return Err(JpyMatcherError::InvalidSpreadConfig.into());
+    }
+
+    // Calendar-aligned daily cap (optional trailing config); absent = rolling 24h
+    let reset_hour_utc = data.get(121).copied();
+    if let Some(hour) = reset_hour_utc {
+        if hour > 23 {
+            msg!("JPY-MATCHER: reset_hour_utc {} out of range 0..=23", hour);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+    }
+
+    let last_verified_offset = parse_last_verified_offset(data)?;
+
+    let clock = Clock::get()?;
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
 
     write_header(&mut ctx_data, JPY_MATCHER_MAGIC, data[1], lp_pda.key);
@@ -85,13 +137,48 @@ pub fn process_init(
     ctx_data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&data[61..77]);
     ctx_data[MAX_FILL_OFFSET..MAX_FILL_OFFSET + 16].copy_from_slice(&data[77..93]);
 
+    // Promotional spread window (optional trailing config)
+    if data.len() >= 113 {
+        ctx_data[PROMO_START_TS_OFFSET..PROMO_START_TS_OFFSET + 8].copy_from_slice(&data[93..101]);
+        ctx_data[PROMO_END_TS_OFFSET..PROMO_END_TS_OFFSET + 8].copy_from_slice(&data[101..109]);
+        ctx_data[PROMO_SPREAD_OFFSET..PROMO_SPREAD_OFFSET + 4].copy_from_slice(&data[109..113]);
+    } else {
+        ctx_data[PROMO_START_TS_OFFSET..PROMO_SPREAD_OFFSET + 4].fill(0);
+    }
+
+    // Whitelist freshness (optional trailing config)
+    if data.len() >= 117 {
+        ctx_data[WHITELIST_MAX_AGE_OFFSET..WHITELIST_MAX_AGE_OFFSET + 4].copy_from_slice(&data[113..117]);
+    } else {
+        ctx_data[WHITELIST_MAX_AGE_OFFSET..WHITELIST_MAX_AGE_OFFSET + 4].fill(0);
+    }
+
+    // Spread floor (optional trailing config)
+    ctx_data[MIN_SPREAD_OFFSET..MIN_SPREAD_OFFSET + 4].copy_from_slice(&min_spread.to_le_bytes());
+
+    // Daily cap reset hour
+    ctx_data[DAY_ALIGNED_OFFSET] = reset_hour_utc.is_some() as u8;
+    ctx_data[RESET_HOUR_UTC_OFFSET] = reset_hour_utc.unwrap_or(0);
+
+    // No oracle update yet
+    ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+
+    // Creation stamp
+    ctx_data[CREATED_SLOT_OFFSET..CREATED_SLOT_OFFSET + 8].copy_from_slice(&clock.slot.to_le_bytes());
+
+    // No fill yet
+    ctx_data[LAST_FEE_OFFSET..LAST_FEE_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+
+    // Where the freshness check finds last_verified in a WhitelistEntry
+    ctx_data[LAST_VERIFIED_AT_OFFSET..LAST_VERIFIED_AT_OFFSET + 2].copy_from_slice(&last_verified_offset.to_le_bytes());
+
     // Zero reserved
-    ctx_data[232..CTX_SIZE].fill(0);
+    ctx_data[RESERVED_OFFSET..CTX_SIZE].fill(0);
 
     let base_spread = u32::from_le_bytes(data[36..40].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     let kyc_discount = u32::from_le_bytes(data[40..44].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
 
-    msg!(
+    matcher_log!(
         "INIT: lp_pda={} mode={} min_kyc={} base_spread={} kyc_discount={} blocked=0x{:02x}",
         lp_pda.key,
         data[1],
@@ -104,13 +191,40 @@ pub fn process_init(
     Ok(())
 }
 
+/// Optional `[122..124]` WhitelistEntry offset of `last_verified`; 0 when absent.
+/// An offset whose 8 bytes would overlap a field the matcher reads as something
+/// else is a misconfiguration, not a timestamp.
+fn parse_last_verified_offset(data: &[u8]) -> Result<u16, ProgramError> {
+    let Some(bytes) = data.get(122..124) else {
+        return Ok(0);
+    };
+    let offset = u16::from_le_bytes(bytes.try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let (start, end) = (offset as usize, offset as usize + 8);
+    if offset != 0 && WHITELIST_RESERVED_RANGES.iter().any(|&(lo, hi)| start < hi && lo < end) {
+        msg!("JPY-MATCHER: whitelist_last_verified_offset {} overlaps a WhitelistEntry field", offset);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(offset)
+}
+
+/// Reject impact_k > 0 with zero liquidity (would divide by zero once impact is priced).
+fn validate_liquidity_config(impact_k_bps: u32, liquidity_notional_e6: u128) -> Result<(), ProgramError> {
+    if impact_k_bps > 0 && liquidity_notional_e6 == 0 {
+        msg!("JPY-MATCHER: impact_k_bps={} requires nonzero liquidity", impact_k_bps);
+        return Err(JpyMatcherError::InvalidLiquidityConfig.into());
+    }
+    Ok(())
+}
+
 /// Tag 0x03: Update oracle price (JPY/USD)
 /// Accounts:
 ///   [0] Authority (signer)
 ///   [1] Matcher context account (writable)
 /// Data:
-///   [0]    tag (0x03)
-///   [1..9] new_oracle_price_e6 (u64 LE)
+///   [0]     tag (0x03)
+///   [1..9]  new_oracle_price_e6 (u64 LE)
+///   [9..17] update_slot (u64 LE): the slot the price was read at; must be
+///           after the stored last_oracle_slot and not past the current slot
 pub fn process_oracle_update(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -119,7 +233,7 @@ pub fn process_oracle_update(
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 9 {
+    if data.len() < 17 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
@@ -147,15 +261,241 @@ pub fn process_oracle_update(
     if new_price == 0 {
         return Err(JpyMatcherError::OraclePriceNotSet.into());
     }
+    let update_slot = u64::from_le_bytes(
+        data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
 
+    let clock = Clock::get()?;
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
     let old_price = u64::from_le_bytes(
         ctx_data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let last_oracle_slot = u64::from_le_bytes(
+        ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    check_oracle_update_slot(last_oracle_slot, update_slot, clock.slot)?;
     ctx_data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8]
         .copy_from_slice(&new_price.to_le_bytes());
+    ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&update_slot.to_le_bytes());
+
+    matcher_log!("ORACLE_SYNC: old={} new={}", old_price, new_price);
+
+    Ok(())
+}
+
+/// Only a price read at a strictly newer slot may replace the cached JPY/USD
+/// price. The slot travels in the update itself, so a replay is rejected in
+/// any later slot, not just the one it first landed in; a slot from the
+/// future is no reading at all.
+fn check_oracle_update_slot(last_oracle_slot: u64, update_slot: u64, slot: u64) -> ProgramResult {
+    if update_slot <= last_oracle_slot {
+        msg!("JPY-MATCHER: Stale oracle update for slot {} (last update for {})", update_slot, last_oracle_slot);
+        return Err(JpyMatcherError::StaleUpdate.into());
+    }
+    if update_slot > slot {
+        msg!("JPY-MATCHER: Oracle update for slot {} is past the current slot {}", update_slot, slot);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
 
-    msg!("ORACLE_SYNC: old={} new={}", old_price, new_price);
+/// Accepted init payload lengths: the base layout, then each optional
+/// trailing group (promo window, whitelist max age, min spread, reset hour,
+/// whitelist last_verified offset) in full.
+const INIT_DATA_LENS: [usize; 6] = [93, 113, 117, 121, 122, 124];
 
+/// Reject truncated optional groups and trailing garbage on init
+fn check_init_len(len: usize) -> Result<(), ProgramError> {
+    if !INIT_DATA_LENS.contains(&len) {
+        msg!("JPY-MATCHER: Init data length {}, expected one of {:?}", len, INIT_DATA_LENS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_init_len, process_init, validate_liquidity_config, INIT_DATA_LENS};
+    use crate::compliance::tests::FixedClock;
+    use crate::errors::JpyMatcherError;
+    use crate::state::{
+        read_created_slot, read_last_verified_offset, CTX_SIZE, DAY_ALIGNED_OFFSET, MAX_MODE, MIN_SPREAD_OFFSET,
+        MODE_OFFSET, RESERVED_OFFSET, RESET_HOUR_UTC_OFFSET,
+    };
+    use solana_program::{
+        account_info::AccountInfo, program_error::ProgramError, program_stubs::set_syscall_stubs,
+        pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_impact_without_liquidity_rejected() {
+        assert_eq!(
+            validate_liquidity_config(10, 0),
+            Err(ProgramError::Custom(JpyMatcherError::InvalidLiquidityConfig as u32))
+        );
+        assert!(validate_liquidity_config(10, 1_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_zero_impact_zero_liquidity_accepted() {
+        assert!(validate_liquidity_config(0, 0).is_ok());
+    }
+
+    #[test]
+    fn test_init_len_exact_only() {
+        for len in [93, 113, 117, 121, 122] {
+            assert!(check_init_len(len).is_ok(), "len {}", len);
+        }
+        // Short, partial optional group, or trailing garbage
+        for len in [92, 100, 114, 118, 123] {
+            assert_eq!(check_init_len(len), Err(ProgramError::InvalidInstructionData), "len {}", len);
+        }
+    }
+
+    /// Init payload with every optional trailing group present
+    fn full_init_data() -> Vec<u8> {
+        let mut data = vec![0x02, 0, 2, 1];
+        data.extend_from_slice(&[5u8; 32]); // KYC registry
+        data.extend_from_slice(&20u32.to_le_bytes()); // base spread
+        data.extend_from_slice(&5u32.to_le_bytes()); // KYC discount
+        data.extend_from_slice(&100u32.to_le_bytes()); // max spread
+        data.push(0b0000_0011); // blocked jurisdictions
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes()); // daily cap
+        data.extend_from_slice(&10u32.to_le_bytes()); // impact k
+        data.extend_from_slice(&10_000_000_000u128.to_le_bytes()); // liquidity
+        data.extend_from_slice(&1_000_000_000u128.to_le_bytes()); // max fill
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // promo start
+        data.extend_from_slice(&1_700_086_400i64.to_le_bytes()); // promo end
+        data.extend_from_slice(&5u32.to_le_bytes()); // promo spread
+        data.extend_from_slice(&(90 * 86_400u32).to_le_bytes()); // whitelist max age
+        data.extend_from_slice(&3u32.to_le_bytes()); // min spread
+        data.push(9); // daily cap reset hour (UTC)
+        data.extend_from_slice(&72u16.to_le_bytes()); // whitelist last_verified offset
+        data
+    }
+
+    fn init_over(fill: u8, data: &[u8]) -> Vec<u8> {
+        try_init_over(fill, data).unwrap()
+    }
+
+    /// Run process_init over a context buffer pre-filled with `fill`
+    fn try_init_over(fill: u8, data: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let (mut lp_data, mut ctx_data) = (Vec::new(), vec![fill; CTX_SIZE]);
+        {
+            let accounts = [
+                AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut ctx_data, &program_id, false, 0),
+            ];
+            process_init(&program_id, &accounts, data)?;
+        }
+        Ok(ctx_data)
+    }
+
+    #[test]
+    fn test_init_overwrites_every_byte() {
+        // Any byte that differs between a zero- and 0xFF-prefilled account is
+        // one the init path never wrote -- stale data leaking into a new market
+        let data = full_init_data();
+        for len in INIT_DATA_LENS {
+            let (zeros, ones) = (init_over(0x00, &data[..len]), init_over(0xFF, &data[..len]));
+            let leaked: Vec<usize> = (0..CTX_SIZE).filter(|&i| zeros[i] != ones[i]).collect();
+            assert!(leaked.is_empty(), "len {}: init left bytes {:?} unwritten", len, leaked);
+        }
+    }
+
+    #[test]
+    fn test_init_rejects_min_spread_above_max() {
+        let mut data = full_init_data();
+        let stored = init_over(0, &data);
+        assert_eq!(&stored[MIN_SPREAD_OFFSET..MIN_SPREAD_OFFSET + 4], &3u32.to_le_bytes());
+        // Floor equal to the cap is a fixed spread; one past it can never be met
+        data[117..121].copy_from_slice(&100u32.to_le_bytes());
+        assert!(try_init_over(0, &data).is_ok());
+        data[117..121].copy_from_slice(&101u32.to_le_bytes());
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(JpyMatcherError::InvalidSpreadConfig as u32))
+        );
+    }
+
+    #[test]
+    fn test_init_rejects_promo_spread_above_max() {
+        let mut data = full_init_data();
+        // A promo at the cap is allowed; past it would quote wider than max_spread_bps
+        data[109..113].copy_from_slice(&100u32.to_le_bytes());
+        assert!(try_init_over(0, &data).is_ok());
+        data[109..113].copy_from_slice(&101u32.to_le_bytes());
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(JpyMatcherError::InvalidSpreadConfig as u32))
+        );
+    }
+
+    #[test]
+    fn test_init_zeroes_reserved_tail() {
+        // Written is not enough: a reused account must not carry stale bytes
+        // into the reserved range a later layout version will claim
+        let data = full_init_data();
+        for len in INIT_DATA_LENS {
+            let ctx = init_over(0xFF, &data[..len]);
+            assert!(ctx[RESERVED_OFFSET..].iter().all(|&b| b == 0), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_init_last_verified_offset() {
+        // Absent or 0 reads the default; a configured offset is stored as given
+        assert_eq!(read_last_verified_offset(&init_over(0xFF, &full_init_data()[..122])).unwrap(), 64);
+        assert_eq!(read_last_verified_offset(&init_over(0xFF, &full_init_data())).unwrap(), 72);
+        let mut data = full_init_data();
+        data[122..124].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(read_last_verified_offset(&init_over(0xFF, &data)).unwrap(), 64);
+        // Never over the discriminator or the KYC level, expiry and jurisdiction fields
+        for offset in [4u16, 33, 48, 56] {
+            data[122..124].copy_from_slice(&offset.to_le_bytes());
+            assert_eq!(try_init_over(0, &data), Err(ProgramError::InvalidInstructionData), "offset {}", offset);
+        }
+        data[122..124].copy_from_slice(&57u16.to_le_bytes());
+        assert!(try_init_over(0, &data).is_ok());
+    }
+
+    #[test]
+    fn test_init_stamps_created_slot() {
+        let ctx = init_over(0xFF, &full_init_data());
+        assert_eq!(read_created_slot(&ctx).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_init_reset_hour() {
+        let mut data = full_init_data();
+        let stored = init_over(0, &data);
+        assert_eq!((stored[DAY_ALIGNED_OFFSET], stored[RESET_HOUR_UTC_OFFSET]), (1, 9));
+        // Omitted: the cap keeps rolling 24h from the first trade
+        let stored = init_over(0xFF, &data[..121]);
+        assert_eq!((stored[DAY_ALIGNED_OFFSET], stored[RESET_HOUR_UTC_OFFSET]), (0, 0));
+        data[121] = 24;
+        assert_eq!(try_init_over(0, &data), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_init_rejects_unknown_mode() {
+        let mut data = full_init_data();
+        for mode in 0..=MAX_MODE {
+            data[1] = mode;
+            assert_eq!(init_over(0, &data)[MODE_OFFSET], mode);
+        }
+        // An unknown mode would otherwise be stored and silently priced as mode 0
+        data[1] = 200;
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(JpyMatcherError::InvalidMode as u32))
+        );
+    }
+}
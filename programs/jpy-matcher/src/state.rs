@@ -1,3 +1,5 @@
+use solana_program::program_error::ProgramError;
+
 pub use matcher_common::{CTX_SIZE, verify_magic as verify_magic_generic};
 
 /// Magic bytes: "JPYMATCH" as u64 LE
@@ -10,6 +12,7 @@ pub const VERSION_OFFSET: usize = 72;             // u32
 pub const MODE_OFFSET: usize = 76;                // u8: 0=PassiveKYC, 1=vAMMKYC
 pub const MIN_KYC_LEVEL_OFFSET: usize = 77;       // u8: 0=Basic..3=Institutional
 pub const REQUIRE_SAME_JURISDICTION_OFFSET: usize = 78; // u8: 0 or 1
+pub const MATCH_GUARD_OFFSET: usize = 79;         // u8: 1 while a match is in flight (reentrancy guard)
 pub const KYC_REGISTRY_OFFSET: usize = 112;       // Pubkey (32)
 pub const BASE_SPREAD_OFFSET: usize = 144;        // u32
 pub const KYC_DISCOUNT_OFFSET: usize = 148;       // u32: fee discount for Institutional
@@ -22,12 +25,51 @@ pub const DAY_RESET_TIMESTAMP_OFFSET: usize = 188; // i64
 pub const IMPACT_K_OFFSET: usize = 196;           // u32
 pub const LIQUIDITY_OFFSET: usize = 200;          // u128 (16 bytes)
 pub const MAX_FILL_OFFSET: usize = 216;           // u128 (16 bytes)
-// 232..320 = reserved
+pub const PROMO_START_TS_OFFSET: usize = 232;     // i64
+pub const PROMO_END_TS_OFFSET: usize = 240;       // i64 (exclusive, 0 = no promo)
+pub const PROMO_SPREAD_OFFSET: usize = 248;       // u32: spread override inside the window
+pub const WHITELIST_MAX_AGE_OFFSET: usize = 252;  // u32: max seconds since KYC re-verification (0 = off)
+pub const MIN_SPREAD_OFFSET: usize = 256;         // u32: floor on the final spread (0 = off)
+pub const DAY_ALIGNED_OFFSET: usize = 260;        // u8: 1 = daily cap resets at reset_hour_utc, 0 = rolling 24h
+pub const RESET_HOUR_UTC_OFFSET: usize = 261;     // u8: 0..=23
+pub const LAST_ORACLE_SLOT_OFFSET: usize = 262;   // u64: slot of the last oracle update (0 = never)
+pub const CREATED_SLOT_OFFSET: usize = 270;       // u64: slot init ran at (never rewritten)
+pub const LAST_FEE_OFFSET: usize = 278;           // u64: fee (e6) charged on the last match
+pub const LAST_VERIFIED_AT_OFFSET: usize = 286;   // u16: WhitelistEntry offset of last_verified (0 = the default)
+pub const RESERVED_OFFSET: usize = 288;           // 288..320 = reserved, zero after init
+
+// Reserved tail starts right after the last field
+const _: () = assert!(RESERVED_OFFSET == LAST_VERIFIED_AT_OFFSET + 2 && RESERVED_OFFSET <= CTX_SIZE);
+
+/// Seconds in a daily-cap window
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Return region slot for the absolute fee (e6) charged on the fill
+pub const FEE_RETURN_OFFSET: usize = 8;           // u64
 
 /// Meridian WhitelistEntry offsets (from transfer-hook state)
 pub const WHITELIST_KYC_LEVEL_OFFSET: usize = 40;
 pub const WHITELIST_EXPIRY_OFFSET: usize = 48;
 pub const WHITELIST_JURISDICTION_OFFSET: usize = 56;
+/// Where the freshness check reads `last_verified` (i64 unix seconds) unless
+/// Init configures another offset. The offsets above come from Meridian's
+/// transfer-hook state; this one does not: it is the first 8-aligned slot past
+/// the jurisdiction byte, an assumption a deployment whose entries keep the
+/// stamp elsewhere overrides at Init.
+pub const DEFAULT_WHITELIST_LAST_VERIFIED_OFFSET: usize = 64;
+/// Bytes the freshness check must never read `last_verified` from: the
+/// account discriminator and the KYC level, expiry and jurisdiction fields
+pub const WHITELIST_RESERVED_RANGES: [(usize, usize); 2] = [(0, 8), (WHITELIST_KYC_LEVEL_OFFSET, WHITELIST_JURISDICTION_OFFSET + 1)];
+
+/// The WhitelistEntry offset the freshness check reads `last_verified` from
+pub fn read_last_verified_offset(ctx_data: &[u8]) -> Result<usize, ProgramError> {
+    let offset = u16::from_le_bytes(
+        ctx_data[LAST_VERIFIED_AT_OFFSET..LAST_VERIFIED_AT_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok(if offset == 0 { DEFAULT_WHITELIST_LAST_VERIFIED_OFFSET } else { offset as usize })
+}
 
 /// KYC levels
 #[allow(dead_code)]
@@ -38,6 +80,64 @@ pub const KYC_STANDARD: u8 = 1;
 pub const KYC_ENHANCED: u8 = 2;
 pub const KYC_INSTITUTIONAL: u8 = 3;
 
+/// Highest valid mode byte (0=PassiveKYC, 1=vAMMKYC)
+pub const MAX_MODE: u8 = 1;
+
 pub fn verify_magic(ctx_data: &[u8]) -> bool {
     verify_magic_generic(ctx_data, JPY_MATCHER_MAGIC)
 }
+
+/// Daily volume state: `(cap_e6, current_day_volume_e6, day_reset_timestamp)`.
+/// `current` is as stored -- it isn't rolled over until the next match.
+pub fn read_daily_volume(ctx_data: &[u8]) -> Result<(u64, u64, i64), ProgramError> {
+    let cap = u64::from_le_bytes(
+        ctx_data[DAILY_VOLUME_CAP_OFFSET..DAILY_VOLUME_CAP_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let current = u64::from_le_bytes(
+        ctx_data[CURRENT_DAY_VOLUME_OFFSET..CURRENT_DAY_VOLUME_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let reset_ts = i64::from_le_bytes(
+        ctx_data[DAY_RESET_TIMESTAMP_OFFSET..DAY_RESET_TIMESTAMP_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok((cap, current, reset_ts))
+}
+
+/// Slot this context was initialized at. Written once by init, for audits
+/// and analytics that need the market's age; no instruction reads it back.
+pub fn read_created_slot(ctx_data: &[u8]) -> Result<u64, ProgramError> {
+    Ok(u64::from_le_bytes(
+        ctx_data[CREATED_SLOT_OFFSET..CREATED_SLOT_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Fee (e6) the last Match charged, rounded as in `matcher_shared::trade_fee`
+pub fn read_last_fee(ctx_data: &[u8]) -> Result<u64, ProgramError> {
+    Ok(u64::from_le_bytes(
+        ctx_data[LAST_FEE_OFFSET..LAST_FEE_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_magic_matches_shared_layout() {
+        assert_eq!(matcher_shared::matcher_magic(matcher_shared::MatcherKind::Jpy), JPY_MATCHER_MAGIC);
+        // A context this program initialized is identified as its own
+        let mut data = vec![0u8; CTX_SIZE];
+        matcher_common::write_header(&mut data, JPY_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(matcher_shared::identify(&data), Some(matcher_shared::MatcherKind::Jpy));
+    }
+}
@@ -1,3 +1,4 @@
+use matcher_shared::SidePriceError;
 use solana_program::program_error::ProgramError;
 
 #[derive(Debug, Clone, Copy)]
@@ -9,6 +10,18 @@ pub enum MacroMatcherError {
     InvalidSignalSeverity = 0x304,
     ArithmeticOverflow = 0x305,
     InvalidIndexValue = 0x306,
+    InvalidLiquidityConfig = 0x307,
+    InvalidHedgeReference = 0x308,
+    OracleSlotMismatch = 0x309,
+    UnauthorizedKeeper = 0x30A,
+    MatchReentered = 0x30B,
+    SlippageExceeded = 0x30C,
+    InvalidMode = 0x30D,
+    StaleUpdate = 0x30E,
+    SpreadTooWide = 0x30F,
+    UnsupportedVersion = 0x310,
+    IndexOutOfBounds = 0x311,
+    LiquidityExhausted = 0x312,
 }
 
 impl From<MacroMatcherError> for ProgramError {
@@ -16,3 +29,12 @@ impl From<MacroMatcherError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+
+impl From<SidePriceError> for MacroMatcherError {
+    fn from(e: SidePriceError) -> Self {
+        match e {
+            SidePriceError::Overflow => MacroMatcherError::ArithmeticOverflow,
+            SidePriceError::SpreadTooWide => MacroMatcherError::SpreadTooWide,
+        }
+    }
+}
@@ -6,20 +6,54 @@ pub enum MacroMatcherInstruction {
     /// Execute match — compute regime-adjusted execution price for real rate perp
     #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes)")]
+    #[account(2, optional, name = "keys", desc = "Keys PDA [\"keys\", context] (required when a hedge reference is configured)")]
+    #[account(3, optional, name = "hedge_reference", desc = "Vol-matcher context (required when a hedge reference is configured)")]
+    #[account(4, optional, name = "macro_oracle", desc = "Macro oracle account (enables the slot-stamp cross-check)")]
     Match,
 
     /// Initialize macro matcher context
     #[account(0, name = "lp_pda", desc = "LP PDA to store")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes, writable)")]
+    #[account(2, optional, signer, writable, name = "payer", desc = "Pays for the keys PDA (only when a keeper authority or hedge reference is supplied)")]
+    #[account(3, optional, writable, name = "keys", desc = "Keys PDA [\"keys\", context] (only when a keeper authority or hedge reference is supplied)")]
+    #[account(4, optional, name = "system_program", desc = "System program (only when a keeper authority or hedge reference is supplied)")]
+    #[account(5, optional, name = "hedge_reference", desc = "Vol-matcher context (required when a hedge reference is supplied)")]
+    #[account(6, optional, name = "vol_matcher_program", desc = "Vol-matcher program owning the hedge reference (required with it)")]
     Init,
 
     /// Sync index — keeper updates real rate index and signal intelligence
     #[account(0, writable, name = "matcher_context", desc = "Matcher context account")]
     #[account(1, name = "macro_oracle", desc = "Authorized macro oracle account")]
+    #[account(2, optional, signer, name = "keeper_authority", desc = "Index sync keeper (required signer when configured)")]
+    #[account(3, optional, name = "keys", desc = "Keys PDA [\"keys\", context] (required when a keeper authority is configured)")]
     IndexSync,
 
     /// Update regime — change macro regime (requires oracle signer)
     #[account(0, writable, name = "matcher_context", desc = "Matcher context account")]
     #[account(1, signer, name = "macro_oracle", desc = "Authorized macro oracle account (must be signer)")]
     RegimeUpdate,
+
+    /// Replenish — LP frees consumed liquidity (an amount, or all of it) so depleted matching resumes
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    Replenish,
+
+    /// Batch match — price up to 8 sized, directed basket legs in order with a single LP PDA check
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes)")]
+    #[account(2, optional, name = "keys", desc = "Keys PDA [\"keys\", context] (required when a hedge reference is configured)")]
+    #[account(3, optional, name = "hedge_reference", desc = "Vol-matcher context (required when a hedge reference is configured)")]
+    MatchBatch,
+
+    /// Query spread — bid and ask spreads Match would charge at the stored state as return data; signer-free, no state changes
+    #[account(0, name = "matcher_context", desc = "Matcher context account")]
+    #[account(1, optional, name = "keys", desc = "Keys PDA [\"keys\", context] (required when a hedge reference is configured)")]
+    #[account(2, optional, name = "hedge_reference", desc = "Vol-matcher context (required when a hedge reference is configured)")]
+    QuerySpread,
+
+    /// Sweep excess - move lamports above the rent-exempt minimum to a destination
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "destination", desc = "Receives the excess lamports")]
+    SweepExcess,
 }
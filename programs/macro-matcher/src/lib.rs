@@ -3,12 +3,18 @@ use solana_program::{
     program_error::ProgramError, pubkey::Pubkey,
 };
 
+#[macro_use]
+extern crate matcher_shared;
+
 mod errors;
 mod instructions;
 mod state;
 mod pricing;
 
-use pricing::{process_init, process_match, process_index_sync, process_regime_update};
+use pricing::{
+    process_init, process_match, process_match_batch, process_index_sync, process_query_spread,
+    process_regime_update, process_replenish,
+};
 
 entrypoint!(process_instruction);
 
@@ -23,21 +29,37 @@ pub fn process_instruction(
 
     match instruction_data[0] {
         0x00 => {
-            msg!("MACRO-MATCHER: Match instruction");
+            matcher_log!("MACRO-MATCHER: Match instruction");
             process_match(program_id, accounts, instruction_data)
         }
         0x02 => {
-            msg!("MACRO-MATCHER: Init instruction");
+            matcher_log!("MACRO-MATCHER: Init instruction");
             process_init(program_id, accounts, instruction_data)
         }
         0x03 => {
-            msg!("MACRO-MATCHER: Index sync instruction");
+            matcher_log!("MACRO-MATCHER: Index sync instruction");
             process_index_sync(program_id, accounts, instruction_data)
         }
         0x04 => {
-            msg!("MACRO-MATCHER: Regime update instruction");
+            matcher_log!("MACRO-MATCHER: Regime update instruction");
             process_regime_update(program_id, accounts, instruction_data)
         }
+        0x05 => {
+            matcher_log!("MACRO-MATCHER: Replenish instruction");
+            process_replenish(program_id, accounts, instruction_data)
+        }
+        0x0A => {
+            matcher_log!("MACRO-MATCHER: Match batch instruction");
+            process_match_batch(program_id, accounts, instruction_data)
+        }
+        0x0D => {
+            matcher_log!("MACRO-MATCHER: Query spread instruction");
+            process_query_spread(program_id, accounts, instruction_data)
+        }
+        0x0E => {
+            matcher_log!("MACRO-MATCHER: Sweep excess instruction");
+            matcher_shared::process_sweep_excess(program_id, accounts, state::MACRO_MATCHER_MAGIC, "MACRO-MATCHER")
+        }
         _ => {
             msg!("MACRO-MATCHER: Unknown instruction tag {}", instruction_data[0]);
             Err(ProgramError::InvalidInstructionData)
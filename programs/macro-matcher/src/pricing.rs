@@ -1,12 +1,13 @@
 use solana_program::{
     account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
-    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+    program::set_return_data, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
 
 use matcher_common::{
-    compute_exec_price, verify_init_preconditions, verify_lp_pda as verify_lp_pda_common,
+    verify_init_preconditions, verify_lp_pda as verify_lp_pda_common,
     write_exec_price, write_header,
 };
+use matcher_shared::{bump_stats, create_pda_account, side_price, verify_ctx_owner, verify_ctx_size, verify_lp_pda_key};
 
 use crate::errors::MacroMatcherError;
 use crate::state::*;
@@ -15,6 +16,11 @@ use crate::state::*;
 /// Accounts:
 ///   [0] LP PDA (signer)
 ///   [1] Matcher context account (writable, 320 bytes)
+///   [2] Payer (signer, writable; only when a keeper authority or hedge reference is supplied)
+///   [3] Keys PDA (writable, ["keys", context]; only when a keeper authority or hedge reference is supplied)
+///   [4] System program (only when a keeper authority or hedge reference is supplied)
+///   [5] Hedge reference context (read, only when a hedge reference is supplied)
+///   [6] Vol-matcher program (executable, owner of [5]; required with [5])
 /// Data layout:
 ///   [0]    tag (0x02)
 ///   [1]    mode (u8: 0=RealRate, 1=HousingRatio)
@@ -25,34 +31,91 @@ use crate::state::*;
 ///   [18..34] liquidity_notional_e6 (u128 LE)
 ///   [34..50] max_fill_abs (u128 LE)
 ///   [50..82] macro_oracle pubkey (32 bytes)
+///   [82..84] skew_k_bps (u16 LE, optional, 0 = no inventory skew)
+///   [84..86] max_skew_bps (u16 LE, optional)
+///   [86..118] hedge_reference pubkey (32 bytes, optional, zero = no hedge reference)
+///   [118..120] hedge_vol_threshold_bps (u16 LE, optional)
+///   [120..122] hedge_spread_bps (u16 LE, optional)
+///   [122..124] oracle_slot_stamp_offset (u16 LE, optional, 0 = no stamp check)
+///   [124]      oracle_slot_tolerance (u8, optional)
+///   [125..157] keeper_authority pubkey (32 bytes, optional, zero = oracle key match alone authorizes index syncs)
+///   [157..165] regime_multipliers_pct ([u16 LE; 4] by regime, optional, all zero = built-in table, else all nonzero)
+///   [165]      rounding_mode (u8, optional: 0=floor, 1=LP-favorable)
+///   [166..168] static_skew_bps (i16 LE, optional, 0 = symmetric quotes)
+///   [168..170] max_bid_spread_bps (u16 LE, optional, 0 = max_spread_bps, below 10000)
+///   [170..172] max_ask_spread_bps (u16 LE, optional, 0 = max_spread_bps)
+///   [172..176] index_min_e6 (u32 LE, optional, 0 = no floor)
+///   [176..180] index_max_e6 (u32 LE, optional, 0 = no ceiling, else at least index_min_e6)
 pub fn process_init(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 82 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
+    check_init_len(data.len())?;
 
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
     // Verify writable, sized, and not already initialized
+    verify_ctx_owner(ctx_account, program_id, "MACRO-MATCHER")?;
     verify_init_preconditions(ctx_account, MACRO_MATCHER_MAGIC, "MACRO-MATCHER")?;
+    verify_ctx_size(ctx_account, "MACRO-MATCHER")?;
+    verify_lp_pda_key(lp_pda.key, "MACRO-MATCHER")?;
+
+    if data[1] > MAX_MODE {
+        msg!("MACRO-MATCHER: Invalid mode {}, expected 0..={}", data[1], MAX_MODE);
+        return Err(MacroMatcherError::InvalidMode.into());
+    }
+
+    let base_spread = init_u32(data, 2)?;
+    let regime_spread = init_u32(data, 6)?;
+    let max_spread = init_u32(data, 10)?;
+    let impact_k = init_u32(data, 14)?;
+    let liquidity = u128::from_le_bytes(data[18..34].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    validate_liquidity_config(impact_k, liquidity)?;
+
+    let hedge_ref = if data.len() >= 122 { init_pubkey(data, 86)? } else { Pubkey::default() };
+    if hedge_ref != Pubkey::default() {
+        verify_hedge_reference_owner(&hedge_ref, accounts.get(5..).unwrap_or_default())?;
+    }
+    let keeper = if data.len() >= 157 { init_pubkey(data, 125)? } else { Pubkey::default() };
+
+    let multipliers = if data.len() >= 165 { parse_regime_multipliers(&data[157..165])? } else { [0; 4] };
+
+    let rounding_mode = if data.len() >= 166 { data[165] } else { ROUNDING_FLOOR };
+    if rounding_mode > ROUNDING_LP_FAVORABLE {
+        msg!("MACRO-MATCHER: Invalid rounding mode {}", rounding_mode);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // A bid spread of 10_000 bps or more would price the bid at zero
+    if data.len() >= 172 {
+        let max_bid_spread = u16::from_le_bytes([data[168], data[169]]);
+        if max_bid_spread >= 10_000 {
+            msg!("MACRO-MATCHER: max_bid_spread_bps {} must be below 10000", max_bid_spread);
+            return Err(MacroMatcherError::SpreadTooWide.into());
+        }
+    }
+
+    let (index_min, index_max) = if data.len() >= 180 { parse_index_bounds(&data[172..180])? } else { (0, 0) };
+
+    let keys = create_keys(program_id, accounts, keeper, hedge_ref)?;
 
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
 
     // Write standard header (return data, magic, version, mode, padding, LP PDA)
     write_header(&mut ctx_data, MACRO_MATCHER_MAGIC, data[1], lp_pda.key);
+    ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&CTX_VERSION.to_le_bytes());
+    ctx_data[KEYS_OFFSET] = keys;
 
     // Spread params
-    ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&data[2..6]);
-    ctx_data[REGIME_SPREAD_OFFSET..REGIME_SPREAD_OFFSET + 4].copy_from_slice(&data[6..10]);
-    ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&data[10..14]);
-    ctx_data[IMPACT_K_OFFSET..IMPACT_K_OFFSET + 4].copy_from_slice(&data[14..18]);
+    ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&base_spread.to_le_bytes());
+    ctx_data[REGIME_SPREAD_OFFSET..REGIME_SPREAD_OFFSET + 4].copy_from_slice(&regime_spread.to_le_bytes());
+    ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&max_spread.to_le_bytes());
+    ctx_data[IMPACT_K_OFFSET..IMPACT_K_OFFSET + 4].copy_from_slice(&impact_k.to_le_bytes());
 
     // Initialize index data to zero (oracle not yet synced)
     ctx_data[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8]
@@ -62,7 +125,7 @@ pub fn process_init(
     ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
         .copy_from_slice(&0u64.to_le_bytes());
     ctx_data[REGIME_OFFSET] = 1; // Stagnation (default)
-    ctx_data[REGIME_OFFSET + 1..REGIME_OFFSET + 8].fill(0); // padding
+    ctx_data[ORACLE_SLOT_TOLERANCE_OFFSET] = 0;
 
     // Signal (init to none)
     ctx_data[SIGNAL_SEVERITY_OFFSET..SIGNAL_SEVERITY_OFFSET + 8]
@@ -83,33 +146,222 @@ pub fn process_init(
     ctx_data[TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8]
         .copy_from_slice(&0u64.to_le_bytes());
 
-    // Zero reserved
-    ctx_data[264..CTX_SIZE].fill(0);
+    // Inventory skew (optional trailing config)
+    ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&0i64.to_le_bytes());
+    if data.len() >= 86 {
+        ctx_data[SKEW_K_OFFSET..SKEW_K_OFFSET + 2].copy_from_slice(&data[82..84]);
+        ctx_data[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2].copy_from_slice(&data[84..86]);
+    } else {
+        ctx_data[SKEW_K_OFFSET..MAX_SKEW_OFFSET + 2].fill(0);
+    }
+
+    // Hedge widening (optional trailing config; the reference itself is in the keys PDA)
+    if data.len() >= 122 {
+        ctx_data[HEDGE_VOL_THRESHOLD_OFFSET..HEDGE_VOL_THRESHOLD_OFFSET + 2].copy_from_slice(&data[118..120]);
+        ctx_data[HEDGE_SPREAD_OFFSET..HEDGE_SPREAD_OFFSET + 2].copy_from_slice(&data[120..122]);
+    } else {
+        ctx_data[HEDGE_VOL_THRESHOLD_OFFSET..HEDGE_SPREAD_OFFSET + 2].fill(0);
+    }
+
+    // Oracle slot-stamp cross-check (optional trailing config)
+    if data.len() >= 125 {
+        ctx_data[ORACLE_SLOT_STAMP_OFFSET..ORACLE_SLOT_STAMP_OFFSET + 2].copy_from_slice(&data[122..124]);
+        ctx_data[ORACLE_SLOT_TOLERANCE_OFFSET] = data[124];
+    } else {
+        ctx_data[ORACLE_SLOT_STAMP_OFFSET..ORACLE_SLOT_STAMP_OFFSET + 2].fill(0);
+    }
+
+    // Regime spread multiplier table (optional trailing config)
+    for (i, multiplier) in multipliers.iter().enumerate() {
+        let offset = REGIME_MULTIPLIERS_OFFSET + i * 2;
+        ctx_data[offset..offset + 2].copy_from_slice(&multiplier.to_le_bytes());
+    }
+
+    // Exec price rounding (optional trailing config)
+    ctx_data[ROUNDING_MODE_OFFSET] = rounding_mode;
+
+    // Static spread skew (optional trailing config)
+    if data.len() >= 168 {
+        ctx_data[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2].copy_from_slice(&data[166..168]);
+    } else {
+        ctx_data[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2].fill(0);
+    }
+
+    // Per-side spread caps (optional trailing config)
+    if data.len() >= 172 {
+        ctx_data[MAX_BID_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].copy_from_slice(&data[168..172]);
+    } else {
+        ctx_data[MAX_BID_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].fill(0);
+    }
+
+    // Absolute index bounds (optional trailing config)
+    ctx_data[INDEX_MIN_OFFSET..INDEX_MIN_OFFSET + 4].copy_from_slice(&index_min.to_le_bytes());
+    ctx_data[INDEX_MAX_OFFSET..INDEX_MAX_OFFSET + 4].copy_from_slice(&index_max.to_le_bytes());
 
-    let base_spread_val = u32::from_le_bytes(data[2..6].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
-    let regime_spread_val = u32::from_le_bytes(data[6..10].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
-    let max_spread_val = u32::from_le_bytes(data[10..14].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    // Liquidity depletion (nothing consumed yet)
+    ctx_data[CONSUMED_LIQUIDITY_OFFSET..CONSUMED_LIQUIDITY_OFFSET + 16].copy_from_slice(&0u128.to_le_bytes());
 
-    msg!(
+    // Reserved tail
+    ctx_data[ORACLE_SLOT_STAMP_OFFSET + 2..CTX_SIZE].fill(0);
+
+    matcher_log!(
         "INIT: lp_pda={} mode={} base_spread={} regime_spread={} max_spread={}",
         lp_pda.key,
         data[1],
-        base_spread_val,
-        regime_spread_val,
-        max_spread_val,
+        base_spread,
+        regime_spread,
+        max_spread,
     );
 
     Ok(())
 }
 
+fn init_u32(data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(data[offset..offset + 4].try_into().map_err(|_| ProgramError::InvalidInstructionData)?))
+}
+
+fn init_pubkey(data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    Ok(Pubkey::new_from_array(data[offset..offset + 32].try_into().map_err(|_| ProgramError::InvalidInstructionData)?))
+}
+
+/// Create the keys PDA when Init supplies a keeper authority or hedge
+/// reference, returning the KEYS flags to store. With neither supplied nothing
+/// is created and the accounts past [1] aren't needed.
+fn create_keys(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    keeper: Pubkey,
+    hedge_ref: Pubkey,
+) -> Result<u8, ProgramError> {
+    let mut flags = 0;
+    if keeper != Pubkey::default() {
+        flags |= KEY_KEEPER;
+    }
+    if hedge_ref != Pubkey::default() {
+        flags |= KEY_HEDGE_REF;
+    }
+    if flags == 0 {
+        return Ok(0);
+    }
+    if accounts.len() < 5 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let ctx_account = &accounts[1];
+    let payer = &accounts[2];
+    let keys = &accounts[3];
+    let system_program = &accounts[4];
+
+    if !payer.is_signer {
+        msg!("MACRO-MATCHER: Payer must be a signer to create the keys");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected, bump) = Pubkey::find_program_address(&[KEYS_SEED, ctx_account.key.as_ref()], program_id);
+    if *keys.key != expected {
+        msg!("MACRO-MATCHER: Keys must be the PDA {}", expected);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !keys.data_is_empty() {
+        msg!("MACRO-MATCHER: Keys already set for {}", ctx_account.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        keys,
+        system_program,
+        KEYS_SIZE,
+        &[KEYS_SEED, ctx_account.key.as_ref(), &[bump]],
+    )?;
+
+    let mut keys_data = keys.try_borrow_mut_data()?;
+    keys_data[..KEYS_SIZE].fill(0);
+    keys_data[KEYS_MAGIC_OFFSET..KEYS_MAGIC_OFFSET + 8].copy_from_slice(&KEYS_MAGIC.to_le_bytes());
+    keys_data[KEYS_BUMP_OFFSET] = bump;
+    keys_data[KEEPER_AUTHORITY_OFFSET..KEEPER_AUTHORITY_OFFSET + 32].copy_from_slice(keeper.as_ref());
+    keys_data[HEDGE_REF_OFFSET..HEDGE_REF_OFFSET + 32].copy_from_slice(hedge_ref.as_ref());
+
+    matcher_log!("INIT_KEYS: ctx={} keeper={} hedge_ref={}", ctx_account.key, keeper, hedge_ref);
+
+    Ok(flags)
+}
+
+/// The hedge reference must be a context owned by the vol-matcher program
+/// passed alongside it. Vol-matcher never reassigns or closes a context, so
+/// the owner can't change once checked here; Match re-checks the key and magic.
+fn verify_hedge_reference_owner(hedge_key: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let [hedge_ref, vol_program, ..] = accounts else {
+        msg!("MACRO-MATCHER: Hedge reference and vol-matcher program accounts required");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if hedge_ref.key != hedge_key {
+        msg!("MACRO-MATCHER: Hedge reference mismatch: {}", hedge_ref.key);
+        return Err(MacroMatcherError::InvalidHedgeReference.into());
+    }
+    if !vol_program.executable || hedge_ref.owner != vol_program.key {
+        msg!("MACRO-MATCHER: Hedge reference {} is not owned by program {}", hedge_ref.key, vol_program.key);
+        return Err(MacroMatcherError::InvalidHedgeReference.into());
+    }
+    let ref_data = hedge_ref.try_borrow_data()?;
+    if ref_data.len() < CTX_SIZE || !verify_magic_generic(&ref_data, HEDGE_REF_MAGIC) {
+        msg!("MACRO-MATCHER: Hedge reference is not a vol-matcher context");
+        return Err(MacroMatcherError::InvalidHedgeReference.into());
+    }
+    Ok(())
+}
+
+/// Operator multiplier table: all zero keeps the built-in one; otherwise every
+/// regime needs a multiplier, since a 0 would silently drop the regime spread
+fn parse_regime_multipliers(data: &[u8]) -> Result<[u16; 4], ProgramError> {
+    let mut multipliers = [0u16; 4];
+    for (multiplier, bytes) in multipliers.iter_mut().zip(data.chunks_exact(2)) {
+        *multiplier = u16::from_le_bytes([bytes[0], bytes[1]]);
+    }
+    if multipliers.contains(&0) && multipliers != [0; 4] {
+        msg!("MACRO-MATCHER: Regime multiplier table {:?} has a zero entry", multipliers);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(multipliers)
+}
+
+/// `(index_min_e6, index_max_e6)`; zero leaves that side open. A set ceiling
+/// below the floor would reject every sync, so it's refused at init.
+fn parse_index_bounds(data: &[u8]) -> Result<(u32, u32), ProgramError> {
+    let index_min = u32::from_le_bytes(data[0..4].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let index_max = u32::from_le_bytes(data[4..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    if index_max != 0 && index_max < index_min {
+        msg!("MACRO-MATCHER: index_max_e6 {} is below index_min_e6 {}", index_max, index_min);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok((index_min, index_max))
+}
+
+/// impact_k_bps > 0 requires liquidity_notional_e6 > 0; both zero disables impact.
+fn validate_liquidity_config(impact_k_bps: u32, liquidity_notional_e6: u128) -> Result<(), ProgramError> {
+    if impact_k_bps > 0 && liquidity_notional_e6 == 0 {
+        msg!("MACRO-MATCHER: impact_k_bps={} requires nonzero liquidity", impact_k_bps);
+        return Err(MacroMatcherError::InvalidLiquidityConfig.into());
+    }
+    Ok(())
+}
+
 /// Tag 0x00: Execute match — compute regime-adjusted execution price
 /// Accounts:
 ///   [0] LP PDA (signer)
 ///   [1] Matcher context account (writable)
+///   [2] Keys PDA (read, required when a hedge reference is configured)
+///   [3] Hedge reference context (read, required when configured)
+///   [..] Macro oracle account (read, optional — enables the slot-stamp check)
+/// Data layout:
+///   [0]    tag (0x00)
+///   [1..9] trade_size_abs (u64 LE, optional)
+///   [9]    direction (u8, optional: 0=buy from LP, 1=sell to LP)
+///   [10..18] limit_price (u64 LE, optional: worst acceptable exec price for the direction)
 pub fn process_match(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -118,37 +370,264 @@ pub fn process_match(
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
-    // Verify LP PDA signature, magic, and PDA match
+    // Verify LP PDA signature, magic, PDA match, and context version
+    verify_ctx_owner(ctx_account, program_id, "MACRO-MATCHER")?;
     verify_lp_pda_common(lp_pda, ctx_account, MACRO_MATCHER_MAGIC, "MACRO-MATCHER")?;
+    check_ctx_version(&ctx_account.try_borrow_data()?)?;
+
+    // Reentrancy guard: held until this match completes
+    enter_match_guard(&mut ctx_account.try_borrow_mut_data()?)?;
+
+    let trade = parse_trade(data)?;
+    let direction = trade.map_or(DIRECTION_BUY, |(_, d)| d);
 
-    // Read pricing parameters
+    let clock = Clock::get()?;
     let ctx_data = ctx_account.try_borrow_data()?;
-    let base_spread = u32::from_le_bytes(
-        ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidAccountData)?,
+    let regime = MacroRegime::from_u8(ctx_data[REGIME_OFFSET]);
+    let hedge_adj = read_hedge_adjustment(&ctx_data, ctx_account.key, program_id, &accounts[2..])?;
+    verify_oracle_slot_stamp(&ctx_data, &accounts[2..])?;
+    let (exec_price, side_spread, skew) = quote(&ctx_data, clock.slot, direction, hedge_adj)?;
+
+    drop(ctx_data);
+
+    check_limit_price(exec_price, direction, parse_limit_price(data)?)?;
+
+    // Write execution price to return buffer and update stats
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if let Some((size, _)) = trade {
+        consume_liquidity(&mut ctx_data, fill_notional(size, exec_price))?;
+    }
+    write_exec_price(&mut ctx_data, exec_price);
+
+    // Update trade stats
+    bump_stats(&mut ctx_data, TOTAL_TRADES_OFFSET, TOTAL_VOLUME_OFFSET, 1, trade.map_or(0, |(size, _)| size) as u128);
+
+    if let Some((size, direction)) = trade {
+        apply_fill_to_inventory(&mut ctx_data, size, direction)?;
+    }
+
+    // Release the reentrancy guard
+    ctx_data[MATCH_GUARD_OFFSET] = 0;
+
+    matcher_log!(
+        "MATCH: price={} spread={} skew={} direction={} regime={:?}",
+        exec_price,
+        side_spread,
+        skew,
+        direction,
+        regime
     );
-    let regime_spread = u32::from_le_bytes(
-        ctx_data[REGIME_SPREAD_OFFSET..REGIME_SPREAD_OFFSET + 4]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidAccountData)?,
+
+    Ok(())
+}
+
+/// Notional (e6) of a fill: `size * price / 1e6`
+fn fill_notional(trade_size: u64, exec_price: u64) -> u128 {
+    trade_size as u128 * exec_price as u128 / 1_000_000
+}
+
+/// Count `notional` against the LP's quoting depth, refusing a fill that would
+/// take the consumed total past `liquidity_notional_e6`. Zero liquidity means
+/// no depth was configured and nothing is tracked.
+fn consume_liquidity(ctx_data: &mut [u8], notional: u128) -> ProgramResult {
+    let liquidity = u128::from_le_bytes(
+        ctx_data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let max_spread = u32::from_le_bytes(
-        ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4]
+    if liquidity == 0 {
+        return Ok(());
+    }
+    let consumed = read_consumed_liquidity(ctx_data)?;
+    match consumed.checked_add(notional) {
+        Some(total) if total <= liquidity => {
+            ctx_data[CONSUMED_LIQUIDITY_OFFSET..CONSUMED_LIQUIDITY_OFFSET + 16].copy_from_slice(&total.to_le_bytes());
+            Ok(())
+        }
+        _ => {
+            msg!("MACRO-MATCHER: Fill of {} notional exceeds remaining liquidity ({} of {} consumed)", notional, consumed, liquidity);
+            Err(MacroMatcherError::LiquidityExhausted.into())
+        }
+    }
+}
+
+fn read_consumed_liquidity(ctx_data: &[u8]) -> Result<u128, ProgramError> {
+    Ok(u128::from_le_bytes(
+        ctx_data[CONSUMED_LIQUIDITY_OFFSET..CONSUMED_LIQUIDITY_OFFSET + 16]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Tag 0x05: Replenish — LP frees consumed liquidity so matching can resume
+/// Accounts:
+///   [0] LP PDA (signer)
+///   [1] Matcher context account (writable)
+/// Data layout:
+///   [0]    tag (0x05)
+///   [1..9] amount_e6 (u64 LE, optional: notional to free; omitted = reset to 0)
+pub fn process_replenish(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    verify_ctx_owner(ctx_account, program_id, "MACRO-MATCHER")?;
+    verify_lp_pda_common(lp_pda, ctx_account, MACRO_MATCHER_MAGIC, "MACRO-MATCHER")?;
+    check_ctx_version(&ctx_account.try_borrow_data()?)?;
+
+    let amount = match data.len() {
+        1 => u128::MAX,
+        9 => u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?) as u128,
+        len => {
+            msg!("MACRO-MATCHER: Replenish data length {}, expected 1 or 9", len);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+    };
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    let consumed = read_consumed_liquidity(&ctx_data)?;
+    let remaining = consumed.saturating_sub(amount);
+    ctx_data[CONSUMED_LIQUIDITY_OFFSET..CONSUMED_LIQUIDITY_OFFSET + 16].copy_from_slice(&remaining.to_le_bytes());
+
+    matcher_log!("REPLENISH: consumed {} -> {}", consumed, remaining);
+
+    Ok(())
+}
+
+/// Every instruction reads this layout only; a context stamped with another
+/// version keeps its fields elsewhere
+fn check_ctx_version(ctx_data: &[u8]) -> ProgramResult {
+    let version = read_version(ctx_data)?;
+    if version != CTX_VERSION {
+        msg!("MACRO-MATCHER: Context version {}, expected {}", version, CTX_VERSION);
+        return Err(MacroMatcherError::UnsupportedVersion.into());
+    }
+    Ok(())
+}
+
+/// Take the reentrancy guard for the duration of a match. A nested match on
+/// the same context finds it held and is rejected before touching any stats.
+fn enter_match_guard(ctx_data: &mut [u8]) -> ProgramResult {
+    if ctx_data[MATCH_GUARD_OFFSET] != 0 {
+        msg!("MACRO-MATCHER: Re-entrant match rejected");
+        return Err(MacroMatcherError::MatchReentered.into());
+    }
+    ctx_data[MATCH_GUARD_OFFSET] = 1;
+    Ok(())
+}
+
+/// Tag 0x0A: Batch match — price several basket legs under one LP PDA check
+/// Accounts:
+///   [0] LP PDA (signer)
+///   [1] Matcher context account (writable)
+///   [2] Keys PDA (read, required when a hedge reference is configured)
+///   [3] Hedge reference context (read, required when configured)
+/// Data layout:
+///   [0]    tag (0x0A)
+///   [1]    count (u8, 0..=MAX_BATCH_LEGS)
+///   [2..]  count x leg: trade_size_abs (u64 LE) then direction (u8: 0=buy from LP, 1=sell to LP)
+/// Legs are priced in order, each after the previous legs' fills have moved
+/// the inventory, so a batch prices exactly as the same legs matched one by one.
+/// Leg i's exec price is written to return bytes `i*8..i*8+8`.
+pub fn process_match_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    verify_ctx_owner(ctx_account, program_id, "MACRO-MATCHER")?;
+    verify_lp_pda_common(lp_pda, ctx_account, MACRO_MATCHER_MAGIC, "MACRO-MATCHER")?;
+    check_ctx_version(&ctx_account.try_borrow_data()?)?;
+
+    let legs = parse_batch(data)?;
+    if legs.is_empty() {
+        matcher_log!("MATCH_BATCH: empty batch");
+        return Ok(());
+    }
+
+    let clock = Clock::get()?;
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    let hedge_adj = read_hedge_adjustment(&ctx_data, ctx_account.key, program_id, &accounts[2..])?;
+    let mut prices = Vec::with_capacity(legs.len());
+    let mut notional = 0u128;
+    for &(size, direction) in &legs {
+        let (exec_price, _, _) = quote(&ctx_data, clock.slot, direction, hedge_adj)?;
+        prices.push(exec_price);
+        notional = notional.checked_add(fill_notional(size, exec_price)).ok_or(MacroMatcherError::ArithmeticOverflow)?;
+        bump_stats(&mut ctx_data, TOTAL_TRADES_OFFSET, TOTAL_VOLUME_OFFSET, 1, size as u128);
+        apply_fill_to_inventory(&mut ctx_data, size, direction)?;
+    }
+    consume_liquidity(&mut ctx_data, notional)?;
+    write_batch_prices(&mut ctx_data, &prices);
+
+    matcher_log!("MATCH_BATCH: legs={} first_price={}", prices.len(), prices[0]);
+
+    Ok(())
+}
+
+/// Tag 0x0D: Query spread — the spread Match would charge on each side at the
+/// stored regime, signal, hedge reference, skews and per-side caps, for
+/// analytics. Needs no synced or fresh index. Read-only and signer-free: the
+/// context's return region is left alone.
+/// Accounts:
+///   [0] Matcher context account
+///   [1] Keys PDA (read, required when a hedge reference is configured)
+///   [2] Hedge reference context (read, required when configured)
+/// Data layout:
+///   [0] tag (0x0D)
+/// Return data (`set_return_data`): bid spread bps then ask spread bps, u64 LE each
+pub fn process_query_spread(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let ctx_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    verify_ctx_owner(ctx_account, program_id, "MACRO-MATCHER")?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_ctx_version(&ctx_data)?;
+    let hedge_adj = read_hedge_adjustment(&ctx_data, ctx_account.key, program_id, &accounts[1..])?;
+    let (bid_spread, _) = side_spread(&ctx_data, DIRECTION_SELL, hedge_adj)?;
+    let (ask_spread, _) = side_spread(&ctx_data, DIRECTION_BUY, hedge_adj)?;
+
+    let mut spreads = [0u8; 16];
+    spreads[..8].copy_from_slice(&bid_spread.to_le_bytes());
+    spreads[8..].copy_from_slice(&ask_spread.to_le_bytes());
+    set_return_data(&spreads);
+
+    matcher_log!(
+        "QUERY_SPREAD: bid={} ask={} symmetric={} regime={:?}",
+        bid_spread,
+        ask_spread,
+        compute_current_spread(&ctx_data)?,
+        MacroRegime::from_u8(ctx_data[REGIME_OFFSET])
     );
+
+    Ok(())
+}
+
+/// Regime-adjusted exec price for one side. Rejects an unsynced or stale index.
+/// Returns `(exec_price, side_spread, skew)`.
+fn quote(ctx_data: &[u8], slot: u64, direction: u8, hedge_adj: u64) -> Result<(u64, u64, i64), ProgramError> {
     let mark_price = u64::from_le_bytes(
         ctx_data[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let regime = MacroRegime::from_u8(ctx_data[REGIME_OFFSET]);
-    let signal_adj = u64::from_le_bytes(
-        ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidAccountData)?,
-    );
 
     // Reject if index not synced (mark == 0)
     if mark_price == 0 {
@@ -162,56 +641,283 @@ pub fn process_match(
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let clock = Clock::get()?;
-    if clock.slot.saturating_sub(last_update) > MAX_STALENESS_SLOTS {
+    if slot.saturating_sub(last_update) > MAX_STALENESS_SLOTS {
         msg!(
             "MACRO-MATCHER: Oracle stale — last update slot {}, current {}",
             last_update,
-            clock.slot
+            slot
         );
         return Err(MacroMatcherError::OracleStale.into());
     }
 
-    // Compute regime-adjusted spread
-    let regime_multiplier = regime.spread_multiplier();
-    let adjusted_regime = (regime_spread as u64)
-        .checked_mul(regime_multiplier)
-        .ok_or(MacroMatcherError::ArithmeticOverflow)?
-        / 100;
+    let (side_spread, skew) = side_spread(ctx_data, direction, hedge_adj)?;
 
-    let total_spread = std::cmp::min(
-        (base_spread as u64)
-            .saturating_add(adjusted_regime)
-            .saturating_add(signal_adj),
-        max_spread as u64,
-    );
+    let lp_favorable = ctx_data[ROUNDING_MODE_OFFSET] == ROUNDING_LP_FAVORABLE;
+    let exec_price = side_price(mark_price, side_spread, direction, lp_favorable).map_err(MacroMatcherError::from)?;
+    Ok((exec_price, side_spread, skew))
+}
 
-    // Compute execution price using shared utility
-    let exec_price = compute_exec_price(mark_price, total_spread)?;
+/// The spread one side is charged, and the inventory skew behind it
+fn side_spread(ctx_data: &[u8], direction: u8, hedge_adj: u64) -> Result<(u64, i64), ProgramError> {
+    // Regime-adjusted spread plus the hedge reference's widening
+    let total_spread = spread_before_cap(ctx_data)?.saturating_add(hedge_adj);
 
-    drop(ctx_data);
+    // Inventory skew: LP net long tightens the ask and widens the bid
+    let skew = read_inventory_skew(ctx_data)?;
+    let side_spread = skewed_spread(total_spread, skew, direction);
+    let side_spread = static_skewed_spread(side_spread, read_static_skew(ctx_data)?, direction);
 
-    // Write execution price to return buffer and update stats
-    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    write_exec_price(&mut ctx_data, exec_price);
+    // This side's cap bounds the spread actually charged, skews included
+    Ok((std::cmp::min(side_spread, side_max_spread(ctx_data, direction)?), skew))
+}
 
-    // Update trade stats
-    let old_trades = u64::from_le_bytes(
-        ctx_data[TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8]
+/// If the stored macro oracle is among `extra_accounts` and a stamp offset is
+/// configured, cross-check the oracle's own slot against `last_update_slot`.
+/// Catches a keeper re-signing an old oracle value with a fresh sync slot.
+fn verify_oracle_slot_stamp(ctx_data: &[u8], extra_accounts: &[AccountInfo]) -> Result<(), ProgramError> {
+    let stored_oracle = read_macro_oracle(ctx_data)?;
+    let Some(oracle) = extra_accounts.iter().find(|a| *a.key == stored_oracle) else {
+        return Ok(());
+    };
+    let oracle_data = oracle.try_borrow_data()?;
+    check_oracle_slot_stamp(ctx_data, &oracle_data)
+}
+
+fn check_oracle_slot_stamp(ctx_data: &[u8], oracle_data: &[u8]) -> Result<(), ProgramError> {
+    let stamp_offset = u16::from_le_bytes(
+        ctx_data[ORACLE_SLOT_STAMP_OFFSET..ORACLE_SLOT_STAMP_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as usize;
+    if stamp_offset == 0 {
+        return Ok(());
+    }
+    let oracle_slot = u64::from_le_bytes(
+        oracle_data
+            .get(stamp_offset..stamp_offset + 8)
+            .ok_or(ProgramError::InvalidAccountData)?
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    ctx_data[TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8]
-        .copy_from_slice(&(old_trades.saturating_add(1)).to_le_bytes());
+    let last_update = u64::from_le_bytes(
+        ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let tolerance = ctx_data[ORACLE_SLOT_TOLERANCE_OFFSET] as u64;
+    if oracle_slot.abs_diff(last_update) > tolerance {
+        msg!(
+            "MACRO-MATCHER: Oracle slot {} disagrees with synced slot {} (tolerance {})",
+            oracle_slot,
+            last_update,
+            tolerance
+        );
+        return Err(MacroMatcherError::OracleSlotMismatch.into());
+    }
+    Ok(())
+}
 
-    msg!(
-        "MATCH: price={} spread={} regime={:?} mark={}",
-        exec_price,
-        total_spread,
-        regime,
-        mark_price
+/// Extra spread from the configured hedge reference, if any. When a reference is
+/// configured `accounts` must start with this context's keys PDA and the
+/// reference it names, carrying the vol-matcher magic; Init already checked
+/// its owner.
+fn read_hedge_adjustment(
+    ctx_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<u64, ProgramError> {
+    if ctx_data[KEYS_OFFSET] & KEY_HEDGE_REF == 0 {
+        return Ok(0);
+    }
+    let [keys, hedge_ref, ..] = accounts else {
+        msg!("MACRO-MATCHER: Keys PDA and hedge reference accounts required");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let keys_data = keys.try_borrow_data()?;
+    verify_keys(keys, &keys_data, ctx_key, program_id)?;
+    let ref_data = hedge_ref.try_borrow_data()?;
+    hedge_spread_adjustment(ctx_data, &read_key(&keys_data, HEDGE_REF_OFFSET)?, hedge_ref.key, &ref_data)
+}
+
+fn hedge_spread_adjustment(ctx_data: &[u8], stored_ref: &Pubkey, ref_key: &Pubkey, ref_data: &[u8]) -> Result<u64, ProgramError> {
+    if ref_key != stored_ref {
+        msg!("MACRO-MATCHER: Hedge reference mismatch: {}", ref_key);
+        return Err(MacroMatcherError::InvalidHedgeReference.into());
+    }
+    if ref_data.len() < CTX_SIZE || !verify_magic_generic(ref_data, HEDGE_REF_MAGIC) {
+        msg!("MACRO-MATCHER: Hedge reference is not a vol-matcher context");
+        return Err(MacroMatcherError::InvalidHedgeReference.into());
+    }
+    let ref_vol = u64::from_le_bytes(
+        ref_data[HEDGE_REF_VOL_OFFSET..HEDGE_REF_VOL_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let threshold = u16::from_le_bytes(
+        ctx_data[HEDGE_VOL_THRESHOLD_OFFSET..HEDGE_VOL_THRESHOLD_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let hedge_spread = u16::from_le_bytes(
+        ctx_data[HEDGE_SPREAD_OFFSET..HEDGE_SPREAD_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    Ok(if ref_vol >= threshold as u64 { hedge_spread as u64 } else { 0 })
+}
+
+/// Count-prefixed trade sizes; the return region holds at most MAX_BATCH_LEGS prices
+fn parse_batch(data: &[u8]) -> Result<Vec<(u64, u8)>, ProgramError> {
+    let count = *data.get(1).ok_or(ProgramError::InvalidInstructionData)? as usize;
+    if count > MAX_BATCH_LEGS {
+        msg!("MACRO-MATCHER: Batch of {} exceeds {} legs", count, MAX_BATCH_LEGS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if data.len() < 2 + count * BATCH_LEG_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    data[2..2 + count * BATCH_LEG_LEN]
+        .chunks_exact(BATCH_LEG_LEN)
+        .map(|leg| {
+            let size = u64::from_le_bytes(leg[..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+            if leg[8] > DIRECTION_SELL {
+                msg!("MACRO-MATCHER: Invalid direction {}", leg[8]);
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            Ok((size, leg[8]))
+        })
+        .collect()
+}
+
+fn write_batch_prices(ctx_data: &mut [u8], prices: &[u64]) {
+    for (i, price) in prices.iter().enumerate() {
+        ctx_data[i * 8..i * 8 + 8].copy_from_slice(&price.to_le_bytes());
+    }
+}
+
+/// Parse the optional limit price following the direction byte; None when
+/// omitted. A partial limit is rejected rather than read as no protection.
+fn parse_limit_price(data: &[u8]) -> Result<Option<u64>, ProgramError> {
+    if data.len() <= 10 {
+        return Ok(None);
+    }
+    if data.len() < 18 {
+        msg!("MACRO-MATCHER: Truncated limit price ({} of 8 bytes)", data.len() - 10);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(Some(u64::from_le_bytes(data[10..18].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)))
+}
 
+/// Reject a fill worse than the trader's limit: a buy above it, a sell below it.
+/// The limit itself is acceptable.
+fn check_limit_price(exec_price: u64, direction: u8, limit_price: Option<u64>) -> ProgramResult {
+    let Some(limit) = limit_price else {
+        return Ok(());
+    };
+    let exceeded = if direction == DIRECTION_BUY { exec_price > limit } else { exec_price < limit };
+    if exceeded {
+        msg!("MACRO-MATCHER: Exec price {} past limit {} (direction {})", exec_price, limit, direction);
+        return Err(MacroMatcherError::SlippageExceeded.into());
+    }
+    Ok(())
+}
+
+/// Parse optional `(trade_size_abs, direction)` from match data; None when omitted
+fn parse_trade(data: &[u8]) -> Result<Option<(u64, u8)>, ProgramError> {
+    if data.len() < 10 {
+        return Ok(None);
+    }
+    let size = u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let direction = data[9];
+    if direction > DIRECTION_SELL {
+        msg!("MACRO-MATCHER: Invalid direction {}", direction);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(Some((size, direction)))
+}
+
+/// Signed inventory skew (bps): `net_inventory * skew_k / liquidity`, clamped to ±max_skew
+fn read_inventory_skew(ctx_data: &[u8]) -> Result<i64, ProgramError> {
+    let net_inventory = i64::from_le_bytes(
+        ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let skew_k = u16::from_le_bytes(
+        ctx_data[SKEW_K_OFFSET..SKEW_K_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let max_skew = u16::from_le_bytes(
+        ctx_data[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let liquidity = u128::from_le_bytes(
+        ctx_data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if skew_k == 0 || liquidity == 0 {
+        return Ok(0);
+    }
+    let raw = (net_inventory as i128) * (skew_k as i128) / i128::try_from(liquidity).unwrap_or(i128::MAX);
+    Ok(raw.clamp(-(max_skew as i128), max_skew as i128) as i64)
+}
+
+/// Positive skew (LP long) subtracts from the ask and adds to the bid
+fn skewed_spread(spread: u64, skew_bps: i64, direction: u8) -> u64 {
+    let adj = if direction == DIRECTION_BUY { -skew_bps } else { skew_bps };
+    if adj >= 0 {
+        spread.saturating_add(adj as u64)
+    } else {
+        spread.saturating_sub(adj.unsigned_abs())
+    }
+}
+
+/// Configured directional view: a positive skew widens the ask and tightens
+/// the bid by half each (the odd bp goes to the ask), so the ask sits `skew`
+/// bps further out than the bid. Either side clamps at zero spread.
+fn static_skewed_spread(spread: u64, skew_bps: i16, direction: u8) -> u64 {
+    let bid_adj = skew_bps as i64 / 2;
+    let adj = if direction == DIRECTION_BUY { skew_bps as i64 - bid_adj } else { -bid_adj };
+    if adj >= 0 {
+        spread.saturating_add(adj as u64)
+    } else {
+        spread.saturating_sub(adj.unsigned_abs())
+    }
+}
+
+/// Spread cap for one side: its own `max_bid_spread_bps` / `max_ask_spread_bps`,
+/// or the shared `max_spread_bps` when that side's is 0
+fn side_max_spread(ctx_data: &[u8], direction: u8) -> Result<u64, ProgramError> {
+    let offset = if direction == DIRECTION_BUY { MAX_ASK_SPREAD_OFFSET } else { MAX_BID_SPREAD_OFFSET };
+    let side_cap = u16::from_le_bytes(ctx_data[offset..offset + 2].try_into().map_err(|_| ProgramError::InvalidAccountData)?);
+    if side_cap != 0 {
+        return Ok(side_cap as u64);
+    }
+    Ok(u32::from_le_bytes(
+        ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as u64)
+}
+
+/// A taker buy leaves the LP shorter; a taker sell leaves it longer
+fn apply_fill_to_inventory(ctx_data: &mut [u8], size: u64, direction: u8) -> Result<(), ProgramError> {
+    let delta = i64::try_from(size).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let net_inventory = i64::from_le_bytes(
+        ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let updated = if direction == DIRECTION_BUY {
+        net_inventory.saturating_sub(delta)
+    } else {
+        net_inventory.saturating_add(delta)
+    };
+    ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&updated.to_le_bytes());
     Ok(())
 }
 
@@ -219,21 +925,25 @@ pub fn process_match(
 /// Accounts:
 ///   [0] Matcher context account (writable)
 ///   [1] Macro oracle account (must match stored oracle)
+///   [2] Keeper authority (signer, required when configured)
+///   [3] Keys PDA (read, required when a keeper authority is configured)
 /// Data layout:
 ///   [0]    tag (0x03)
 ///   [1..9] current_index_e6 (u64 LE) — real rate mark price
 ///   [9..17] index_components_packed (u64 LE) — nominal(high32) | inflation(low32)
 ///   [17..25] signal_severity (u64 LE, 0-3)
 ///   [25..33] signal_adjusted_spread (u64 LE)
+///   [33..41] update_slot (u64 LE): the slot the keeper read the index at; must be
+///            after the stored last_update_slot and not past the current slot
 pub fn process_index_sync(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 33 {
+    if data.len() < 41 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
@@ -250,45 +960,65 @@ pub fn process_index_sync(
         if !verify_magic(&ctx_data) {
             return Err(ProgramError::UninitializedAccount);
         }
+        check_ctx_version(&ctx_data)?;
 
         let stored_oracle = read_macro_oracle(&ctx_data)?;
         if *oracle.key != stored_oracle {
             msg!("MACRO-MATCHER: Oracle mismatch");
             return Err(MacroMatcherError::OracleMismatch.into());
         }
+
+        if ctx_data[KEYS_OFFSET] & KEY_KEEPER != 0 {
+            let [_, _, keeper, keys, ..] = accounts else {
+                msg!("MACRO-MATCHER: Keeper authority and keys PDA accounts required");
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let keys_data = keys.try_borrow_data()?;
+            verify_keys(keys, &keys_data, ctx_account.key, program_id)?;
+            check_index_sync_signer(&read_key(&keys_data, KEEPER_AUTHORITY_OFFSET)?, keeper.key, keeper.is_signer)?;
+        }
     }
 
     let new_index = u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     let components_packed = u64::from_le_bytes(data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     let signal_severity = u64::from_le_bytes(data[17..25].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     let signal_spread = u64::from_le_bytes(data[25..33].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let update_slot = u64::from_le_bytes(data[33..41].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
 
     // Validate signal severity
     if signal_severity > SIGNAL_CRITICAL {
         return Err(MacroMatcherError::InvalidSignalSeverity.into());
     }
+    check_index_value(new_index, components_packed)?;
 
     let clock = Clock::get()?;
 
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    check_index_bounds(&ctx_data, new_index)?;
     let old_index = u64::from_le_bytes(
         ctx_data[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let last_update = u64::from_le_bytes(
+        ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    check_sync_slot(last_update, update_slot, clock.slot)?;
 
     ctx_data[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8]
         .copy_from_slice(&new_index.to_le_bytes());
     ctx_data[INDEX_COMPONENTS_PACKED_OFFSET..INDEX_COMPONENTS_PACKED_OFFSET + 8]
         .copy_from_slice(&components_packed.to_le_bytes());
     ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
-        .copy_from_slice(&clock.slot.to_le_bytes());
+        .copy_from_slice(&update_slot.to_le_bytes());
     ctx_data[SIGNAL_SEVERITY_OFFSET..SIGNAL_SEVERITY_OFFSET + 8]
         .copy_from_slice(&signal_severity.to_le_bytes());
     ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8]
         .copy_from_slice(&signal_spread.to_le_bytes());
 
-    msg!(
+    matcher_log!(
         "INDEX_SYNC: old_index={} new_index={} signal={}",
         old_index,
         new_index,
@@ -298,7 +1028,93 @@ pub fn process_index_sync(
     Ok(())
 }
 
-/// Tag 0x04: Regime update — change macro regime
+/// Reject an index sync not read at a later slot than the stored one, so a
+/// replayed or reordered keeper transaction can't restore an older index in
+/// whatever slot it lands, and one claiming a slot that hasn't happened yet
+fn check_sync_slot(last_update_slot: u64, update_slot: u64, slot: u64) -> ProgramResult {
+    if update_slot <= last_update_slot {
+        msg!("MACRO-MATCHER: Stale index sync read at slot {} (last update read at {})", update_slot, last_update_slot);
+        return Err(MacroMatcherError::StaleUpdate.into());
+    }
+    if update_slot > slot {
+        msg!("MACRO-MATCHER: Index sync read at slot {} is past the current slot {}", update_slot, slot);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+/// A regime change needs an index synced within MAX_STALENESS_SLOTS, the same
+/// window Match prices against. A context never synced is stale.
+fn check_regime_feed_fresh(last_update_slot: u64, slot: u64) -> ProgramResult {
+    if slot.saturating_sub(last_update_slot) > MAX_STALENESS_SLOTS {
+        msg!(
+            "MACRO-MATCHER: Regime update on a stale feed — last update slot {}, current {}",
+            last_update_slot,
+            slot
+        );
+        return Err(MacroMatcherError::OracleStale.into());
+    }
+    Ok(())
+}
+
+/// The synced index must be the mark the packed components imply, so a
+/// keeper can't publish a price its own rates don't back. Mark 0 is how Match
+/// tells an unsynced context apart: a real rate (nominal - inflation) at or
+/// below -RATE_OFFSET floors to that mark, so reject it here as an
+/// out-of-range value instead of letting it surface later as IndexNotSynced.
+fn check_index_value(new_index: u64, components_packed: u64) -> ProgramResult {
+    let nominal_bps = (components_packed >> 32) as u32 as i64;
+    let inflation_bps = components_packed as u32 as i64;
+    let real_rate_bps = nominal_bps - inflation_bps;
+    let mark = compute_mark_price(real_rate_bps);
+    if mark == 0 {
+        msg!("MACRO-MATCHER: Real rate {}bps is at or below the -{}bps mark floor", real_rate_bps, RATE_OFFSET);
+        return Err(MacroMatcherError::InvalidIndexValue.into());
+    }
+    if new_index != mark {
+        msg!("MACRO-MATCHER: Index {} doesn't match the {} mark of real rate {}bps", new_index, mark, real_rate_bps);
+        return Err(MacroMatcherError::InvalidIndexValue.into());
+    }
+    Ok(())
+}
+
+/// The synced index must sit inside the configured `[index_min, index_max]`,
+/// both inclusive; a zero bound is open on that side
+fn check_index_bounds(ctx_data: &[u8], new_index: u64) -> ProgramResult {
+    let index_min = u32::from_le_bytes(
+        ctx_data[INDEX_MIN_OFFSET..INDEX_MIN_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let index_max = u32::from_le_bytes(
+        ctx_data[INDEX_MAX_OFFSET..INDEX_MAX_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if new_index < index_min as u64 || (index_max != 0 && new_index > index_max as u64) {
+        msg!("MACRO-MATCHER: Index {} outside [{}, {}]", new_index, index_min, index_max);
+        return Err(MacroMatcherError::IndexOutOfBounds.into());
+    }
+    Ok(())
+}
+
+/// Index syncs must be signed by the keeper authority. Contexts initialized
+/// without one keep the original check: the oracle account's key (already
+/// matched by the caller) authorizes the sync, signed or not.
+fn check_index_sync_signer(keeper: &Pubkey, signer: &Pubkey, is_signer: bool) -> Result<(), ProgramError> {
+    if *keeper == Pubkey::default() {
+        return Ok(());
+    }
+    if !is_signer {
+        msg!("MACRO-MATCHER: Index sync keeper must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if signer != keeper {
+        msg!("MACRO-MATCHER: Unauthorized keeper: expected {}, got {}", keeper, signer);
+        return Err(MacroMatcherError::UnauthorizedKeeper.into());
+    }
+    Ok(())
+}
+
+/// Tag 0x04: Regime update — change macro regime. Only while the index feed is
+/// fresh (synced within MAX_STALENESS_SLOTS), so a dormant oracle key can't
+/// flip the regime of a market nobody is maintaining.
 /// Accounts:
 ///   [0] Matcher context account (writable)
 ///   [1] Macro oracle account (signer, must match stored oracle)
@@ -336,12 +1152,20 @@ pub fn process_regime_update(
         if !verify_magic(&ctx_data) {
             return Err(ProgramError::UninitializedAccount);
         }
+        check_ctx_version(&ctx_data)?;
 
         let stored_oracle = read_macro_oracle(&ctx_data)?;
         if *oracle.key != stored_oracle {
             msg!("MACRO-MATCHER: Oracle mismatch");
             return Err(MacroMatcherError::OracleMismatch.into());
         }
+
+        let last_update = u64::from_le_bytes(
+            ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        check_regime_feed_fresh(last_update, Clock::get()?.slot)?;
     }
 
     let new_regime = data[1];
@@ -353,7 +1177,7 @@ pub fn process_regime_update(
     let old_regime = ctx_data[REGIME_OFFSET];
     ctx_data[REGIME_OFFSET] = new_regime;
 
-    msg!(
+    matcher_log!(
         "REGIME_UPDATE: old={} new={} ({})",
         old_regime,
         new_regime,
@@ -369,10 +1193,39 @@ pub fn process_regime_update(
     Ok(())
 }
 
+/// Accepted init payload lengths: the base layout, then each optional trailing group
+/// (skew, hedge reference, oracle slot stamp, keeper authority, regime multipliers,
+/// rounding mode, static skew, per-side spread caps) in full.
+const INIT_DATA_LENS: [usize; 10] = [82, 86, 122, 125, 157, 165, 166, 168, 172, 180];
+
+/// Reject truncated optional groups and trailing garbage on init
+fn check_init_len(len: usize) -> Result<(), ProgramError> {
+    if !INIT_DATA_LENS.contains(&len) {
+        msg!("MACRO-MATCHER: Init data length {}, expected one of {:?}", len, INIT_DATA_LENS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{
+        apply_fill_to_inventory, check_index_bounds, check_index_sync_signer, check_index_value, check_init_len,
+        check_limit_price, check_oracle_slot_stamp, check_sync_slot, enter_match_guard, fill_notional,
+        hedge_spread_adjustment, parse_batch, parse_limit_price, process_init, process_match, process_match_batch,
+        process_index_sync, process_query_spread, process_regime_update, process_replenish, quote, read_inventory_skew,
+        skewed_spread, static_skewed_spread, validate_liquidity_config,
+        INIT_DATA_LENS,
+    };
+    use crate::errors::MacroMatcherError;
     use crate::state::*;
-    use matcher_common::compute_exec_price;
+    use matcher_common::{compute_exec_price, MAGIC_OFFSET};
+    use matcher_shared::side_price;
+    use solana_program::{
+        account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, instruction::Instruction,
+        program_error::ProgramError, program::get_return_data, program_stubs::{set_syscall_stubs, SyscallStubs}, pubkey::Pubkey, rent::Rent,
+    };
+    use std::cell::RefCell;
 
     // ---------------------------------------------------------------------------
     // Helper: replicate the pricing math from process_match for unit-testing
@@ -561,4 +1414,1081 @@ mod tests {
         // adjusted = 400, total = min(100 + 400 + 100, 150) = 150
         assert_eq!(spread, 150);
     }
+
+    // --- Inventory skew ---
+
+    fn skew_ctx(net_inventory: i64) -> Vec<u8> {
+        let mut data = vec![0u8; CTX_SIZE];
+        data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&net_inventory.to_le_bytes());
+        data[SKEW_K_OFFSET..SKEW_K_OFFSET + 2].copy_from_slice(&30u16.to_le_bytes());
+        data[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2].copy_from_slice(&20u16.to_le_bytes());
+        data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&3_000_000u128.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_zero_inventory_no_skew() {
+        let skew = read_inventory_skew(&skew_ctx(0)).unwrap();
+        assert_eq!(skew, 0);
+        assert_eq!(skewed_spread(60, skew, DIRECTION_BUY), 60);
+        assert_eq!(skewed_spread(60, skew, DIRECTION_SELL), 60);
+    }
+
+    #[test]
+    fn test_long_inventory_tightens_ask() {
+        // skew = 1_000_000 * 30 / 3_000_000 = 10 bps
+        let skew = read_inventory_skew(&skew_ctx(1_000_000)).unwrap();
+        assert_eq!(skew, 10);
+        assert_eq!(skewed_spread(60, skew, DIRECTION_BUY), 50);
+        assert_eq!(skewed_spread(60, skew, DIRECTION_SELL), 70);
+        // mark 7_000_000: ask = 7_035_000, bid = 6_951_000
+        assert_eq!(side_price(7_000_000, 50, DIRECTION_BUY, false).unwrap(), 7_035_000);
+        assert_eq!(side_price(7_000_000, 70, DIRECTION_SELL, false).unwrap(), 6_951_000);
+    }
+
+    #[test]
+    fn test_lp_favorable_rounding() {
+        let mut data = full_init_data();
+        assert_eq!(init_over(0, &data)[ROUNDING_MODE_OFFSET], ROUNDING_LP_FAVORABLE);
+        assert_eq!(init_over(0, &data[..165])[ROUNDING_MODE_OFFSET], ROUNDING_FLOOR);
+        data[165] = 2;
+        assert_eq!(try_init_over(0, &data), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_short_inventory_tightens_bid() {
+        // skew = -9_000_000 * 30 / 3_000_000 = -90, clamped to -20
+        let skew = read_inventory_skew(&skew_ctx(-9_000_000)).unwrap();
+        assert_eq!(skew, -20);
+        assert_eq!(skewed_spread(60, skew, DIRECTION_BUY), 80);
+        assert_eq!(skewed_spread(60, skew, DIRECTION_SELL), 40);
+    }
+
+    #[test]
+    fn test_fills_update_inventory() {
+        let mut data = skew_ctx(0);
+        apply_fill_to_inventory(&mut data, 500, DIRECTION_BUY).unwrap();
+        apply_fill_to_inventory(&mut data, 200, DIRECTION_SELL).unwrap();
+        let inv = i64::from_le_bytes(data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].try_into().unwrap());
+        assert_eq!(inv, -300);
+    }
+
+    #[test]
+    fn test_impact_without_liquidity_rejected() {
+        assert_eq!(
+            validate_liquidity_config(10, 0),
+            Err(ProgramError::Custom(MacroMatcherError::InvalidLiquidityConfig as u32))
+        );
+        assert!(validate_liquidity_config(10, 1_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_zero_impact_zero_liquidity_accepted() {
+        assert!(validate_liquidity_config(0, 0).is_ok());
+    }
+
+    // --- Batch match ---
+
+    fn batch_data(legs: &[(u64, u8)]) -> Vec<u8> {
+        let mut data = vec![0x0A, legs.len() as u8];
+        for (size, direction) in legs {
+            data.extend_from_slice(&size.to_le_bytes());
+            data.push(*direction);
+        }
+        data
+    }
+
+    #[test]
+    fn test_three_leg_batch() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data()[..82]);
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&7_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        ctx[REGIME_OFFSET] = MacroRegime::Stagnation as u8;
+        ctx[SKEW_K_OFFSET..SKEW_K_OFFSET + 2].copy_from_slice(&1_000u16.to_le_bytes());
+        ctx[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2].copy_from_slice(&50u16.to_le_bytes());
+
+        let legs = [(100_000_000u64, DIRECTION_BUY), (300_000_000, DIRECTION_SELL), (200_000_000, DIRECTION_BUY)];
+        assert_eq!(parse_batch(&batch_data(&legs)).unwrap(), legs.to_vec());
+
+        // The same legs matched one by one, each moving the inventory the next is skewed by
+        let mut one_by_one = ctx.clone();
+        let mut expected = Vec::new();
+        for (size, direction) in legs {
+            let mut data = vec![0x00];
+            data.extend_from_slice(&size.to_le_bytes());
+            data.push(direction);
+            run_lp_ix(process_match, &mut one_by_one, &data).unwrap();
+            expected.push(u64::from_le_bytes(one_by_one[0..8].try_into().unwrap()));
+        }
+
+        run_lp_ix(process_match_batch, &mut ctx, &batch_data(&legs)).unwrap();
+        for (i, price) in expected.iter().enumerate() {
+            assert_eq!(u64::from_le_bytes(ctx[i * 8..i * 8 + 8].try_into().unwrap()), *price, "leg {}", i);
+        }
+        assert_eq!(&ctx[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8], &0i64.to_le_bytes());
+        for range in [
+            TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8,
+            TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16,
+            CONSUMED_LIQUIDITY_OFFSET..CONSUMED_LIQUIDITY_OFFSET + 16,
+        ] {
+            assert_eq!(ctx[range.clone()], one_by_one[range]);
+        }
+        let trades = u64::from_le_bytes(ctx[TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8].try_into().unwrap());
+        let volume = u128::from_le_bytes(ctx[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16].try_into().unwrap());
+        assert_eq!((trades, volume), (3, 600_000_000));
+
+        // Stagnation, flat book: the first ask is 20 + 40 = 60 bps over the index.
+        // The sell leg is priced off the short the buy left, not off a flat book.
+        assert_eq!(expected[0], 7_042_000);
+        let flat_bid = side_price(7_000_000, 60, DIRECTION_SELL, false).unwrap();
+        assert!(expected[1] > flat_bid, "bid {} not tightened by the LP's short", expected[1]);
+    }
+
+    #[test]
+    fn test_batch_over_capacity_rejected() {
+        let legs = [(1u64, DIRECTION_BUY); MAX_BATCH_LEGS + 1];
+        assert_eq!(parse_batch(&batch_data(&legs)), Err(ProgramError::InvalidInstructionData));
+        assert!(parse_batch(&batch_data(&[])).unwrap().is_empty());
+        // A leg with no valid direction
+        assert_eq!(parse_batch(&batch_data(&[(1, 2)])), Err(ProgramError::InvalidInstructionData));
+    }
+
+    // --- Hedge reference ---
+
+    fn hedge_ctx() -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[KEYS_OFFSET] = KEY_HEDGE_REF;
+        ctx[HEDGE_VOL_THRESHOLD_OFFSET..HEDGE_VOL_THRESHOLD_OFFSET + 2].copy_from_slice(&6_000u16.to_le_bytes());
+        ctx[HEDGE_SPREAD_OFFSET..HEDGE_SPREAD_OFFSET + 2].copy_from_slice(&25u16.to_le_bytes());
+        ctx
+    }
+
+    fn vol_ctx(current_vol_bps: u64) -> Vec<u8> {
+        let mut data = vec![0u8; CTX_SIZE];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 8].copy_from_slice(&HEDGE_REF_MAGIC.to_le_bytes());
+        data[HEDGE_REF_VOL_OFFSET..HEDGE_REF_VOL_OFFSET + 8].copy_from_slice(&current_vol_bps.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_high_referenced_vol_widens_spread() {
+        let ref_key = Pubkey::new_unique();
+        let mut ctx = hedge_ctx();
+        ctx[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&20u32.to_le_bytes());
+        ctx[REGIME_SPREAD_OFFSET..REGIME_SPREAD_OFFSET + 4].copy_from_slice(&40u32.to_le_bytes());
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&200u32.to_le_bytes());
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&7_000_000u64.to_le_bytes());
+        ctx[REGIME_OFFSET] = MacroRegime::Stagnation as u8;
+
+        // 45% vol: below threshold, no widening
+        let calm = hedge_spread_adjustment(&ctx, &ref_key, &ref_key, &vol_ctx(4_500)).unwrap();
+        assert_eq!(calm, 0);
+        // 80% vol: +25 bps
+        let stressed = hedge_spread_adjustment(&ctx, &ref_key, &ref_key, &vol_ctx(8_000)).unwrap();
+        assert_eq!(stressed, 25);
+
+        let (calm_price, calm_spread, _) = quote(&ctx, 0, DIRECTION_BUY, calm).unwrap();
+        let (wide_price, wide_spread, _) = quote(&ctx, 0, DIRECTION_BUY, stressed).unwrap();
+        assert_eq!(calm_spread, 60);
+        assert_eq!(wide_spread, 85);
+        assert!(wide_price > calm_price);
+    }
+
+    /// Synced index in Crisis: base 20, regime spread 40, max 500
+    fn crisis_ctx() -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&20u32.to_le_bytes());
+        ctx[REGIME_SPREAD_OFFSET..REGIME_SPREAD_OFFSET + 4].copy_from_slice(&40u32.to_le_bytes());
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&500u32.to_le_bytes());
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&5_000_000u64.to_le_bytes());
+        ctx[REGIME_OFFSET] = MacroRegime::Crisis as u8;
+        ctx
+    }
+
+    fn set_multipliers(ctx: &mut [u8], multipliers: [u16; 4]) {
+        for (i, multiplier) in multipliers.iter().enumerate() {
+            let offset = REGIME_MULTIPLIERS_OFFSET + i * 2;
+            ctx[offset..offset + 2].copy_from_slice(&multiplier.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_custom_crisis_multiplier_changes_exec_price() {
+        let mut ctx = crisis_ctx();
+        // Crisis at 2.5x instead of the built-in 2x: 20 + 40 * 250% = 120 bps
+        set_multipliers(&mut ctx, [60, 100, 250, 125]);
+        let (price, spread, _) = quote(&ctx, 0, DIRECTION_BUY, 0).unwrap();
+        assert_eq!(spread, 120);
+        assert_eq!(price, 5_060_000);
+
+        // Past what a byte could hold: 20 + 40 * 400% = 180 bps
+        set_multipliers(&mut ctx, [60, 100, 400, 125]);
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 180);
+    }
+
+    #[test]
+    fn test_init_rejects_a_zero_multiplier_in_a_set_table() {
+        let mut data = full_init_data();
+        let ctx = init_over(0, &data);
+        assert_eq!(regime_multiplier(&ctx, MacroRegime::Crisis), 250);
+        assert_eq!(regime_multiplier(&ctx, MacroRegime::Recovery), 120);
+
+        // Expansion at 0 would drop the regime spread entirely
+        data[157..159].fill(0);
+        assert_eq!(try_init_over(0, &data), Err(ProgramError::InvalidInstructionData));
+
+        // All zero is "no table": the built-in multipliers apply
+        data[157..165].fill(0);
+        let ctx = init_over(0, &data);
+        assert_eq!(regime_multiplier(&ctx, MacroRegime::Crisis), 200);
+    }
+
+    #[test]
+    fn test_zero_multiplier_table_uses_builtin_defaults() {
+        let ctx = crisis_ctx();
+        assert_eq!(regime_multiplier(&ctx, MacroRegime::Crisis), 200);
+        assert_eq!(regime_multiplier(&ctx, MacroRegime::Expansion), 60);
+        // 20 + 40 * 200% = 100 bps
+        let (price, spread, _) = quote(&ctx, 0, DIRECTION_BUY, 0).unwrap();
+        assert_eq!(spread, 100);
+        assert_eq!(price, 5_050_000);
+    }
+
+    #[test]
+    fn test_static_skew_shifts_quotes_around_base_spread() {
+        let mut ctx = crisis_ctx();
+        // Zero skew: both sides at the 100 bps symmetric spread
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().0, 5_050_000);
+        assert_eq!(quote(&ctx, 0, DIRECTION_SELL, 0).unwrap().0, 4_950_000);
+
+        // +10: ask at 105 bps, bid at 95 -- the 200 bps width is unchanged
+        ctx[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2].copy_from_slice(&10i16.to_le_bytes());
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 105);
+        assert_eq!(quote(&ctx, 0, DIRECTION_SELL, 0).unwrap().1, 95);
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().0, 5_052_500);
+        assert_eq!(quote(&ctx, 0, DIRECTION_SELL, 0).unwrap().0, 4_952_500);
+
+        // Odd skews keep the full ask-bid difference; negative leans the other way
+        assert_eq!(static_skewed_spread(100, 5, DIRECTION_BUY), 103);
+        assert_eq!(static_skewed_spread(100, 5, DIRECTION_SELL), 98);
+        assert_eq!(static_skewed_spread(100, -10, DIRECTION_BUY), 95);
+        assert_eq!(static_skewed_spread(100, -10, DIRECTION_SELL), 105);
+        // A side can't go below zero spread
+        assert_eq!(static_skewed_spread(3, 20, DIRECTION_SELL), 0);
+
+        let mut data = full_init_data();
+        assert_eq!(read_static_skew(&init_over(0, &data)).unwrap(), 4);
+        assert_eq!(read_static_skew(&init_over(0xFF, &data[..166])).unwrap(), 0);
+        data[166..168].copy_from_slice(&(-300i16).to_le_bytes());
+        assert_eq!(read_static_skew(&init_over(0, &data)).unwrap(), -300);
+    }
+
+    #[test]
+    fn test_side_caps_bound_bid_and_ask_separately() {
+        let mut ctx = crisis_ctx();
+        // 20 + 40 * 200% + 50 signal = 150 bps before any cap
+        ctx[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8].copy_from_slice(&50u64.to_le_bytes());
+        ctx[MAX_BID_SPREAD_OFFSET..MAX_BID_SPREAD_OFFSET + 2].copy_from_slice(&60u16.to_le_bytes());
+        ctx[MAX_ASK_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].copy_from_slice(&120u16.to_le_bytes());
+        let (ask, ask_spread, _) = quote(&ctx, 0, DIRECTION_BUY, 0).unwrap();
+        let (bid, bid_spread, _) = quote(&ctx, 0, DIRECTION_SELL, 0).unwrap();
+        assert_eq!((ask_spread, ask), (120, 5_060_000));
+        assert_eq!((bid_spread, bid), (60, 4_970_000));
+
+        let data = full_init_data();
+        let ctx = init_over(0, &data);
+        assert_eq!(ctx[MAX_BID_SPREAD_OFFSET..MAX_BID_SPREAD_OFFSET + 2], 150u16.to_le_bytes());
+        assert_eq!(ctx[MAX_ASK_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2], 180u16.to_le_bytes());
+        assert_eq!(init_over(0xFF, &data[..168])[MAX_BID_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2], [0; 4]);
+    }
+
+    #[test]
+    fn test_side_caps_apply_after_skews() {
+        let mut ctx = crisis_ctx();
+        // 100 bps symmetric, ask capped at 102: a +10 static skew would put the ask at 105
+        ctx[MAX_ASK_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].copy_from_slice(&102u16.to_le_bytes());
+        ctx[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2].copy_from_slice(&10i16.to_le_bytes());
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 102);
+        assert_eq!(quote(&ctx, 0, DIRECTION_SELL, 0).unwrap().1, 95);
+
+        // A long LP widens the bid by the inventory skew, still under the bid cap
+        ctx[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2].copy_from_slice(&0i16.to_le_bytes());
+        ctx[MAX_BID_SPREAD_OFFSET..MAX_BID_SPREAD_OFFSET + 2].copy_from_slice(&101u16.to_le_bytes());
+        ctx[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&10_000_000u128.to_le_bytes());
+        ctx[SKEW_K_OFFSET..SKEW_K_OFFSET + 2].copy_from_slice(&10u16.to_le_bytes());
+        ctx[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2].copy_from_slice(&50u16.to_le_bytes());
+        ctx[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&20_000_000i64.to_le_bytes());
+        let (_, bid_spread, skew) = quote(&ctx, 0, DIRECTION_SELL, 0).unwrap();
+        assert_eq!((skew, bid_spread), (20, 101));
+    }
+
+    #[test]
+    fn test_wide_static_skew_clamps_the_ask_under_its_cap() {
+        let mut ctx = crisis_ctx();
+        // +400 (beyond an i8): ask at 100 + 200 = 300, bid at 100 - 200 -> 0
+        ctx[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2].copy_from_slice(&400i16.to_le_bytes());
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 300);
+        assert_eq!(quote(&ctx, 0, DIRECTION_SELL, 0).unwrap().1, 0);
+
+        // The ask cap holds the skewed ask, and so does the shared max when no side cap is set
+        ctx[MAX_ASK_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].copy_from_slice(&250u16.to_le_bytes());
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 250);
+        ctx[MAX_ASK_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].fill(0);
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&120u32.to_le_bytes());
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 120);
+    }
+
+    #[test]
+    fn test_init_rejects_bid_cap_of_full_price() {
+        let mut data = full_init_data();
+        data[168..170].copy_from_slice(&10_000u16.to_le_bytes());
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(MacroMatcherError::SpreadTooWide as u32))
+        );
+        data[168..170].copy_from_slice(&9_999u16.to_le_bytes());
+        assert!(try_init_over(0, &data).is_ok());
+    }
+
+    #[test]
+    fn test_zero_side_caps_fall_back_to_max_spread() {
+        let mut ctx = crisis_ctx();
+        ctx[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8].copy_from_slice(&50u64.to_le_bytes());
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&90u32.to_le_bytes());
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 90);
+        assert_eq!(quote(&ctx, 0, DIRECTION_SELL, 0).unwrap().1, 90);
+
+        // One side set, the other still on the shared cap
+        ctx[MAX_ASK_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].copy_from_slice(&140u16.to_le_bytes());
+        assert_eq!(quote(&ctx, 0, DIRECTION_BUY, 0).unwrap().1, 140);
+        assert_eq!(quote(&ctx, 0, DIRECTION_SELL, 0).unwrap().1, 90);
+    }
+
+    #[test]
+    fn test_hedge_reference_validated_by_magic() {
+        let ref_key = Pubkey::new_unique();
+        let ctx = hedge_ctx();
+
+        // Right key, wrong magic (e.g. another macro context)
+        let mut not_vol = vol_ctx(8_000);
+        not_vol[MAGIC_OFFSET..MAGIC_OFFSET + 8].copy_from_slice(&MACRO_MATCHER_MAGIC.to_le_bytes());
+        assert_eq!(
+            hedge_spread_adjustment(&ctx, &ref_key, &ref_key, &not_vol),
+            Err(ProgramError::Custom(MacroMatcherError::InvalidHedgeReference as u32))
+        );
+
+        // Valid vol context under a key sharing the stored key's first 8 bytes
+        let mut near_key = ref_key.to_bytes();
+        near_key[31] ^= 1;
+        assert_eq!(
+            hedge_spread_adjustment(&ctx, &ref_key, &Pubkey::new_from_array(near_key), &vol_ctx(8_000)),
+            Err(ProgramError::Custom(MacroMatcherError::InvalidHedgeReference as u32))
+        );
+
+        // Valid vol context under a different key
+        assert_eq!(
+            hedge_spread_adjustment(&ctx, &ref_key, &Pubkey::new_unique(), &vol_ctx(8_000)),
+            Err(ProgramError::Custom(MacroMatcherError::InvalidHedgeReference as u32))
+        );
+    }
+
+    // --- Oracle slot stamp ---
+
+    fn stamped(last_update: u64, tolerance: u8) -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&last_update.to_le_bytes());
+        ctx[ORACLE_SLOT_STAMP_OFFSET..ORACLE_SLOT_STAMP_OFFSET + 2].copy_from_slice(&8u16.to_le_bytes());
+        ctx[ORACLE_SLOT_TOLERANCE_OFFSET] = tolerance;
+        ctx
+    }
+
+    fn oracle_at(slot: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 64];
+        data[8..16].copy_from_slice(&slot.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_oracle_slot_stamp_within_tolerance() {
+        let ctx = stamped(10_005, 10);
+        assert!(check_oracle_slot_stamp(&ctx, &oracle_at(10_005)).is_ok());
+        assert!(check_oracle_slot_stamp(&ctx, &oracle_at(9_995)).is_ok());
+    }
+
+    #[test]
+    fn test_keeper_replay_flagged() {
+        // Keeper synced at 10_005 but the oracle last published at 9_000
+        let ctx = stamped(10_005, 10);
+        assert_eq!(
+            check_oracle_slot_stamp(&ctx, &oracle_at(9_000)),
+            Err(ProgramError::Custom(MacroMatcherError::OracleSlotMismatch as u32))
+        );
+        // Unconfigured stamp offset skips the check
+        let mut off = ctx.clone();
+        off[ORACLE_SLOT_STAMP_OFFSET..ORACLE_SLOT_STAMP_OFFSET + 2].fill(0);
+        assert!(check_oracle_slot_stamp(&off, &oracle_at(9_000)).is_ok());
+    }
+
+    #[test]
+    fn test_init_len_exact_only() {
+        for len in INIT_DATA_LENS {
+            assert!(check_init_len(len).is_ok(), "len {}", len);
+        }
+        // Short, partial optional group, or trailing garbage
+        for len in [81, 85, 124, 126, 156, 158, 161, 164, 167, 169, 173, 176, 181] {
+            assert_eq!(check_init_len(len), Err(ProgramError::InvalidInstructionData), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_index_sync_bounds() {
+        let out = Err(ProgramError::Custom(MacroMatcherError::IndexOutOfBounds as u32));
+        // full_init_data bounds the index to [1_000_000, 20_000_000]
+        let mut ctx = init_over(0, &full_init_data());
+        assert!(check_index_bounds(&ctx, 1_000_000).is_ok());
+        assert!(check_index_bounds(&ctx, 20_000_000).is_ok());
+        assert_eq!(check_index_bounds(&ctx, 999_999), out);
+        assert_eq!(check_index_bounds(&ctx, 20_000_001), out);
+
+        // Zero opens that side
+        ctx[INDEX_MAX_OFFSET..INDEX_MAX_OFFSET + 4].fill(0);
+        assert!(check_index_bounds(&ctx, u64::MAX).is_ok());
+        assert_eq!(check_index_bounds(&ctx, 999_999), out);
+        ctx[INDEX_MIN_OFFSET..INDEX_MIN_OFFSET + 4].fill(0);
+        assert!(check_index_bounds(&ctx, 1).is_ok());
+    }
+
+    #[test]
+    fn test_init_rejects_inverted_index_bounds() {
+        let mut data = full_init_data();
+        data[176..180].copy_from_slice(&999_999u32.to_le_bytes());
+        assert_eq!(try_init_over(0, &data), Err(ProgramError::InvalidInstructionData));
+        // A single-point range is allowed, as is a floor with no ceiling
+        data[176..180].copy_from_slice(&1_000_000u32.to_le_bytes());
+        assert!(try_init_over(0, &data).is_ok());
+        data[176..180].fill(0);
+        assert!(try_init_over(0, &data).is_ok());
+    }
+
+    #[test]
+    fn test_index_sync_requires_keeper_signature() {
+        let oracle = Pubkey::new_unique();
+        let keeper = Pubkey::new_unique();
+
+        // Correct key passed read-only
+        assert_eq!(
+            check_index_sync_signer(&keeper, &keeper, false),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        // Oracle identity alone no longer authorizes syncs once a keeper is set
+        assert_eq!(
+            check_index_sync_signer(&keeper, &oracle, true),
+            Err(ProgramError::Custom(MacroMatcherError::UnauthorizedKeeper as u32))
+        );
+        assert!(check_index_sync_signer(&keeper, &keeper, true).is_ok());
+    }
+
+    #[test]
+    fn test_index_sync_without_keeper_keeps_oracle_key_check() {
+        let oracle = Pubkey::new_unique();
+        // The oracle account is usually a data account that can't sign
+        assert!(check_index_sync_signer(&Pubkey::default(), &oracle, false).is_ok());
+        assert!(check_index_sync_signer(&Pubkey::default(), &oracle, true).is_ok());
+    }
+
+    /// Init payload with every optional trailing group present
+    fn full_init_data() -> Vec<u8> {
+        let mut data = vec![0x02, 0];
+        data.extend_from_slice(&20u32.to_le_bytes()); // base spread
+        data.extend_from_slice(&40u32.to_le_bytes()); // regime spread
+        data.extend_from_slice(&200u32.to_le_bytes()); // max spread
+        data.extend_from_slice(&10u32.to_le_bytes()); // impact k
+        data.extend_from_slice(&10_000_000_000u128.to_le_bytes()); // liquidity
+        data.extend_from_slice(&1_000_000_000u128.to_le_bytes()); // max fill
+        data.extend_from_slice(&[5u8; 32]); // macro oracle
+        data.extend_from_slice(&10u16.to_le_bytes()); // skew k
+        data.extend_from_slice(&50u16.to_le_bytes()); // max skew
+        data.extend_from_slice(&[6u8; 32]); // hedge reference
+        data.extend_from_slice(&8_000u16.to_le_bytes()); // hedge vol threshold
+        data.extend_from_slice(&25u16.to_le_bytes()); // hedge spread
+        data.extend_from_slice(&40u16.to_le_bytes()); // oracle slot stamp offset
+        data.push(5); // oracle slot tolerance
+        data.extend_from_slice(&[8u8; 32]); // keeper authority
+        for multiplier in [50u16, 100, 250, 120] {
+            data.extend_from_slice(&multiplier.to_le_bytes()); // regime multipliers
+        }
+        data.push(ROUNDING_LP_FAVORABLE); // rounding mode
+        data.extend_from_slice(&4i16.to_le_bytes()); // static skew
+        data.extend_from_slice(&150u16.to_le_bytes()); // max bid spread
+        data.extend_from_slice(&180u16.to_le_bytes()); // max ask spread
+        data.extend_from_slice(&1_000_000u32.to_le_bytes()); // index min
+        data.extend_from_slice(&20_000_000u32.to_le_bytes()); // index max
+        data
+    }
+
+    fn init_over(fill: u8, data: &[u8]) -> Vec<u8> {
+        try_init_over(fill, data).unwrap()
+    }
+
+    /// Run process_init over a context buffer pre-filled with `fill`
+    fn try_init_over(fill: u8, data: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        try_init_with_hedge(fill, data, vol_ctx(0), Pubkey::new_from_array([9u8; 32]), true).map(|(ctx, _)| ctx)
+    }
+
+    fn keys_key() -> Pubkey {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        Pubkey::find_program_address(&[KEYS_SEED, ctx_key.as_ref()], &program_id).0
+    }
+
+    /// Run process_init with the accounts a keys PDA needs, then the hedge
+    /// reference [6; 32] (owned by `hedge_owner`) and the vol-matcher program
+    /// [9; 32]. Returns the context and the keys PDA's data, empty when Init
+    /// created none.
+    fn try_init_with_hedge(
+        fill: u8,
+        data: &[u8],
+        mut hedge_data: Vec<u8>,
+        hedge_owner: Pubkey,
+        vol_program_executable: bool,
+    ) -> Result<(Vec<u8>, Vec<u8>), ProgramError> {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let payer_key = Pubkey::new_from_array([4u8; 32]);
+        let (keys_key, system_key) = (keys_key(), Pubkey::default());
+        let (hedge_key, vol_program) = (Pubkey::new_from_array([6u8; 32]), Pubkey::new_from_array([9u8; 32]));
+        let loader = Pubkey::new_unique();
+        let (mut lp_lamports, mut ctx_lamports, mut payer_lamports) = (0u64, 0u64, 1_000_000_000u64);
+        let (mut keys_lamports, mut system_lamports, mut hedge_lamports, mut vol_lamports) = (0u64, 0u64, 0u64, 0u64);
+        let (mut lp_data, mut ctx_data, mut payer_data) = (Vec::new(), vec![fill; CTX_SIZE], Vec::new());
+        let (mut keys_data, mut system_data, mut vol_data) = (Vec::new(), Vec::new(), Vec::new());
+        let keys = {
+            let accounts = [
+                AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut ctx_data, &program_id, false, 0),
+                AccountInfo::new(&payer_key, true, true, &mut payer_lamports, &mut payer_data, &system_key, false, 0),
+                AccountInfo::new(&keys_key, false, true, &mut keys_lamports, &mut keys_data, &program_id, false, 0),
+                AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+                AccountInfo::new(&hedge_key, false, false, &mut hedge_lamports, &mut hedge_data, &hedge_owner, false, 0),
+                AccountInfo::new(&vol_program, false, false, &mut vol_lamports, &mut vol_data, &loader, vol_program_executable, 0),
+            ];
+            process_init(&program_id, &accounts, data)?;
+            let created = accounts[3].data.borrow().to_vec();
+            created
+        };
+        Ok((ctx_data, keys))
+    }
+
+    #[test]
+    fn test_init_checks_hedge_reference_owner() {
+        let data = full_init_data();
+        let vol_program = Pubkey::new_from_array([9u8; 32]);
+        let (ctx, keys) = try_init_with_hedge(0, &data, vol_ctx(0), vol_program, true).unwrap();
+        assert_eq!(ctx[KEYS_OFFSET], KEY_KEEPER | KEY_HEDGE_REF);
+        assert_eq!(read_key(&keys, HEDGE_REF_OFFSET).unwrap(), Pubkey::new_from_array([6u8; 32]));
+
+        let invalid = Err(ProgramError::Custom(MacroMatcherError::InvalidHedgeReference as u32));
+        // Owned by some other program, e.g. one that copied the vol-matcher magic
+        assert_eq!(try_init_with_hedge(0, &data, vol_ctx(0), Pubkey::new_unique(), true), invalid);
+        // The named owner isn't a program
+        assert_eq!(try_init_with_hedge(0, &data, vol_ctx(0), vol_program, false), invalid);
+        // Right owner, but not a vol-matcher context
+        assert_eq!(try_init_with_hedge(0, &data, vec![0u8; CTX_SIZE], vol_program, true), invalid);
+
+        // A different key in the payload than the account passed
+        let mut other_key = data.clone();
+        other_key[86..118].copy_from_slice(&[3u8; 32]);
+        assert_eq!(try_init_with_hedge(0, &other_key, vol_ctx(0), vol_program, true), invalid);
+
+        // Without a hedge reference the vol-matcher accounts aren't checked
+        let mut no_hedge = data.clone();
+        no_hedge[86..118].fill(0);
+        let (ctx, keys) = try_init_with_hedge(0, &no_hedge, Vec::new(), Pubkey::new_unique(), false).unwrap();
+        assert_eq!(ctx[KEYS_OFFSET], KEY_KEEPER);
+        assert_eq!(read_key(&keys, HEDGE_REF_OFFSET).unwrap(), Pubkey::default());
+
+        // With neither key no keys PDA is created
+        no_hedge[125..157].fill(0);
+        let (ctx, keys) = try_init_with_hedge(0, &no_hedge, Vec::new(), Pubkey::new_unique(), false).unwrap();
+        assert_eq!(ctx[KEYS_OFFSET], 0);
+        assert!(keys.is_empty());
+    }
+
+    #[test]
+    fn test_init_overwrites_every_byte() {
+        // Any byte that differs between a zero- and 0xFF-prefilled account is
+        // one the init path never wrote -- stale data leaking into a new market
+        let data = full_init_data();
+        for len in INIT_DATA_LENS {
+            let (zeros, ones) = (init_over(0x00, &data[..len]), init_over(0xFF, &data[..len]));
+            let leaked: Vec<usize> = (0..CTX_SIZE).filter(|&i| zeros[i] != ones[i]).collect();
+            assert!(leaked.is_empty(), "len {}: init left bytes {:?} unwritten", len, leaked);
+        }
+    }
+
+    #[test]
+    fn test_init_rejects_unknown_mode() {
+        let mut data = full_init_data();
+        for mode in 0..=MAX_MODE {
+            data[1] = mode;
+            assert_eq!(init_over(0, &data)[MODE_OFFSET], mode);
+        }
+        // An unknown mode would otherwise be stored and silently priced as mode 0
+        data[1] = 200;
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(MacroMatcherError::InvalidMode as u32))
+        );
+    }
+
+    #[test]
+    fn test_match_guard_rejects_nested_match() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        enter_match_guard(&mut ctx).unwrap();
+        assert_eq!(ctx[MATCH_GUARD_OFFSET], 1);
+        // A re-entrant match lands while the outer one still holds the guard
+        assert_eq!(
+            enter_match_guard(&mut ctx),
+            Err(ProgramError::Custom(MacroMatcherError::MatchReentered as u32))
+        );
+        ctx[MATCH_GUARD_OFFSET] = 0;
+        assert!(enter_match_guard(&mut ctx).is_ok());
+    }
+
+    #[test]
+    fn test_limit_price_slippage_protection() {
+        let exceeded = Err(ProgramError::Custom(MacroMatcherError::SlippageExceeded as u32));
+        // Buy: exec at or under the limit passes, above is rejected
+        assert_eq!(check_limit_price(1_000, DIRECTION_BUY, Some(1_010)), Ok(()));
+        assert_eq!(check_limit_price(1_010, DIRECTION_BUY, Some(1_010)), Ok(()));
+        assert_eq!(check_limit_price(1_011, DIRECTION_BUY, Some(1_010)), exceeded);
+        // Sell: exec at or over the limit passes, below is rejected
+        assert_eq!(check_limit_price(1_000, DIRECTION_SELL, Some(990)), Ok(()));
+        assert_eq!(check_limit_price(990, DIRECTION_SELL, Some(990)), Ok(()));
+        assert_eq!(check_limit_price(989, DIRECTION_SELL, Some(990)), exceeded);
+        // No limit, no protection
+        assert_eq!(check_limit_price(u64::MAX, DIRECTION_BUY, None), Ok(()));
+
+        let mut data = vec![0x00];
+        data.extend_from_slice(&5_000_000u64.to_le_bytes());
+        data.push(DIRECTION_SELL);
+        assert_eq!(parse_limit_price(&data), Ok(None));
+        data.extend_from_slice(&990u64.to_le_bytes());
+        assert_eq!(parse_limit_price(&data), Ok(Some(990)));
+    }
+
+    #[test]
+    fn test_match_enforces_limit_price() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data()[..82]);
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&compute_mark_price(200).to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+
+        // Learn the buy price without a limit, then quote the limit around it
+        let mut probe = ctx.clone();
+        run_lp_ix(process_match, &mut probe, &[0x00]).unwrap();
+        let exec = u64::from_le_bytes(probe[0..8].try_into().unwrap());
+        let with_limit = |limit: &[u8]| {
+            let mut data = vec![0x00];
+            data.extend_from_slice(&1_000u64.to_le_bytes());
+            data.push(DIRECTION_BUY);
+            data.extend_from_slice(limit);
+            run_lp_ix(process_match, &mut ctx.clone(), &data)
+        };
+        assert_eq!(with_limit(&exec.to_le_bytes()), Ok(()));
+        assert_eq!(
+            with_limit(&(exec - 1).to_le_bytes()),
+            Err(ProgramError::Custom(MacroMatcherError::SlippageExceeded as u32))
+        );
+        // A limit cut short is malformed, not "no limit"
+        assert_eq!(with_limit(&exec.to_le_bytes()[..4]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(with_limit(&[]), Ok(()));
+    }
+
+    #[test]
+    fn test_match_rejects_other_context_versions() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data()[..82]);
+        assert_eq!(read_version(&ctx).unwrap(), CTX_VERSION);
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&compute_mark_price(200).to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(run_lp_ix(process_match, &mut ctx.clone(), &[0x00]), Ok(()));
+
+        // Any other version keeps its fields elsewhere
+        let unsupported = Err(ProgramError::Custom(MacroMatcherError::UnsupportedVersion as u32));
+        for version in [0u32, CTX_VERSION + 1] {
+            let mut other = ctx.clone();
+            other[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&version.to_le_bytes());
+            assert_eq!(run_lp_ix(process_match, &mut other.clone(), &[0x00]), unsupported);
+            assert_eq!(run_lp_ix(process_match_batch, &mut other, &[0x0A, 1, 1, 0, 0, 0, 0, 0, 0, 0]), unsupported);
+        }
+    }
+
+    #[test]
+    fn test_liquidity_depletion_and_replenish() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let exhausted = Err(ProgramError::Custom(MacroMatcherError::LiquidityExhausted as u32));
+        let mut ctx = init_over(0, &full_init_data()[..82]);
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&compute_mark_price(200).to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        let mut probe = ctx.clone();
+        run_lp_ix(process_match, &mut probe, &[0x00]).unwrap();
+        let exec = u64::from_le_bytes(probe[0..8].try_into().unwrap());
+
+        // Depth of exactly two 1_000_000-size fills at the buy price
+        let notional = fill_notional(1_000_000, exec);
+        ctx[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&(2 * notional).to_le_bytes());
+        let mut buy = vec![0x00];
+        buy.extend_from_slice(&1_000_000u64.to_le_bytes());
+        buy.push(DIRECTION_BUY);
+        let consumed = |ctx: &[u8]| u128::from_le_bytes(ctx[CONSUMED_LIQUIDITY_OFFSET..CONSUMED_LIQUIDITY_OFFSET + 16].try_into().unwrap());
+
+        // The second fill lands exactly on the depth; the third would pass it
+        run_lp_ix(process_match, &mut ctx, &buy).unwrap();
+        run_lp_ix(process_match, &mut ctx, &buy).unwrap();
+        assert_eq!(consumed(&ctx), 2 * notional);
+        assert_eq!(run_lp_ix(process_match, &mut ctx.clone(), &buy), exhausted);
+        assert_eq!(run_lp_ix(process_match_batch, &mut ctx.clone(), &batch_data(&[(1_000_000, DIRECTION_BUY)])), exhausted);
+        // A sizeless quote consumes nothing
+        run_lp_ix(process_match, &mut ctx.clone(), &[0x00]).unwrap();
+
+        // Topping up one fill's worth reopens exactly one fill
+        let mut top_up = vec![0x05];
+        top_up.extend_from_slice(&(notional as u64).to_le_bytes());
+        run_lp_ix(process_replenish, &mut ctx, &top_up).unwrap();
+        assert_eq!(consumed(&ctx), notional);
+        run_lp_ix(process_match, &mut ctx, &buy).unwrap();
+        assert_eq!(run_lp_ix(process_match, &mut ctx.clone(), &buy), exhausted);
+
+        // A bare Replenish resets the counter
+        run_lp_ix(process_replenish, &mut ctx, &[0x05]).unwrap();
+        assert_eq!(consumed(&ctx), 0);
+        run_lp_ix(process_match_batch, &mut ctx, &batch_data(&[(1_000_000, DIRECTION_BUY), (1_000_000, DIRECTION_BUY)])).unwrap();
+        assert_eq!(consumed(&ctx), 2 * notional);
+        assert_eq!(run_lp_ix(process_replenish, &mut ctx, &[0x05, 1, 2]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_replenish_requires_lp_signer() {
+        let mut ctx = init_over(0, &full_init_data()[..82]);
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&lp_key, false, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut ctx, &program_id, false, 0),
+        ];
+        assert!(process_replenish(&program_id, &accounts, &[0x05]).is_err());
+    }
+
+    #[test]
+    fn test_init_stores_spreads_and_amounts_at_full_width() {
+        let mut data = full_init_data();
+        data[10..14].copy_from_slice(&(u16::MAX as u32 + 1).to_le_bytes());
+        data[18..34].copy_from_slice(&(u64::MAX as u128 + 1).to_le_bytes());
+        let ctx = init_over(0, &data);
+        assert_eq!(ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4], (u16::MAX as u32 + 1).to_le_bytes());
+        assert_eq!(ctx[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16], (u64::MAX as u128 + 1).to_le_bytes());
+        assert_eq!(ctx[MAX_FILL_OFFSET..MAX_FILL_OFFSET + 16], 1_000_000_000u128.to_le_bytes());
+        // Stats start at zero in their baseline widths
+        assert_eq!(ctx[TOTAL_VOLUME_OFFSET..TOTAL_TRADES_OFFSET + 8], [0; 24]);
+    }
+
+    #[test]
+    fn test_sync_must_advance_slot() {
+        let stale = Err(ProgramError::Custom(MacroMatcherError::StaleUpdate as u32));
+        // Never synced: init leaves the stamp at 0
+        assert_eq!(check_sync_slot(0, 1, 1), Ok(()));
+        assert_eq!(check_sync_slot(1_000, 1_001, 1_001), Ok(()));
+        // Read at the stored update's slot, or behind it
+        assert_eq!(check_sync_slot(1_000, 1_000, 1_001), stale);
+        assert_eq!(check_sync_slot(1_000, 999, 1_001), stale);
+        // Read in a slot that hasn't happened yet
+        assert_eq!(check_sync_slot(1_000, 1_002, 1_001), Err(ProgramError::InvalidInstructionData));
+    }
+
+    /// Run Index Sync from the stored oracle (no keeper configured) for an
+    /// index read at `update_slot`
+    fn run_index_sync(ctx: &mut [u8], real_rate_bps: u32, update_slot: u64) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (ctx_key, oracle_key) = (Pubkey::new_from_array([2u8; 32]), Pubkey::new_from_array([5u8; 32]));
+        let (mut ctx_lamports, mut oracle_lamports) = (0u64, 0u64);
+        let mut oracle_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&oracle_key, false, false, &mut oracle_lamports, &mut oracle_data, &program_id, false, 0),
+        ];
+        // Nominal 3.00% over real_rate_bps of inflation-adjusted yield
+        let packed = ((300 + real_rate_bps as u64) << 32) | 300;
+        let mut data = vec![0x03];
+        data.extend_from_slice(&compute_mark_price(real_rate_bps as i64).to_le_bytes());
+        data.extend_from_slice(&packed.to_le_bytes());
+        data.extend_from_slice(&[0u8; 16]);
+        data.extend_from_slice(&update_slot.to_le_bytes());
+        process_index_sync(&program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_index_sync_replay_in_later_slot_rejected() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let stale = Err(ProgramError::Custom(MacroMatcherError::StaleUpdate as u32));
+        let mut ctx = init_over(0, &full_init_data()[..82]);
+        let index = |ctx: &[u8]| u64::from_le_bytes(ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].try_into().unwrap());
+
+        // Reads at 950 then 960 land; the stored slot is the read slot, not the clock's
+        run_index_sync(&mut ctx, 200, 950).unwrap();
+        run_index_sync(&mut ctx, 250, 960).unwrap();
+        assert_eq!(&ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8], &960u64.to_le_bytes());
+
+        // The 950 sync replayed now, 40 slots on: rejected, the newer index stands
+        assert_eq!(run_index_sync(&mut ctx, 200, 950), stale);
+        assert_eq!(index(&ctx), compute_mark_price(250));
+        assert_eq!(run_index_sync(&mut ctx, 200, 1_001), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_real_rate_past_mark_floor_rejected() {
+        let invalid = Err(ProgramError::Custom(MacroMatcherError::InvalidIndexValue as u32));
+        let packed = |nominal: u32, inflation: u32| ((nominal as u64) << 32) | inflation as u64;
+        // -6.00% real (1.00% nominal, 7.00% inflation) would floor to mark 0
+        assert_eq!(check_index_value(compute_mark_price(-600), packed(100, 700)), invalid);
+        assert_eq!(check_index_value(0, packed(100, 600)), invalid);
+        // -4.99% is the deepest rate with a tradable mark
+        assert_eq!(check_index_value(compute_mark_price(-499), packed(100, 599)), Ok(()));
+        assert_eq!(check_index_value(compute_mark_price(200), packed(450, 250)), Ok(()));
+        // A zero index reads as unsynced whatever the components say
+        assert_eq!(check_index_value(0, packed(450, 250)), invalid);
+        // Any other index than the components' mark is rejected
+        assert_eq!(check_index_value(compute_mark_price(200) + 1, packed(450, 250)), invalid);
+        assert_eq!(check_index_value(compute_mark_price(199), packed(450, 250)), invalid);
+    }
+
+    thread_local! {
+        static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Match reads the clock; serve a fixed slot off-chain. Also keeps this
+    /// thread's `set_return_data` so Query tests can read it back.
+    struct FixedClock;
+
+    impl SyscallStubs for FixedClock {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Clock) = Clock { slot: 1_000, ..Clock::default() } };
+            0
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+
+        fn sol_set_return_data(&self, data: &[u8]) {
+            RETURN_DATA.with(|r| *r.borrow_mut() = data.to_vec());
+        }
+
+        fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+            Some((Pubkey::default(), RETURN_DATA.with(|r| r.borrow().clone())))
+        }
+
+        /// Init's keys PDA: apply the System Program CreateAccount in place
+        fn sol_invoke_signed(&self, ix: &Instruction, infos: &[AccountInfo], _seeds: &[&[&[u8]]]) -> ProgramResult {
+            let info = |n: usize| infos.iter().find(|a| *a.key == ix.accounts[n].pubkey).unwrap();
+            assert_eq!(ix.data[..4], 0u32.to_le_bytes(), "only CreateAccount is stubbed");
+            let lamports = u64::from_le_bytes(ix.data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(ix.data[12..20].try_into().unwrap()) as usize;
+            **info(0).try_borrow_mut_lamports()? -= lamports;
+            **info(1).try_borrow_mut_lamports()? += lamports;
+            *info(1).data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+            Ok(())
+        }
+    }
+
+    /// Run signer-free Query Spread over a read-only `ctx`; `(bid, ask)` from return data
+    fn run_query_spread(ctx: &[u8]) -> Result<(u64, u64), ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let mut ctx_lamports = 0u64;
+        let mut ctx_data = ctx.to_vec();
+        let accounts = [AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, &mut ctx_data, &program_id, false, 0)];
+        RETURN_DATA.with(|r| r.borrow_mut().clear());
+        process_query_spread(&program_id, &accounts, &[0x0D])?;
+        assert_eq!(ctx_data, ctx, "Query Spread wrote to the context");
+        let (_, data) = get_return_data().unwrap();
+        Ok((u64::from_le_bytes(data[..8].try_into().unwrap()), u64::from_le_bytes(data[8..16].try_into().unwrap())))
+    }
+
+    /// Run an LP-signed instruction against `ctx`
+    fn run_lp_ix(ix: fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult, ctx: &mut [u8], data: &[u8]) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+        ];
+        ix(&program_id, &accounts, data)
+    }
+
+    #[test]
+    fn test_query_spread_matches_match_pricing() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mark = compute_mark_price(200);
+        // Base layout only: no hedge reference, floor rounding, no static skew
+        let mut ctx = init_over(0, &full_init_data()[..82]);
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&mark.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        ctx[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8].copy_from_slice(&15u64.to_le_bytes());
+
+        // Each quoted side is the spread a match on that side is charged
+        let quoted_sides = |ctx: &mut Vec<u8>| {
+            let (bid, ask) = run_query_spread(ctx).unwrap();
+            for (direction, spread) in [(DIRECTION_SELL, bid), (DIRECTION_BUY, ask)] {
+                let mut data = vec![0x00];
+                data.extend_from_slice(&0u64.to_le_bytes());
+                data.push(direction);
+                run_lp_ix(process_match, ctx, &data).unwrap();
+                let exec = u64::from_le_bytes(ctx[0..8].try_into().unwrap());
+                assert_eq!(exec, side_price(mark, spread, direction, false).unwrap(), "direction {}", direction);
+            }
+            (bid, ask)
+        };
+        let priced_spread = |ctx: &mut Vec<u8>, regime: MacroRegime| {
+            ctx[REGIME_OFFSET] = regime as u8;
+            let (bid, ask) = quoted_sides(ctx);
+            // No skew or side caps: both sides are the symmetric spread
+            assert_eq!((bid, ask), (compute_current_spread(ctx).unwrap(), compute_current_spread(ctx).unwrap()));
+            ask
+        };
+
+        // 20 base + 40 regime spread scaled by the built-in table + 15 signal
+        assert_eq!(priced_spread(&mut ctx, MacroRegime::Expansion), 59);
+        assert_eq!(priced_spread(&mut ctx, MacroRegime::Stagnation), 75);
+        assert_eq!(priced_spread(&mut ctx, MacroRegime::Crisis), 115);
+        assert_eq!(priced_spread(&mut ctx, MacroRegime::Recovery), 85);
+
+        // An operator table replaces the built-in multipliers in both
+        set_multipliers(&mut ctx, [50, 100, 250, 120]);
+        assert_eq!(priced_spread(&mut ctx, MacroRegime::Crisis), 135);
+
+        // Capped at max_spread, as in Match
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&100u32.to_le_bytes());
+        assert_eq!(priced_spread(&mut ctx, MacroRegime::Crisis), 100);
+
+        // Static skew and the per-side caps split the sides, in the query as in Match
+        ctx[REGIME_OFFSET] = MacroRegime::Stagnation as u8;
+        ctx[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2].copy_from_slice(&10i16.to_le_bytes());
+        ctx[MAX_ASK_SPREAD_OFFSET..MAX_ASK_SPREAD_OFFSET + 2].copy_from_slice(&80u16.to_le_bytes());
+        let (bid, ask) = quoted_sides(&mut ctx);
+        assert!(bid < 75 && ask == 80, "bid {} ask {}", bid, ask);
+
+        // Uninitialized or another layout version is refused
+        assert_eq!(run_query_spread(&vec![0u8; CTX_SIZE]), Err(ProgramError::UninitializedAccount));
+        ctx[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&(CTX_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            run_query_spread(&ctx),
+            Err(ProgramError::Custom(MacroMatcherError::UnsupportedVersion as u32))
+        );
+    }
+
+    /// Run RegimeUpdate at slot 1_000, signed by the stored macro oracle [5; 32]
+    fn run_regime_update(ctx: &mut [u8], new_regime: u8) -> ProgramResult {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (ctx_key, oracle_key) = (Pubkey::new_from_array([2u8; 32]), Pubkey::new_from_array([5u8; 32]));
+        let (mut ctx_lamports, mut oracle_lamports) = (0u64, 0u64);
+        let mut oracle_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&oracle_key, true, false, &mut oracle_lamports, &mut oracle_data, &program_id, false, 0),
+        ];
+        process_regime_update(&program_id, &accounts, &[0x04, new_regime])
+    }
+
+    #[test]
+    fn test_regime_update_requires_fresh_feed() {
+        let mut ctx = init_over(0, &full_init_data());
+        let crisis = MacroRegime::Crisis as u8;
+
+        // Synced 100 slots ago: within the 150-slot window, the flip lands
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&900u64.to_le_bytes());
+        run_regime_update(&mut ctx, crisis).unwrap();
+        assert_eq!(ctx[REGIME_OFFSET], crisis);
+
+        // Synced 200 slots ago: rejected, regime untouched
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&800u64.to_le_bytes());
+        let before = ctx.clone();
+        assert_eq!(
+            run_regime_update(&mut ctx, MacroRegime::Expansion as u8),
+            Err(ProgramError::Custom(MacroMatcherError::OracleStale as u32))
+        );
+        assert_eq!(ctx, before);
+
+        // Never synced counts as stale
+        assert_eq!(
+            run_regime_update(&mut init_over(0, &full_init_data()), crisis),
+            Err(ProgramError::Custom(MacroMatcherError::OracleStale as u32))
+        );
+    }
+
+    #[test]
+    fn test_keys_pda_names_the_hedge_reference_and_keeper() {
+        let (mut ctx, mut keys) = try_init_with_hedge(0, &full_init_data(), vol_ctx(0), Pubkey::new_from_array([9u8; 32]), true).unwrap();
+        ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8].copy_from_slice(&compute_mark_price(200).to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&990u64.to_le_bytes());
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let (hedge_key, keys_key) = (Pubkey::new_from_array([6u8; 32]), keys_key());
+        let vol_program = Pubkey::new_from_array([9u8; 32]);
+
+        // Match with the keys PDA then the hedge reference: 80% vol over the
+        // 8_000 threshold widens both sides by the 25 bps hedge spread
+        let run_match = |ctx: &mut [u8], keys: &mut [u8], keys_at: &Pubkey, vol_bps: u64| {
+            let (mut lp_lamports, mut ctx_lamports, mut keys_lamports, mut hedge_lamports) = (0u64, 0u64, 0u64, 0u64);
+            let (mut lp_data, mut hedge_data) = (Vec::new(), vol_ctx(vol_bps));
+            let accounts = [
+                AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+                AccountInfo::new(keys_at, false, false, &mut keys_lamports, keys, &program_id, false, 0),
+                AccountInfo::new(&hedge_key, false, false, &mut hedge_lamports, &mut hedge_data, &vol_program, false, 0),
+            ];
+            process_match(&program_id, &accounts, &[0x00]).map(|_| u64::from_le_bytes(ctx[0..8].try_into().unwrap()))
+        };
+        let calm = run_match(&mut ctx, &mut keys, &keys_key, 0).unwrap();
+        let stressed = run_match(&mut ctx, &mut keys, &keys_key, 8_000).unwrap();
+        assert!(stressed > calm, "calm {} stressed {}", calm, stressed);
+        // A program-owned copy of the keys anywhere but the PDA is refused
+        assert_eq!(run_match(&mut ctx.clone(), &mut keys.clone(), &Pubkey::new_unique(), 0), Err(ProgramError::InvalidSeeds));
+        // Without the PDA the configured reference can't be priced
+        assert_eq!(run_lp_ix(process_match, &mut ctx.clone(), &[0x00]), Err(ProgramError::NotEnoughAccountKeys));
+
+        // Index Sync needs the keeper [8; 32] to sign, found through the keys PDA
+        let run_sync = |ctx: &mut [u8], keys: &mut [u8], keeper_key: &Pubkey, update_slot: u64| {
+            let oracle_key = Pubkey::new_from_array([5u8; 32]);
+            let (mut ctx_lamports, mut oracle_lamports, mut keeper_lamports, mut keys_lamports) = (0u64, 0u64, 0u64, 0u64);
+            let (mut oracle_data, mut keeper_data) = (Vec::new(), Vec::new());
+            let accounts = [
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+                AccountInfo::new(&oracle_key, false, false, &mut oracle_lamports, &mut oracle_data, &program_id, false, 0),
+                AccountInfo::new(keeper_key, true, false, &mut keeper_lamports, &mut keeper_data, &program_id, false, 0),
+                AccountInfo::new(&keys_key, false, false, &mut keys_lamports, keys, &program_id, false, 0),
+            ];
+            let mut data = vec![0x03];
+            data.extend_from_slice(&compute_mark_price(250).to_le_bytes());
+            data.extend_from_slice(&((550u64 << 32) | 300).to_le_bytes());
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&update_slot.to_le_bytes());
+            process_index_sync(&program_id, &accounts, &data)
+        };
+        assert_eq!(
+            run_sync(&mut ctx, &mut keys, &Pubkey::new_unique(), 1_000),
+            Err(ProgramError::Custom(MacroMatcherError::UnauthorizedKeeper as u32))
+        );
+        run_sync(&mut ctx, &mut keys, &Pubkey::new_from_array([8u8; 32]), 1_000).unwrap();
+        assert_eq!(ctx[CURRENT_INDEX_OFFSET..CURRENT_INDEX_OFFSET + 8], compute_mark_price(250).to_le_bytes());
+    }
 }
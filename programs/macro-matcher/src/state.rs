@@ -1,16 +1,20 @@
 // Re-export shared constants and functions from matcher-common
 pub use matcher_common::{CTX_SIZE, verify_magic as verify_magic_generic};
 
-use solana_program::{pubkey::Pubkey, program_error::ProgramError};
+use solana_program::{account_info::AccountInfo, msg, pubkey::Pubkey, program_error::ProgramError};
+
+use crate::errors::MacroMatcherError;
 
 /// Magic bytes: "MACOMATC" as u64 LE
 pub const MACRO_MATCHER_MAGIC: u64 = 0x4d41_434f_4d41_5443;
 
 // Macro-matcher-specific field offsets
-#[allow(dead_code)]
 pub const VERSION_OFFSET: usize = 72;                     // u32
 #[allow(dead_code)]
 pub const MODE_OFFSET: usize = 76;                        // u8: 0=RealRate, 1=HousingRatio (future)
+pub const MATCH_GUARD_OFFSET: usize = 77;                 // u8: 1 while a match is in flight (reentrancy guard)
+pub const ROUNDING_MODE_OFFSET: usize = 78;               // u8: ROUNDING_FLOOR or ROUNDING_LP_FAVORABLE
+pub const KEYS_OFFSET: usize = 79;                        // u8: KEY_* flags set in the keys PDA
 pub const BASE_SPREAD_OFFSET: usize = 112;                // u32
 pub const REGIME_SPREAD_OFFSET: usize = 116;              // u32: additional spread scaled by regime
 pub const MAX_SPREAD_OFFSET: usize = 120;                 // u32
@@ -19,6 +23,10 @@ pub const CURRENT_INDEX_OFFSET: usize = 128;              // u64: real rate inde
 pub const INDEX_COMPONENTS_PACKED_OFFSET: usize = 136;    // u64: nominal(high32) | inflation(low32)
 pub const LAST_UPDATE_SLOT_OFFSET: usize = 144;           // u64
 pub const REGIME_OFFSET: usize = 152;                     // u8: MacroRegime (0-3)
+pub const ORACLE_SLOT_TOLERANCE_OFFSET: usize = 153;      // u8: max |oracle stamp - last_update_slot|
+pub const STATIC_SKEW_OFFSET: usize = 154;                // i16: ask-minus-bid spread skew (bps), independent of inventory
+pub const HEDGE_VOL_THRESHOLD_OFFSET: usize = 156;        // u16: referenced vol (bps) that triggers widening
+pub const HEDGE_SPREAD_OFFSET: usize = 158;               // u16: extra spread (bps) above the threshold
 pub const SIGNAL_SEVERITY_OFFSET: usize = 160;            // u64 (0-3)
 pub const SIGNAL_ADJUSTED_SPREAD_OFFSET: usize = 168;     // u64
 pub const LIQUIDITY_OFFSET: usize = 176;                  // u128 (16 bytes)
@@ -26,7 +34,57 @@ pub const MAX_FILL_OFFSET: usize = 192;                   // u128 (16 bytes)
 pub const MACRO_ORACLE_OFFSET: usize = 208;               // Pubkey (32 bytes)
 pub const TOTAL_VOLUME_OFFSET: usize = 240;               // u128 (16 bytes)
 pub const TOTAL_TRADES_OFFSET: usize = 256;               // u64
-// 264..320 = reserved
+pub const NET_INVENTORY_OFFSET: usize = 264;              // i64: LP signed inventory (+ = net long)
+pub const SKEW_K_OFFSET: usize = 272;                     // u16: inventory skew coefficient (bps)
+pub const MAX_SKEW_OFFSET: usize = 274;                   // u16: skew clamp (bps)
+pub const MAX_BID_SPREAD_OFFSET: usize = 276;             // u16: bid-side spread cap (bps, 0 = max_spread)
+pub const MAX_ASK_SPREAD_OFFSET: usize = 278;             // u16: ask-side spread cap (bps, 0 = max_spread)
+pub const CONSUMED_LIQUIDITY_OFFSET: usize = 280;         // u128: fill notional (e6) since init or the last Replenish
+pub const REGIME_MULTIPLIERS_OFFSET: usize = 296;         // [u16; 4]: spread multiplier % per regime (all zero = built-in)
+pub const INDEX_MIN_OFFSET: usize = 304;                  // u32: lowest index (e6) a sync may store (0 = no floor)
+pub const INDEX_MAX_OFFSET: usize = 308;                  // u32: highest index (e6) a sync may store (0 = no ceiling)
+pub const ORACLE_SLOT_STAMP_OFFSET: usize = 312;          // u16: where the oracle account stores its u64 slot (0 = off)
+// 314..320 = reserved
+
+/// Context layout version written by init; matches reject any other
+pub const CTX_VERSION: u32 = 1;
+
+/// Keys: one PDA per context holding the keeper authority and the hedge
+/// reference, which don't fit in the context, created by Init when either is
+/// supplied. The context's KEYS byte records which are set, so an instruction
+/// can tell a missing PDA from one that was never configured.
+pub const KEYS_SEED: &[u8] = b"keys";
+/// Magic bytes: "MACOKEYS" as u64 LE
+pub const KEYS_MAGIC: u64 = 0x4d41_434f_4b45_5953;
+pub const KEYS_SIZE: usize = 80;
+pub const KEYS_MAGIC_OFFSET: usize = 0;            // u64
+pub const KEYS_BUMP_OFFSET: usize = 8;             // u8: PDA bump, so readers skip the seed search
+pub const KEEPER_AUTHORITY_OFFSET: usize = 16;     // Pubkey: index sync signer
+pub const HEDGE_REF_OFFSET: usize = 48;            // Pubkey: vol-matcher context whose vol widens the spread
+
+/// KEYS flag: a keeper authority is stored and must sign Index Sync
+pub const KEY_KEEPER: u8 = 1;
+/// KEYS flag: a hedge reference is stored and must be passed to price
+pub const KEY_HEDGE_REF: u8 = 2;
+
+/// Hedge reference: a vol-matcher context ("VOLMATCH") whose current vol widens our spread
+pub const HEDGE_REF_MAGIC: u64 = 0x564F_4c4d_4154_4348;
+pub const HEDGE_REF_VOL_OFFSET: usize = 128;              // u64: current_vol_bps in the vol-matcher layout
+
+/// MatchBatch writes one u64 price per leg into the 64-byte return region
+pub const MAX_BATCH_LEGS: usize = matcher_common::RETURN_DATA_SIZE / 8;
+/// MatchBatch leg: trade_size_abs (u64 LE) then direction (u8)
+pub const BATCH_LEG_LEN: usize = 9;
+
+/// Trade direction (match data byte 9)
+pub use matcher_shared::{DIRECTION_BUY, DIRECTION_SELL};
+
+/// Highest valid mode byte (0=RealRate, 1=HousingRatio)
+pub const MAX_MODE: u8 = 1;
+
+/// Exec price rounding: floor both sides, or round asks up so the remainder stays with the LP
+pub const ROUNDING_FLOOR: u8 = 0;
+pub const ROUNDING_LP_FAVORABLE: u8 = 1;
 
 /// Rate offset: +500 bps (+5.00%) to keep mark price positive
 #[allow(dead_code)]
@@ -77,8 +135,7 @@ impl MacroRegime {
 
 /// Compute mark price from real rate in bps.
 /// mark_price_e6 = (real_rate_bps + RATE_OFFSET) * 10_000
-/// Floored at 0.
-#[allow(dead_code)]
+/// Floored at 0; Index Sync rejects rates that reach the floor.
 pub fn compute_mark_price(real_rate_bps: i64) -> u64 {
     let shifted = real_rate_bps + RATE_OFFSET;
     if shifted <= 0 {
@@ -92,6 +149,24 @@ pub fn verify_magic(ctx_data: &[u8]) -> bool {
     verify_magic_generic(ctx_data, MACRO_MATCHER_MAGIC)
 }
 
+/// Read the context layout version stamped at init
+pub fn read_version(ctx_data: &[u8]) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Read the signed static skew (bps)
+pub fn read_static_skew(ctx_data: &[u8]) -> Result<i16, ProgramError> {
+    Ok(i16::from_le_bytes(
+        ctx_data[STATIC_SKEW_OFFSET..STATIC_SKEW_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
 /// Read the macro oracle pubkey from the context account
 pub fn read_macro_oracle(ctx_data: &[u8]) -> Result<Pubkey, ProgramError> {
     Ok(Pubkey::new_from_array(
@@ -101,10 +176,103 @@ pub fn read_macro_oracle(ctx_data: &[u8]) -> Result<Pubkey, ProgramError> {
     ))
 }
 
+/// Reject a keys account that isn't this context's PDA. Owner and magic alone
+/// would accept any program-owned account that happens to start with the
+/// magic; the PDA can only hold what Init wrote.
+pub fn verify_keys(
+    keys: &AccountInfo,
+    keys_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if keys.owner != program_id
+        || keys_data.len() < KEYS_SIZE
+        || keys_data[KEYS_MAGIC_OFFSET..KEYS_MAGIC_OFFSET + 8] != KEYS_MAGIC.to_le_bytes()
+    {
+        msg!("MACRO-MATCHER: Keys for {} not initialized", ctx_key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let seeds: &[&[u8]] = &[KEYS_SEED, ctx_key.as_ref(), &[keys_data[KEYS_BUMP_OFFSET]]];
+    let expected = Pubkey::create_program_address(seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)?;
+    if *keys.key != expected {
+        msg!("MACRO-MATCHER: {} is not the keys PDA of {}", keys.key, ctx_key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// Read a Pubkey stored in the keys PDA at `offset`
+pub fn read_key(keys_data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    Ok(Pubkey::new_from_array(
+        keys_data[offset..offset + 32]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Regime spread multiplier (percent): the operator's stored table, or the
+/// built-in `MacroRegime::spread_multiplier` when no table was configured
+pub fn regime_multiplier(ctx_data: &[u8], regime: MacroRegime) -> u64 {
+    let table = &ctx_data[REGIME_MULTIPLIERS_OFFSET..REGIME_MULTIPLIERS_OFFSET + 8];
+    if table.iter().all(|&b| b == 0) {
+        regime.spread_multiplier()
+    } else {
+        let offset = regime as usize * 2;
+        u16::from_le_bytes([table[offset], table[offset + 1]]) as u64
+    }
+}
+
+/// Spread in bps at the stored regime and signal: base plus the regime-scaled
+/// regime spread plus the signal adjustment, capped at max. The hedge widening
+/// needs the reference account, and inventory skew and the per-side caps
+/// depend on the side, so none of them is included; with no hedge reference,
+/// no side caps and flat inventory this is the spread Match charges.
+pub fn compute_current_spread(ctx_data: &[u8]) -> Result<u64, ProgramError> {
+    let max_spread = u32::from_le_bytes(
+        ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok(std::cmp::min(spread_before_cap(ctx_data)?, max_spread as u64))
+}
+
+/// `compute_current_spread` before any cap, for pricing that applies its own
+pub fn spread_before_cap(ctx_data: &[u8]) -> Result<u64, ProgramError> {
+    let read_u32 = |offset: usize| {
+        ctx_data[offset..offset + 4]
+            .try_into()
+            .map(u32::from_le_bytes)
+            .map_err(|_| ProgramError::InvalidAccountData)
+    };
+    let base_spread = read_u32(BASE_SPREAD_OFFSET)?;
+    let regime_spread = read_u32(REGIME_SPREAD_OFFSET)?;
+    let signal_adj = u64::from_le_bytes(
+        ctx_data[SIGNAL_ADJUSTED_SPREAD_OFFSET..SIGNAL_ADJUSTED_SPREAD_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let regime = MacroRegime::from_u8(ctx_data[REGIME_OFFSET]);
+
+    let adjusted_regime = (regime_spread as u64)
+        .checked_mul(regime_multiplier(ctx_data, regime))
+        .ok_or(MacroMatcherError::ArithmeticOverflow)?
+        / 100;
+    Ok((base_spread as u64).saturating_add(adjusted_regime).saturating_add(signal_adj))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_magic_matches_shared_layout() {
+        assert_eq!(matcher_shared::matcher_magic(matcher_shared::MatcherKind::Macro), MACRO_MATCHER_MAGIC);
+        // A context this program initialized is identified as its own
+        let mut data = vec![0u8; CTX_SIZE];
+        matcher_common::write_header(&mut data, MACRO_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(matcher_shared::identify(&data), Some(matcher_shared::MatcherKind::Macro));
+    }
+
     #[test]
     fn test_mark_price_positive_rate() {
         // +2.00% (200 bps) -> mark = (200 + 500) * 10_000 = 7_000_000
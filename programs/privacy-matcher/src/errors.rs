@@ -1,3 +1,4 @@
+use matcher_shared::SidePriceError;
 use solana_program::program_error::ProgramError;
 
 #[derive(Debug, Clone, Copy)]
@@ -6,6 +7,17 @@ pub enum PrivacyMatcherError {
     UnauthorizedSolver = 0x11,
     OraclePriceNotSet = 0x12,
     ArithmeticOverflow = 0x13,
+    MatcherPaused = 0x14,
+    MatchReentered = 0x15,
+    OraclePriceOutOfRange = 0x16,
+    OracleStale = 0x17,
+    MatchThrottled = 0x18,
+    UnsupportedVersion = 0x19,
+    StaleUpdate = 0x1A,
+    SpreadTooWide = 0x1B,
+    ConfigUpdateTooSoon = 0x1C,
+    FillsAwaitingMarkOut = 0x1D,
+    CounterpartyMismatch = 0x1E,
 }
 
 impl From<PrivacyMatcherError> for ProgramError {
@@ -13,3 +25,12 @@ impl From<PrivacyMatcherError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+
+impl From<SidePriceError> for PrivacyMatcherError {
+    fn from(e: SidePriceError) -> Self {
+        match e {
+            SidePriceError::Overflow => PrivacyMatcherError::ArithmeticOverflow,
+            SidePriceError::SpreadTooWide => PrivacyMatcherError::SpreadTooWide,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use solana_program::{msg, program_error::ProgramError};
+
+/// Current params encoding. Bump when fields are added; older versions stay decodable.
+pub const INIT_PARAMS_VERSION: u8 = 6;
+/// Encoded v1 body: the raw Init layout after the tag, through the epoch volume cap
+pub const INIT_PARAMS_V1_LEN: usize = 79;
+/// Encoded v2 body: v1 plus the match cooldown
+pub const INIT_PARAMS_V2_LEN: usize = 81;
+/// Encoded v3 body: v2 plus the initial oracle price
+pub const INIT_PARAMS_V3_LEN: usize = 89;
+/// Encoded v4 body: v3 plus the spread floor
+pub const INIT_PARAMS_V4_LEN: usize = 91;
+/// Encoded v5 body: v4 plus the two-sided rebate
+pub const INIT_PARAMS_V5_LEN: usize = 93;
+/// Encoded v6 body: v5 plus the config update interval
+pub const INIT_PARAMS_V6_LEN: usize = 95;
+
+/// Typed Init parameters, an alternative to packing the raw Init bytes by hand.
+/// Framed as `[version u8][body_len u16 LE][body]`; the body is the raw Init
+/// layout with every optional group present, so both paths write the same context.
+/// Fields a version predates decode as 0 (off).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrivacyInitParams {
+    pub base_spread_bps: u32,
+    pub max_spread_bps: u32,
+    pub solver_fee_bps: u32,
+    pub solver_encryption_key: [u8; 32],
+    pub slippage_window_slots: u32,
+    pub max_cum_slippage_bps: u32,
+    pub ema_alpha_bps: u16,
+    /// 0..=18, or ORACLE_DECIMALS_NONE for no raw-units export
+    pub oracle_decimals: u8,
+    pub revalidate_ttl_slots: u16,
+    pub toxicity_spread_bps: u16,
+    pub toxicity_horizon_slots: u16,
+    pub min_oracle_price_e6: u64,
+    pub resume_max_oracle_age_slots: u16,
+    pub max_epoch_volume_e6: u64,
+    pub min_slots_between_matches: u16,
+    pub initial_oracle_price_e6: u64,
+    pub min_spread_bps: u16,
+    pub two_sided_rebate_bps: u16,
+    pub min_config_interval_slots: u16,
+}
+
+impl PrivacyInitParams {
+    /// Encode with the current version header
+    pub fn pack(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(3 + INIT_PARAMS_V6_LEN);
+        out.push(INIT_PARAMS_VERSION);
+        out.extend_from_slice(&(INIT_PARAMS_V6_LEN as u16).to_le_bytes());
+        out.extend_from_slice(&self.base_spread_bps.to_le_bytes());
+        out.extend_from_slice(&self.max_spread_bps.to_le_bytes());
+        out.extend_from_slice(&self.solver_fee_bps.to_le_bytes());
+        out.extend_from_slice(&self.solver_encryption_key);
+        out.extend_from_slice(&self.slippage_window_slots.to_le_bytes());
+        out.extend_from_slice(&self.max_cum_slippage_bps.to_le_bytes());
+        out.extend_from_slice(&self.ema_alpha_bps.to_le_bytes());
+        out.push(self.oracle_decimals);
+        out.extend_from_slice(&self.revalidate_ttl_slots.to_le_bytes());
+        out.extend_from_slice(&self.toxicity_spread_bps.to_le_bytes());
+        out.extend_from_slice(&self.toxicity_horizon_slots.to_le_bytes());
+        out.extend_from_slice(&self.min_oracle_price_e6.to_le_bytes());
+        out.extend_from_slice(&self.resume_max_oracle_age_slots.to_le_bytes());
+        out.extend_from_slice(&self.max_epoch_volume_e6.to_le_bytes());
+        out.extend_from_slice(&self.min_slots_between_matches.to_le_bytes());
+        out.extend_from_slice(&self.initial_oracle_price_e6.to_le_bytes());
+        out.extend_from_slice(&self.min_spread_bps.to_le_bytes());
+        out.extend_from_slice(&self.two_sided_rebate_bps.to_le_bytes());
+        out.extend_from_slice(&self.min_config_interval_slots.to_le_bytes());
+        out
+    }
+
+    /// Decode a framed params payload, rejecting unknown versions and a body
+    /// length that doesn't match the version or the bytes supplied
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < 3 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let version = data[0];
+        let body_len = u16::from_le_bytes([data[1], data[2]]) as usize;
+        let expected_len = match version {
+            1 => INIT_PARAMS_V1_LEN,
+            2 => INIT_PARAMS_V2_LEN,
+            3 => INIT_PARAMS_V3_LEN,
+            4 => INIT_PARAMS_V4_LEN,
+            5 => INIT_PARAMS_V5_LEN,
+            6 => INIT_PARAMS_V6_LEN,
+            _ => {
+                msg!("PRIVACY-MATCHER: Unsupported init params version {}", version);
+                return Err(ProgramError::InvalidInstructionData);
+            }
+        };
+        if body_len != expected_len || data.len() != 3 + body_len {
+            msg!(
+                "PRIVACY-MATCHER: Init params body {} bytes (declared {}), expected {}",
+                data.len() - 3,
+                body_len,
+                expected_len
+            );
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let mut r = Reader { data: &data[3..], at: 0 };
+        Ok(Self {
+            base_spread_bps: u32::from_le_bytes(r.take()?),
+            max_spread_bps: u32::from_le_bytes(r.take()?),
+            solver_fee_bps: u32::from_le_bytes(r.take()?),
+            solver_encryption_key: r.take()?,
+            slippage_window_slots: u32::from_le_bytes(r.take()?),
+            max_cum_slippage_bps: u32::from_le_bytes(r.take()?),
+            ema_alpha_bps: u16::from_le_bytes(r.take()?),
+            oracle_decimals: u8::from_le_bytes(r.take()?),
+            revalidate_ttl_slots: u16::from_le_bytes(r.take()?),
+            toxicity_spread_bps: u16::from_le_bytes(r.take()?),
+            toxicity_horizon_slots: u16::from_le_bytes(r.take()?),
+            min_oracle_price_e6: u64::from_le_bytes(r.take()?),
+            resume_max_oracle_age_slots: u16::from_le_bytes(r.take()?),
+            max_epoch_volume_e6: u64::from_le_bytes(r.take()?),
+            min_slots_between_matches: if version >= 2 { u16::from_le_bytes(r.take()?) } else { 0 },
+            initial_oracle_price_e6: if version >= 3 { u64::from_le_bytes(r.take()?) } else { 0 },
+            min_spread_bps: if version >= 4 { u16::from_le_bytes(r.take()?) } else { 0 },
+            two_sided_rebate_bps: if version >= 5 { u16::from_le_bytes(r.take()?) } else { 0 },
+            min_config_interval_slots: if version >= 6 { u16::from_le_bytes(r.take()?) } else { 0 },
+        })
+    }
+
+    /// The equivalent raw Init instruction data (tag 0x02 + full layout)
+    pub fn to_init_data(&self) -> Vec<u8> {
+        let mut data = vec![0x02];
+        data.extend_from_slice(&self.pack()[3..]);
+        data
+    }
+}
+
+/// Sequential fixed-width field reader over a params body
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ProgramError> {
+        let bytes = self
+            .data
+            .get(self.at..self.at + N)
+            .ok_or(ProgramError::InvalidInstructionData)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        self.at += N;
+        Ok(bytes)
+    }
+}
@@ -6,6 +6,9 @@ pub enum PrivacyMatcherInstruction {
     /// Execute match - compute execution price from solver-verified trade
     #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes)")]
+    #[account(2, optional, writable, name = "mark_out", desc = "Mark-out PDA, seeds [\"mark_out\", context] (while toxicity or slippage is on)")]
+    #[account(3, optional, writable, name = "counterparty_flow", desc = "Counterparty flow PDA (records the fill, rebates two-sided flow)")]
+    #[account(4, optional, signer, name = "counterparty", desc = "Counterparty the flow belongs to (must be signer; required with a flow)")]
     Match,
 
     /// Initialize privacy matcher context
@@ -14,8 +17,75 @@ pub enum PrivacyMatcherInstruction {
     #[account(2, name = "solver", desc = "Authorized solver wallet")]
     Init,
 
-    /// Update oracle price (solver-only)
+    /// Update oracle price (solver-only, price signed by the stored oracle signer)
     #[account(0, signer, name = "solver", desc = "Authorized solver (must be signer)")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, name = "instructions_sysvar", desc = "Instructions sysvar (Ed25519 verify must precede)")]
+    #[account(3, name = "oracle_signers", desc = "Oracle signers PDA holding the Ed25519 price signer")]
+    #[account(4, optional, writable, name = "mark_out", desc = "Mark-out PDA, seeds [\"mark_out\", context] (while toxicity or slippage is on)")]
+    #[account(5, optional, signer, name = "co_signer", desc = "Co-signer stored in the oracle signers PDA (when required)")]
     OracleUpdate,
+
+    /// Pause or resume matching (solver-only)
+    #[account(0, signer, name = "solver", desc = "Authorized solver (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, optional, writable, name = "mark_out", desc = "Mark-out PDA, seeds [\"mark_out\", context] (while toxicity or slippage is on)")]
+    SetPaused,
+
+    /// Claim accrued solver fees (solver-only)
+    #[account(0, signer, name = "solver", desc = "Authorized solver (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    ClaimFee,
+
+    /// Quote match - Match pricing written to the return buffer without touching stats
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account (return buffer only)")]
+    #[account(2, optional, name = "mark_out", desc = "Mark-out PDA, seeds [\"mark_out\", context] (while toxicity or slippage is on)")]
+    QuoteMatch,
+
+    /// Initialize privacy matcher context from a versioned, typed params payload
+    #[account(0, name = "lp_pda", desc = "LP PDA to store")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes, writable)")]
+    #[account(2, name = "solver", desc = "Authorized solver wallet")]
+    InitParams,
+
+    /// Create the oracle signers PDA and store the Ed25519 price signer and optional co-signer (solver-only, once)
+    #[account(0, signer, writable, name = "solver", desc = "Authorized solver (must be signer, pays for the PDA)")]
+    #[account(1, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "oracle_signers", desc = "Oracle signers PDA, seeds [\"oracle_signers\", context]")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitOracleSigner,
+
+    /// Create a counterparty flow PDA for the two-sided rebate (anyone pays)
+    #[account(0, signer, writable, name = "payer", desc = "Pays for the PDA")]
+    #[account(1, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "counterparty_flow", desc = "Counterparty flow PDA, seeds [\"flow\", context, counterparty]")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitCounterpartyFlow,
+
+    /// Update config - replace the spread parameters, rate-limited by min_config_interval_slots (solver-only)
+    #[account(0, signer, name = "solver", desc = "Authorized solver (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, optional, name = "mark_out", desc = "Mark-out PDA, seeds [\"mark_out\", context] (while toxicity or slippage is on)")]
+    UpdateConfig,
+
+    /// Create the mark-out PDA holding fills awaiting their mark-out and the toxicity and slippage state (anyone pays, once)
+    #[account(0, signer, writable, name = "payer", desc = "Pays for the PDA")]
+    #[account(1, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "mark_out", desc = "Mark-out PDA, seeds [\"mark_out\", context]")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitMarkOut,
+
+    /// Rotate oracle signer - replace the Ed25519 price signer and co-signer setting (solver-only, plus the current co-signer while co-signing is on)
+    #[account(0, signer, name = "solver", desc = "Authorized solver (must be signer)")]
+    #[account(1, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "oracle_signers", desc = "Oracle signers PDA, seeds [\"oracle_signers\", context]")]
+    #[account(3, optional, signer, name = "co_signer", desc = "Current co-signer (required while co-signing is on)")]
+    RotateOracleSigner,
+
+    /// Sweep excess - move lamports above the rent-exempt minimum to a destination
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "destination", desc = "Receives the excess lamports")]
+    SweepExcess,
 }
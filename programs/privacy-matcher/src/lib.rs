@@ -3,12 +3,20 @@ use solana_program::{
     program_error::ProgramError, pubkey::Pubkey,
 };
 
+#[macro_use]
+extern crate matcher_shared;
+
 mod errors;
+pub mod init_params;
 mod instructions;
 mod match_engine;
-mod state;
+pub mod state;
 
-use match_engine::{process_init, process_match, process_oracle_update};
+use match_engine::{
+    process_claim_fee, process_init, process_init_counterparty_flow, process_init_mark_out,
+    process_init_oracle_signer, process_init_params, process_match, process_oracle_update, process_quote_match,
+    process_rotate_oracle_signer, process_set_paused, process_update_config,
+};
 
 entrypoint!(process_instruction);
 
@@ -23,17 +31,57 @@ pub fn process_instruction(
 
     match instruction_data[0] {
         0x00 => {
-            msg!("PRIVACY-MATCHER: Match instruction");
+            matcher_log!("PRIVACY-MATCHER: Match instruction");
             process_match(program_id, accounts, instruction_data)
         }
         0x02 => {
-            msg!("PRIVACY-MATCHER: Init instruction");
+            matcher_log!("PRIVACY-MATCHER: Init instruction");
             process_init(program_id, accounts, instruction_data)
         }
         0x03 => {
-            msg!("PRIVACY-MATCHER: Oracle update instruction");
+            matcher_log!("PRIVACY-MATCHER: Oracle update instruction");
             process_oracle_update(program_id, accounts, instruction_data)
         }
+        0x04 => {
+            matcher_log!("PRIVACY-MATCHER: Set paused instruction");
+            process_set_paused(program_id, accounts, instruction_data)
+        }
+        0x05 => {
+            matcher_log!("PRIVACY-MATCHER: Claim fee instruction");
+            process_claim_fee(program_id, accounts, instruction_data)
+        }
+        0x06 => {
+            matcher_log!("PRIVACY-MATCHER: Init params instruction");
+            process_init_params(program_id, accounts, instruction_data)
+        }
+        0x07 => {
+            matcher_log!("PRIVACY-MATCHER: Init oracle signer instruction");
+            process_init_oracle_signer(program_id, accounts, instruction_data)
+        }
+        0x08 => {
+            matcher_log!("PRIVACY-MATCHER: Init counterparty flow instruction");
+            process_init_counterparty_flow(program_id, accounts, instruction_data)
+        }
+        0x09 => {
+            matcher_log!("PRIVACY-MATCHER: Update config instruction");
+            process_update_config(program_id, accounts, instruction_data)
+        }
+        0x0A => {
+            matcher_log!("PRIVACY-MATCHER: Rotate oracle signer instruction");
+            process_rotate_oracle_signer(program_id, accounts, instruction_data)
+        }
+        0x0B => {
+            matcher_log!("PRIVACY-MATCHER: Quote match instruction");
+            process_quote_match(program_id, accounts, instruction_data)
+        }
+        0x0C => {
+            matcher_log!("PRIVACY-MATCHER: Init mark-out instruction");
+            process_init_mark_out(program_id, accounts, instruction_data)
+        }
+        0x0E => {
+            matcher_log!("PRIVACY-MATCHER: Sweep excess instruction");
+            matcher_shared::process_sweep_excess(program_id, accounts, state::PRIVACY_MATCHER_MAGIC, "PRIVACY-MATCHER")
+        }
         _ => {
             msg!("PRIVACY-MATCHER: Unknown instruction tag {}", instruction_data[0]);
             Err(ProgramError::InvalidInstructionData)
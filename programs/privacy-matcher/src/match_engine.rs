@@ -1,13 +1,20 @@
+use std::cell::RefMut;
+
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
-    pubkey::Pubkey,
+    account_info::AccountInfo, clock::Clock, ed25519_program, entrypoint::ProgramResult,
+    instruction::Instruction, msg, program_error::ProgramError, pubkey::Pubkey,
+    sysvar::{instructions as sysvar_instructions, Sysvar},
 };
 
 use crate::errors::PrivacyMatcherError;
+use crate::init_params::PrivacyInitParams;
 use crate::state::*;
 use matcher_common::{
     verify_lp_pda as verify_lp_pda_common, verify_init_preconditions, write_header,
-    write_exec_price, compute_exec_price,
+    write_exec_price,
+};
+use matcher_shared::{
+    config_interval_elapsed, create_pda_account, side_price, verify_ctx_owner, verify_ctx_size, verify_lp_pda_key,
 };
 
 /// Tag 0x02: Initialize privacy matcher context
@@ -20,57 +27,100 @@ use matcher_common::{
 ///   [1..5] base_spread_bps (u32 LE)
 ///   [5..9] max_spread_bps (u32 LE)
 ///   [9..13] solver_fee_bps (u32 LE)
-///   [13..45] solver_encryption_pubkey ([u8;32])
+///   [13..45] solver_encryption_pubkey ([u8;32], nonzero)
+///   [45..49] slippage_window_slots (u32 LE, optional, 0 = disabled)
+///   [49..53] max_cum_slippage_bps (u32 LE, optional)
+///   [53..55] ema_alpha_bps (u16 LE, optional, 0 = no smoothing, max 10000)
+///   [55]    oracle_decimals (u8, optional, 0..=18, 0xFF = e6 export only)
+///   [56..58] revalidate_ttl_slots (u16 LE, optional, 0 = no revalidate stamp)
+///   [58..60] toxicity_spread_bps (u16 LE, optional, 0 = no toxicity widening)
+///   [60..62] toxicity_horizon_slots (u16 LE, optional with the above)
+///   [62..70] min_oracle_price_e6 (u64 LE, optional, 0 = no floor)
+///   [70..72] resume_max_oracle_age_slots (u16 LE, optional, 0 = resume any time)
+///   [72..80] max_epoch_volume_e6 (u64 LE, optional, 0 = no epoch cap)
+///   [80..82] min_slots_between_matches (u16 LE, optional, 0 = no cooldown)
+///   [82..90] initial_oracle_price_e6 (u64 LE, optional, 0 = wait for the first Oracle Update)
+///   [90..92] min_spread_bps (u16 LE, optional, 0 = no floor; must not exceed max_spread_bps)
+///   [92..94] two_sided_rebate_bps (u16 LE, optional, 0 = off, at most 10000)
+///   [94..96] min_config_interval_slots (u16 LE, optional, 0 = Update Config unthrottled)
 pub fn process_init(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 3 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 45 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
+    check_init_len(data.len())?;
 
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
     let solver = &accounts[2];
 
     // Verify context account is writable, correct size, and not already initialized
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
     verify_init_preconditions(ctx_account, PRIVACY_MATCHER_MAGIC, "PRIVACY-MATCHER")?;
+    verify_ctx_size(ctx_account, "PRIVACY-MATCHER")?;
+    verify_lp_pda_key(lp_pda.key, "PRIVACY-MATCHER")?;
 
-    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    // Users encrypt intents to this key; all zeros is no key at all
+    if data[13..45] == [0u8; 32] {
+        msg!("PRIVACY-MATCHER: Solver encryption key must be set");
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
-    // Write standard header (return data, magic, version, mode, padding, LP PDA)
-    write_header(&mut ctx_data, PRIVACY_MATCHER_MAGIC, 0, lp_pda.key);
+    // Parse spread params
+    let base_spread = u32::from_le_bytes(data[1..5].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let max_spread = u32::from_le_bytes(data[5..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let solver_fee = u32::from_le_bytes(data[9..13].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let (window_slots, max_cum_slippage) = if data.len() >= 53 {
+        (
+            u32::from_le_bytes(data[45..49].try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+            u32::from_le_bytes(data[49..53].try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+        )
+    } else {
+        (0, 0)
+    };
 
-    // Store solver pubkey
-    ctx_data[SOLVER_PUBKEY_OFFSET..SOLVER_PUBKEY_OFFSET + 32]
-        .copy_from_slice(&solver.key.to_bytes());
+    let min_spread = if data.len() >= 92 {
+        u16::from_le_bytes(data[90..92].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
 
-    // Parse spread params
-    let base_spread = u32::from_le_bytes(
-        data[1..5]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidInstructionData)?,
-    );
-    let max_spread = u32::from_le_bytes(
-        data[5..9]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidInstructionData)?,
-    );
-    let solver_fee = u32::from_le_bytes(
-        data[9..13]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidInstructionData)?,
-    );
+    let two_sided_rebate = if data.len() >= 94 {
+        u16::from_le_bytes(data[92..94].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    if two_sided_rebate > 10_000 {
+        msg!("PRIVACY-MATCHER: two_sided_rebate_bps ({}) exceeds 10000", two_sided_rebate);
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
     // Validate spreads
     if base_spread > max_spread {
         msg!("PRIVACY-MATCHER: base_spread ({}) exceeds max_spread ({})", base_spread, max_spread);
         return Err(PrivacyMatcherError::InvalidSpreadConfig.into());
     }
+    // A floor above the cap would quote past max_spread on every fill
+    if min_spread as u32 > max_spread {
+        msg!("PRIVACY-MATCHER: min_spread_bps ({}) exceeds max_spread ({})", min_spread, max_spread);
+        return Err(PrivacyMatcherError::InvalidSpreadConfig.into());
+    }
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+
+    // Write standard header (return data, magic, version, mode, padding, LP PDA)
+    write_header(&mut ctx_data, PRIVACY_MATCHER_MAGIC, 0, lp_pda.key);
+    ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&CTX_VERSION.to_le_bytes());
+    for (start, end) in RESERVED_RANGES {
+        ctx_data[start..end].fill(0);
+    }
+
+    // Store solver pubkey
+    ctx_data[SOLVER_PUBKEY_OFFSET..SOLVER_PUBKEY_OFFSET + 32]
+        .copy_from_slice(&solver.key.to_bytes());
 
     ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4]
         .copy_from_slice(&base_spread.to_le_bytes());
@@ -78,24 +128,147 @@ pub fn process_init(
         .copy_from_slice(&max_spread.to_le_bytes());
     ctx_data[SOLVER_FEE_OFFSET..SOLVER_FEE_OFFSET + 4]
         .copy_from_slice(&solver_fee.to_le_bytes());
+    ctx_data[MIN_SPREAD_OFFSET..MIN_SPREAD_OFFSET + 2]
+        .copy_from_slice(&min_spread.to_le_bytes());
 
-    // Initialize oracle price and stats to zero
-    ctx_data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    // Initialize stats to zero
     ctx_data[LAST_EXEC_PRICE_OFFSET..LAST_EXEC_PRICE_OFFSET + 8]
         .copy_from_slice(&0u64.to_le_bytes());
     ctx_data[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16]
         .copy_from_slice(&0u128.to_le_bytes());
+    ctx_data[TWO_SIDED_REBATE_BPS_OFFSET..TWO_SIDED_REBATE_BPS_OFFSET + 2]
+        .copy_from_slice(&two_sided_rebate.to_le_bytes());
+
+    // Update Config rate limit (optional trailing config); no update yet
+    let min_config_interval = if data.len() >= 96 {
+        u16::from_le_bytes(data[94..96].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    ctx_data[MIN_CONFIG_INTERVAL_SLOTS_OFFSET..MIN_CONFIG_INTERVAL_SLOTS_OFFSET + 2]
+        .copy_from_slice(&min_config_interval.to_le_bytes());
+    ctx_data[LAST_CONFIG_SLOT_OFFSET..LAST_CONFIG_SLOT_OFFSET + 4].copy_from_slice(&0u32.to_le_bytes());
     ctx_data[TOTAL_ORDERS_OFFSET..TOTAL_ORDERS_OFFSET + 8]
         .copy_from_slice(&0u64.to_le_bytes());
 
-    // Store solver encryption pubkey
+    // Store solver encryption pubkey (X25519, for intents)
     ctx_data[SOLVER_ENCRYPTION_KEY_OFFSET..SOLVER_ENCRYPTION_KEY_OFFSET + 32]
         .copy_from_slice(&data[13..45]);
 
-    // Zero reserved area
-    ctx_data[228..CTX_SIZE].fill(0);
+    // Cumulative slippage guard (optional trailing config); the running
+    // window lives in the mark-out PDA
+    ctx_data[SLIPPAGE_WINDOW_SLOTS_OFFSET..SLIPPAGE_WINDOW_SLOTS_OFFSET + 4]
+        .copy_from_slice(&window_slots.to_le_bytes());
+    ctx_data[MAX_CUM_SLIPPAGE_BPS_OFFSET..MAX_CUM_SLIPPAGE_BPS_OFFSET + 4]
+        .copy_from_slice(&max_cum_slippage.to_le_bytes());
+
+    // Fee accounting
+    ctx_data[ACCRUED_SOLVER_FEE_OFFSET..ACCRUED_SOLVER_FEE_OFFSET + 16]
+        .copy_from_slice(&0u128.to_le_bytes());
+
+    // Oracle EMA smoothing (optional trailing config)
+    let ema_alpha = if data.len() >= 55 {
+        u16::from_le_bytes(data[53..55].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    if ema_alpha > 10_000 {
+        msg!("PRIVACY-MATCHER: ema_alpha_bps ({}) exceeds 10000", ema_alpha);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    ctx_data[EMA_ALPHA_BPS_OFFSET..EMA_ALPHA_BPS_OFFSET + 2].copy_from_slice(&ema_alpha.to_le_bytes());
+
+    // Raw-decimals price export (optional trailing config)
+    let oracle_decimals = if data.len() >= 56 { data[55] } else { ORACLE_DECIMALS_NONE };
+    if oracle_decimals > MAX_ORACLE_DECIMALS && oracle_decimals != ORACLE_DECIMALS_NONE {
+        msg!("PRIVACY-MATCHER: oracle_decimals ({}) exceeds {}", oracle_decimals, MAX_ORACLE_DECIMALS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    ctx_data[ORACLE_DECIMALS_OFFSET] = oracle_decimals;
+
+    // Stale-while-revalidate TTL (optional trailing config)
+    let revalidate_ttl = if data.len() >= 58 {
+        u16::from_le_bytes(data[56..58].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    ctx_data[REVALIDATE_TTL_SLOTS_OFFSET..REVALIDATE_TTL_SLOTS_OFFSET + 2]
+        .copy_from_slice(&revalidate_ttl.to_le_bytes());
+    ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8]
+        .copy_from_slice(&0u64.to_le_bytes());
+
+    // Fill toxicity widening (optional trailing config); the fill history
+    // lives in the mark-out PDA
+    let (toxicity_spread, toxicity_horizon) = if data.len() >= 62 {
+        (
+            u16::from_le_bytes(data[58..60].try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+            u16::from_le_bytes(data[60..62].try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+        )
+    } else {
+        (0, 0)
+    };
+    ctx_data[TOXICITY_SPREAD_BPS_OFFSET..TOXICITY_SPREAD_BPS_OFFSET + 2]
+        .copy_from_slice(&toxicity_spread.to_le_bytes());
+    ctx_data[TOXICITY_HORIZON_SLOTS_OFFSET..TOXICITY_HORIZON_SLOTS_OFFSET + 2]
+        .copy_from_slice(&toxicity_horizon.to_le_bytes());
+
+    // Oracle price floor (optional trailing config)
+    let min_oracle_price = if data.len() >= 70 {
+        u64::from_le_bytes(data[62..70].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    ctx_data[MIN_ORACLE_PRICE_OFFSET..MIN_ORACLE_PRICE_OFFSET + 8]
+        .copy_from_slice(&min_oracle_price.to_le_bytes());
+
+    // Oracle heartbeat required to resume (optional trailing config)
+    let resume_max_age = if data.len() >= 72 {
+        u16::from_le_bytes(data[70..72].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    ctx_data[RESUME_MAX_ORACLE_AGE_OFFSET..RESUME_MAX_ORACLE_AGE_OFFSET + 2]
+        .copy_from_slice(&resume_max_age.to_le_bytes());
+
+    // Epoch volume cap (optional trailing config); the first epoch starts now
+    let max_epoch_volume = if data.len() >= 80 {
+        u64::from_le_bytes(data[72..80].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    ctx_data[MAX_EPOCH_VOLUME_OFFSET..MAX_EPOCH_VOLUME_OFFSET + 8]
+        .copy_from_slice(&max_epoch_volume.to_le_bytes());
+    ctx_data[EPOCH_VOLUME_OFFSET..EPOCH_VOLUME_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+
+    // Match cooldown (optional trailing config); no match recorded yet
+    let min_match_gap = if data.len() >= 82 {
+        u16::from_le_bytes(data[80..82].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    ctx_data[MIN_SLOTS_BETWEEN_MATCHES_OFFSET..MIN_SLOTS_BETWEEN_MATCHES_OFFSET + 2]
+        .copy_from_slice(&min_match_gap.to_le_bytes());
+    ctx_data[LAST_MATCH_SLOT_OFFSET..LAST_MATCH_SLOT_OFFSET + 4].copy_from_slice(&0u32.to_le_bytes());
+
+    // Initial oracle price (optional trailing config): tradeable straight away
+    // instead of live but unmatchable until the solver's first update lands.
+    // last_oracle_slot stays 0 -- this price wasn't delivered by an update.
+    let initial_oracle_price = if data.len() >= 90 {
+        u64::from_le_bytes(data[82..90].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)
+    } else {
+        0
+    };
+    if initial_oracle_price != 0 && initial_oracle_price < min_oracle_price {
+        msg!(
+            "PRIVACY-MATCHER: Initial oracle price {} below floor {}",
+            initial_oracle_price,
+            min_oracle_price
+        );
+        return Err(PrivacyMatcherError::OraclePriceOutOfRange.into());
+    }
+    ctx_data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&initial_oracle_price.to_le_bytes());
 
-    msg!(
+    matcher_log!(
         "INIT: lp_pda={} solver={} base_spread={} max_spread={} solver_fee={}",
         lp_pda.key,
         solver.key,
@@ -107,15 +280,42 @@ pub fn process_init(
     Ok(())
 }
 
+/// Tag 0x06: Initialize from a versioned `PrivacyInitParams` payload
+/// Accounts: same as Init
+/// Data layout:
+///   [0]    tag (0x06)
+///   [1]    params version (1..=6)
+///   [2..4] body length (u16 LE: 79 for v1, 81 for v2, 89 for v3, 91 for v4, 93 for v5,
+///          95 for v6)
+///   [4..]  body
+/// Decodes the typed params and runs them through Init, so validation and the
+/// written context are identical to the raw path.
+pub fn process_init_params(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params = PrivacyInitParams::unpack(data.get(1..).unwrap_or_default())?;
+    process_init(program_id, accounts, &params.to_init_data())
+}
+
 /// Tag 0x00: Execute match — compute execution price from solver-verified trade
 /// Accounts:
 ///   [0] LP PDA (signer)
 ///   [1] Matcher context account (writable)
+///   [2] Mark-out PDA (writable, seeds `[MARK_OUT_SEED, context]`; required
+///       while the toxicity spread or slippage guard is on, and ahead of a
+///       counterparty flow)
+///   [3] Counterparty flow PDA (writable, optional: records the fill and
+///       rebates the fee on two-sided flow)
+///   [4] Counterparty (signer, required with [3]: the taker Percolator
+///       forwards, whose key the flow was created for)
 /// Data layout:
 ///   [0] tag (0x00)
 ///   [1..9] trade_size_abs (u64 LE) — absolute trade size for volume tracking
+///   [9] direction (u8, optional: 0 = buy from LP, 1 = sell to LP; default 0)
 pub fn process_match(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
@@ -126,26 +326,277 @@ pub fn process_match(
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
-    // Verify LP PDA signature, context initialization, and PDA match
-    verify_lp_pda_common(lp_pda, ctx_account, PRIVACY_MATCHER_MAGIC, "PRIVACY-MATCHER")?;
+    // Verify LP PDA signature, context initialization, PDA match, and not paused
+    guard_match(program_id, lp_pda, ctx_account)?;
 
-    // Read pricing parameters
-    let ctx_data = ctx_account.try_borrow_data()?;
-    let base_spread = u32::from_le_bytes(
-        ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4]
+    // Reentrancy guard: held until this match completes
+    enter_match_guard(&mut ctx_account.try_borrow_mut_data()?)?;
+
+    let direction = parse_direction(data)?;
+
+    let clock = Clock::get()?;
+    check_match_cooldown(&ctx_account.try_borrow_data()?, clock.slot)?;
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    let mut mark_out = borrow_mark_out(program_id, ctx_account, &ctx_data, accounts, 2)?;
+    let (exec_price, total_spread, oracle_price, solver_fee) =
+        quote_exec_price(&ctx_data, mark_out.as_ref().map(|data| &data[..]), direction)?;
+
+    // Write execution price to return buffer (plus raw-decimals copy and
+    // revalidate stamp when configured)
+    write_exec_price(&mut ctx_data, exec_price);
+    write_raw_exec_price(&mut ctx_data, exec_price)?;
+    write_revalidate_stamp(&mut ctx_data)?;
+
+    // Update last execution price
+    ctx_data[LAST_EXEC_PRICE_OFFSET..LAST_EXEC_PRICE_OFFSET + 8]
+        .copy_from_slice(&exec_price.to_le_bytes());
+
+    // Update order count
+    let count = u64::from_le_bytes(
+        ctx_data[TOTAL_ORDERS_OFFSET..TOTAL_ORDERS_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    ctx_data[TOTAL_ORDERS_OFFSET..TOTAL_ORDERS_OFFSET + 8]
+        .copy_from_slice(&count.saturating_add(1).to_le_bytes());
+
+    let trade_size = parse_trade_size(data)?;
+
+    // Fee for Percolator to split between the LP and protocol (0 without a size)
+    let mut trade_fee = matcher_shared::trade_fee(oracle_price, total_spread as u64, trade_size)
+        .ok_or(PrivacyMatcherError::ArithmeticOverflow)?;
+    // Rebated when the counterparty's flow trades both sides; the exec price
+    // and the solver's cut are unchanged
+    if let Some(flow) = accounts.get(3) {
+        let rebate_bps = u16::from_le_bytes(
+            ctx_data[TWO_SIDED_REBATE_BPS_OFFSET..TWO_SIDED_REBATE_BPS_OFFSET + 2]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let Some(counterparty) = accounts.get(4) else {
+            msg!("PRIVACY-MATCHER: Counterparty account required with a counterparty flow");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        trade_fee = apply_two_sided_rebate(
+            program_id,
+            flow,
+            counterparty,
+            ctx_account.key,
+            rebate_bps,
+            direction,
+            trade_size,
+            trade_fee,
+        )?;
+    }
+    ctx_data[FEE_RETURN_OFFSET..FEE_RETURN_OFFSET + 8].copy_from_slice(&trade_fee.to_le_bytes());
+
+    // Update volume if trade size provided
+    if data.len() >= 9 {
+        let current_volume = u128::from_le_bytes(
+            ctx_data[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let new_volume = current_volume.saturating_add(trade_size as u128);
+        ctx_data[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16]
+            .copy_from_slice(&new_volume.to_le_bytes());
+
+        // Accrue the solver's cut of this fill
+        let fee = compute_solver_fee(oracle_price, solver_fee, trade_size)?;
+        accrue_solver_fee(&mut ctx_data, fee)?;
+
+        // Epoch notional limit -- the fill that crosses it completes, then matching pauses
+        if accumulate_epoch_volume(&mut ctx_data, trade_size)? {
+            ctx_data[PAUSED_OFFSET] = 1;
+            matcher_log!("AUTO_PAUSE: epoch volume cap exceeded");
+        }
+    }
+
+    // Queue this fill for a mark-out against the next oracle update
+    if let Some(mark_out) = mark_out.as_mut() {
+        record_fill(mark_out, clock.slot, direction)?;
+    }
+
+    // Start the cooldown from this match
+    ctx_data[LAST_MATCH_SLOT_OFFSET..LAST_MATCH_SLOT_OFFSET + 4]
+        .copy_from_slice(&(clock.slot as u32).to_le_bytes());
+
+    // Release the reentrancy guard
+    ctx_data[MATCH_GUARD_OFFSET] = 0;
+
+    matcher_log!(
+        "MATCH: price={} spread={} oracle={} direction={}",
+        exec_price,
+        total_spread,
+        oracle_price,
+        direction
+    );
+
+    Ok(())
+}
+
+/// Add a fill to the counterparty's flow and rebate its fee by
+/// `rebate_bps * lighter side / heavier side` of the flow so far: the full
+/// rebate once buys and sells balance, none while it only trades one way.
+/// The flow only counts for the counterparty that signed this trade, so no
+/// one can earn a rebate on someone else's balanced flow.
+#[allow(clippy::too_many_arguments)]
+fn apply_two_sided_rebate(
+    program_id: &Pubkey,
+    flow: &AccountInfo,
+    counterparty: &AccountInfo,
+    ctx_key: &Pubkey,
+    rebate_bps: u16,
+    direction: u8,
+    trade_size: u64,
+    trade_fee: u64,
+) -> Result<u64, ProgramError> {
+    let mut flow_data = flow.try_borrow_mut_data()?;
+    verify_counterparty_flow(flow, &flow_data, ctx_key, program_id)?;
+    if !counterparty.is_signer {
+        msg!("PRIVACY-MATCHER: Counterparty {} must sign to use its flow", counterparty.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if flow_data[FLOW_COUNTERPARTY_OFFSET..FLOW_COUNTERPARTY_OFFSET + 32] != counterparty.key.to_bytes() {
+        msg!("PRIVACY-MATCHER: Flow {} does not belong to counterparty {}", flow.key, counterparty.key);
+        return Err(PrivacyMatcherError::CounterpartyMismatch.into());
+    }
+
+    let side_offset = if direction == DIRECTION_BUY { FLOW_BUY_VOLUME_OFFSET } else { FLOW_SELL_VOLUME_OFFSET };
+    let side = read_ctx_u64(&flow_data, side_offset)?.saturating_add(trade_size);
+    flow_data[side_offset..side_offset + 8].copy_from_slice(&side.to_le_bytes());
+
+    let buys = read_ctx_u64(&flow_data, FLOW_BUY_VOLUME_OFFSET)?;
+    let sells = read_ctx_u64(&flow_data, FLOW_SELL_VOLUME_OFFSET)?;
+    let (lighter, heavier) = (buys.min(sells), buys.max(sells));
+    if rebate_bps == 0 || lighter == 0 {
+        return Ok(trade_fee);
+    }
+    // lighter <= heavier and rebate_bps <= 10000, so the rebate never exceeds the fee
+    let rebate = (trade_fee as u128 * rebate_bps as u128 / 10_000) * lighter as u128 / heavier as u128;
+    Ok(trade_fee - rebate as u64)
+}
+
+/// Take the reentrancy guard for the duration of a match. A nested match on
+/// the same context finds it held and is rejected before touching any stats.
+fn enter_match_guard(ctx_data: &mut [u8]) -> ProgramResult {
+    if ctx_data[MATCH_GUARD_OFFSET] != 0 {
+        msg!("PRIVACY-MATCHER: Re-entrant match rejected");
+        return Err(PrivacyMatcherError::MatchReentered.into());
+    }
+    ctx_data[MATCH_GUARD_OFFSET] = 1;
+    Ok(())
+}
+
+/// Reject a match fewer than `min_slots_between_matches` after the previous one,
+/// rate-limiting a runaway keeper across slots. The first match (no orders yet)
+/// is never throttled. Slots compare by low 32 bits with wrapping subtraction.
+fn check_match_cooldown(ctx_data: &[u8], slot: u64) -> Result<(), ProgramError> {
+    let min_gap = u16::from_le_bytes(
+        ctx_data[MIN_SLOTS_BETWEEN_MATCHES_OFFSET..MIN_SLOTS_BETWEEN_MATCHES_OFFSET + 2]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let max_spread = u32::from_le_bytes(
-        ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4]
+    let total_orders = u64::from_le_bytes(
+        ctx_data[TOTAL_ORDERS_OFFSET..TOTAL_ORDERS_OFFSET + 8]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let solver_fee = u32::from_le_bytes(
-        ctx_data[SOLVER_FEE_OFFSET..SOLVER_FEE_OFFSET + 4]
+    if min_gap == 0 || total_orders == 0 {
+        return Ok(());
+    }
+    let last_match = u32::from_le_bytes(
+        ctx_data[LAST_MATCH_SLOT_OFFSET..LAST_MATCH_SLOT_OFFSET + 4]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let elapsed = (slot as u32).wrapping_sub(last_match);
+    if elapsed < min_gap as u32 {
+        msg!("PRIVACY-MATCHER: Match throttled: {} slots since last match, need {}", elapsed, min_gap);
+        return Err(PrivacyMatcherError::MatchThrottled.into());
+    }
+    Ok(())
+}
+
+/// Every check Match and QuoteMatch make before pricing, in one place:
+/// context owner, LP PDA signer, magic, PDA match, context version, and not paused
+fn guard_match(program_id: &Pubkey, lp_pda: &AccountInfo, ctx_account: &AccountInfo) -> ProgramResult {
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    verify_lp_pda_common(lp_pda, ctx_account, PRIVACY_MATCHER_MAGIC, "PRIVACY-MATCHER")?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    check_ctx_version(&ctx_data)?;
+    if is_paused(&ctx_data) {
+        msg!("PRIVACY-MATCHER: Matcher is paused");
+        return Err(PrivacyMatcherError::MatcherPaused.into());
+    }
+    Ok(())
+}
+
+/// Reject a context stamped with another layout version: every offset this
+/// program reads or writes belongs to `CTX_VERSION`. Every instruction that
+/// writes the context, or a PDA derived from it, checks this first.
+fn check_ctx_version(ctx_data: &[u8]) -> ProgramResult {
+    let version = read_version(ctx_data)?;
+    if version != CTX_VERSION {
+        msg!("PRIVACY-MATCHER: Context version {}, expected {}", version, CTX_VERSION);
+        return Err(PrivacyMatcherError::UnsupportedVersion.into());
+    }
+    Ok(())
+}
+
+/// The mark-out PDA at `accounts[index]`, verified and mutably borrowed, while
+/// the toxicity spread or slippage guard is on; `None` when both are off, and
+/// the account isn't read
+fn borrow_mark_out<'a, 'b>(
+    program_id: &Pubkey,
+    ctx_account: &AccountInfo,
+    ctx_data: &[u8],
+    accounts: &'a [AccountInfo<'b>],
+    index: usize,
+) -> Result<Option<RefMut<'a, &'b mut [u8]>>, ProgramError> {
+    if !mark_outs_enabled(ctx_data) {
+        return Ok(None);
+    }
+    let mark_out = accounts.get(index).ok_or_else(|| {
+        msg!("PRIVACY-MATCHER: Mark-out PDA required at account {}", index);
+        ProgramError::NotEnoughAccountKeys
+    })?;
+    let mark_out_data = mark_out.try_borrow_mut_data()?;
+    verify_mark_out(mark_out, &mark_out_data, ctx_account.key, program_id)?;
+    Ok(Some(mark_out_data))
+}
+
+/// Trade size at `[1..9]`, 0 when omitted
+fn parse_trade_size(data: &[u8]) -> Result<u64, ProgramError> {
+    if data.len() < 9 {
+        return Ok(0);
+    }
+    Ok(u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?))
+}
+
+/// Optional direction byte at `[9]`; omitted means a buy, as before it existed
+fn parse_direction(data: &[u8]) -> Result<u8, ProgramError> {
+    let direction = data.get(9).copied().unwrap_or(DIRECTION_BUY);
+    if direction > DIRECTION_SELL {
+        msg!("PRIVACY-MATCHER: Invalid direction {}", direction);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(direction)
+}
+
+/// Pricing shared by Match and QuoteMatch: `(exec_price, total_spread, oracle_price, solver_fee_bps)`.
+/// Buys fill at the ask above the oracle, sells at the bid below it. `mark_out`
+/// is the mark-out PDA's data, `None` while mark-outs are off.
+fn quote_exec_price(
+    ctx_data: &[u8],
+    mark_out: Option<&[u8]>,
+    direction: u8,
+) -> Result<(u64, u32, u64, u32), ProgramError> {
+    let base_spread = read_ctx_u32(ctx_data, BASE_SPREAD_OFFSET)?;
+    let max_spread = read_ctx_u32(ctx_data, MAX_SPREAD_OFFSET)?;
+    let solver_fee = read_ctx_u32(ctx_data, SOLVER_FEE_OFFSET)?;
     let oracle_price = u64::from_le_bytes(
         ctx_data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8]
             .try_into()
@@ -158,83 +609,224 @@ pub fn process_match(
         return Err(PrivacyMatcherError::OraclePriceNotSet.into());
     }
 
+    // A price under the floor is a truncated or mis-scaled oracle write
+    let min_oracle_price = u64::from_le_bytes(
+        ctx_data[MIN_ORACLE_PRICE_OFFSET..MIN_ORACLE_PRICE_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if oracle_price < min_oracle_price {
+        msg!("PRIVACY-MATCHER: Oracle price {} below floor {}", oracle_price, min_oracle_price);
+        return Err(PrivacyMatcherError::OraclePriceOutOfRange.into());
+    }
+
+    let min_spread = u16::from_le_bytes(
+        ctx_data[MIN_SPREAD_OFFSET..MIN_SPREAD_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as u32;
+
     // Compute execution price
-    // Total spread = min(base_spread + solver_fee, max_spread)
+    // Total spread = max(min(base_spread + solver_fee + toxicity, max_spread), min_spread):
+    // the floor applies last, so no configuration quotes at the oracle
     let total_spread = std::cmp::min(
-        base_spread.saturating_add(solver_fee),
+        base_spread
+            .saturating_add(solver_fee)
+            .saturating_add(toxicity_spread(ctx_data, mark_out)?),
         max_spread,
-    );
+    )
+    .max(min_spread);
+
+    let exec_price = side_price(oracle_price, total_spread as u64, direction, false).map_err(PrivacyMatcherError::from)?;
+
+    Ok((exec_price, total_spread, oracle_price, solver_fee))
+}
+
+/// Tag 0x0B: Quote match -- the Match pricing path without committing a trade.
+/// Writes the exec price (and its raw-decimals copy) and the fee on the quoted
+/// size to the return buffer and leaves order count, volume, fees, last exec
+/// price and the slippage window alone.
+/// Accounts:
+///   [0] LP PDA (signer)
+///   [1] Matcher context account (writable -- return buffer only)
+///   [2] Mark-out PDA (seeds `[MARK_OUT_SEED, context]`; required while the
+///       toxicity spread or slippage guard is on, for the fill history)
+/// Data layout:
+///   [0] tag (0x0B)
+///   [1..9] trade_size_abs (u64 LE, optional) — sizes the quoted fee
+///   [9] direction (u8, optional: 0 = buy from LP, 1 = sell to LP; default 0)
+pub fn process_quote_match(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
 
-    let exec_price = compute_exec_price(oracle_price, total_spread as u64)?;
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
 
-    // Drop read borrow before mutable borrow
-    drop(ctx_data);
+    guard_match(program_id, lp_pda, ctx_account)?;
+    let direction = parse_direction(data)?;
 
-    // Write execution price to return buffer
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    let mark_out = borrow_mark_out(program_id, ctx_account, &ctx_data, accounts, 2)?;
+    let (exec_price, total_spread, oracle_price, _) =
+        quote_exec_price(&ctx_data, mark_out.as_ref().map(|data| &data[..]), direction)?;
+
     write_exec_price(&mut ctx_data, exec_price);
+    write_raw_exec_price(&mut ctx_data, exec_price)?;
+    // The fee before any two-sided rebate, which needs the taker's flow; a
+    // quote without a size returns 0 rather than the last match's fee
+    let trade_fee = matcher_shared::trade_fee(oracle_price, total_spread as u64, parse_trade_size(data)?)
+        .ok_or(PrivacyMatcherError::ArithmeticOverflow)?;
+    ctx_data[FEE_RETURN_OFFSET..FEE_RETURN_OFFSET + 8].copy_from_slice(&trade_fee.to_le_bytes());
 
-    // Update last execution price
-    ctx_data[LAST_EXEC_PRICE_OFFSET..LAST_EXEC_PRICE_OFFSET + 8]
-        .copy_from_slice(&exec_price.to_le_bytes());
+    matcher_log!(
+        "QUOTE: price={} spread={} oracle={}",
+        exec_price,
+        total_spread,
+        oracle_price
+    );
 
-    // Update order count
-    let count = u64::from_le_bytes(
-        ctx_data[TOTAL_ORDERS_OFFSET..TOTAL_ORDERS_OFFSET + 8]
+    Ok(())
+}
+
+/// Convert an e6 price into `decimals` units (truncating when decimals < 6)
+fn e6_to_decimals(price_e6: u64, decimals: u8) -> Result<u64, ProgramError> {
+    if decimals >= 6 {
+        let scale = 10u64.pow((decimals - 6) as u32);
+        Ok(price_e6
+            .checked_mul(scale)
+            .ok_or(PrivacyMatcherError::ArithmeticOverflow)?)
+    } else {
+        Ok(price_e6 / 10u64.pow((6 - decimals) as u32))
+    }
+}
+
+/// Mirror the e6 exec price into the return region in the oracle's declared decimals
+fn write_raw_exec_price(ctx_data: &mut [u8], price_e6: u64) -> Result<(), ProgramError> {
+    let decimals = ctx_data[ORACLE_DECIMALS_OFFSET];
+    if decimals == ORACLE_DECIMALS_NONE {
+        return Ok(());
+    }
+    let raw = e6_to_decimals(price_e6, decimals)?;
+    ctx_data[RAW_EXEC_PRICE_RETURN_OFFSET..RAW_EXEC_PRICE_RETURN_OFFSET + 8]
+        .copy_from_slice(&raw.to_le_bytes());
+    Ok(())
+}
+
+/// Stamp `last_oracle_slot + ttl` into the return region so a reader knows
+/// until which slot the returned price may be reused without a fresh match
+fn write_revalidate_stamp(ctx_data: &mut [u8]) -> Result<(), ProgramError> {
+    let ttl = u16::from_le_bytes(
+        ctx_data[REVALIDATE_TTL_SLOTS_OFFSET..REVALIDATE_TTL_SLOTS_OFFSET + 2]
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    ctx_data[TOTAL_ORDERS_OFFSET..TOTAL_ORDERS_OFFSET + 8]
-        .copy_from_slice(&count.saturating_add(1).to_le_bytes());
-
-    // Update volume if trade size provided
-    if data.len() >= 9 {
-        let trade_size = u64::from_le_bytes(
-            data[1..9]
-                .try_into()
-                .map_err(|_| ProgramError::InvalidInstructionData)?,
-        );
-        let current_volume = u128::from_le_bytes(
-            ctx_data[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16]
-                .try_into()
-                .map_err(|_| ProgramError::InvalidAccountData)?,
-        );
-        let new_volume = current_volume.saturating_add(trade_size as u128);
-        ctx_data[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16]
-            .copy_from_slice(&new_volume.to_le_bytes());
+    if ttl == 0 {
+        return Ok(());
     }
-
-    msg!(
-        "MATCH: price={} spread={} oracle={}",
-        exec_price,
-        total_spread,
-        oracle_price
+    let last_oracle_slot = u64::from_le_bytes(
+        ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let revalidate_after = last_oracle_slot.saturating_add(ttl as u64);
+    ctx_data[REVALIDATE_AFTER_SLOT_RETURN_OFFSET..REVALIDATE_AFTER_SLOT_RETURN_OFFSET + 8]
+        .copy_from_slice(&revalidate_after.to_le_bytes());
+    Ok(())
+}
 
+/// Solver fee portion of a fill (e6): `oracle_price * solver_fee_bps / 10000 * trade_size / 1e6`
+fn compute_solver_fee(oracle_price: u64, solver_fee_bps: u32, trade_size: u64) -> Result<u128, ProgramError> {
+    let fee = (oracle_price as u128)
+        .checked_mul(solver_fee_bps as u128)
+        .ok_or(PrivacyMatcherError::ArithmeticOverflow)?
+        / 10_000u128;
+    Ok(fee
+        .checked_mul(trade_size as u128)
+        .ok_or(PrivacyMatcherError::ArithmeticOverflow)?
+        / 1_000_000u128)
+}
+
+/// Add a fill's solver fee to the unclaimed accumulator (saturating)
+fn accrue_solver_fee(ctx_data: &mut [u8], fee: u128) -> Result<(), ProgramError> {
+    let accrued = read_accrued_fee(ctx_data)?.saturating_add(fee);
+    ctx_data[ACCRUED_SOLVER_FEE_OFFSET..ACCRUED_SOLVER_FEE_OFFSET + 16]
+        .copy_from_slice(&accrued.to_le_bytes());
     Ok(())
 }
 
+/// Add an oracle update's mark-out of the pending fills (bps) to the current
+/// slippage window, rolling the window when it has expired. Returns true once
+/// the cumulative adverse move exceeds `max_cum_slippage_bps`. No-op when the
+/// window is 0.
+fn accumulate_slippage(
+    ctx_data: &[u8],
+    mark_out: &mut [u8],
+    adverse_bps: u64,
+    slot: u64,
+) -> Result<bool, ProgramError> {
+    let window_slots = read_ctx_u32(ctx_data, SLIPPAGE_WINDOW_SLOTS_OFFSET)?;
+    if window_slots == 0 {
+        return Ok(false);
+    }
+
+    let max_cum = read_ctx_u32(ctx_data, MAX_CUM_SLIPPAGE_BPS_OFFSET)?;
+    let window_start = read_ctx_u64(mark_out, SLIPPAGE_WINDOW_START_OFFSET)?;
+    let mut cum = read_ctx_u32(mark_out, CUM_SLIPPAGE_BPS_OFFSET)?;
+
+    // Window expired (or never opened) -- start a fresh one at this slot
+    if window_start == 0 || slot.saturating_sub(window_start) >= window_slots as u64 {
+        mark_out[SLIPPAGE_WINDOW_START_OFFSET..SLIPPAGE_WINDOW_START_OFFSET + 8]
+            .copy_from_slice(&slot.to_le_bytes());
+        cum = 0;
+    }
+
+    cum = cum.saturating_add(u32::try_from(adverse_bps).unwrap_or(u32::MAX));
+
+    mark_out[CUM_SLIPPAGE_BPS_OFFSET..CUM_SLIPPAGE_BPS_OFFSET + 4]
+        .copy_from_slice(&cum.to_le_bytes());
+
+    Ok(cum > max_cum)
+}
+
 /// Tag 0x03: Update oracle price — only callable by authorized solver
+/// The instruction immediately before this one must be an Ed25519 precompile
+/// verification of the price and slot signed by the context's oracle signer.
 /// Accounts:
 ///   [0] Solver wallet (signer)
 ///   [1] Matcher context account (writable)
+///   [2] Instructions sysvar
+///   [3] Oracle signers PDA (seeds `[ORACLE_SIGNERS_SEED, context]`)
+///   [4] Mark-out PDA (writable, seeds `[MARK_OUT_SEED, context]`; required
+///       while the toxicity spread or slippage guard is on, and ahead of a
+///       co-signer)
+///   [5] Co-signer (signer; only when the oracle signers PDA requires one)
 /// Data layout:
-///   [0]    tag (0x03)
-///   [1..9] new_oracle_price_e6 (u64 LE)
+///   [0]     tag (0x03)
+///   [1..9]  new_oracle_price_e6 (u64 LE)
+///   [9..17] update_slot (u64 LE): the slot the price was read at; must be
+///           after the stored last_oracle_slot and not past the current slot
+/// The Ed25519 signature covers bytes [1..17], price and slot together.
 pub fn process_oracle_update(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
-    if accounts.len() < 2 {
+    if accounts.len() < 4 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 9 {
+    if data.len() < 17 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
     let solver = &accounts[0];
     let ctx_account = &accounts[1];
+    let instructions_sysvar = &accounts[2];
+    let oracle_signers = &accounts[3];
 
     // Verify solver is signer
     if !solver.is_signer {
@@ -242,12 +834,26 @@ pub fn process_oracle_update(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    // Parse new oracle price and the slot it was read at
+    let new_price = u64::from_le_bytes(
+        data[1..9]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    let update_slot = u64::from_le_bytes(
+        data[9..17]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
     // Verify context is initialized
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
     {
         let ctx_data = ctx_account.try_borrow_data()?;
         if !verify_magic(&ctx_data) {
             return Err(ProgramError::UninitializedAccount);
         }
+        check_ctx_version(&ctx_data)?;
 
         // Verify caller is the authorized solver
         let stored_solver = read_solver_pubkey(&ctx_data)?;
@@ -259,17 +865,32 @@ pub fn process_oracle_update(
             );
             return Err(PrivacyMatcherError::UnauthorizedSolver.into());
         }
-    }
 
-    // Parse new oracle price
-    let new_price = u64::from_le_bytes(
-        data[1..9]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidInstructionData)?,
-    );
+        // Verify the price was signed by the context's oracle signer
+        let signers_data = oracle_signers.try_borrow_data()?;
+        verify_oracle_signers(oracle_signers, &signers_data, ctx_account.key, program_id)?;
+        let current_index = sysvar_instructions::load_current_index_checked(instructions_sysvar)?;
+        if current_index == 0 {
+            msg!("PRIVACY-MATCHER: Oracle update must follow an Ed25519 verify instruction");
+            return Err(PrivacyMatcherError::UnauthorizedSolver.into());
+        }
+        let verify_ix = sysvar_instructions::load_instruction_at_checked(
+            current_index as usize - 1,
+            instructions_sysvar,
+        )?;
+        verify_price_signature(&verify_ix, &read_oracle_signer(&signers_data)?, new_price, update_slot)?;
 
-    if new_price == 0 {
-        msg!("PRIVACY-MATCHER: Oracle price cannot be zero");
+        // With co-signing on, the solver's key alone can't move the price
+        if let Some(co_signer) = read_co_signer(&signers_data)? {
+            if !accounts.get(5).is_some_and(|a| *a.key == co_signer && a.is_signer) {
+                msg!("PRIVACY-MATCHER: Oracle update must also be signed by co-signer {}", co_signer);
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+    }
+
+    if new_price == 0 {
+        msg!("PRIVACY-MATCHER: Oracle price cannot be zero");
         return Err(PrivacyMatcherError::OraclePriceNotSet.into());
     }
 
@@ -280,129 +901,2823 @@ pub fn process_oracle_update(
             .try_into()
             .map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let ema_alpha = u16::from_le_bytes(
+        ctx_data[EMA_ALPHA_BPS_OFFSET..EMA_ALPHA_BPS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let smoothed_price = smooth_oracle_price(old_price, new_price, ema_alpha)?;
+    let last_oracle_slot = u64::from_le_bytes(
+        ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let clock = Clock::get()?;
+    check_oracle_update_slot(last_oracle_slot, update_slot, clock.slot)?;
+
+    // Mark the fills since the last update out against this price. Sustained
+    // adverse selection trips the pause once the window's cumulative adverse
+    // move crosses the threshold; the price is still taken.
+    if let Some(mut mark_out) = borrow_mark_out(program_id, ctx_account, &ctx_data, accounts, 4)? {
+        let adverse_bps = score_pending_fills(&ctx_data, &mut mark_out, new_price, clock.slot)?;
+        if accumulate_slippage(&ctx_data, &mut mark_out, adverse_bps, clock.slot)? {
+            ctx_data[PAUSED_OFFSET] = 1;
+            matcher_log!("AUTO_PAUSE: cumulative slippage threshold exceeded at slot {}", clock.slot);
+        }
+    }
     ctx_data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8]
-        .copy_from_slice(&new_price.to_le_bytes());
+        .copy_from_slice(&smoothed_price.to_le_bytes());
+    ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8]
+        .copy_from_slice(&update_slot.to_le_bytes());
 
-    msg!("ORACLE_SYNC: old={} new={}", old_price, new_price);
+    matcher_log!("ORACLE_SYNC: old={} new={} stored={}", old_price, new_price, smoothed_price);
 
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::state::*;
-    use matcher_common::compute_exec_price;
-    use solana_program::pubkey::Pubkey;
+/// An oracle update must carry a later slot than the last one accepted. The
+/// slot is signed with the price, so a replayed or reordered update still
+/// verifies but names a slot at or behind the stored one, in whatever slot it
+/// lands; a slot from the future is no reading at all.
+fn check_oracle_update_slot(last_oracle_slot: u64, update_slot: u64, slot: u64) -> ProgramResult {
+    if update_slot <= last_oracle_slot {
+        msg!("PRIVACY-MATCHER: Stale oracle update for slot {} (last update for {})", update_slot, last_oracle_slot);
+        return Err(PrivacyMatcherError::StaleUpdate.into());
+    }
+    if update_slot > slot {
+        msg!("PRIVACY-MATCHER: Oracle update for slot {} is past the current slot {}", update_slot, slot);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
 
-    #[test]
-    fn test_normal_pricing() {
-        // oracle=100_000_000, base_spread=15, solver_fee=10, max_spread=100
-        // total_spread = min(15 + 10, 100) = 25
-        // exec_price = 100_000_000 * (10000 + 25) / 10000 = 100_250_000
-        let oracle_price: u64 = 100_000_000;
-        let base_spread: u32 = 15;
-        let solver_fee: u32 = 10;
-        let max_spread: u32 = 100;
+/// Ed25519 precompile data: `[num_signatures u8, padding u8]` then one 14-byte
+/// offsets record per signature
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
 
-        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
-        assert_eq!(total_spread, 25);
+/// Check that `ix` is an Ed25519 precompile call verifying exactly one signature
+/// by `oracle_signer` over `new_price` then `update_slot`, 8 LE bytes each.
+/// The precompile itself has already checked the signature by the time this
+/// instruction runs; this pins down whose key and which message it covered.
+fn verify_price_signature(
+    ix: &Instruction,
+    oracle_signer: &[u8],
+    new_price: u64,
+    update_slot: u64,
+) -> Result<(), ProgramError> {
+    let unauthorized = |reason: &str| -> ProgramError {
+        msg!("PRIVACY-MATCHER: Price signature rejected: {}", reason);
+        PrivacyMatcherError::UnauthorizedSolver.into()
+    };
 
-        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
-        assert_eq!(exec_price, 100_250_000);
+    if ix.program_id != ed25519_program::ID {
+        return Err(unauthorized("preceding instruction is not Ed25519 verify"));
+    }
+    let data = &ix.data;
+    if data.len() < ED25519_OFFSETS_START + ED25519_OFFSETS_LEN || data[0] != 1 {
+        return Err(unauthorized("expected exactly one signature"));
     }
 
-    #[test]
-    fn test_spread_capping() {
-        // base=80, solver=50, max=100
-        // total_spread = min(80 + 50, 100) = 100
-        // exec_price = 100_000_000 * 10100 / 10000 = 101_000_000
-        let oracle_price: u64 = 100_000_000;
-        let base_spread: u32 = 80;
-        let solver_fee: u32 = 50;
-        let max_spread: u32 = 100;
+    let field = |i: usize| -> usize {
+        let at = ED25519_OFFSETS_START + i * 2;
+        u16::from_le_bytes([data[at], data[at + 1]]) as usize
+    };
+    // Offsets record: signature, signature ix, pubkey, pubkey ix, message, message size, message ix
+    let (pubkey_offset, message_offset, message_size) = (field(2), field(4), field(5));
+    // All referenced bytes must live in the precompile instruction itself
+    if [field(1), field(3), field(6)].iter().any(|&ix_index| ix_index != u16::MAX as usize) {
+        return Err(unauthorized("signature data must be inline"));
+    }
 
-        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
-        assert_eq!(total_spread, 100);
+    let signed_key = data.get(pubkey_offset..pubkey_offset + 32);
+    if signed_key != Some(oracle_signer) {
+        return Err(unauthorized("signer is not the oracle signer"));
+    }
+    let message = data.get(message_offset..message_offset + message_size);
+    if message != Some(&price_update_message(new_price, update_slot)[..]) {
+        return Err(unauthorized("signed message is not the new price and slot"));
+    }
+    Ok(())
+}
 
-        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
-        assert_eq!(exec_price, 101_000_000);
+/// The bytes an oracle update signs: price then slot, as in its instruction data
+fn price_update_message(new_price: u64, update_slot: u64) -> [u8; 16] {
+    let mut message = [0u8; 16];
+    message[..8].copy_from_slice(&new_price.to_le_bytes());
+    message[8..].copy_from_slice(&update_slot.to_le_bytes());
+    message
+}
+
+/// Tag 0x07: Init oracle signer — create the context's oracle signers PDA and
+/// store the Ed25519 key oracle prices must be signed with. Once per context;
+/// Rotate Oracle Signer replaces the keys afterwards.
+/// Accounts:
+///   [0] Solver wallet (signer, writable: pays for the PDA)
+///   [1] Matcher context account
+///   [2] Oracle signers PDA (writable, seeds `[ORACLE_SIGNERS_SEED, context]`)
+///   [3] System program
+/// Data layout:
+///   [0]      tag (0x07)
+///   [1..33]  oracle_signer (Ed25519 pubkey, nonzero)
+///   [33]     require_co_signer (u8, optional, 0 = off, 1 = Oracle Update must
+///            also be signed by co_signer)
+///   [34..66] co_signer (pubkey, nonzero; required when require_co_signer is 1)
+pub fn process_init_oracle_signer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
     }
 
-    #[test]
-    fn test_zero_solver_fee() {
-        // base=15, solver=0, max=100
-        // total_spread = min(15 + 0, 100) = 15
-        // exec_price = 100_000_000 * 10015 / 10000 = 100_150_000
-        let oracle_price: u64 = 100_000_000;
-        let base_spread: u32 = 15;
-        let solver_fee: u32 = 0;
-        let max_spread: u32 = 100;
+    let solver = &accounts[0];
+    let ctx_account = &accounts[1];
+    let oracle_signers = &accounts[2];
+    let system_program = &accounts[3];
 
-        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
-        assert_eq!(total_spread, 15);
+    if !solver.is_signer {
+        msg!("PRIVACY-MATCHER: Solver must be a signer to set the oracle signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
 
-        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
-        assert_eq!(exec_price, 100_150_000);
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if !verify_magic(&ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        check_ctx_version(&ctx_data)?;
+        let stored_solver = read_solver_pubkey(&ctx_data)?;
+        if *solver.key != stored_solver {
+            msg!(
+                "PRIVACY-MATCHER: Unauthorized solver: expected {}, got {}",
+                stored_solver,
+                solver.key
+            );
+            return Err(PrivacyMatcherError::UnauthorizedSolver.into());
+        }
     }
 
-    #[test]
-    fn test_large_price_btc() {
-        // oracle=70_000_000_000, base=15, solver=10, max=100
-        // total_spread = 25
-        // exec_price = 70_000_000_000 * 10025 / 10000 = 70_175_000_000
-        let oracle_price: u64 = 70_000_000_000;
-        let base_spread: u32 = 15;
-        let solver_fee: u32 = 10;
-        let max_spread: u32 = 100;
+    let (oracle_signer, co_signer) = parse_oracle_signers(data)?;
 
-        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
-        assert_eq!(total_spread, 25);
+    let (expected, bump) =
+        Pubkey::find_program_address(&[ORACLE_SIGNERS_SEED, ctx_account.key.as_ref()], program_id);
+    if *oracle_signers.key != expected {
+        msg!("PRIVACY-MATCHER: Oracle signers must be the PDA {}", expected);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !oracle_signers.data_is_empty() {
+        msg!("PRIVACY-MATCHER: Oracle signer already set for {}", ctx_account.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        program_id,
+        solver,
+        oracle_signers,
+        system_program,
+        ORACLE_SIGNERS_SIZE,
+        &[ORACLE_SIGNERS_SEED, ctx_account.key.as_ref(), &[bump]],
+    )?;
 
-        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
-        assert_eq!(exec_price, 70_175_000_000);
+    let mut signers_data = oracle_signers.try_borrow_mut_data()?;
+    signers_data[..ORACLE_SIGNERS_SIZE].fill(0);
+    signers_data[SIGNERS_MAGIC_OFFSET..SIGNERS_MAGIC_OFFSET + 8].copy_from_slice(&ORACLE_SIGNERS_MAGIC.to_le_bytes());
+    signers_data[SIGNERS_BUMP_OFFSET] = bump;
+    write_oracle_signers(&mut signers_data, &oracle_signer, co_signer.as_ref());
+    if let Some(co_signer) = co_signer {
+        matcher_log!("INIT_ORACLE_SIGNER: ctx={} signer={} co_signer={}", ctx_account.key, oracle_signer, co_signer);
+    } else {
+        matcher_log!("INIT_ORACLE_SIGNER: ctx={} signer={}", ctx_account.key, oracle_signer);
     }
 
-    #[test]
-    fn test_verify_magic() {
-        // Create a 320-byte buffer with the correct magic at offset 64
-        let mut data = vec![0u8; CTX_SIZE];
-        data[MAGIC_OFFSET..MAGIC_OFFSET + 8]
-            .copy_from_slice(&PRIVACY_MATCHER_MAGIC.to_le_bytes());
+    Ok(())
+}
 
-        assert!(verify_magic(&data));
+/// Tag 0x0A: Rotate oracle signer — replace the Ed25519 oracle signer and the
+/// co-signer setting in an existing oracle signers PDA, e.g. after a keeper
+/// key is lost or leaked. While co-signing is on the current co-signer must
+/// also sign, so a compromised solver wallet alone still can't swap in a
+/// signer of its own.
+/// Accounts:
+///   [0] Solver wallet (signer)
+///   [1] Matcher context account
+///   [2] Oracle signers PDA (writable, seeds `[ORACLE_SIGNERS_SEED, context]`)
+///   [3] Current co-signer (signer, required while co-signing is on)
+/// Data layout: as Init Oracle Signer
+///   [0]      tag (0x0A)
+///   [1..33]  oracle_signer (Ed25519 pubkey, nonzero)
+///   [33]     require_co_signer (u8, optional, 0 = off, 1 = on)
+///   [34..66] co_signer (pubkey, nonzero; required when require_co_signer is 1)
+pub fn process_rotate_oracle_signer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
     }
 
-    #[test]
-    fn test_verify_magic_wrong_value() {
-        let mut data = vec![0u8; CTX_SIZE];
-        data[MAGIC_OFFSET..MAGIC_OFFSET + 8]
-            .copy_from_slice(&0xDEAD_BEEF_u64.to_le_bytes());
+    let solver = &accounts[0];
+    let ctx_account = &accounts[1];
+    let oracle_signers = &accounts[2];
 
-        assert!(!verify_magic(&data));
+    if !solver.is_signer {
+        msg!("PRIVACY-MATCHER: Solver must be a signer to rotate the oracle signer");
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    #[test]
-    fn test_verify_magic_empty_buffer() {
-        let data = vec![0u8; CTX_SIZE];
-        assert!(!verify_magic(&data));
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if !verify_magic(&ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        check_ctx_version(&ctx_data)?;
+        let stored_solver = read_solver_pubkey(&ctx_data)?;
+        if *solver.key != stored_solver {
+            msg!(
+                "PRIVACY-MATCHER: Unauthorized solver: expected {}, got {}",
+                stored_solver,
+                solver.key
+            );
+            return Err(PrivacyMatcherError::UnauthorizedSolver.into());
+        }
     }
 
-    #[test]
-    fn test_read_solver_pubkey() {
-        // Create a buffer with a known pubkey at SOLVER_PUBKEY_OFFSET (112)
-        let mut data = vec![0u8; CTX_SIZE];
-        let expected_pubkey = Pubkey::new_unique();
-        data[SOLVER_PUBKEY_OFFSET..SOLVER_PUBKEY_OFFSET + 32]
-            .copy_from_slice(&expected_pubkey.to_bytes());
+    let (oracle_signer, co_signer) = parse_oracle_signers(data)?;
 
-        let read_pubkey = read_solver_pubkey(&data).unwrap();
-        assert_eq!(read_pubkey, expected_pubkey);
+    let mut signers_data = oracle_signers.try_borrow_mut_data()?;
+    verify_oracle_signers(oracle_signers, &signers_data, ctx_account.key, program_id)?;
+    if let Some(current) = read_co_signer(&signers_data)? {
+        if !accounts.get(3).is_some_and(|a| *a.key == current && a.is_signer) {
+            msg!("PRIVACY-MATCHER: Rotation must also be signed by co-signer {}", current);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
     }
+    write_oracle_signers(&mut signers_data, &oracle_signer, co_signer.as_ref());
 
-    #[test]
-    fn test_read_solver_pubkey_zeroed() {
-        let data = vec![0u8; CTX_SIZE];
-        let read_pubkey = read_solver_pubkey(&data).unwrap();
-        assert_eq!(read_pubkey, Pubkey::default());
+    if let Some(co_signer) = co_signer {
+        matcher_log!("ROTATE_ORACLE_SIGNER: ctx={} signer={} co_signer={}", ctx_account.key, oracle_signer, co_signer);
+    } else {
+        matcher_log!("ROTATE_ORACLE_SIGNER: ctx={} signer={}", ctx_account.key, oracle_signer);
+    }
+
+    Ok(())
+}
+
+/// Parse the oracle signer and optional co-signer shared by Init and Rotate
+/// Oracle Signer. All zeros would leave oracle updates unverifiable.
+fn parse_oracle_signers(data: &[u8]) -> Result<(Pubkey, Option<Pubkey>), ProgramError> {
+    let oracle_signer = Pubkey::new_from_array(data[1..33].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    if oracle_signer == Pubkey::default() {
+        msg!("PRIVACY-MATCHER: Oracle signer must be set");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let co_signer = match data.get(33).copied().unwrap_or(0) {
+        0 => None,
+        1 => {
+            let key = data
+                .get(34..66)
+                .and_then(|b| <[u8; 32]>::try_from(b).ok())
+                .map(Pubkey::new_from_array)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            if key == Pubkey::default() {
+                msg!("PRIVACY-MATCHER: Co-signer must be set when required");
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            Some(key)
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    Ok((oracle_signer, co_signer))
+}
+
+/// Store the oracle signer and co-signer, clearing the co-signer when off
+fn write_oracle_signers(signers_data: &mut [u8], oracle_signer: &Pubkey, co_signer: Option<&Pubkey>) {
+    signers_data[ORACLE_SIGNER_OFFSET..ORACLE_SIGNER_OFFSET + 32].copy_from_slice(oracle_signer.as_ref());
+    signers_data[CO_SIGN_REQUIRED_OFFSET] = co_signer.is_some() as u8;
+    signers_data[CO_SIGNER_OFFSET..CO_SIGNER_OFFSET + 32]
+        .copy_from_slice(co_signer.copied().unwrap_or_default().as_ref());
+}
+
+/// Tag 0x08: Init counterparty flow — create the PDA Match records one
+/// counterparty's buys and sells in, starting flat. Anyone can pay for it:
+/// the balances only move on matches the LP PDA signed.
+/// Accounts:
+///   [0] Payer (signer, writable)
+///   [1] Matcher context account
+///   [2] Counterparty flow PDA (writable, seeds `[COUNTERPARTY_FLOW_SEED, context, counterparty]`)
+///   [3] System program
+/// Data layout:
+///   [0]     tag (0x08)
+///   [1..33] counterparty (pubkey)
+pub fn process_init_counterparty_flow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let payer = &accounts[0];
+    let ctx_account = &accounts[1];
+    let flow = &accounts[2];
+    let system_program = &accounts[3];
+
+    if !payer.is_signer {
+        msg!("PRIVACY-MATCHER: Payer must be a signer to create a counterparty flow");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if !verify_magic(&ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        check_ctx_version(&ctx_data)?;
+    }
+
+    let counterparty = &data[1..33];
+    let (expected, bump) = Pubkey::find_program_address(
+        &[COUNTERPARTY_FLOW_SEED, ctx_account.key.as_ref(), counterparty],
+        program_id,
+    );
+    if *flow.key != expected {
+        msg!("PRIVACY-MATCHER: Counterparty flow must be the PDA {}", expected);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !flow.data_is_empty() {
+        msg!("PRIVACY-MATCHER: Counterparty flow {} already exists", flow.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        flow,
+        system_program,
+        COUNTERPARTY_FLOW_SIZE,
+        &[COUNTERPARTY_FLOW_SEED, ctx_account.key.as_ref(), counterparty, &[bump]],
+    )?;
+
+    let mut flow_data = flow.try_borrow_mut_data()?;
+    flow_data[..COUNTERPARTY_FLOW_SIZE].fill(0);
+    flow_data[FLOW_MAGIC_OFFSET..FLOW_MAGIC_OFFSET + 8].copy_from_slice(&COUNTERPARTY_FLOW_MAGIC.to_le_bytes());
+    flow_data[FLOW_BUMP_OFFSET] = bump;
+    flow_data[FLOW_COUNTERPARTY_OFFSET..FLOW_COUNTERPARTY_OFFSET + 32].copy_from_slice(counterparty);
+
+    matcher_log!("INIT_COUNTERPARTY_FLOW: ctx={} flow={}", ctx_account.key, flow.key);
+
+    Ok(())
+}
+
+/// Tag 0x0C: Init mark-out — create the PDA holding the context's fills
+/// awaiting their mark-out, toxicity scores and slippage window. Needed before
+/// the first Match or Oracle Update with the toxicity spread or slippage guard
+/// on. Anyone can pay for it: it only moves on matches the LP PDA signed and
+/// on oracle updates the solver signed.
+/// Accounts:
+///   [0] Payer (signer, writable)
+///   [1] Matcher context account
+///   [2] Mark-out PDA (writable, seeds `[MARK_OUT_SEED, context]`)
+///   [3] System program
+/// Data layout:
+///   [0] tag (0x0C)
+pub fn process_init_mark_out(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let payer = &accounts[0];
+    let ctx_account = &accounts[1];
+    let mark_out = &accounts[2];
+    let system_program = &accounts[3];
+
+    if !payer.is_signer {
+        msg!("PRIVACY-MATCHER: Payer must be a signer to create the mark-out state");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if !verify_magic(&ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        check_ctx_version(&ctx_data)?;
+    }
+
+    let (expected, bump) =
+        Pubkey::find_program_address(&[MARK_OUT_SEED, ctx_account.key.as_ref()], program_id);
+    if *mark_out.key != expected {
+        msg!("PRIVACY-MATCHER: Mark-out state must be the PDA {}", expected);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !mark_out.data_is_empty() {
+        msg!("PRIVACY-MATCHER: Mark-out state for {} already exists", ctx_account.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        mark_out,
+        system_program,
+        MARK_OUT_SIZE,
+        &[MARK_OUT_SEED, ctx_account.key.as_ref(), &[bump]],
+    )?;
+
+    let mut mark_out_data = mark_out.try_borrow_mut_data()?;
+    mark_out_data[..MARK_OUT_SIZE].fill(0);
+    mark_out_data[MARK_OUT_MAGIC_OFFSET..MARK_OUT_MAGIC_OFFSET + 8].copy_from_slice(&MARK_OUT_MAGIC.to_le_bytes());
+    mark_out_data[MARK_OUT_BUMP_OFFSET] = bump;
+
+    matcher_log!("INIT_MARK_OUT: ctx={} mark_out={}", ctx_account.key, mark_out.key);
+
+    Ok(())
+}
+
+/// Extra spread from recent fill toxicity: `toxicity_spread_bps * toxic / scored`,
+/// scored from the mark-out PDA's history (0 without one)
+fn toxicity_spread(ctx_data: &[u8], mark_out: Option<&[u8]>) -> Result<u32, ProgramError> {
+    let max_adj = u16::from_le_bytes(
+        ctx_data[TOXICITY_SPREAD_BPS_OFFSET..TOXICITY_SPREAD_BPS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let Some(mark_out) = mark_out else {
+        return Ok(0);
+    };
+    let scored = mark_out[SCORED_FILLS_OFFSET].min(TOXICITY_HISTORY_LEN);
+    if max_adj == 0 || scored == 0 {
+        return Ok(0);
+    }
+    let bits = u16::from_le_bytes(
+        mark_out[TOXIC_FILL_BITS_OFFSET..TOXIC_FILL_BITS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let window = if scored == TOXICITY_HISTORY_LEN { u16::MAX } else { (1u16 << scored) - 1 };
+    let toxic = (bits & window).count_ones();
+    Ok(max_adj as u32 * toxic / scored as u32)
+}
+
+/// Queue a fill in the mark-out PDA for its mark-out against the next oracle
+/// update. A fill in the newest entry's slot joins it; one in a later slot
+/// takes the next entry, or folds into the newest once all
+/// `PENDING_FILL_ENTRIES` are in use, and is then marked out from that entry's
+/// slot. Nothing is scored here, so a later fill never displaces one still
+/// waiting for its print.
+fn record_fill(mark_out: &mut [u8], slot: u64, direction: u8) -> Result<(), ProgramError> {
+    let count = mark_out[PENDING_FILL_COUNT_OFFSET].min(PENDING_FILL_ENTRIES);
+    let slot = slot as u32;
+    let newest = count.checked_sub(1).map(pending_fill_offset);
+    let entry = match newest {
+        Some(at) if count == PENDING_FILL_ENTRIES || read_ctx_u32(mark_out, at)? == slot => at,
+        _ => {
+            let at = pending_fill_offset(count);
+            mark_out[at..at + 4].copy_from_slice(&slot.to_le_bytes());
+            mark_out[at + 4..at + PENDING_FILL_SIZE].fill(0);
+            mark_out[PENDING_FILL_COUNT_OFFSET] = count + 1;
+            at
+        }
+    };
+    let side = entry + 4 + direction as usize;
+    mark_out[side] = mark_out[side].saturating_add(1);
+    Ok(())
+}
+
+/// Mark every queued fill out against a fresh oracle price, oldest first,
+/// returning the summed adverse move (bps of each fill price) for the slippage
+/// guard. On a buy the LP went short at the ask, on a sell long at the bid: a
+/// print through the fill price in the taker's favour went against the LP, and
+/// inside the horizon also scores the fill toxic. Quotes only move on an oracle
+/// update, so every fill queued since the last one traded at the ask or bid the
+/// context quotes until this price is written; a side that can't be quoted had
+/// no fills.
+fn score_pending_fills(
+    ctx_data: &[u8],
+    mark_out: &mut [u8],
+    oracle_price: u64,
+    slot: u64,
+) -> Result<u64, ProgramError> {
+    let count = mark_out[PENDING_FILL_COUNT_OFFSET].min(PENDING_FILL_ENTRIES);
+    if count == 0 {
+        return Ok(0);
     }
+    let ask = quote_exec_price(ctx_data, Some(&*mark_out), DIRECTION_BUY).ok().map(|quote| quote.0);
+    let bid = quote_exec_price(ctx_data, Some(&*mark_out), DIRECTION_SELL).ok().map(|quote| quote.0);
+    let ask_adverse_bps = ask.map_or(0, |ask| adverse_move_bps(ask, oracle_price.saturating_sub(ask)));
+    let bid_adverse_bps = bid.map_or(0, |bid| adverse_move_bps(bid, bid.saturating_sub(oracle_price)));
+    let scores_toxicity = ctx_data[TOXICITY_SPREAD_BPS_OFFSET..TOXICITY_SPREAD_BPS_OFFSET + 2] != [0, 0];
+    let horizon = u16::from_le_bytes(
+        ctx_data[TOXICITY_HORIZON_SLOTS_OFFSET..TOXICITY_HORIZON_SLOTS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+
+    let mut adverse_bps = 0u64;
+    for index in 0..count {
+        let at = pending_fill_offset(index);
+        let (buys, sells) = (mark_out[at + 4], mark_out[at + 5]);
+        adverse_bps = adverse_bps
+            .saturating_add(ask_adverse_bps.saturating_mul(buys as u64))
+            .saturating_add(bid_adverse_bps.saturating_mul(sells as u64));
+        if !scores_toxicity {
+            continue;
+        }
+        let in_horizon = (slot as u32).wrapping_sub(read_ctx_u32(mark_out, at)?) <= horizon as u32;
+        for _ in 0..buys.min(TOXICITY_HISTORY_LEN) {
+            push_fill_score(mark_out, in_horizon && ask.is_some_and(|ask| oracle_price > ask))?;
+        }
+        for _ in 0..sells.min(TOXICITY_HISTORY_LEN) {
+            push_fill_score(mark_out, in_horizon && bid.is_some_and(|bid| oracle_price < bid))?;
+        }
+    }
+    mark_out[PENDING_FILL_COUNT_OFFSET] = 0;
+    mark_out[PENDING_FILLS_OFFSET..PENDING_FILLS_OFFSET + PENDING_FILL_SIZE * PENDING_FILL_ENTRIES as usize].fill(0);
+    Ok(adverse_bps)
+}
+
+/// `moved` as bps of `fill_price` (0 for a fill price of 0)
+fn adverse_move_bps(fill_price: u64, moved: u64) -> u64 {
+    if fill_price == 0 {
+        return 0;
+    }
+    u64::try_from(moved as u128 * 10_000 / fill_price as u128).unwrap_or(u64::MAX)
+}
+
+fn pending_fill_offset(index: u8) -> usize {
+    PENDING_FILLS_OFFSET + index as usize * PENDING_FILL_SIZE
+}
+
+fn read_ctx_u32(ctx_data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[offset..offset + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_ctx_u64(ctx_data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    Ok(u64::from_le_bytes(
+        ctx_data[offset..offset + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn push_fill_score(mark_out: &mut [u8], toxic: bool) -> Result<(), ProgramError> {
+    let bits = u16::from_le_bytes(
+        mark_out[TOXIC_FILL_BITS_OFFSET..TOXIC_FILL_BITS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let bits = (bits << 1) | toxic as u16;
+    mark_out[TOXIC_FILL_BITS_OFFSET..TOXIC_FILL_BITS_OFFSET + 2].copy_from_slice(&bits.to_le_bytes());
+    mark_out[SCORED_FILLS_OFFSET] = mark_out[SCORED_FILLS_OFFSET].saturating_add(1).min(TOXICITY_HISTORY_LEN);
+    Ok(())
+}
+
+/// EMA blend of the incoming oracle price: `(new * alpha + old * (10000 - alpha)) / 10000`.
+/// alpha == 0 and the first update (old == 0) both take the raw price.
+fn smooth_oracle_price(old_price: u64, new_price: u64, alpha_bps: u16) -> Result<u64, ProgramError> {
+    if alpha_bps == 0 || old_price == 0 {
+        return Ok(new_price);
+    }
+    let alpha = alpha_bps as u128;
+    let blended = (new_price as u128)
+        .checked_mul(alpha)
+        .and_then(|n| n.checked_add((old_price as u128).checked_mul(10_000u128 - alpha)?))
+        .ok_or(PrivacyMatcherError::ArithmeticOverflow)?
+        / 10_000u128;
+    Ok(blended as u64)
+}
+
+/// Tag 0x09: Update config — only callable by authorized solver. Replaces the
+/// spread parameters, validated as at Init. With `min_config_interval_slots`
+/// set, rejected with `ConfigUpdateTooSoon` until that many slots have passed
+/// since the previous update. Fills still awaiting their mark-out were priced
+/// at the old spreads, so the update also waits for the next Oracle Update to
+/// score them (`FillsAwaitingMarkOut`).
+/// Accounts:
+///   [0] Solver wallet (signer)
+///   [1] Matcher context account (writable)
+///   [2] Mark-out PDA (seeds `[MARK_OUT_SEED, context]`; required while the
+///       toxicity spread or slippage guard is on)
+/// Data layout:
+///   [0]    tag (0x09)
+///   [1..3] base_spread_bps (u16 LE)
+///   [3..5] max_spread_bps (u16 LE)
+///   [5..7] solver_fee_bps (u16 LE)
+///   [7..9] min_spread_bps (u16 LE)
+pub fn process_update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 9 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let solver = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    if !solver.is_signer {
+        msg!("PRIVACY-MATCHER: Solver must be a signer to update config");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_ctx_version(&ctx_data)?;
+    let stored_solver = read_solver_pubkey(&ctx_data)?;
+    if *solver.key != stored_solver {
+        msg!(
+            "PRIVACY-MATCHER: Unauthorized solver: expected {}, got {}",
+            stored_solver,
+            solver.key
+        );
+        return Err(PrivacyMatcherError::UnauthorizedSolver.into());
+    }
+
+    let field = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let (base_spread, max_spread, solver_fee, min_spread) = (field(1), field(3), field(5), field(7));
+    if base_spread > max_spread || min_spread > max_spread {
+        msg!(
+            "PRIVACY-MATCHER: base_spread ({}) and min_spread ({}) must not exceed max_spread ({})",
+            base_spread,
+            min_spread,
+            max_spread
+        );
+        return Err(PrivacyMatcherError::InvalidSpreadConfig.into());
+    }
+
+    let clock = Clock::get()?;
+    let min_interval = u16::from_le_bytes(
+        ctx_data[MIN_CONFIG_INTERVAL_SLOTS_OFFSET..MIN_CONFIG_INTERVAL_SLOTS_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let last_config = read_ctx_u32(&ctx_data, LAST_CONFIG_SLOT_OFFSET)?;
+    if !config_interval_elapsed(last_config, min_interval, clock.slot) {
+        msg!(
+            "PRIVACY-MATCHER: Config updated at slot {}, next update allowed {} slots later",
+            last_config,
+            min_interval
+        );
+        return Err(PrivacyMatcherError::ConfigUpdateTooSoon.into());
+    }
+    if let Some(mark_out) = borrow_mark_out(program_id, ctx_account, &ctx_data, accounts, 2)? {
+        if mark_out[PENDING_FILL_COUNT_OFFSET] != 0 {
+            msg!("PRIVACY-MATCHER: Fills priced at the current spreads await their mark-out");
+            return Err(PrivacyMatcherError::FillsAwaitingMarkOut.into());
+        }
+    }
+
+    ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&(base_spread as u32).to_le_bytes());
+    ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&(max_spread as u32).to_le_bytes());
+    ctx_data[SOLVER_FEE_OFFSET..SOLVER_FEE_OFFSET + 4].copy_from_slice(&(solver_fee as u32).to_le_bytes());
+    ctx_data[MIN_SPREAD_OFFSET..MIN_SPREAD_OFFSET + 2].copy_from_slice(&min_spread.to_le_bytes());
+    // Slot 0's low bits would read as "never updated"; 0 itself is never a live slot
+    ctx_data[LAST_CONFIG_SLOT_OFFSET..LAST_CONFIG_SLOT_OFFSET + 4]
+        .copy_from_slice(&(clock.slot as u32).max(1).to_le_bytes());
+
+    matcher_log!(
+        "UPDATE_CONFIG: base_spread={} max_spread={} solver_fee={} min_spread={}",
+        base_spread,
+        max_spread,
+        solver_fee,
+        min_spread
+    );
+
+    Ok(())
+}
+
+/// Tag 0x04: Set paused flag — only callable by authorized solver
+/// Accounts:
+///   [0] Solver wallet (signer)
+///   [1] Matcher context account (writable)
+///   [2] Mark-out PDA (writable, seeds `[MARK_OUT_SEED, context]`; required
+///       while the toxicity spread or slippage guard is on)
+/// Data layout:
+///   [0] tag (0x04)
+///   [1] paused (u8: 0=resume, 1=pause)
+/// With `resume_max_oracle_age_slots` set, resuming also needs an oracle update
+/// within that many slots, so matching never restarts on a stale price.
+pub fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 || data[1] > 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let solver = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    if !solver.is_signer {
+        msg!("PRIVACY-MATCHER: Solver must be a signer to change pause state");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_ctx_version(&ctx_data)?;
+
+    let stored_solver = read_solver_pubkey(&ctx_data)?;
+    if *solver.key != stored_solver {
+        msg!(
+            "PRIVACY-MATCHER: Unauthorized solver: expected {}, got {}",
+            stored_solver,
+            solver.key
+        );
+        return Err(PrivacyMatcherError::UnauthorizedSolver.into());
+    }
+
+    let paused = data[1];
+    if paused == 0 {
+        check_resume_heartbeat(&ctx_data, Clock::get()?.slot)?;
+    }
+    ctx_data[PAUSED_OFFSET] = paused;
+
+    // Resuming starts a clean slippage window and a new volume epoch
+    if paused == 0 {
+        if let Some(mut mark_out) = borrow_mark_out(program_id, ctx_account, &ctx_data, accounts, 2)? {
+            mark_out[CUM_SLIPPAGE_BPS_OFFSET..CUM_SLIPPAGE_BPS_OFFSET + 4]
+                .copy_from_slice(&0u32.to_le_bytes());
+            mark_out[SLIPPAGE_WINDOW_START_OFFSET..SLIPPAGE_WINDOW_START_OFFSET + 8]
+                .copy_from_slice(&0u64.to_le_bytes());
+        }
+        ctx_data[EPOCH_VOLUME_OFFSET..EPOCH_VOLUME_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    }
+
+    matcher_log!("SET_PAUSED: paused={}", paused);
+
+    Ok(())
+}
+
+/// Tag 0x05: Claim accrued solver fees — zeroes the accumulator
+/// Accounts:
+///   [0] Solver wallet (signer)
+///   [1] Matcher context account (writable)
+/// Data layout:
+///   [0] tag (0x05)
+pub fn process_claim_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let solver = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    if !solver.is_signer {
+        msg!("PRIVACY-MATCHER: Solver must be a signer to claim fees");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    verify_ctx_owner(ctx_account, program_id, "PRIVACY-MATCHER")?;
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_ctx_version(&ctx_data)?;
+
+    let stored_solver = read_solver_pubkey(&ctx_data)?;
+    if *solver.key != stored_solver {
+        msg!(
+            "PRIVACY-MATCHER: Unauthorized solver: expected {}, got {}",
+            stored_solver,
+            solver.key
+        );
+        return Err(PrivacyMatcherError::UnauthorizedSolver.into());
+    }
+
+    let claimed = claim_accrued_fee(&mut ctx_data)?;
+
+    matcher_log!("CLAIM_FEE: solver={} amount={}", solver.key, claimed);
+
+    Ok(())
+}
+
+/// Add a fill to the current epoch's notional. Returns true once the epoch
+/// has traded past `max_epoch_volume_e6` (never when the cap is 0).
+fn accumulate_epoch_volume(ctx_data: &mut [u8], trade_size: u64) -> Result<bool, ProgramError> {
+    let max_epoch_volume = u64::from_le_bytes(
+        ctx_data[MAX_EPOCH_VOLUME_OFFSET..MAX_EPOCH_VOLUME_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if max_epoch_volume == 0 {
+        return Ok(false);
+    }
+    let epoch_volume = u64::from_le_bytes(
+        ctx_data[EPOCH_VOLUME_OFFSET..EPOCH_VOLUME_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    )
+    .saturating_add(trade_size);
+    ctx_data[EPOCH_VOLUME_OFFSET..EPOCH_VOLUME_OFFSET + 8].copy_from_slice(&epoch_volume.to_le_bytes());
+    Ok(epoch_volume > max_epoch_volume)
+}
+
+/// Reject a resume when the last oracle update is older than the configured
+/// heartbeat (a never-updated oracle always is). No-op when unconfigured.
+fn check_resume_heartbeat(ctx_data: &[u8], slot: u64) -> Result<(), ProgramError> {
+    let max_age = u16::from_le_bytes(
+        ctx_data[RESUME_MAX_ORACLE_AGE_OFFSET..RESUME_MAX_ORACLE_AGE_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if max_age == 0 {
+        return Ok(());
+    }
+    let last_oracle_slot = u64::from_le_bytes(
+        ctx_data[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if last_oracle_slot == 0 || slot.saturating_sub(last_oracle_slot) > max_age as u64 {
+        msg!(
+            "PRIVACY-MATCHER: Oracle last updated at slot {}, resume needs one within {} slots of {}",
+            last_oracle_slot,
+            max_age,
+            slot
+        );
+        return Err(PrivacyMatcherError::OracleStale.into());
+    }
+    Ok(())
+}
+
+/// Zero the solver fee accumulator, returning the amount that was owed
+fn claim_accrued_fee(ctx_data: &mut [u8]) -> Result<u128, ProgramError> {
+    let claimed = read_accrued_fee(ctx_data)?;
+    ctx_data[ACCRUED_SOLVER_FEE_OFFSET..ACCRUED_SOLVER_FEE_OFFSET + 16]
+        .copy_from_slice(&0u128.to_le_bytes());
+    Ok(claimed)
+}
+
+/// Accepted init payload lengths: the base layout, then each optional
+/// trailing group (window/threshold, EMA alpha, oracle decimals, revalidate TTL,
+/// toxicity spread/horizon, oracle price floor, resume heartbeat, epoch volume cap,
+/// match cooldown, initial oracle price, spread floor) in full.
+const INIT_DATA_LENS: [usize; 14] = [45, 53, 55, 56, 58, 62, 70, 72, 80, 82, 90, 92, 94, 96];
+
+/// Reject truncated optional groups and trailing garbage on init
+fn check_init_len(len: usize) -> Result<(), ProgramError> {
+    if !INIT_DATA_LENS.contains(&len) {
+        msg!("PRIVACY-MATCHER: Init data length {}, expected one of {:?}", len, INIT_DATA_LENS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        accrue_solver_fee, accumulate_slippage, check_init_len, check_match_cooldown,
+        check_oracle_update_slot, claim_accrued_fee, compute_solver_fee, e6_to_decimals, guard_match,
+        price_update_message, process_init, process_init_counterparty_flow, process_init_mark_out,
+        process_init_oracle_signer, process_claim_fee, process_init_params, process_match, process_oracle_update,
+        process_quote_match, process_rotate_oracle_signer, process_set_paused,
+        process_update_config, quote_exec_price, read_ctx_u32, read_ctx_u64, record_fill, score_pending_fills,
+        smooth_oracle_price, toxicity_spread, verify_price_signature, write_raw_exec_price,
+        write_revalidate_stamp, INIT_DATA_LENS,
+    };
+    use crate::errors::PrivacyMatcherError;
+    use crate::init_params::PrivacyInitParams;
+    use crate::state::*;
+    use matcher_common::compute_exec_price;
+    use solana_program::{
+        account_info::AccountInfo, clock::Clock, ed25519_program, entrypoint::ProgramResult,
+        instruction::Instruction, program_error::ProgramError,
+        program_stubs::{set_syscall_stubs, SyscallStubs}, pubkey::Pubkey, rent::Rent, system_program,
+        sysvar::instructions::{self as sysvar_instructions, BorrowedInstruction},
+    };
+
+    #[test]
+    fn test_normal_pricing() {
+        // oracle=100_000_000, base_spread=15, solver_fee=10, max_spread=100
+        // total_spread = min(15 + 10, 100) = 25
+        // exec_price = 100_000_000 * (10000 + 25) / 10000 = 100_250_000
+        let oracle_price: u64 = 100_000_000;
+        let base_spread: u32 = 15;
+        let solver_fee: u32 = 10;
+        let max_spread: u32 = 100;
+
+        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
+        assert_eq!(total_spread, 25);
+
+        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
+        assert_eq!(exec_price, 100_250_000);
+    }
+
+    #[test]
+    fn test_spread_capping() {
+        // base=80, solver=50, max=100
+        // total_spread = min(80 + 50, 100) = 100
+        // exec_price = 100_000_000 * 10100 / 10000 = 101_000_000
+        let oracle_price: u64 = 100_000_000;
+        let base_spread: u32 = 80;
+        let solver_fee: u32 = 50;
+        let max_spread: u32 = 100;
+
+        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
+        assert_eq!(total_spread, 100);
+
+        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
+        assert_eq!(exec_price, 101_000_000);
+    }
+
+    #[test]
+    fn test_zero_solver_fee() {
+        // base=15, solver=0, max=100
+        // total_spread = min(15 + 0, 100) = 15
+        // exec_price = 100_000_000 * 10015 / 10000 = 100_150_000
+        let oracle_price: u64 = 100_000_000;
+        let base_spread: u32 = 15;
+        let solver_fee: u32 = 0;
+        let max_spread: u32 = 100;
+
+        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
+        assert_eq!(total_spread, 15);
+
+        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
+        assert_eq!(exec_price, 100_150_000);
+    }
+
+    #[test]
+    fn test_large_price_btc() {
+        // oracle=70_000_000_000, base=15, solver=10, max=100
+        // total_spread = 25
+        // exec_price = 70_000_000_000 * 10025 / 10000 = 70_175_000_000
+        let oracle_price: u64 = 70_000_000_000;
+        let base_spread: u32 = 15;
+        let solver_fee: u32 = 10;
+        let max_spread: u32 = 100;
+
+        let total_spread = std::cmp::min(base_spread.saturating_add(solver_fee), max_spread);
+        assert_eq!(total_spread, 25);
+
+        let exec_price = compute_exec_price(oracle_price, total_spread as u64).unwrap();
+        assert_eq!(exec_price, 70_175_000_000);
+    }
+
+    #[test]
+    fn test_verify_magic() {
+        // Create a 320-byte buffer with the correct magic at offset 64
+        let mut data = vec![0u8; CTX_SIZE];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 8]
+            .copy_from_slice(&PRIVACY_MATCHER_MAGIC.to_le_bytes());
+
+        assert!(verify_magic(&data));
+    }
+
+    #[test]
+    fn test_verify_magic_wrong_value() {
+        let mut data = vec![0u8; CTX_SIZE];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 8]
+            .copy_from_slice(&0xDEAD_BEEF_u64.to_le_bytes());
+
+        assert!(!verify_magic(&data));
+    }
+
+    #[test]
+    fn test_verify_magic_empty_buffer() {
+        let data = vec![0u8; CTX_SIZE];
+        assert!(!verify_magic(&data));
+    }
+
+    #[test]
+    fn test_read_solver_pubkey() {
+        // Create a buffer with a known pubkey at SOLVER_PUBKEY_OFFSET (112)
+        let mut data = vec![0u8; CTX_SIZE];
+        let expected_pubkey = Pubkey::new_unique();
+        data[SOLVER_PUBKEY_OFFSET..SOLVER_PUBKEY_OFFSET + 32]
+            .copy_from_slice(&expected_pubkey.to_bytes());
+
+        let read_pubkey = read_solver_pubkey(&data).unwrap();
+        assert_eq!(read_pubkey, expected_pubkey);
+    }
+
+    #[test]
+    fn test_read_solver_pubkey_zeroed() {
+        let data = vec![0u8; CTX_SIZE];
+        let read_pubkey = read_solver_pubkey(&data).unwrap();
+        assert_eq!(read_pubkey, Pubkey::default());
+    }
+
+    fn slippage_ctx(window_slots: u32, max_cum_bps: u32) -> Vec<u8> {
+        let mut data = vec![0u8; CTX_SIZE];
+        data[SLIPPAGE_WINDOW_SLOTS_OFFSET..SLIPPAGE_WINDOW_SLOTS_OFFSET + 4]
+            .copy_from_slice(&window_slots.to_le_bytes());
+        data[MAX_CUM_SLIPPAGE_BPS_OFFSET..MAX_CUM_SLIPPAGE_BPS_OFFSET + 4]
+            .copy_from_slice(&max_cum_bps.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_slippage_window_rolls() {
+        // 25 bps per mark-out, but the window rolls every 100 slots so cum never exceeds 50
+        let data = slippage_ctx(100, 60);
+        let mut mark_out = vec![0u8; MARK_OUT_SIZE];
+        for i in 0..10u64 {
+            let slot = 1_000 + i * 60;
+            assert!(!accumulate_slippage(&data, &mut mark_out, 25, slot).unwrap());
+        }
+        // A third mark-out inside the window opened at 1_480 trips it
+        assert!(accumulate_slippage(&data, &mut mark_out, 25, 1_560).unwrap());
+    }
+
+    #[test]
+    fn test_slippage_guard_disabled() {
+        let data = slippage_ctx(0, 0);
+        let mut mark_out = vec![0u8; MARK_OUT_SIZE];
+        assert!(!accumulate_slippage(&data, &mut mark_out, 10_000, 500).unwrap());
+        assert!(!is_paused(&data));
+        assert!(!mark_outs_enabled(&data));
+        assert!(mark_outs_enabled(&slippage_ctx(100, 60)));
+    }
+
+    #[test]
+    fn test_solver_fee_accrual_and_claim() {
+        // oracle=100_000_000, solver_fee=10 bps -> 100_000 per unit (e6)
+        // fills of 1_000_000, 2_000_000, 500_000 -> 100_000 + 200_000 + 50_000
+        let mut data = vec![0u8; CTX_SIZE];
+        for size in [1_000_000u64, 2_000_000, 500_000] {
+            let fee = compute_solver_fee(100_000_000, 10, size).unwrap();
+            accrue_solver_fee(&mut data, fee).unwrap();
+        }
+        assert_eq!(read_accrued_fee(&data).unwrap(), 350_000);
+
+        assert_eq!(claim_accrued_fee(&mut data).unwrap(), 350_000);
+        assert_eq!(read_accrued_fee(&data).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_accrued_solver_fee_keeps_its_u128_width() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[ACCRUED_SOLVER_FEE_OFFSET..ACCRUED_SOLVER_FEE_OFFSET + 16].copy_from_slice(&(u64::MAX as u128).to_le_bytes());
+
+        // A fill's cut (10 bps on 1_000_000 at 100_000_000) carries past u64::MAX
+        run_lp_ix(process_match, &mut ctx, &sized_trade(1_000_000, DIRECTION_BUY)).unwrap();
+        let expected = u64::MAX as u128 + 100_000;
+        assert_eq!(read_accrued_fee(&ctx), Ok(expected));
+        assert_eq!(PrivacyContextView::from_bytes(&ctx).unwrap().accrued_solver_fee(), expected);
+
+        // The solver's claim clears all 16 bytes, not just the low 8
+        run_solver_ix(process_claim_fee, &mut ctx, &[0x05]).unwrap();
+        assert_eq!(ctx[ACCRUED_SOLVER_FEE_OFFSET..ACCRUED_SOLVER_FEE_OFFSET + 16], [0u8; 16]);
+
+        // And saturates instead of wrapping at the top of the u128
+        ctx[ACCRUED_SOLVER_FEE_OFFSET..ACCRUED_SOLVER_FEE_OFFSET + 16].copy_from_slice(&u128::MAX.to_le_bytes());
+        run_lp_ix(process_match, &mut ctx, &sized_trade(1_000_000, DIRECTION_BUY)).unwrap();
+        assert_eq!(read_accrued_fee(&ctx), Ok(u128::MAX));
+    }
+
+    #[test]
+    fn test_solver_fee_zero_bps() {
+        assert_eq!(compute_solver_fee(100_000_000, 0, 1_000_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ema_halfway_smoothing() {
+        // alpha=5000: (200_000_000 * 5000 + 100_000_000 * 5000) / 10000 = 150_000_000
+        assert_eq!(smooth_oracle_price(100_000_000, 200_000_000, 5_000).unwrap(), 150_000_000);
+    }
+
+    #[test]
+    fn test_ema_zero_alpha_passthrough() {
+        assert_eq!(smooth_oracle_price(100_000_000, 200_000_000, 0).unwrap(), 200_000_000);
+    }
+
+    #[test]
+    fn test_ema_first_update_takes_raw_price() {
+        assert_eq!(smooth_oracle_price(0, 200_000_000, 5_000).unwrap(), 200_000_000);
+    }
+
+    #[test]
+    fn test_raw_exec_price_matches_e6_for_declared_decimals() {
+        let exec_e6 = 100_250_000u64; // 100.25
+        // 8 decimals (Pyth-style): 100.25 * 1e8
+        assert_eq!(e6_to_decimals(exec_e6, 8).unwrap(), 10_025_000_000);
+        // 6 decimals: identical to e6
+        assert_eq!(e6_to_decimals(exec_e6, 6).unwrap(), exec_e6);
+        // 4 decimals: 100.25 * 1e4
+        assert_eq!(e6_to_decimals(exec_e6, 4).unwrap(), 1_002_500);
+        // Overflow at 18 decimals is surfaced, not wrapped
+        assert!(e6_to_decimals(u64::MAX / 2, 18).is_err());
+    }
+
+    #[test]
+    fn test_raw_exec_price_written_alongside_e6() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        let exec_e6 = 100_250_000u64;
+        ctx[0..8].copy_from_slice(&exec_e6.to_le_bytes());
+
+        // Not configured: second slot untouched
+        ctx[ORACLE_DECIMALS_OFFSET] = ORACLE_DECIMALS_NONE;
+        write_raw_exec_price(&mut ctx, exec_e6).unwrap();
+        assert_eq!(&ctx[RAW_EXEC_PRICE_RETURN_OFFSET..RAW_EXEC_PRICE_RETURN_OFFSET + 8], &[0u8; 8]);
+
+        // A 0-decimals oracle is a real configuration, not "off": whole units
+        ctx[ORACLE_DECIMALS_OFFSET] = 0;
+        write_raw_exec_price(&mut ctx, exec_e6).unwrap();
+        assert_eq!(read_u64_at(&ctx, RAW_EXEC_PRICE_RETURN_OFFSET), 100);
+
+        ctx[ORACLE_DECIMALS_OFFSET] = 8;
+        write_raw_exec_price(&mut ctx, exec_e6).unwrap();
+        let e6 = u64::from_le_bytes(ctx[0..8].try_into().unwrap());
+        let raw = u64::from_le_bytes(
+            ctx[RAW_EXEC_PRICE_RETURN_OFFSET..RAW_EXEC_PRICE_RETURN_OFFSET + 8].try_into().unwrap(),
+        );
+        assert_eq!(raw, e6 * 100);
+    }
+
+    #[test]
+    fn test_oracle_decimals_sentinel_keeps_zero_decimals_expressible() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut data = full_init_data();
+        // Group omitted: no raw-units export
+        assert_eq!(init_over(0, &data[..53])[ORACLE_DECIMALS_OFFSET], ORACLE_DECIMALS_NONE);
+        // 0 is an oracle quoting whole units, stored as given
+        data[55] = 0;
+        assert_eq!(init_over(0, &data)[ORACLE_DECIMALS_OFFSET], 0);
+        data[55] = ORACLE_DECIMALS_NONE;
+        assert_eq!(init_over(0, &data)[ORACLE_DECIMALS_OFFSET], ORACLE_DECIMALS_NONE);
+        data[55] = MAX_ORACLE_DECIMALS + 1;
+        assert_eq!(init_with(process_init, 0, &data), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_init_len_exact_only() {
+        for len in INIT_DATA_LENS {
+            assert!(check_init_len(len).is_ok(), "len {}", len);
+        }
+        // Short, partial optional group, or trailing garbage
+        for len in [44, 46, 54, 57, 59, 60, 63, 83, 91, 93, 95, 97] {
+            assert_eq!(check_init_len(len), Err(ProgramError::InvalidInstructionData), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_revalidate_stamp_is_last_oracle_slot_plus_ttl() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&5_000u64.to_le_bytes());
+        let stamp = |ctx: &[u8]| {
+            u64::from_le_bytes(
+                ctx[REVALIDATE_AFTER_SLOT_RETURN_OFFSET..REVALIDATE_AFTER_SLOT_RETURN_OFFSET + 8]
+                    .try_into()
+                    .unwrap(),
+            )
+        };
+
+        // TTL unset: no stamp
+        write_revalidate_stamp(&mut ctx).unwrap();
+        assert_eq!(stamp(&ctx), 0);
+
+        ctx[REVALIDATE_TTL_SLOTS_OFFSET..REVALIDATE_TTL_SLOTS_OFFSET + 2].copy_from_slice(&150u16.to_le_bytes());
+        write_revalidate_stamp(&mut ctx).unwrap();
+        assert_eq!(stamp(&ctx), 5_150);
+
+        // Saturates rather than wrapping near u64::MAX
+        ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        write_revalidate_stamp(&mut ctx).unwrap();
+        assert_eq!(stamp(&ctx), u64::MAX);
+    }
+
+    /// Init payload with every optional trailing group present
+    fn full_init_data() -> Vec<u8> {
+        let mut data = vec![0x02];
+        data.extend_from_slice(&15u32.to_le_bytes()); // base spread
+        data.extend_from_slice(&100u32.to_le_bytes()); // max spread
+        data.extend_from_slice(&10u32.to_le_bytes()); // solver fee
+        data.extend_from_slice(&[9u8; 32]); // encryption key
+        data.extend_from_slice(&100u32.to_le_bytes()); // slippage window
+        data.extend_from_slice(&50u32.to_le_bytes()); // slippage threshold
+        data.extend_from_slice(&5_000u16.to_le_bytes()); // ema alpha
+        data.push(8); // oracle decimals
+        data.extend_from_slice(&150u16.to_le_bytes()); // revalidate ttl
+        data.extend_from_slice(&40u16.to_le_bytes()); // toxicity spread
+        data.extend_from_slice(&20u16.to_le_bytes()); // toxicity horizon
+        data.extend_from_slice(&1_000_000u64.to_le_bytes()); // min oracle price
+        data.extend_from_slice(&50u16.to_le_bytes()); // resume heartbeat
+        data.extend_from_slice(&20_000_000u64.to_le_bytes()); // epoch volume cap
+        data.extend_from_slice(&0u16.to_le_bytes()); // match cooldown (off: tests match repeatedly at one slot)
+        data.extend_from_slice(&100_000_000u64.to_le_bytes()); // initial oracle price
+        data.extend_from_slice(&0u16.to_le_bytes()); // spread floor (off: base + fee already clears it)
+        data.extend_from_slice(&0u16.to_le_bytes()); // two-sided rebate
+        data.extend_from_slice(&0u16.to_le_bytes()); // config update interval
+        data
+    }
+
+    /// Priced context (oracle 100_000_000, base 15 + fee 10, max 100) with
+    /// toxicity widening of up to 40 bps over a 20-slot horizon
+    fn toxicity_ctx() -> Vec<u8> {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&15u32.to_le_bytes());
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&100u32.to_le_bytes());
+        ctx[SOLVER_FEE_OFFSET..SOLVER_FEE_OFFSET + 4].copy_from_slice(&10u32.to_le_bytes());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+        ctx[TOXICITY_SPREAD_BPS_OFFSET..TOXICITY_SPREAD_BPS_OFFSET + 2].copy_from_slice(&40u16.to_le_bytes());
+        ctx[TOXICITY_HORIZON_SLOTS_OFFSET..TOXICITY_HORIZON_SLOTS_OFFSET + 2].copy_from_slice(&20u16.to_le_bytes());
+        ctx
+    }
+
+    /// Buy at the ask on `slot`, then an oracle print `markout_slots` later
+    fn fill_then_oracle(ctx: &[u8], mark_out: &mut [u8], slot: u64, oracle_price: u64, markout_slots: u64) {
+        record_fill(mark_out, slot, DIRECTION_BUY).unwrap();
+        score_pending_fills(ctx, mark_out, oracle_price, slot + markout_slots).unwrap();
+    }
+
+    #[test]
+    fn test_toxic_fill_history_widens_quotes() {
+        let ctx = toxicity_ctx();
+        let mut mark_out = vec![0u8; MARK_OUT_SIZE];
+        assert_eq!(quote_exec_price(&ctx, Some(&mark_out), DIRECTION_BUY).unwrap().0, 100_250_000);
+        // Oracle runs through the ask within the horizon, every time, even as it widens
+        for i in 0..4 {
+            fill_then_oracle(&ctx, &mut mark_out, 1_000 + i * 100, 101_000_000, 5);
+        }
+        assert_eq!(toxicity_spread(&ctx, Some(&mark_out)).unwrap(), 40);
+        // 25 + 40 = 65 bps
+        assert_eq!(quote_exec_price(&ctx, Some(&mark_out), DIRECTION_BUY).unwrap().0, 100_650_000);
+        // No mark-out state, no history to widen on
+        assert_eq!(quote_exec_price(&ctx, None, DIRECTION_BUY).unwrap().0, 100_250_000);
+
+        // Half the recent fills toxic -> half the widening
+        for i in 4..8 {
+            fill_then_oracle(&ctx, &mut mark_out, 1_000 + i * 100, 100_000_000, 5);
+        }
+        assert_eq!(toxicity_spread(&ctx, Some(&mark_out)).unwrap(), 20);
+    }
+
+    #[test]
+    fn test_benign_fill_history_keeps_quotes_tight() {
+        let ctx = toxicity_ctx();
+        let mut mark_out = vec![0u8; MARK_OUT_SIZE];
+        for i in 0..4 {
+            // Oracle stays under the ask
+            fill_then_oracle(&ctx, &mut mark_out, 1_000 + i * 100, 100_100_000, 5);
+        }
+        // Adverse move, but only after the horizon closed
+        fill_then_oracle(&ctx, &mut mark_out, 2_000, 101_000_000, 21);
+        assert_eq!(mark_out[SCORED_FILLS_OFFSET], 5);
+        assert_eq!(toxicity_spread(&ctx, Some(&mark_out)).unwrap(), 0);
+        assert_eq!(quote_exec_price(&ctx, Some(&mark_out), DIRECTION_BUY).unwrap().0, 100_250_000);
+    }
+
+    #[test]
+    fn test_sell_fill_toxic_when_oracle_drops_through_it() {
+        let ctx = toxicity_ctx();
+        let mut mark_out = vec![0u8; MARK_OUT_SIZE];
+        assert_eq!(quote_exec_price(&ctx, Some(&mark_out), DIRECTION_SELL).unwrap().0, 99_750_000);
+        // LP bought at the bid; a higher print is benign, a lower one toxic
+        for (slot, oracle) in [(1_000, 99_900_000), (1_100, 99_600_000)] {
+            record_fill(&mut mark_out, slot, DIRECTION_SELL).unwrap();
+            score_pending_fills(&ctx, &mut mark_out, oracle, slot + 5).unwrap();
+        }
+        assert_eq!(mark_out[SCORED_FILLS_OFFSET], 2);
+        assert_eq!(toxicity_spread(&ctx, Some(&mark_out)).unwrap(), 20);
+    }
+
+    #[test]
+    fn test_fills_between_prints_all_scored() {
+        let ctx = toxicity_ctx();
+        let mut mark_out = vec![0u8; MARK_OUT_SIZE];
+        // Two buys in one slot share an entry; a sell in a later slot takes the next
+        record_fill(&mut mark_out, 1_000, DIRECTION_BUY).unwrap();
+        record_fill(&mut mark_out, 1_000, DIRECTION_BUY).unwrap();
+        record_fill(&mut mark_out, 1_015, DIRECTION_SELL).unwrap();
+        assert_eq!(mark_out[PENDING_FILL_COUNT_OFFSET], 2);
+        assert_eq!(mark_out[SCORED_FILLS_OFFSET], 0);
+
+        // One print through the ask scores all three: the buys toxic, the sell benign
+        score_pending_fills(&ctx, &mut mark_out, 101_000_000, 1_020).unwrap();
+        assert_eq!(mark_out[SCORED_FILLS_OFFSET], 3);
+        assert_eq!(mark_out[TOXIC_FILL_BITS_OFFSET], 0b110);
+        assert_eq!(mark_out[PENDING_FILL_COUNT_OFFSET], 0);
+
+        // The horizon runs per entry: the buy at 1_100 is past it when the
+        // print lands at 1_125, the one at 1_110 isn't
+        record_fill(&mut mark_out, 1_100, DIRECTION_BUY).unwrap();
+        record_fill(&mut mark_out, 1_110, DIRECTION_BUY).unwrap();
+        score_pending_fills(&ctx, &mut mark_out, 102_000_000, 1_125).unwrap();
+        assert_eq!(mark_out[SCORED_FILLS_OFFSET], 5);
+        assert_eq!(mark_out[TOXIC_FILL_BITS_OFFSET] & 0b11, 0b01);
+
+        // Fills in more slots than there are entries fold into the newest one
+        for slot in [2_000, 2_001, 2_002, 2_003] {
+            record_fill(&mut mark_out, slot, DIRECTION_SELL).unwrap();
+        }
+        assert_eq!(mark_out[PENDING_FILL_COUNT_OFFSET], PENDING_FILL_ENTRIES);
+        let newest = PENDING_FILLS_OFFSET + (PENDING_FILL_ENTRIES as usize - 1) * PENDING_FILL_SIZE;
+        assert_eq!(mark_out[newest + 5], 2);
+        score_pending_fills(&ctx, &mut mark_out, 100_000_000, 2_010).unwrap();
+        assert_eq!(mark_out[SCORED_FILLS_OFFSET], 9);
+        // Scoring empties the queue and leaves the magic and bump alone
+        assert!(mark_out[PENDING_FILLS_OFFSET..].iter().all(|&b| b == 0));
+
+        // Toxicity and slippage off: Match leaves the mark-out PDA unread
+        assert!(mark_outs_enabled(&ctx));
+        assert!(!mark_outs_enabled(&vec![0u8; CTX_SIZE]));
+    }
+
+    /// Ed25519 precompile instruction in the layout the client SDKs emit:
+    /// offsets record, then pubkey, signature, message
+    fn ed25519_verify_ix(pubkey: &[u8; 32], message: &[u8]) -> Instruction {
+        let (pubkey_offset, signature_offset, message_offset) = (16u16, 48u16, 112u16);
+        let mut data = vec![1u8, 0];
+        for field in [
+            signature_offset, u16::MAX, pubkey_offset, u16::MAX,
+            message_offset, message.len() as u16, u16::MAX,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(pubkey);
+        data.extend_from_slice(&[0xAB; 64]);
+        data.extend_from_slice(message);
+        Instruction { program_id: ed25519_program::ID, accounts: vec![], data }
+    }
+
+    #[test]
+    fn test_price_signature_from_oracle_signer_accepted() {
+        let key = [9u8; 32];
+        let price = 100_000_000u64;
+        let ix = ed25519_verify_ix(&key, &price_update_message(price, 990));
+        assert!(verify_price_signature(&ix, &key, price, 990).is_ok());
+    }
+
+    #[test]
+    fn test_price_signature_rejections() {
+        let unauthorized = Err(ProgramError::Custom(PrivacyMatcherError::UnauthorizedSolver as u32));
+        let key = [9u8; 32];
+        let price = 100_000_000u64;
+        let message = price_update_message(price, 990);
+
+        // Signed by some other key
+        let ix = ed25519_verify_ix(&[8u8; 32], &message);
+        assert_eq!(verify_price_signature(&ix, &key, price, 990), unauthorized);
+
+        // Right key, different price
+        let ix = ed25519_verify_ix(&key, &price_update_message(price + 1, 990));
+        assert_eq!(verify_price_signature(&ix, &key, price, 990), unauthorized);
+
+        // Right key and price, re-stamped with a newer slot than was signed
+        let ix = ed25519_verify_ix(&key, &message);
+        assert_eq!(verify_price_signature(&ix, &key, price, 991), unauthorized);
+
+        // The price alone, without its slot
+        let ix = ed25519_verify_ix(&key, &price.to_le_bytes());
+        assert_eq!(verify_price_signature(&ix, &key, price, 990), unauthorized);
+
+        // Pubkey borrowed from another instruction
+        let mut ix = ed25519_verify_ix(&key, &message);
+        ix.data[8..10].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(verify_price_signature(&ix, &key, price, 990), unauthorized);
+
+        // Not the Ed25519 precompile
+        let mut ix = ed25519_verify_ix(&key, &message);
+        ix.program_id = Pubkey::new_unique();
+        assert_eq!(verify_price_signature(&ix, &key, price, 990), unauthorized);
+    }
+
+    /// Oracle signers PDA of the `init_over` context holding `signer`
+    fn oracle_signers_data(signer: &[u8; 32]) -> (Pubkey, Vec<u8>) {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (key, bump) = Pubkey::find_program_address(&[ORACLE_SIGNERS_SEED, ctx_key.as_ref()], &program_id);
+        let mut data = vec![0u8; ORACLE_SIGNERS_SIZE];
+        data[SIGNERS_MAGIC_OFFSET..SIGNERS_MAGIC_OFFSET + 8].copy_from_slice(&ORACLE_SIGNERS_MAGIC.to_le_bytes());
+        data[SIGNERS_BUMP_OFFSET] = bump;
+        data[ORACLE_SIGNER_OFFSET..ORACLE_SIGNER_OFFSET + 32].copy_from_slice(signer);
+        (key, data)
+    }
+
+    /// Run Oracle Update for `(price, update_slot)` behind `verify_ix`, with the
+    /// oracle signers account `signers` owned by `signers_owner` and a fresh
+    /// mark-out PDA
+    fn run_oracle_update(
+        ctx: &mut [u8],
+        signers: (Pubkey, Vec<u8>),
+        signers_owner: Pubkey,
+        verify_ix: &Instruction,
+        price: u64,
+        update_slot: u64,
+    ) -> ProgramResult {
+        run_co_signed_oracle_update(ctx, signers, signers_owner, verify_ix, price, update_slot, &mut mark_out_pda(), None)
+    }
+
+    /// `run_oracle_update` with the mark-out account `mark_out` and a sixth
+    /// account `(key, is_signer)` for the co-signer
+    #[allow(clippy::too_many_arguments)]
+    fn run_co_signed_oracle_update(
+        ctx: &mut [u8],
+        signers: (Pubkey, Vec<u8>),
+        signers_owner: Pubkey,
+        verify_ix: &Instruction,
+        price: u64,
+        update_slot: u64,
+        mark_out: &mut (Pubkey, Vec<u8>),
+        co_signer: Option<(Pubkey, bool)>,
+    ) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (solver_key, ctx_key) = (Pubkey::new_from_array([3u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let mut data = vec![0x03];
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&update_slot.to_le_bytes());
+
+        // The verify instruction at index 0, this update at index 1
+        let ixs = [
+            BorrowedInstruction { program_id: &verify_ix.program_id, accounts: vec![], data: &verify_ix.data },
+            BorrowedInstruction { program_id: &program_id, accounts: vec![], data: &data },
+        ];
+        let mut sysvar_data = sysvar_instructions::construct_instructions_data(&ixs);
+        // The current instruction index is the sysvar's trailing u16
+        let len = sysvar_data.len();
+        sysvar_data[len - 2..].copy_from_slice(&1u16.to_le_bytes());
+
+        let (signers_key, mut signers_data) = signers;
+        let (mut solver_lamports, mut ctx_lamports, mut sysvar_lamports, mut signers_lamports) = (0u64, 0u64, 0u64, 0u64);
+        let mut mark_out_lamports = 0u64;
+        let (mut co_signer_lamports, mut co_signer_data) = (0u64, Vec::new());
+        let mut solver_data = Vec::new();
+        let mut accounts = vec![
+            AccountInfo::new(&solver_key, true, false, &mut solver_lamports, &mut solver_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(
+                &sysvar_instructions::ID, false, false, &mut sysvar_lamports, &mut sysvar_data, &program_id, false, 0,
+            ),
+            AccountInfo::new(
+                &signers_key, false, false, &mut signers_lamports, &mut signers_data, &signers_owner, false, 0,
+            ),
+            AccountInfo::new(&mark_out.0, false, true, &mut mark_out_lamports, &mut mark_out.1, &program_id, false, 0),
+        ];
+        let co_signer_key = co_signer.map(|(key, _)| key).unwrap_or_default();
+        if let Some((_, is_signer)) = co_signer {
+            accounts.push(AccountInfo::new(
+                &co_signer_key, is_signer, false, &mut co_signer_lamports, &mut co_signer_data, &program_id, false, 0,
+            ));
+        }
+        process_oracle_update(&program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_oracle_update_verifies_against_oracle_signer() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let unauthorized = Err(ProgramError::Custom(PrivacyMatcherError::UnauthorizedSolver as u32));
+        // full_init_data stores [9; 32] as the solver encryption key
+        let (signer, encryption_key) = ([6u8; 32], [9u8; 32]);
+        let mut ctx = init_over(0, &full_init_data());
+        let message = price_update_message(101_000_000, 990);
+
+        // Signed by the encryption key: not a price signer, whatever the context holds
+        let ix = ed25519_verify_ix(&encryption_key, &message);
+        let result = run_oracle_update(&mut ctx, oracle_signers_data(&signer), program_id, &ix, 101_000_000, 990);
+        assert_eq!(result, unauthorized);
+
+        // No signer set yet: an empty system account at the PDA
+        let (signers_key, _) = oracle_signers_data(&signer);
+        let ix = ed25519_verify_ix(&signer, &message);
+        let result = run_oracle_update(&mut ctx, (signers_key, Vec::new()), Pubkey::default(), &ix, 101_000_000, 990);
+        assert_eq!(result, Err(ProgramError::UninitializedAccount));
+
+        // The right contents at some other address
+        let (_, signers) = oracle_signers_data(&signer);
+        let result = run_oracle_update(&mut ctx, (Pubkey::new_unique(), signers), program_id, &ix, 101_000_000, 990);
+        assert_eq!(result, Err(ProgramError::InvalidSeeds));
+
+        // Signed by the stored oracle signer
+        run_oracle_update(&mut ctx, oracle_signers_data(&signer), program_id, &ix, 101_000_000, 990).unwrap();
+        assert_eq!(read_u64_at(&ctx, LAST_ORACLE_SLOT_OFFSET), 990);
+        // EMA at 50% from the 100_000_000 seed
+        assert_eq!(read_u64_at(&ctx, ORACLE_PRICE_OFFSET), 100_500_000);
+    }
+
+    #[test]
+    fn test_oracle_update_scores_every_match_since_the_last() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let signer = [6u8; 32];
+        let mut ctx = init_over(0, &full_init_data());
+
+        let mut mark_out = mark_out_pda();
+
+        // Two buys at the 100_250_000 ask before the solver's next print
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &[0x00]).unwrap();
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &[0x00]).unwrap();
+        assert_eq!(mark_out.1[PENDING_FILLS_OFFSET + 4], 2);
+
+        let ix = ed25519_verify_ix(&signer, &price_update_message(101_000_000, 990));
+        let signers = oracle_signers_data(&signer);
+        run_co_signed_oracle_update(&mut ctx, signers, program_id, &ix, 101_000_000, 990, &mut mark_out, None).unwrap();
+        assert_eq!(mark_out.1[SCORED_FILLS_OFFSET], 2);
+        assert_eq!(toxicity_spread(&ctx, Some(&mark_out.1)).unwrap(), 40);
+        assert_eq!(mark_out.1[PENDING_FILL_COUNT_OFFSET], 0);
+    }
+
+    #[test]
+    fn test_adverse_mark_outs_pause_matching() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let signer = [6u8; 32];
+        // full_init_data: 100-slot window, 50 bps threshold; 25 bps either side of 100_000_000
+        let mut trade = vec![0x00];
+        trade.extend_from_slice(&1_000_000u64.to_le_bytes());
+
+        // Fills priced off the oracle no longer count against the guard by themselves
+        let mut ctx = init_over(0, &full_init_data());
+        let mut mark_out = mark_out_pda();
+        for _ in 0..3 {
+            run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &trade).unwrap();
+        }
+        assert!(!is_paused(&ctx));
+
+        // The next print lands under the ask: no adverse move, matching goes on
+        let ix = ed25519_verify_ix(&signer, &price_update_message(100_100_000, 990));
+        let signers = oracle_signers_data(&signer);
+        run_co_signed_oracle_update(&mut ctx, signers, program_id, &ix, 100_100_000, 990, &mut mark_out, None).unwrap();
+        assert_eq!(read_ctx_u32(&mark_out.1, CUM_SLIPPAGE_BPS_OFFSET).unwrap(), 0);
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &trade).unwrap();
+
+        // Two buys at the 100_250_000 ask marked out at 100_750_000: 49 bps each,
+        // 98 in the window against a 50 bps threshold
+        let mut ctx = init_over(0, &full_init_data());
+        let mut mark_out = mark_out_pda();
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &trade).unwrap();
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &trade).unwrap();
+        let ix = ed25519_verify_ix(&signer, &price_update_message(100_750_000, 990));
+        let signers = oracle_signers_data(&signer);
+        run_co_signed_oracle_update(&mut ctx, signers, program_id, &ix, 100_750_000, 990, &mut mark_out, None).unwrap();
+        assert_eq!(read_ctx_u32(&mark_out.1, CUM_SLIPPAGE_BPS_OFFSET).unwrap(), 98);
+        assert_eq!(
+            run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &trade),
+            Err(ProgramError::Custom(PrivacyMatcherError::MatcherPaused as u32))
+        );
+
+        // Resuming opens a clean window in the mark-out state
+        ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        run_solver_ix_with(process_set_paused, &mut ctx, &mut mark_out, &[0x04, 0]).unwrap();
+        assert_eq!(read_ctx_u32(&mark_out.1, CUM_SLIPPAGE_BPS_OFFSET).unwrap(), 0);
+        assert_eq!(read_ctx_u64(&mark_out.1, SLIPPAGE_WINDOW_START_OFFSET).unwrap(), 0);
+    }
+
+    /// Run Init Oracle Signer from `solver` against an empty PDA, returning the
+    /// PDA's data afterwards
+    fn run_init_oracle_signer(ctx: &mut [u8], solver_key: Pubkey, signers_data: Vec<u8>, data: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (signers_key, _) = oracle_signers_data(&[0u8; 32]);
+        let system_program = system_program::id();
+        let mut signers_data = signers_data;
+        let (mut solver_lamports, mut ctx_lamports, mut signers_lamports, mut system_lamports) =
+            (10_000_000_000u64, 0u64, 0u64, 0u64);
+        let (mut solver_data, mut system_data) = (Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&solver_key, true, true, &mut solver_lamports, &mut solver_data, &system_program, false, 0),
+            AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&signers_key, false, true, &mut signers_lamports, &mut signers_data, &system_program, false, 0),
+            AccountInfo::new(&system_program, false, false, &mut system_lamports, &mut system_data, &system_program, true, 0),
+        ];
+        process_init_oracle_signer(&program_id, &accounts, data)?;
+        let data = accounts[2].try_borrow_data()?.to_vec();
+        Ok(data)
+    }
+
+    #[test]
+    fn test_init_oracle_signer_creates_pda_once() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let solver = Pubkey::new_from_array([3u8; 32]);
+        let mut ctx = init_over(0, &full_init_data());
+        let mut data = vec![0x07];
+        data.extend_from_slice(&[6u8; 32]);
+
+        // Only the stored solver, and only with a real key
+        let result = run_init_oracle_signer(&mut ctx, Pubkey::new_unique(), Vec::new(), &data);
+        assert_eq!(result, Err(ProgramError::Custom(PrivacyMatcherError::UnauthorizedSolver as u32)));
+        let mut zero_key = data.clone();
+        zero_key[1..].fill(0);
+        let result = run_init_oracle_signer(&mut ctx, solver, Vec::new(), &zero_key);
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+
+        let (_, expected) = oracle_signers_data(&[6u8; 32]);
+        let created = run_init_oracle_signer(&mut ctx, solver, Vec::new(), &data).unwrap();
+        assert_eq!(created, expected);
+        assert_eq!(read_oracle_signer(&created), Ok([6u8; 32]));
+
+        // A second call can't replace the signer
+        let result = run_init_oracle_signer(&mut ctx, solver, created, &data);
+        assert_eq!(result, Err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    /// Run Rotate Oracle Signer from `solver` against the oracle signers account
+    /// `signers`, with `(co_signer, is_signer)` at [3] when given, returning the
+    /// PDA's data afterwards
+    fn run_rotate_oracle_signer(
+        ctx: &mut [u8],
+        solver_key: Pubkey,
+        signers: (Pubkey, Vec<u8>),
+        co_signer: Option<(Pubkey, bool)>,
+        data: &[u8],
+    ) -> Result<Vec<u8>, ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (signers_key, mut signers_data) = signers;
+        let co_signer_key = co_signer.map(|(key, _)| key).unwrap_or_default();
+        let (mut solver_lamports, mut ctx_lamports, mut signers_lamports, mut co_signer_lamports) = (0u64, 0u64, 0u64, 0u64);
+        let (mut solver_data, mut co_signer_data) = (Vec::new(), Vec::new());
+        let mut accounts = vec![
+            AccountInfo::new(&solver_key, true, false, &mut solver_lamports, &mut solver_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&signers_key, false, true, &mut signers_lamports, &mut signers_data, &program_id, false, 0),
+        ];
+        if let Some((_, is_signer)) = co_signer {
+            accounts.push(AccountInfo::new(
+                &co_signer_key, is_signer, false, &mut co_signer_lamports, &mut co_signer_data, &program_id, false, 0,
+            ));
+        }
+        process_rotate_oracle_signer(&program_id, &accounts, data)?;
+        let data = accounts[2].try_borrow_data()?.to_vec();
+        Ok(data)
+    }
+
+    #[test]
+    fn test_rotate_oracle_signer() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let solver = Pubkey::new_from_array([3u8; 32]);
+        let mut ctx = init_over(0, &full_init_data());
+        let (signers_key, signers) = oracle_signers_data(&[6u8; 32]);
+        let mut data = vec![0x0A];
+        data.extend_from_slice(&[8u8; 32]);
+
+        // Only the stored solver, only an initialized PDA, and only with a real key
+        let result = run_rotate_oracle_signer(&mut ctx, Pubkey::new_unique(), (signers_key, signers.clone()), None, &data);
+        assert_eq!(result, Err(ProgramError::Custom(PrivacyMatcherError::UnauthorizedSolver as u32)));
+        let result = run_rotate_oracle_signer(&mut ctx, solver, (signers_key, Vec::new()), None, &data);
+        assert_eq!(result, Err(ProgramError::UninitializedAccount));
+        let result = run_rotate_oracle_signer(&mut ctx, solver, (Pubkey::new_unique(), signers.clone()), None, &data);
+        assert_eq!(result, Err(ProgramError::InvalidSeeds));
+        let mut zero_key = data.clone();
+        zero_key[1..].fill(0);
+        let result = run_rotate_oracle_signer(&mut ctx, solver, (signers_key, signers.clone()), None, &zero_key);
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+
+        // The new key signs oracle prices from then on
+        let rotated = run_rotate_oracle_signer(&mut ctx, solver, (signers_key, signers), None, &data).unwrap();
+        assert_eq!(rotated, oracle_signers_data(&[8u8; 32]).1);
+        let ix = ed25519_verify_ix(&[8u8; 32], &price_update_message(101_000_000, 990));
+        run_oracle_update(&mut ctx, (signers_key, rotated.clone()), program_id, &ix, 101_000_000, 990).unwrap();
+        assert_eq!(read_u64_at(&ctx, LAST_ORACLE_SLOT_OFFSET), 990);
+
+        // Turning co-signing on needs only the solver
+        let co_signer = Pubkey::new_from_array([5u8; 32]);
+        let mut co_signed = data.clone();
+        co_signed.push(1);
+        co_signed.extend_from_slice(co_signer.as_ref());
+        let rotated = run_rotate_oracle_signer(&mut ctx, solver, (signers_key, rotated), None, &co_signed).unwrap();
+        assert_eq!(read_co_signer(&rotated), Ok(Some(co_signer)));
+
+        // Once on, the solver alone can't rotate: the current co-signer must sign too
+        let mut replaced = vec![0x0A];
+        replaced.extend_from_slice(&[9u8; 32]);
+        let new_co_signer = Pubkey::new_from_array([4u8; 32]);
+        replaced.push(1);
+        replaced.extend_from_slice(new_co_signer.as_ref());
+        for co in [None, Some((co_signer, false)), Some((new_co_signer, true))] {
+            let result = run_rotate_oracle_signer(&mut ctx, solver, (signers_key, rotated.clone()), co, &replaced);
+            assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+        }
+        let rotated =
+            run_rotate_oracle_signer(&mut ctx, solver, (signers_key, rotated), Some((co_signer, true)), &replaced).unwrap();
+        assert_eq!(read_oracle_signer(&rotated), Ok([9u8; 32]));
+        assert_eq!(read_co_signer(&rotated), Ok(Some(new_co_signer)));
+
+        // And with it, co-signing can be turned off again, clearing the key
+        let rotated =
+            run_rotate_oracle_signer(&mut ctx, solver, (signers_key, rotated), Some((new_co_signer, true)), &data).unwrap();
+        assert_eq!(rotated, oracle_signers_data(&[8u8; 32]).1);
+    }
+
+    /// Counterparty flow PDA of the `init_over` context for `counterparty`,
+    /// holding `(buys, sells)`
+    fn flow_data(counterparty: &Pubkey, buys: u64, sells: u64) -> (Pubkey, Vec<u8>) {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (key, bump) = Pubkey::find_program_address(
+            &[COUNTERPARTY_FLOW_SEED, ctx_key.as_ref(), counterparty.as_ref()],
+            &program_id,
+        );
+        let mut data = vec![0u8; COUNTERPARTY_FLOW_SIZE];
+        data[FLOW_MAGIC_OFFSET..FLOW_MAGIC_OFFSET + 8].copy_from_slice(&COUNTERPARTY_FLOW_MAGIC.to_le_bytes());
+        data[FLOW_BUMP_OFFSET] = bump;
+        data[FLOW_COUNTERPARTY_OFFSET..FLOW_COUNTERPARTY_OFFSET + 32].copy_from_slice(counterparty.as_ref());
+        data[FLOW_BUY_VOLUME_OFFSET..FLOW_BUY_VOLUME_OFFSET + 8].copy_from_slice(&buys.to_le_bytes());
+        data[FLOW_SELL_VOLUME_OFFSET..FLOW_SELL_VOLUME_OFFSET + 8].copy_from_slice(&sells.to_le_bytes());
+        (key, data)
+    }
+
+    /// Run Match with the counterparty flow account `flow`, signed by the
+    /// counterparty it belongs to, returning the flow data afterwards
+    fn run_match_with_flow(ctx: &mut [u8], flow: (Pubkey, Vec<u8>), data: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        let counterparty = Pubkey::try_from(&flow.1[FLOW_COUNTERPARTY_OFFSET..FLOW_COUNTERPARTY_OFFSET + 32]).unwrap();
+        run_match_with_flow_as(ctx, flow, Some((counterparty, true)), data)
+    }
+
+    /// Run Match with `flow` at [3] and, when given, `(counterparty, is_signer)`
+    /// at [4], returning the flow data afterwards
+    fn run_match_with_flow_as(
+        ctx: &mut [u8],
+        flow: (Pubkey, Vec<u8>),
+        counterparty: Option<(Pubkey, bool)>,
+        data: &[u8],
+    ) -> Result<Vec<u8>, ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let (flow_key, mut flow_data) = flow;
+        let (mark_out_key, mut mark_out_data) = mark_out_pda();
+        let (counterparty_key, counterparty_signs) = counterparty.unwrap_or_default();
+        let (mut lp_lamports, mut ctx_lamports, mut mark_out_lamports, mut flow_lamports, mut counterparty_lamports) =
+            (0u64, 0u64, 0u64, 0u64, 0u64);
+        let (mut lp_data, mut counterparty_data) = (Vec::new(), Vec::new());
+        let system_program = Pubkey::default();
+        let mut accounts = vec![
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&mark_out_key, false, true, &mut mark_out_lamports, &mut mark_out_data, &program_id, false, 0),
+            AccountInfo::new(&flow_key, false, true, &mut flow_lamports, &mut flow_data, &program_id, false, 0),
+        ];
+        if counterparty.is_some() {
+            accounts.push(AccountInfo::new(
+                &counterparty_key,
+                counterparty_signs,
+                false,
+                &mut counterparty_lamports,
+                &mut counterparty_data,
+                &system_program,
+                false,
+                0,
+            ));
+        }
+        process_match(&program_id, &accounts, data)?;
+        let flow_data = accounts[3].try_borrow_data()?.to_vec();
+        Ok(flow_data)
+    }
+
+    /// Match data for `size` on `direction`
+    fn sized_trade(size: u64, direction: u8) -> Vec<u8> {
+        let mut data = vec![0x00];
+        data.extend_from_slice(&size.to_le_bytes());
+        data.push(direction);
+        data
+    }
+
+    #[test]
+    fn test_two_sided_flow_earns_the_rebate() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let counterparty = Pubkey::new_unique();
+        // 50% rebate; 25 bps on 1_000_000 at 100_000_000 is a 250_000 fee
+        let mut data = full_init_data();
+        data[92..94].copy_from_slice(&5_000u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        assert_eq!(PrivacyContextView::from_bytes(&ctx).unwrap().two_sided_rebate_bps(), 5_000);
+
+        // The first buy has nothing to balance it
+        let flow = run_match_with_flow(&mut ctx, flow_data(&counterparty, 0, 0), &sized_trade(1_000_000, DIRECTION_BUY)).unwrap();
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 250_000);
+
+        // A matching sell balances the flow: the full 50% comes off
+        let (flow_key, _) = flow_data(&counterparty, 0, 0);
+        let flow = run_match_with_flow(&mut ctx, (flow_key, flow), &sized_trade(1_000_000, DIRECTION_SELL)).unwrap();
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 125_000);
+        assert_eq!(read_u64_at(&flow, FLOW_BUY_VOLUME_OFFSET), 1_000_000);
+        assert_eq!(read_u64_at(&flow, FLOW_SELL_VOLUME_OFFSET), 1_000_000);
+
+        // Tipped to 1M bought against 3M sold: a third of it on a 500_000 fee
+        run_match_with_flow(&mut ctx, (flow_key, flow), &sized_trade(2_000_000, DIRECTION_SELL)).unwrap();
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 416_667);
+
+        // The solver's cut is never rebated: 10 bps on all 4M
+        assert_eq!(read_accrued_fee(&ctx), Ok(400_000));
+
+        // Another counterparty's balances can't be passed off under a new address
+        let (_, flow) = flow_data(&counterparty, 1_000_000, 1_000_000);
+        let result = run_match_with_flow(&mut ctx, (Pubkey::new_unique(), flow), &sized_trade(1_000_000, DIRECTION_BUY));
+        assert_eq!(result, Err(ProgramError::InvalidSeeds));
+    }
+
+    #[test]
+    fn test_one_sided_flow_earns_no_rebate() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let counterparty = Pubkey::new_unique();
+        let mut data = full_init_data();
+        data[92..94].copy_from_slice(&5_000u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+
+        let (flow_key, mut flow) = flow_data(&counterparty, 0, 0);
+        for _ in 0..3 {
+            flow = run_match_with_flow(&mut ctx, (flow_key, flow), &sized_trade(1_000_000, DIRECTION_BUY)).unwrap();
+            assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 250_000);
+        }
+        assert_eq!(read_u64_at(&flow, FLOW_BUY_VOLUME_OFFSET), 3_000_000);
+
+        // Balanced flow with the rebate off pays the full fee
+        let mut ctx = init_over(0, &full_init_data());
+        run_match_with_flow(&mut ctx, flow_data(&counterparty, 1_000_000, 0), &sized_trade(1_000_000, DIRECTION_SELL)).unwrap();
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 250_000);
+
+        // A rebate above 100% is rejected at init
+        data[92..94].copy_from_slice(&10_001u16.to_le_bytes());
+        assert_eq!(init_with(process_init, 0, &data), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_flow_only_counts_for_its_signing_counterparty() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let counterparty = Pubkey::new_unique();
+        let mut data = full_init_data();
+        data[92..94].copy_from_slice(&5_000u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        let trade = sized_trade(1_000_000, DIRECTION_SELL);
+
+        // A flow without its counterparty account can't be used
+        let result = run_match_with_flow_as(&mut ctx.clone(), flow_data(&counterparty, 1_000_000, 0), None, &trade);
+        assert_eq!(result, Err(ProgramError::NotEnoughAccountKeys));
+
+        // The counterparty must sign
+        let result =
+            run_match_with_flow_as(&mut ctx.clone(), flow_data(&counterparty, 1_000_000, 0), Some((counterparty, false)), &trade);
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+
+        // Another signer can't borrow someone else's balanced flow
+        let result = run_match_with_flow_as(
+            &mut ctx.clone(),
+            flow_data(&counterparty, 1_000_000, 0),
+            Some((Pubkey::new_unique(), true)),
+            &trade,
+        );
+        assert_eq!(result, Err(ProgramError::Custom(PrivacyMatcherError::CounterpartyMismatch as u32)));
+
+        // Signed by the counterparty it belongs to, the flow earns the full rebate
+        let flow =
+            run_match_with_flow_as(&mut ctx, flow_data(&counterparty, 1_000_000, 0), Some((counterparty, true)), &trade)
+                .unwrap();
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 125_000);
+        assert_eq!(read_u64_at(&flow, FLOW_SELL_VOLUME_OFFSET), 1_000_000);
+    }
+
+    #[test]
+    fn test_total_volume_keeps_its_u128_width() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16].copy_from_slice(&(u64::MAX as u128).to_le_bytes());
+
+        // Volume carries past u64::MAX into the upper bytes instead of wrapping or saturating at 64 bits
+        run_lp_ix(process_match, &mut ctx, &sized_trade(1_000_000, DIRECTION_BUY)).unwrap();
+        let expected = u64::MAX as u128 + 1_000_000;
+        let volume = u128::from_le_bytes(ctx[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16].try_into().unwrap());
+        assert_eq!(volume, expected);
+        assert_eq!(PrivacyContextView::from_bytes(&ctx).unwrap().total_volume(), expected);
+        // Total orders still follows the full 16 bytes
+        assert_eq!(read_u64_at(&ctx, TOTAL_ORDERS_OFFSET), 1);
+    }
+
+    #[test]
+    fn test_init_counterparty_flow_creates_pda_once() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program = system_program::id();
+        let counterparty = Pubkey::new_unique();
+        let mut ctx = init_over(0, &full_init_data());
+        let mut data = vec![0x08];
+        data.extend_from_slice(counterparty.as_ref());
+
+        let run = |ctx: &mut [u8], flow_key: Pubkey, flow_data: Vec<u8>| -> Result<Vec<u8>, ProgramError> {
+            let payer = Pubkey::new_unique();
+            let mut flow_data = flow_data;
+            let (mut payer_lamports, mut ctx_lamports, mut flow_lamports, mut system_lamports) =
+                (10_000_000_000u64, 0u64, 0u64, 0u64);
+            let (mut payer_data, mut system_data) = (Vec::new(), Vec::new());
+            let accounts = [
+                AccountInfo::new(&payer, true, true, &mut payer_lamports, &mut payer_data, &system_program, false, 0),
+                AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, ctx, &program_id, false, 0),
+                AccountInfo::new(&flow_key, false, true, &mut flow_lamports, &mut flow_data, &system_program, false, 0),
+                AccountInfo::new(&system_program, false, false, &mut system_lamports, &mut system_data, &system_program, true, 0),
+            ];
+            process_init_counterparty_flow(&program_id, &accounts, &data)?;
+            let data = accounts[2].try_borrow_data()?.to_vec();
+            Ok(data)
+        };
+
+        let (flow_key, expected) = flow_data(&counterparty, 0, 0);
+        assert_eq!(run(&mut ctx, Pubkey::new_unique(), Vec::new()), Err(ProgramError::InvalidSeeds));
+        let created = run(&mut ctx, flow_key, Vec::new()).unwrap();
+        assert_eq!(created, expected);
+        assert_eq!(run(&mut ctx, flow_key, created), Err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    /// Run Init Mark-Out against `ctx` and the account `(key, data)`, returning
+    /// its data afterwards
+    fn run_init_mark_out(ctx: &mut [u8], mark_out: (Pubkey, Vec<u8>)) -> Result<Vec<u8>, ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program = system_program::id();
+        let payer = Pubkey::new_unique();
+        let (mark_out_key, mut mark_out_data) = mark_out;
+        let (mut payer_lamports, mut ctx_lamports, mut mark_out_lamports, mut system_lamports) =
+            (10_000_000_000u64, 0u64, 0u64, 0u64);
+        let (mut payer_data, mut system_data) = (Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&payer, true, true, &mut payer_lamports, &mut payer_data, &system_program, false, 0),
+            AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(
+                &mark_out_key, false, true, &mut mark_out_lamports, &mut mark_out_data, &system_program, false, 0,
+            ),
+            AccountInfo::new(&system_program, false, false, &mut system_lamports, &mut system_data, &system_program, true, 0),
+        ];
+        process_init_mark_out(&program_id, &accounts, &[0x0C])?;
+        let data = accounts[2].try_borrow_data()?.to_vec();
+        Ok(data)
+    }
+
+    #[test]
+    fn test_init_mark_out_creates_pda_once() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        let (mark_out_key, expected) = mark_out_pda();
+
+        assert_eq!(run_init_mark_out(&mut ctx, (Pubkey::new_unique(), Vec::new())), Err(ProgramError::InvalidSeeds));
+        let created = run_init_mark_out(&mut ctx, (mark_out_key, Vec::new())).unwrap();
+        assert_eq!(created, expected);
+        assert_eq!(run_init_mark_out(&mut ctx, (mark_out_key, created)), Err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    #[test]
+    fn test_match_needs_the_mark_out_pda_while_mark_outs_are_on() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        // LP and context only
+        let run_without = |ctx: &mut [u8]| {
+            let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+            let mut lp_data = Vec::new();
+            let accounts = [
+                AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            ];
+            process_match(&program_id, &accounts, &[0x00])
+        };
+
+        // full_init_data turns both the toxicity spread and the slippage guard on
+        let ctx = init_over(0, &full_init_data());
+        assert_eq!(run_without(&mut ctx.clone()), Err(ProgramError::NotEnoughAccountKeys));
+        let (mark_out_key, mark_out) = mark_out_pda();
+        let result = run_lp_ix_with(process_match, &mut ctx.clone(), &mut (mark_out_key, Vec::new()), &[0x00]);
+        assert_eq!(result, Err(ProgramError::UninitializedAccount));
+        let result = run_lp_ix_with(process_match, &mut ctx.clone(), &mut (Pubkey::new_unique(), mark_out), &[0x00]);
+        assert_eq!(result, Err(ProgramError::InvalidSeeds));
+        assert_eq!(
+            run_lp_ix_with(process_quote_match, &mut ctx.clone(), &mut (mark_out_key, Vec::new()), &[0x0B]),
+            Err(ProgramError::UninitializedAccount)
+        );
+
+        // Both off: the context alone prices and records the fill
+        let mut data = full_init_data();
+        data[45..49].copy_from_slice(&0u32.to_le_bytes());
+        data[58..60].copy_from_slice(&0u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        assert!(!mark_outs_enabled(&ctx));
+        run_without(&mut ctx).unwrap();
+        assert_eq!(read_u64_at(&ctx, TOTAL_ORDERS_OFFSET), 1);
+    }
+
+    #[test]
+    fn test_every_writer_rejects_another_layout_version() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let solver = Pubkey::new_from_array([3u8; 32]);
+        let unsupported = ProgramError::Custom(PrivacyMatcherError::UnsupportedVersion as u32);
+        let mut future = init_over(0, &full_init_data());
+        future[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&(CTX_VERSION + 1).to_le_bytes());
+        let before = future.clone();
+
+        let signer = [6u8; 32];
+        let ix = ed25519_verify_ix(&signer, &price_update_message(101_000_000, 990));
+        let result = run_oracle_update(&mut future, oracle_signers_data(&signer), program_id, &ix, 101_000_000, 990);
+        assert_eq!(result, Err(unsupported.clone()));
+
+        let mut init_signer = vec![0x07];
+        init_signer.extend_from_slice(&signer);
+        assert_eq!(run_init_oracle_signer(&mut future, solver, Vec::new(), &init_signer), Err(unsupported.clone()));
+        init_signer[0] = 0x0A;
+        let result = run_rotate_oracle_signer(&mut future, solver, oracle_signers_data(&signer), None, &init_signer);
+        assert_eq!(result, Err(unsupported.clone()));
+
+        let (mark_out_key, _) = mark_out_pda();
+        assert_eq!(run_init_mark_out(&mut future, (mark_out_key, Vec::new())), Err(unsupported.clone()));
+
+        let counterparty = Pubkey::new_unique();
+        let (flow_key, _) = flow_data(&counterparty, 0, 0);
+        let system_program = system_program::id();
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let mut init_flow = vec![0x08];
+        init_flow.extend_from_slice(counterparty.as_ref());
+        let (mut payer_lamports, mut ctx_lamports, mut flow_lamports, mut system_lamports) =
+            (10_000_000_000u64, 0u64, 0u64, 0u64);
+        let (mut payer_data, mut flow, mut system_data) = (Vec::new(), Vec::new(), Vec::new());
+        let payer = Pubkey::new_unique();
+        let accounts = [
+            AccountInfo::new(&payer, true, true, &mut payer_lamports, &mut payer_data, &system_program, false, 0),
+            AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, &mut future, &program_id, false, 0),
+            AccountInfo::new(&flow_key, false, true, &mut flow_lamports, &mut flow, &system_program, false, 0),
+            AccountInfo::new(&system_program, false, false, &mut system_lamports, &mut system_data, &system_program, true, 0),
+        ];
+        assert_eq!(process_init_counterparty_flow(&program_id, &accounts, &init_flow), Err(unsupported));
+
+        // Update Config, Set Paused and Claim Fee are covered by
+        // test_solver_instructions_check_owner_and_version
+        assert_eq!(future, before);
+    }
+
+    #[test]
+    fn test_oracle_update_co_signer() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let solver = Pubkey::new_from_array([3u8; 32]);
+        let (signer, co_signer) = ([6u8; 32], Pubkey::new_from_array([5u8; 32]));
+        let ix = ed25519_verify_ix(&signer, &price_update_message(101_000_000, 990));
+        let mut data = vec![0x07];
+        data.extend_from_slice(&signer);
+
+        // Required but missing from the payload, or all zeros
+        let mut ctx = init_over(0, &full_init_data());
+        let mut required = data.clone();
+        required.push(1);
+        let result = run_init_oracle_signer(&mut ctx, solver, Vec::new(), &required);
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+        required.extend_from_slice(&[0u8; 32]);
+        let result = run_init_oracle_signer(&mut ctx, solver, Vec::new(), &required);
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+
+        required[34..].copy_from_slice(co_signer.as_ref());
+        let signers = run_init_oracle_signer(&mut ctx, solver, Vec::new(), &required).unwrap();
+        assert_eq!(read_co_signer(&signers), Ok(Some(co_signer)));
+        let (signers_key, _) = oracle_signers_data(&signer);
+
+        // The solver and oracle signer alone, a co-signer account that didn't
+        // sign, or some other signer
+        for co in [None, Some((co_signer, false)), Some((Pubkey::new_unique(), true))] {
+            let result = run_co_signed_oracle_update(
+                &mut ctx, (signers_key, signers.clone()), program_id, &ix, 101_000_000, 990, &mut mark_out_pda(), co,
+            );
+            assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+        }
+        assert_eq!(read_u64_at(&ctx, LAST_ORACLE_SLOT_OFFSET), 0);
+
+        // Both signed
+        run_co_signed_oracle_update(
+            &mut ctx, (signers_key, signers), program_id, &ix, 101_000_000, 990, &mut mark_out_pda(), Some((co_signer, true)),
+        )
+        .unwrap();
+        assert_eq!(read_u64_at(&ctx, LAST_ORACLE_SLOT_OFFSET), 990);
+
+        // Co-signing off, explicitly or by omission: a single signer is enough
+        let mut off = data.clone();
+        off.push(0);
+        for payload in [data, off] {
+            let mut ctx = init_over(0, &full_init_data());
+            let signers = run_init_oracle_signer(&mut ctx, solver, Vec::new(), &payload).unwrap();
+            assert_eq!(read_co_signer(&signers), Ok(None));
+            run_oracle_update(&mut ctx, (signers_key, signers), program_id, &ix, 101_000_000, 990).unwrap();
+        }
+    }
+
+    fn init_over(fill: u8, data: &[u8]) -> Vec<u8> {
+        init_with(process_init, fill, data).unwrap()
+    }
+
+    /// Run an init instruction over a context buffer pre-filled with `fill`
+    fn init_with(
+        ix: fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult,
+        fill: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let solver_key = Pubkey::new_from_array([3u8; 32]);
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let (mut lp_data, mut ctx_data) = (Vec::new(), vec![fill; CTX_SIZE]);
+        let (mut solver_lamports, mut solver_data) = (0u64, Vec::new());
+        {
+            let accounts = [
+                AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut ctx_data, &program_id, false, 0),
+                AccountInfo::new(&solver_key, false, false, &mut solver_lamports, &mut solver_data, &program_id, false, 0),
+            ];
+            ix(&program_id, &accounts, data)?;
+        }
+        Ok(ctx_data)
+    }
+
+    #[test]
+    fn test_init_rejects_zero_encryption_key() {
+        let mut data = full_init_data();
+        data[13..45].fill(0);
+        assert_eq!(init_with(process_init, 0, &data), Err(ProgramError::InvalidInstructionData));
+
+        // A real key is stored as given and read back by the accessor
+        let key = Pubkey::new_unique().to_bytes();
+        data[13..45].copy_from_slice(&key);
+        let ctx = init_over(0, &data);
+        assert_eq!(read_solver_encryption_key(&ctx), Ok(key));
+        assert_eq!(&ctx[SOLVER_ENCRYPTION_KEY_OFFSET..SOLVER_ENCRYPTION_KEY_OFFSET + 32], &key);
+    }
+
+    #[test]
+    fn test_init_keeps_the_v1_field_widths() {
+        // Spreads and slippage config go in at the u32 widths contexts already carry
+        let mut data = full_init_data();
+        data[5..9].copy_from_slice(&70_000u32.to_le_bytes());
+        data[45..49].copy_from_slice(&100_000u32.to_le_bytes());
+        data[49..53].copy_from_slice(&80_000u32.to_le_bytes());
+        let ctx = init_over(0, &data);
+        assert_eq!(read_version(&ctx).unwrap(), 1);
+        assert_eq!(PrivacyContextView::from_bytes(&ctx).unwrap().max_spread(), 70_000);
+        assert_eq!(read_ctx_u32(&ctx, MAX_SPREAD_OFFSET).unwrap(), 70_000);
+        assert_eq!(read_ctx_u32(&ctx, SLIPPAGE_WINDOW_SLOTS_OFFSET).unwrap(), 100_000);
+        assert_eq!(read_ctx_u32(&ctx, MAX_CUM_SLIPPAGE_BPS_OFFSET).unwrap(), 80_000);
+        assert_eq!((ORACLE_PRICE_OFFSET, TOTAL_VOLUME_OFFSET, TOTAL_ORDERS_OFFSET), (156, 172, 188));
+    }
+
+    #[test]
+    fn test_init_overwrites_every_byte() {
+        // Any byte that differs between a zero- and 0xFF-prefilled account is
+        // one the init path never wrote -- stale data leaking into a new market
+        let data = full_init_data();
+        for len in INIT_DATA_LENS {
+            let (zeros, ones) = (init_over(0x00, &data[..len]), init_over(0xFF, &data[..len]));
+            let leaked: Vec<usize> = (0..CTX_SIZE).filter(|&i| zeros[i] != ones[i]).collect();
+            assert!(leaked.is_empty(), "len {}: init left bytes {:?} unwritten", len, leaked);
+        }
+    }
+
+    /// Match reads the clock for the slippage window; serve a fixed one off-chain.
+    /// One stub for every test, as swapping stubs mid-test would race the others.
+    struct FixedClock;
+
+    impl SyscallStubs for FixedClock {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Clock) = Clock { slot: 1_000, ..Clock::default() } };
+            0
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+
+        /// Stands in for the System Program's CreateAccount: moves the
+        /// lamports and sizes the new account
+        fn sol_invoke_signed(&self, ix: &Instruction, infos: &[AccountInfo], _seeds: &[&[&[u8]]]) -> ProgramResult {
+            let info = |n: usize| infos.iter().find(|a| *a.key == ix.accounts[n].pubkey).unwrap();
+            assert_eq!(ix.data[..4], 0u32.to_le_bytes(), "only CreateAccount is stubbed");
+            let lamports = u64::from_le_bytes(ix.data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(ix.data[12..20].try_into().unwrap()) as usize;
+            **info(0).try_borrow_mut_lamports()? -= lamports;
+            **info(1).try_borrow_mut_lamports()? += lamports;
+            *info(1).data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+            Ok(())
+        }
+    }
+
+    /// Mark-out PDA of the `init_over` context, as Init Mark-Out leaves it
+    fn mark_out_pda() -> (Pubkey, Vec<u8>) {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (key, bump) = Pubkey::find_program_address(&[MARK_OUT_SEED, ctx_key.as_ref()], &program_id);
+        let mut data = vec![0u8; MARK_OUT_SIZE];
+        data[MARK_OUT_MAGIC_OFFSET..MARK_OUT_MAGIC_OFFSET + 8].copy_from_slice(&MARK_OUT_MAGIC.to_le_bytes());
+        data[MARK_OUT_BUMP_OFFSET] = bump;
+        (key, data)
+    }
+
+    /// Run an LP-signed instruction against `ctx` (LP PDA matches `init_over`)
+    /// with a fresh mark-out PDA
+    fn run_lp_ix(
+        ix: fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult,
+        ctx: &mut [u8],
+        data: &[u8],
+    ) -> ProgramResult {
+        run_lp_ix_with(ix, ctx, &mut mark_out_pda(), data)
+    }
+
+    /// `run_lp_ix` with the mark-out account `(key, data)` at `[2]`
+    fn run_lp_ix_with(
+        ix: fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult,
+        ctx: &mut [u8],
+        mark_out: &mut (Pubkey, Vec<u8>),
+        data: &[u8],
+    ) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut lp_lamports, mut ctx_lamports, mut mark_out_lamports) = (0u64, 0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&mark_out.0, false, true, &mut mark_out_lamports, &mut mark_out.1, &program_id, false, 0),
+        ];
+        ix(&program_id, &accounts, data)
+    }
+
+    /// guard_match with the LP account keyed `lp_key`, signed or not
+    fn guard_with(ctx: &mut [u8], lp_key: Pubkey, signed: bool) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let lp = AccountInfo::new(&lp_key, signed, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0);
+        let ctx_account = AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0);
+        guard_match(&program_id, &lp, &ctx_account)
+    }
+
+    #[test]
+    fn test_guard_match_failure_modes() {
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx = init_over(0, &full_init_data());
+        assert!(guard_with(&mut ctx.clone(), lp_key, true).is_ok());
+
+        assert_eq!(guard_with(&mut ctx.clone(), lp_key, false), Err(ProgramError::MissingRequiredSignature));
+        let mut bad_magic = ctx.clone();
+        bad_magic[MAGIC_OFFSET] ^= 0xFF;
+        assert_eq!(guard_with(&mut bad_magic, lp_key, true), Err(ProgramError::UninitializedAccount));
+        assert_eq!(
+            guard_with(&mut ctx.clone(), Pubkey::new_from_array([9u8; 32]), true),
+            Err(ProgramError::InvalidAccountData)
+        );
+        let mut future = ctx.clone();
+        future[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&(CTX_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            guard_with(&mut future, lp_key, true),
+            Err(ProgramError::Custom(PrivacyMatcherError::UnsupportedVersion as u32))
+        );
+        let mut paused = ctx.clone();
+        paused[PAUSED_OFFSET] = 1;
+        assert_eq!(
+            guard_with(&mut paused, lp_key, true),
+            Err(ProgramError::Custom(PrivacyMatcherError::MatcherPaused as u32))
+        );
+    }
+
+    #[test]
+    fn test_quote_leaves_stats_untouched_match_updates_them() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+        let mut trade = vec![0x00];
+        trade.extend_from_slice(&5_000_000u64.to_le_bytes());
+
+        let before = ctx.clone();
+        run_lp_ix(process_quote_match, &mut ctx, &[0x0B]).unwrap();
+        // Only the return buffer moved: exec price = 100_000_000 * 10_025 / 10_000
+        assert_eq!(u64::from_le_bytes(ctx[0..8].try_into().unwrap()), 100_250_000);
+        assert_eq!(ctx[64..], before[64..]);
+
+        run_lp_ix(process_match, &mut ctx, &trade).unwrap();
+        assert_eq!(u64::from_le_bytes(ctx[0..8].try_into().unwrap()), 100_250_000);
+        assert_eq!(read_u64_at(&ctx, TOTAL_ORDERS_OFFSET), 1);
+        assert_eq!(read_u64_at(&ctx, LAST_EXEC_PRICE_OFFSET), 100_250_000);
+        assert_eq!(read_u64_at(&ctx, TOTAL_VOLUME_OFFSET), 5_000_000);
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 1_250_000);
+
+        // A quote returns the fee on its own size, never the last match's
+        trade[0] = 0x0B;
+        trade[1..9].copy_from_slice(&2_000_000u64.to_le_bytes());
+        run_lp_ix(process_quote_match, &mut ctx, &trade).unwrap();
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 500_000);
+        run_lp_ix(process_quote_match, &mut ctx, &[0x0B]).unwrap();
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 0);
+    }
+
+    #[test]
+    fn test_oracle_update_must_advance_slot() {
+        let stale = Err(ProgramError::Custom(PrivacyMatcherError::StaleUpdate as u32));
+        // Fresh context: any reading up to the current slot is newer than the zeroed stamp
+        assert_eq!(check_oracle_update_slot(0, 1, 1), Ok(()));
+        assert_eq!(check_oracle_update_slot(1_000, 1_001, 1_005), Ok(()));
+        // The stored reading again, or an older one
+        assert_eq!(check_oracle_update_slot(1_000, 1_000, 1_005), stale);
+        assert_eq!(check_oracle_update_slot(1_000, 999, 1_005), stale);
+        // A replay lands in a later slot but still names its own, older slot
+        assert_eq!(check_oracle_update_slot(1_001, 1_001, 2_000), stale);
+        // A reading from a slot that hasn't happened yet
+        assert_eq!(check_oracle_update_slot(1_000, 1_006, 1_005), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_match_stamps_last_exec() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+        assert_eq!(read_last_exec(&ctx).unwrap(), (0, 0));
+
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        // The clock stub's slot, alongside the price the fill executed at
+        assert_eq!(read_last_exec(&ctx).unwrap(), (100_250_000, 1_000));
+        let view = PrivacyContextView::from_bytes(&ctx).unwrap();
+        assert_eq!((view.last_exec_price(), view.last_match_slot()), (100_250_000, 1_000));
+    }
+
+    fn read_u64_at(ctx: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(ctx[offset..offset + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_reentered_match_rejected_and_guard_released() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+
+        // Completed match leaves the guard free
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(ctx[MATCH_GUARD_OFFSET], 0);
+
+        // Nested call while an outer match holds the guard: rejected, stats untouched
+        ctx[MATCH_GUARD_OFFSET] = 1;
+        let before = ctx.clone();
+        assert_eq!(
+            run_lp_ix(process_match, &mut ctx, &[0x00]),
+            Err(ProgramError::Custom(PrivacyMatcherError::MatchReentered as u32))
+        );
+        assert_eq!(ctx, before);
+    }
+
+    #[test]
+    fn test_oracle_price_floor() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // full_init_data sets a 1_000_000 floor
+        let mut ctx = init_over(0, &full_init_data());
+
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&999_999u64.to_le_bytes());
+        assert_eq!(
+            run_lp_ix(process_match, &mut ctx.clone(), &[0x00]),
+            Err(ProgramError::Custom(PrivacyMatcherError::OraclePriceOutOfRange as u32))
+        );
+
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&1_000_000u64.to_le_bytes());
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(read_u64_at(&ctx, TOTAL_ORDERS_OFFSET), 1);
+    }
+
+    type Processor = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+    /// Run a solver-signed instruction against `ctx` (solver matches `init_over`)
+    /// with a fresh mark-out PDA
+    fn run_solver_ix(
+        ix: Processor,
+        ctx: &mut [u8],
+        data: &[u8],
+    ) -> ProgramResult {
+        run_solver_ix_with(ix, ctx, &mut mark_out_pda(), data)
+    }
+
+    /// `run_solver_ix` with the mark-out account `(key, data)` at `[2]`
+    fn run_solver_ix_with(
+        ix: Processor,
+        ctx: &mut [u8],
+        mark_out: &mut (Pubkey, Vec<u8>),
+        data: &[u8],
+    ) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let solver_key = Pubkey::new_from_array([3u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut solver_lamports, mut ctx_lamports, mut mark_out_lamports) = (0u64, 0u64, 0u64);
+        let mut solver_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&solver_key, true, false, &mut solver_lamports, &mut solver_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&mark_out.0, false, true, &mut mark_out_lamports, &mut mark_out.1, &program_id, false, 0),
+        ];
+        ix(&program_id, &accounts, data)
+    }
+
+    #[test]
+    fn test_update_config_waits_out_the_interval() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let too_soon = Err(ProgramError::Custom(PrivacyMatcherError::ConfigUpdateTooSoon as u32));
+        // 20-slot interval; the clock is at slot 1_000
+        let mut data = full_init_data();
+        data[94..96].copy_from_slice(&20u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        let mut update = vec![0x09];
+        for bps in [20u16, 80, 10, 30] {
+            update.extend_from_slice(&bps.to_le_bytes());
+        }
+
+        // The first update is never held back and stamps its slot
+        run_solver_ix(process_update_config, &mut ctx, &update).unwrap();
+        let view = PrivacyContextView::from_bytes(&ctx).unwrap();
+        assert_eq!((view.base_spread(), view.max_spread(), view.solver_fee(), view.min_spread()), (20, 80, 10, 30));
+        assert_eq!(view.last_config_slot(), 1_000);
+
+        // Same slot, and 10 slots after an update: too soon, nothing changes
+        assert_eq!(run_solver_ix(process_update_config, &mut ctx, &update), too_soon);
+        ctx[LAST_CONFIG_SLOT_OFFSET..LAST_CONFIG_SLOT_OFFSET + 4].copy_from_slice(&990u32.to_le_bytes());
+        update[1..3].copy_from_slice(&25u16.to_le_bytes());
+        assert_eq!(run_solver_ix(process_update_config, &mut ctx, &update), too_soon);
+        assert_eq!(PrivacyContextView::from_bytes(&ctx).unwrap().base_spread(), 20);
+
+        // 20 slots apart is enough
+        ctx[LAST_CONFIG_SLOT_OFFSET..LAST_CONFIG_SLOT_OFFSET + 4].copy_from_slice(&980u32.to_le_bytes());
+        run_solver_ix(process_update_config, &mut ctx, &update).unwrap();
+        assert_eq!(PrivacyContextView::from_bytes(&ctx).unwrap().base_spread(), 25);
+
+        // Without an interval back-to-back updates land
+        let mut ctx = init_over(0, &full_init_data());
+        run_solver_ix(process_update_config, &mut ctx, &update).unwrap();
+        run_solver_ix(process_update_config, &mut ctx, &update).unwrap();
+    }
+
+    #[test]
+    fn test_update_config_validation() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        let config = |base: u16, max: u16, min: u16| {
+            let mut update = vec![0x09];
+            for bps in [base, max, 10, min] {
+                update.extend_from_slice(&bps.to_le_bytes());
+            }
+            update
+        };
+
+        // Base or floor above the cap
+        let invalid = Err(ProgramError::Custom(PrivacyMatcherError::InvalidSpreadConfig as u32));
+        assert_eq!(run_solver_ix(process_update_config, &mut ctx, &config(60, 50, 0)), invalid);
+        assert_eq!(run_solver_ix(process_update_config, &mut ctx, &config(10, 50, 60)), invalid);
+
+        // Only the stored solver
+        let mut other = ctx.clone();
+        other[SOLVER_PUBKEY_OFFSET] ^= 1;
+        assert_eq!(
+            run_solver_ix(process_update_config, &mut other, &config(10, 50, 0)),
+            Err(ProgramError::Custom(PrivacyMatcherError::UnauthorizedSolver as u32))
+        );
+
+        // A fill priced at the old spreads waits for its mark-out first
+        let mut mark_out = mark_out_pda();
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &[0x00]).unwrap();
+        assert_eq!(
+            run_solver_ix_with(process_update_config, &mut ctx, &mut mark_out, &config(10, 50, 0)),
+            Err(ProgramError::Custom(PrivacyMatcherError::FillsAwaitingMarkOut as u32))
+        );
+    }
+
+    #[test]
+    fn test_solver_instructions_check_owner_and_version() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let ctx = init_over(0, &full_init_data());
+        let mut update = vec![0x09];
+        for bps in [10u16, 50, 10, 0] {
+            update.extend_from_slice(&bps.to_le_bytes());
+        }
+        let ixs: [(Processor, &[u8]); 3] =
+            [(process_update_config, &update), (process_set_paused, &[0x04, 1]), (process_claim_fee, &[0x05])];
+
+        for (ix, data) in ixs {
+            // A context stamped with another layout is never written
+            let mut future = ctx.clone();
+            future[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&(CTX_VERSION + 1).to_le_bytes());
+            let before = future.clone();
+            assert_eq!(
+                run_solver_ix(ix, &mut future, data),
+                Err(ProgramError::Custom(PrivacyMatcherError::UnsupportedVersion as u32))
+            );
+            assert_eq!(future, before);
+
+            // Nor is a copy owned by another program
+            let program_id = Pubkey::new_from_array([7u8; 32]);
+            let (solver_key, ctx_key) = (Pubkey::new_from_array([3u8; 32]), Pubkey::new_from_array([2u8; 32]));
+            let (mut solver_lamports, mut ctx_lamports) = (0u64, 0u64);
+            let (mut solver_data, mut foreign) = (Vec::new(), ctx.clone());
+            let accounts = [
+                AccountInfo::new(&solver_key, true, false, &mut solver_lamports, &mut solver_data, &program_id, false, 0),
+                AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut foreign, &system_program::ID, false, 0),
+            ];
+            assert_eq!(ix(&program_id, &accounts, data), Err(ProgramError::IncorrectProgramId));
+        }
+    }
+
+    #[test]
+    fn test_resume_requires_fresh_oracle() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // full_init_data requires an oracle update within 50 slots; the clock is at slot 1_000
+        let mut ctx = init_over(0, &full_init_data());
+        run_solver_ix(process_set_paused, &mut ctx, &[0x04, 1]).unwrap();
+
+        let stale = Err(ProgramError::Custom(PrivacyMatcherError::OracleStale as u32));
+        // Never updated
+        assert_eq!(run_solver_ix(process_set_paused, &mut ctx, &[0x04, 0]), stale);
+        ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&949u64.to_le_bytes());
+        assert_eq!(run_solver_ix(process_set_paused, &mut ctx, &[0x04, 0]), stale);
+        assert!(is_paused(&ctx));
+
+        ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&950u64.to_le_bytes());
+        run_solver_ix(process_set_paused, &mut ctx, &[0x04, 0]).unwrap();
+        assert!(!is_paused(&ctx));
+
+        // Pausing never needs a heartbeat
+        ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+        run_solver_ix(process_set_paused, &mut ctx, &[0x04, 1]).unwrap();
+        assert!(is_paused(&ctx));
+    }
+
+    #[test]
+    fn test_epoch_volume_cap_pauses_until_resume() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // full_init_data caps each epoch at 20_000_000 notional
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+        let mut trade = vec![0x00];
+        trade.extend_from_slice(&8_000_000u64.to_le_bytes());
+
+        // 8M + 8M stays under the cap; the third fill crosses it, completes, and pauses
+        run_lp_ix(process_match, &mut ctx, &trade).unwrap();
+        run_lp_ix(process_match, &mut ctx, &trade).unwrap();
+        assert!(!is_paused(&ctx));
+        run_lp_ix(process_match, &mut ctx, &trade).unwrap();
+        assert!(is_paused(&ctx));
+        assert_eq!(read_u64_at(&ctx, EPOCH_VOLUME_OFFSET), 24_000_000);
+        assert_eq!(
+            run_lp_ix(process_match, &mut ctx.clone(), &trade),
+            Err(ProgramError::Custom(PrivacyMatcherError::MatcherPaused as u32))
+        );
+
+        // Resume opens a new epoch; lifetime volume keeps counting
+        ctx[LAST_ORACLE_SLOT_OFFSET..LAST_ORACLE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        run_solver_ix(process_set_paused, &mut ctx, &[0x04, 0]).unwrap();
+        assert_eq!(read_u64_at(&ctx, EPOCH_VOLUME_OFFSET), 0);
+        run_lp_ix(process_match, &mut ctx, &trade).unwrap();
+        assert!(!is_paused(&ctx));
+        assert_eq!(read_u64_at(&ctx, TOTAL_VOLUME_OFFSET), 32_000_000);
+    }
+
+    #[test]
+    fn test_match_returns_spread_fee_on_notional() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+        let mut trade = vec![0x00];
+        trade.extend_from_slice(&5_000_000u64.to_le_bytes());
+
+        run_lp_ix(process_match, &mut ctx, &trade).unwrap();
+        // notional = 100_000_000 * 5_000_000 / 1e6 = 500_000_000; fee = 25 bps of it
+        assert_eq!(read_u64_at(&ctx, 0), 100_250_000);
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 1_250_000);
+
+        // No size, no fee
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 100_250_000);
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 0);
+    }
+
+    #[test]
+    fn test_buy_and_sell_price_either_side_of_oracle() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        let mut mark_out = mark_out_pda();
+        let trade = |direction: u8| {
+            let mut data = vec![0x00];
+            data.extend_from_slice(&5_000_000u64.to_le_bytes());
+            data.push(direction);
+            data
+        };
+
+        // 25 bps either side of the 100_000_000 oracle
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &trade(DIRECTION_BUY)).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 100_250_000);
+        run_lp_ix_with(process_match, &mut ctx, &mut mark_out, &trade(DIRECTION_SELL)).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 99_750_000);
+        // Both queued for their mark-out, one buy and one sell in the clock's slot
+        assert_eq!(mark_out.1[PENDING_FILL_COUNT_OFFSET], 1);
+        assert_eq!(mark_out.1[PENDING_FILLS_OFFSET + 4..PENDING_FILLS_OFFSET + 6], [1, 1]);
+        // Same spread charged on both sides
+        assert_eq!(read_u64_at(&ctx, FEE_RETURN_OFFSET), 1_250_000);
+
+        let mut quote = trade(DIRECTION_SELL);
+        quote[0] = 0x0B;
+        run_lp_ix(process_quote_match, &mut ctx, &quote).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 99_750_000);
+
+        assert_eq!(
+            run_lp_ix(process_match, &mut ctx.clone(), &trade(2)),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    /// The typed form of `full_init_data`
+    fn full_init_params() -> PrivacyInitParams {
+        PrivacyInitParams {
+            base_spread_bps: 15,
+            max_spread_bps: 100,
+            solver_fee_bps: 10,
+            solver_encryption_key: [9u8; 32],
+            slippage_window_slots: 100,
+            max_cum_slippage_bps: 50,
+            ema_alpha_bps: 5_000,
+            oracle_decimals: 8,
+            revalidate_ttl_slots: 150,
+            toxicity_spread_bps: 40,
+            toxicity_horizon_slots: 20,
+            min_oracle_price_e6: 1_000_000,
+            resume_max_oracle_age_slots: 50,
+            max_epoch_volume_e6: 20_000_000,
+            min_slots_between_matches: 0,
+            initial_oracle_price_e6: 100_000_000,
+            min_spread_bps: 0,
+            two_sided_rebate_bps: 0,
+            min_config_interval_slots: 0,
+        }
+    }
+
+    #[test]
+    fn test_init_params_matches_raw_init() {
+        let params = full_init_params();
+        assert_eq!(params.to_init_data(), full_init_data());
+        assert_eq!(PrivacyInitParams::unpack(&params.pack()), Ok(params.clone()));
+
+        let mut data = vec![0x06];
+        data.extend_from_slice(&params.pack());
+        for fill in [0x00, 0xFF] {
+            assert_eq!(init_with(process_init_params, fill, &data), Ok(init_over(fill, &full_init_data())));
+        }
+    }
+
+    #[test]
+    fn test_init_params_rejects_bad_framing_and_config() {
+        let invalid = Err(ProgramError::InvalidInstructionData);
+        let mut data = vec![0x06];
+        data.extend_from_slice(&full_init_params().pack());
+
+        let mut bad_version = data.clone();
+        bad_version[1] = 7;
+        assert_eq!(init_with(process_init_params, 0, &bad_version), invalid);
+        assert_eq!(init_with(process_init_params, 0, &data[..data.len() - 1]), invalid);
+        assert_eq!(init_with(process_init_params, 0, &[data.as_slice(), &[0]].concat()), invalid);
+        assert_eq!(init_with(process_init_params, 0, &[0x06]), invalid);
+
+        // Decoded params go through Init's validation
+        let params = PrivacyInitParams { base_spread_bps: 200, ..full_init_params() };
+        assert_eq!(
+            init_with(process_init_params, 0, &[&[0x06u8][..], &params.pack()].concat()),
+            Err(ProgramError::Custom(PrivacyMatcherError::InvalidSpreadConfig as u32))
+        );
+    }
+
+    #[test]
+    fn test_match_cooldown() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mut ctx = init_over(0, &full_init_data());
+        let cooldown = MIN_SLOTS_BETWEEN_MATCHES_OFFSET..MIN_SLOTS_BETWEEN_MATCHES_OFFSET + 2;
+        ctx[cooldown.clone()].copy_from_slice(&5u16.to_le_bytes());
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+
+        // First match is never throttled and stamps the clock's slot (1_000)
+        assert_eq!(check_match_cooldown(&ctx, 1_000), Ok(()));
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        let last_match = &ctx[LAST_MATCH_SLOT_OFFSET..LAST_MATCH_SLOT_OFFSET + 4];
+        assert_eq!(u32::from_le_bytes(last_match.try_into().unwrap()), 1_000);
+
+        // Back-to-back within the 5-slot cooldown fails
+        let throttled = Err(ProgramError::Custom(PrivacyMatcherError::MatchThrottled as u32));
+        assert_eq!(run_lp_ix(process_match, &mut ctx.clone(), &[0x00]), throttled);
+        assert_eq!(check_match_cooldown(&ctx, 1_004), throttled);
+        // Once the cooldown has passed it goes through
+        assert_eq!(check_match_cooldown(&ctx, 1_005), Ok(()));
+
+        // Disabled
+        ctx[cooldown].copy_from_slice(&0u16.to_le_bytes());
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+    }
+
+    #[test]
+    fn test_init_with_oracle_price_is_tradeable_immediately() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let data = full_init_data();
+        let mut ctx = init_over(0, &data);
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 100_250_000);
+
+        // Omitted: the zero price blocks matching until an Oracle Update lands
+        let mut ctx = init_over(0, &data[..82]);
+        assert_eq!(
+            run_lp_ix(process_match, &mut ctx.clone(), &[0x00]),
+            Err(ProgramError::Custom(PrivacyMatcherError::OraclePriceNotSet as u32))
+        );
+        ctx[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+
+        // An initial price under the floor would only ever be rejected at match time
+        let mut below_floor = data.clone();
+        below_floor[82..90].copy_from_slice(&999_999u64.to_le_bytes());
+        assert_eq!(
+            init_with(process_init, 0, &below_floor),
+            Err(ProgramError::Custom(PrivacyMatcherError::OraclePriceOutOfRange as u32))
+        );
+    }
+
+    #[test]
+    fn test_min_spread_floor_holds_a_zero_spread() {
+        set_syscall_stubs(Box::new(FixedClock));
+        // No base spread and no solver fee: without a floor the LP quotes at the oracle
+        let mut data = full_init_data();
+        data[1..5].copy_from_slice(&0u32.to_le_bytes());
+        data[9..13].copy_from_slice(&0u32.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 100_000_000);
+
+        // A 5 bps floor holds both sides off it
+        data[90..92].copy_from_slice(&5u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        assert_eq!(PrivacyContextView::from_bytes(&ctx).unwrap().min_spread(), 5);
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 100_050_000);
+        run_lp_ix(process_match, &mut ctx, &[0x00, 0, 0, 0, 0, 0, 0, 0, 0, DIRECTION_SELL]).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 99_950_000);
+
+        // The floor only raises: full_init_data's 25 bps already clears it
+        let mut data = full_init_data();
+        data[90..92].copy_from_slice(&5u16.to_le_bytes());
+        let mut ctx = init_over(0, &data);
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(read_u64_at(&ctx, 0), 100_250_000);
+
+        // A floor above the 100 bps cap is rejected at init; equal to it is fine
+        data[90..92].copy_from_slice(&101u16.to_le_bytes());
+        assert_eq!(
+            init_with(process_init, 0, &data),
+            Err(ProgramError::Custom(PrivacyMatcherError::InvalidSpreadConfig as u32))
+        );
+        data[90..92].copy_from_slice(&100u16.to_le_bytes());
+        assert!(init_with(process_init, 0, &data).is_ok());
+    }
+
+    #[test]
+    fn test_init_params_older_versions_still_decode() {
+        // Each version's body is a prefix of the next; fields it predates decode as 0
+        let params = PrivacyInitParams {
+            min_spread_bps: 5,
+            two_sided_rebate_bps: 7,
+            min_config_interval_slots: 9,
+            ..full_init_params()
+        };
+        let current = params.pack();
+        for (version, len) in [(1u8, 79u16), (2, 81), (3, 89), (4, 91), (5, 93)] {
+            let mut old = vec![version];
+            old.extend_from_slice(&len.to_le_bytes());
+            old.extend_from_slice(&current[3..3 + len as usize]);
+            let expected = PrivacyInitParams {
+                initial_oracle_price_e6: if version >= 3 { params.initial_oracle_price_e6 } else { 0 },
+                min_spread_bps: if version >= 4 { params.min_spread_bps } else { 0 },
+                two_sided_rebate_bps: if version >= 5 { params.two_sided_rebate_bps } else { 0 },
+                min_config_interval_slots: 0,
+                ..params.clone()
+            };
+            assert_eq!(PrivacyInitParams::unpack(&old), Ok(expected), "v{}", version);
+        }
+    }
+
 }
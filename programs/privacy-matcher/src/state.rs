@@ -1,6 +1,6 @@
-use solana_program::{pubkey::Pubkey, program_error::ProgramError};
+use solana_program::{account_info::AccountInfo, msg, pubkey::Pubkey, program_error::ProgramError};
 
-pub use matcher_common::{CTX_SIZE, verify_magic as verify_magic_generic};
+pub use matcher_common::{CTX_SIZE, LP_PDA_OFFSET, verify_magic as verify_magic_generic};
 #[cfg(test)]
 pub use matcher_common::MAGIC_OFFSET;
 
@@ -8,7 +8,6 @@ pub use matcher_common::MAGIC_OFFSET;
 pub const PRIVACY_MATCHER_MAGIC: u64 = 0x5052_4956_4d41_5443;
 
 // Field offsets into the 320-byte context account
-#[allow(dead_code)]
 pub const VERSION_OFFSET: usize = 72;          // u32
 #[allow(dead_code)]
 pub const MODE_OFFSET: usize = 76;             // u8: 0=SolverVerified
@@ -21,13 +20,146 @@ pub const LAST_EXEC_PRICE_OFFSET: usize = 164; // u64
 pub const TOTAL_VOLUME_OFFSET: usize = 172;    // u128 (16 bytes)
 pub const TOTAL_ORDERS_OFFSET: usize = 188;    // u64
 pub const SOLVER_ENCRYPTION_KEY_OFFSET: usize = 196; // [u8;32]
-// 228..320 = reserved
+pub const SLIPPAGE_WINDOW_SLOTS_OFFSET: usize = 228; // u32 (0 = slippage guard disabled)
+pub const MAX_CUM_SLIPPAGE_BPS_OFFSET: usize = 232;  // u32
+pub const MIN_SPREAD_OFFSET: usize = 236;            // u16: floor on the final spread (0 = off)
+pub const TWO_SIDED_REBATE_BPS_OFFSET: usize = 238;  // u16: fee rebate on fully balanced counterparty flow (0 = off)
+pub const MIN_CONFIG_INTERVAL_SLOTS_OFFSET: usize = 240; // u16: slots between Update Config calls (0 = off)
+pub const LAST_CONFIG_SLOT_OFFSET: usize = 244;      // u32: low 32 bits of the last Update Config's slot (0 = never)
+pub const ACCRUED_SOLVER_FEE_OFFSET: usize = 248;    // u128 (16 bytes): unclaimed solver fees (e6)
+pub const EMA_ALPHA_BPS_OFFSET: usize = 264;         // u16 (0 = raw oracle passthrough)
+pub const REVALIDATE_TTL_SLOTS_OFFSET: usize = 266;  // u16 (0 = no revalidate stamp)
+pub const LAST_ORACLE_SLOT_OFFSET: usize = 268;      // u64: slot of the last oracle update
+pub const TOXICITY_SPREAD_BPS_OFFSET: usize = 276;   // u16: extra spread at 100% toxic fills (0 = off)
+pub const TOXICITY_HORIZON_SLOTS_OFFSET: usize = 278; // u16: slots after a fill an oracle move counts against it
+pub const MIN_ORACLE_PRICE_OFFSET: usize = 288;      // u64: matches reject oracle prices below this (0 = no floor)
+pub const RESUME_MAX_ORACLE_AGE_OFFSET: usize = 296; // u16: resume needs an oracle update this recent (0 = off)
+pub const MAX_EPOCH_VOLUME_OFFSET: usize = 298;      // u64: notional per epoch before auto-pause (0 = no cap)
+pub const EPOCH_VOLUME_OFFSET: usize = 306;          // u64: notional traded since the epoch opened (init or resume)
+pub const MIN_SLOTS_BETWEEN_MATCHES_OFFSET: usize = 314; // u16: match cooldown (0 = off)
+pub const LAST_MATCH_SLOT_OFFSET: usize = 316;       // u32: low 32 bits of the last match's slot
+
+/// Unused byte ranges, zeroed at init
+pub const RESERVED_RANGES: [(usize, usize); 2] = [(242, 244), (280, 288)];
+
+// Context is fully allocated: the last match slot ends exactly at CTX_SIZE
+const _: () = assert!(LAST_MATCH_SLOT_OFFSET + 4 == CTX_SIZE);
+
+// Header padding bytes 77..80, written as zero by write_header
+pub const MATCH_GUARD_OFFSET: usize = 77;            // u8: 1 while a match is in flight (reentrancy guard)
+pub const ORACLE_DECIMALS_OFFSET: usize = 78;        // u8: raw-units price export (ORACLE_DECIMALS_NONE = e6 only)
+pub const PAUSED_OFFSET: usize = 79;                 // u8: 0=active, 1=paused
+
+/// Return region slot for the absolute fee (e6) charged on the fill
+pub const FEE_RETURN_OFFSET: usize = 8;              // u64
+/// Return region slot for the exec price in the oracle's own decimals
+pub const RAW_EXEC_PRICE_RETURN_OFFSET: usize = 16;  // u64
+/// Return region slot telling readers when the cached price must be re-matched
+pub const REVALIDATE_AFTER_SLOT_RETURN_OFFSET: usize = 24; // u64
+/// Fills remembered by the toxicity bitmap
+pub const TOXICITY_HISTORY_LEN: u8 = 16;
+/// Largest decimal count whose scale factor fits a u64
+pub const MAX_ORACLE_DECIMALS: u8 = 18;
+/// Oracle decimals byte for "no raw-units export"; outside 0..=MAX_ORACLE_DECIMALS
+/// so an oracle with 0 decimals stays expressible
+pub const ORACLE_DECIMALS_NONE: u8 = 0xFF;
+/// Context layout version written by init; every instruction that writes the
+/// context rejects any other
+pub const CTX_VERSION: u32 = 1;
+
+/// Oracle signers: one PDA per context holding the Ed25519 key every oracle
+/// price must be signed with. Kept apart from the solver's X25519 encryption
+/// key, which can't verify a signature, and created once by the solver.
+pub const ORACLE_SIGNERS_SEED: &[u8] = b"oracle_signers";
+/// Magic bytes: "PRIVSIGN" as u64 LE
+pub const ORACLE_SIGNERS_MAGIC: u64 = 0x5052_4956_5349_474E;
+pub const ORACLE_SIGNERS_SIZE: usize = 80;
+pub const SIGNERS_MAGIC_OFFSET: usize = 0;         // u64
+pub const SIGNERS_BUMP_OFFSET: usize = 8;          // u8: PDA bump, so readers skip the seed search
+pub const CO_SIGN_REQUIRED_OFFSET: usize = 9;      // u8: 1 = the co-signer must also sign oracle updates
+pub const ORACLE_SIGNER_OFFSET: usize = 16;        // Pubkey: Ed25519 key that signs oracle prices
+pub const CO_SIGNER_OFFSET: usize = 48;            // Pubkey: second transaction signer on oracle updates
+
+/// Counterparty flow: one PDA per (context, counterparty) holding the notional
+/// it has bought and sold, so Match can rebate flow that trades both sides.
+/// Created by anyone with Init Counterparty Flow; Match updates it when passed.
+pub const COUNTERPARTY_FLOW_SEED: &[u8] = b"flow";
+/// Magic bytes: "PRIVFLOW" as u64 LE
+pub const COUNTERPARTY_FLOW_MAGIC: u64 = 0x5052_4956_464C_4F57;
+pub const COUNTERPARTY_FLOW_SIZE: usize = 64;
+pub const FLOW_MAGIC_OFFSET: usize = 0;            // u64
+pub const FLOW_BUMP_OFFSET: usize = 8;             // u8: PDA bump
+pub const FLOW_COUNTERPARTY_OFFSET: usize = 16;    // Pubkey: the counterparty this flow belongs to
+pub const FLOW_BUY_VOLUME_OFFSET: usize = 48;      // u64: notional bought from the LP (saturating)
+pub const FLOW_SELL_VOLUME_OFFSET: usize = 56;     // u64: notional sold to the LP (saturating)
+
+/// Mark-out state: one PDA per context holding the fills awaiting their
+/// mark-out and what the toxicity spread and slippage guard have scored, which
+/// don't fit in the context. Created once with Init Mark-Out; read and written
+/// only while either feature is on.
+pub const MARK_OUT_SEED: &[u8] = b"mark_out";
+/// Magic bytes: "PRIVMARK" as u64 LE
+pub const MARK_OUT_MAGIC: u64 = 0x5052_4956_4D41_524B;
+pub const MARK_OUT_SIZE: usize = 56;
+pub const MARK_OUT_MAGIC_OFFSET: usize = 0;          // u64
+pub const MARK_OUT_BUMP_OFFSET: usize = 8;           // u8: PDA bump
+pub const PENDING_FILL_COUNT_OFFSET: usize = 9;      // u8: pending fill entries in use (max PENDING_FILL_ENTRIES)
+pub const SCORED_FILLS_OFFSET: usize = 10;           // u8: scored fills held in the bitmap (max 16)
+pub const TOXIC_FILL_BITS_OFFSET: usize = 12;        // u16: last 16 scored fills, bit set = toxic (bit 0 newest)
+pub const CUM_SLIPPAGE_BPS_OFFSET: usize = 16;       // u32: adverse mark-out accumulated in current window
+pub const SLIPPAGE_WINDOW_START_OFFSET: usize = 24;  // u64: slot the current window opened
+pub const PENDING_FILLS_OFFSET: usize = 32;          // [PendingFill; 3]: fills awaiting their mark-out, oldest first
+
+/// Pending fill entry: u32 low slot bits, then u8 buy and u8 sell fill counts
+pub const PENDING_FILL_SIZE: usize = 6;
+/// Distinct fill slots the pending queue holds before folding into the newest
+pub const PENDING_FILL_ENTRIES: u8 = 3;
+
+const _: () = assert!(PENDING_FILLS_OFFSET + PENDING_FILL_SIZE * PENDING_FILL_ENTRIES as usize <= MARK_OUT_SIZE);
+
+/// Match data byte [9]: which side of the LP's book the taker hits
+pub use matcher_shared::{DIRECTION_BUY, DIRECTION_SELL};
 
 /// Verify magic bytes in context account data
 pub fn verify_magic(ctx_data: &[u8]) -> bool {
     verify_magic_generic(ctx_data, PRIVACY_MATCHER_MAGIC)
 }
 
+/// Read the context layout version stamped at init
+pub fn read_version(ctx_data: &[u8]) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Whether matching is currently paused
+pub fn is_paused(ctx_data: &[u8]) -> bool {
+    ctx_data[PAUSED_OFFSET] != 0
+}
+
+/// Read unclaimed solver fees (e6) from context data
+pub fn read_accrued_fee(ctx_data: &[u8]) -> Result<u128, ProgramError> {
+    Ok(u128::from_le_bytes(
+        ctx_data[ACCRUED_SOLVER_FEE_OFFSET..ACCRUED_SOLVER_FEE_OFFSET + 16]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Last fill as `(exec_price_e6, slot)`. Only the slot's low 32 bits are
+/// stored, so compare ages with wrapping subtraction. `(0, 0)` = never matched.
+pub fn read_last_exec(ctx_data: &[u8]) -> Result<(u64, u32), ProgramError> {
+    let price = read_u64(ctx_data, LAST_EXEC_PRICE_OFFSET)?;
+    let slot = u32::from_le_bytes(
+        ctx_data[LAST_MATCH_SLOT_OFFSET..LAST_MATCH_SLOT_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok((price, slot))
+}
+
 /// Read solver pubkey from context data
 pub fn read_solver_pubkey(ctx_data: &[u8]) -> Result<Pubkey, ProgramError> {
     Ok(Pubkey::new_from_array(
@@ -36,3 +168,413 @@ pub fn read_solver_pubkey(ctx_data: &[u8]) -> Result<Pubkey, ProgramError> {
             .map_err(|_| ProgramError::InvalidAccountData)?,
     ))
 }
+
+/// Read the solver's X25519 NaCl box key that users encrypt intents to. It
+/// can't verify signatures; oracle prices are checked against the oracle signer.
+pub fn read_solver_encryption_key(ctx_data: &[u8]) -> Result<[u8; 32], ProgramError> {
+    ctx_data[SOLVER_ENCRYPTION_KEY_OFFSET..SOLVER_ENCRYPTION_KEY_OFFSET + 32]
+        .try_into()
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Reject an oracle signers account that isn't this context's PDA. Owner and
+/// magic alone would accept any program-owned account that happens to start
+/// with the magic; the PDA can only hold what Init Oracle Signer wrote.
+pub fn verify_oracle_signers(
+    signers: &AccountInfo,
+    signers_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if signers.owner != program_id
+        || signers_data.len() < ORACLE_SIGNERS_SIZE
+        || signers_data[SIGNERS_MAGIC_OFFSET..SIGNERS_MAGIC_OFFSET + 8] != ORACLE_SIGNERS_MAGIC.to_le_bytes()
+    {
+        msg!("PRIVACY-MATCHER: Oracle signers for {} not initialized", ctx_key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let seeds: &[&[u8]] = &[ORACLE_SIGNERS_SEED, ctx_key.as_ref(), &[signers_data[SIGNERS_BUMP_OFFSET]]];
+    let expected = Pubkey::create_program_address(seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)?;
+    if *signers.key != expected {
+        msg!("PRIVACY-MATCHER: {} is not the oracle signers PDA of {}", signers.key, ctx_key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// Read the Ed25519 key oracle prices must be signed with
+pub fn read_oracle_signer(signers_data: &[u8]) -> Result<[u8; 32], ProgramError> {
+    signers_data[ORACLE_SIGNER_OFFSET..ORACLE_SIGNER_OFFSET + 32]
+        .try_into()
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// The co-signer every oracle update must also carry, or `None` when co-signing
+/// is off
+pub fn read_co_signer(signers_data: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    if signers_data[CO_SIGN_REQUIRED_OFFSET] == 0 {
+        return Ok(None);
+    }
+    Ok(Some(Pubkey::new_from_array(
+        signers_data[CO_SIGNER_OFFSET..CO_SIGNER_OFFSET + 32]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    )))
+}
+
+/// Reject a flow account that isn't a counterparty flow PDA of this context,
+/// the same checks as `verify_oracle_signers` with the stored counterparty
+/// in the seeds
+pub fn verify_counterparty_flow(
+    flow: &AccountInfo,
+    flow_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if flow.owner != program_id
+        || flow_data.len() < COUNTERPARTY_FLOW_SIZE
+        || flow_data[FLOW_MAGIC_OFFSET..FLOW_MAGIC_OFFSET + 8] != COUNTERPARTY_FLOW_MAGIC.to_le_bytes()
+    {
+        msg!("PRIVACY-MATCHER: {} is not an initialized counterparty flow", flow.key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let seeds: &[&[u8]] = &[
+        COUNTERPARTY_FLOW_SEED,
+        ctx_key.as_ref(),
+        &flow_data[FLOW_COUNTERPARTY_OFFSET..FLOW_COUNTERPARTY_OFFSET + 32],
+        &[flow_data[FLOW_BUMP_OFFSET]],
+    ];
+    let expected = Pubkey::create_program_address(seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)?;
+    if *flow.key != expected {
+        msg!("PRIVACY-MATCHER: {} is not a counterparty flow PDA of {}", flow.key, ctx_key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// Whether the toxicity spread or the slippage guard is on, so fills are
+/// marked out and the mark-out PDA must be passed
+pub fn mark_outs_enabled(ctx_data: &[u8]) -> bool {
+    ctx_data[TOXICITY_SPREAD_BPS_OFFSET..TOXICITY_SPREAD_BPS_OFFSET + 2] != [0, 0]
+        || ctx_data[SLIPPAGE_WINDOW_SLOTS_OFFSET..SLIPPAGE_WINDOW_SLOTS_OFFSET + 4] != [0; 4]
+}
+
+/// Reject a mark-out account that isn't this context's PDA, the same checks
+/// as `verify_oracle_signers`
+pub fn verify_mark_out(
+    mark_out: &AccountInfo,
+    mark_out_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if mark_out.owner != program_id
+        || mark_out_data.len() < MARK_OUT_SIZE
+        || mark_out_data[MARK_OUT_MAGIC_OFFSET..MARK_OUT_MAGIC_OFFSET + 8] != MARK_OUT_MAGIC.to_le_bytes()
+    {
+        msg!("PRIVACY-MATCHER: Mark-out state for {} not initialized", ctx_key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let seeds: &[&[u8]] = &[MARK_OUT_SEED, ctx_key.as_ref(), &[mark_out_data[MARK_OUT_BUMP_OFFSET]]];
+    let expected = Pubkey::create_program_address(seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)?;
+    if *mark_out.key != expected {
+        msg!("PRIVACY-MATCHER: {} is not the mark-out PDA of {}", mark_out.key, ctx_key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// Typed, validated snapshot of a privacy-matcher context
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrivacyContextView {
+    lp_pda: Pubkey,
+    solver: Pubkey,
+    base_spread: u32,
+    max_spread: u32,
+    solver_fee: u32,
+    min_spread: u16,
+    oracle_price: u64,
+    last_exec_price: u64,
+    total_volume: u128,
+    two_sided_rebate_bps: u16,
+    total_orders: u64,
+    solver_encryption_key: [u8; 32],
+    accrued_solver_fee: u128,
+    ema_alpha_bps: u16,
+    oracle_decimals: u8,
+    revalidate_ttl_slots: u16,
+    last_oracle_slot: u64,
+    toxicity_spread_bps: u16,
+    toxicity_horizon_slots: u16,
+    min_oracle_price: u64,
+    resume_max_oracle_age_slots: u16,
+    max_epoch_volume: u64,
+    epoch_volume: u64,
+    min_slots_between_matches: u16,
+    last_match_slot: u32,
+    min_config_interval_slots: u16,
+    last_config_slot: u32,
+    paused: bool,
+}
+
+impl PrivacyContextView {
+    /// Parse a context account, rejecting short buffers and foreign/uninitialized magic
+    pub fn from_bytes(ctx_data: &[u8]) -> Result<Self, ProgramError> {
+        if ctx_data.len() < CTX_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !verify_magic(ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(Self {
+            lp_pda: read_pubkey(ctx_data, LP_PDA_OFFSET)?,
+            solver: read_solver_pubkey(ctx_data)?,
+            base_spread: read_u32(ctx_data, BASE_SPREAD_OFFSET)?,
+            max_spread: read_u32(ctx_data, MAX_SPREAD_OFFSET)?,
+            solver_fee: read_u32(ctx_data, SOLVER_FEE_OFFSET)?,
+            min_spread: read_u16(ctx_data, MIN_SPREAD_OFFSET)?,
+            oracle_price: read_u64(ctx_data, ORACLE_PRICE_OFFSET)?,
+            last_exec_price: read_u64(ctx_data, LAST_EXEC_PRICE_OFFSET)?,
+            total_volume: read_u128(ctx_data, TOTAL_VOLUME_OFFSET)?,
+            two_sided_rebate_bps: read_u16(ctx_data, TWO_SIDED_REBATE_BPS_OFFSET)?,
+            total_orders: read_u64(ctx_data, TOTAL_ORDERS_OFFSET)?,
+            solver_encryption_key: read_solver_encryption_key(ctx_data)?,
+            accrued_solver_fee: read_accrued_fee(ctx_data)?,
+            ema_alpha_bps: u16::from_le_bytes(
+                ctx_data[EMA_ALPHA_BPS_OFFSET..EMA_ALPHA_BPS_OFFSET + 2]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            ),
+            oracle_decimals: ctx_data[ORACLE_DECIMALS_OFFSET],
+            revalidate_ttl_slots: u16::from_le_bytes(
+                ctx_data[REVALIDATE_TTL_SLOTS_OFFSET..REVALIDATE_TTL_SLOTS_OFFSET + 2]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            ),
+            last_oracle_slot: read_u64(ctx_data, LAST_ORACLE_SLOT_OFFSET)?,
+            toxicity_spread_bps: u16::from_le_bytes(
+                ctx_data[TOXICITY_SPREAD_BPS_OFFSET..TOXICITY_SPREAD_BPS_OFFSET + 2]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            ),
+            toxicity_horizon_slots: u16::from_le_bytes(
+                ctx_data[TOXICITY_HORIZON_SLOTS_OFFSET..TOXICITY_HORIZON_SLOTS_OFFSET + 2]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            ),
+            min_oracle_price: read_u64(ctx_data, MIN_ORACLE_PRICE_OFFSET)?,
+            resume_max_oracle_age_slots: u16::from_le_bytes(
+                ctx_data[RESUME_MAX_ORACLE_AGE_OFFSET..RESUME_MAX_ORACLE_AGE_OFFSET + 2]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            ),
+            max_epoch_volume: read_u64(ctx_data, MAX_EPOCH_VOLUME_OFFSET)?,
+            epoch_volume: read_u64(ctx_data, EPOCH_VOLUME_OFFSET)?,
+            min_slots_between_matches: u16::from_le_bytes(
+                ctx_data[MIN_SLOTS_BETWEEN_MATCHES_OFFSET..MIN_SLOTS_BETWEEN_MATCHES_OFFSET + 2]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            ),
+            last_match_slot: read_last_exec(ctx_data)?.1,
+            min_config_interval_slots: read_u16(ctx_data, MIN_CONFIG_INTERVAL_SLOTS_OFFSET)?,
+            last_config_slot: read_u32(ctx_data, LAST_CONFIG_SLOT_OFFSET)?,
+            paused: is_paused(ctx_data),
+        })
+    }
+
+    pub fn lp_pda(&self) -> Pubkey {
+        self.lp_pda
+    }
+
+    pub fn solver(&self) -> Pubkey {
+        self.solver
+    }
+
+    pub fn base_spread(&self) -> u32 {
+        self.base_spread
+    }
+
+    pub fn max_spread(&self) -> u32 {
+        self.max_spread
+    }
+
+    pub fn solver_fee(&self) -> u32 {
+        self.solver_fee
+    }
+
+    pub fn min_spread(&self) -> u16 {
+        self.min_spread
+    }
+
+    pub fn oracle_price(&self) -> u64 {
+        self.oracle_price
+    }
+
+    pub fn last_exec_price(&self) -> u64 {
+        self.last_exec_price
+    }
+
+    pub fn total_volume(&self) -> u128 {
+        self.total_volume
+    }
+
+    pub fn two_sided_rebate_bps(&self) -> u16 {
+        self.two_sided_rebate_bps
+    }
+
+    pub fn total_orders(&self) -> u64 {
+        self.total_orders
+    }
+
+    pub fn solver_encryption_key(&self) -> [u8; 32] {
+        self.solver_encryption_key
+    }
+
+    pub fn accrued_solver_fee(&self) -> u128 {
+        self.accrued_solver_fee
+    }
+
+    pub fn ema_alpha_bps(&self) -> u16 {
+        self.ema_alpha_bps
+    }
+
+    pub fn oracle_decimals(&self) -> u8 {
+        self.oracle_decimals
+    }
+
+    pub fn revalidate_ttl_slots(&self) -> u16 {
+        self.revalidate_ttl_slots
+    }
+
+    pub fn last_oracle_slot(&self) -> u64 {
+        self.last_oracle_slot
+    }
+
+    pub fn toxicity_spread_bps(&self) -> u16 {
+        self.toxicity_spread_bps
+    }
+
+    pub fn toxicity_horizon_slots(&self) -> u16 {
+        self.toxicity_horizon_slots
+    }
+
+    pub fn min_oracle_price(&self) -> u64 {
+        self.min_oracle_price
+    }
+
+    pub fn resume_max_oracle_age_slots(&self) -> u16 {
+        self.resume_max_oracle_age_slots
+    }
+
+    pub fn max_epoch_volume(&self) -> u64 {
+        self.max_epoch_volume
+    }
+
+    pub fn epoch_volume(&self) -> u64 {
+        self.epoch_volume
+    }
+
+    pub fn min_slots_between_matches(&self) -> u16 {
+        self.min_slots_between_matches
+    }
+
+    /// Low 32 bits of the last match's slot
+    pub fn last_match_slot(&self) -> u32 {
+        self.last_match_slot
+    }
+
+    pub fn min_config_interval_slots(&self) -> u16 {
+        self.min_config_interval_slots
+    }
+
+    /// Low 32 bits of the last Update Config's slot (0 = never)
+    pub fn last_config_slot(&self) -> u32 {
+        self.last_config_slot
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+}
+
+fn read_pubkey(ctx_data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    Ok(Pubkey::new_from_array(
+        ctx_data[offset..offset + 32].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_u16(ctx_data: &[u8], offset: usize) -> Result<u16, ProgramError> {
+    Ok(u16::from_le_bytes(
+        ctx_data[offset..offset + 2].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_u32(ctx_data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[offset..offset + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_u64(ctx_data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    Ok(u64::from_le_bytes(
+        ctx_data[offset..offset + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_u128(ctx_data: &[u8], offset: usize) -> Result<u128, ProgramError> {
+    Ok(u128::from_le_bytes(
+        ctx_data[offset..offset + 16].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matcher_common::write_header;
+
+    #[test]
+    fn test_magic_matches_shared_layout() {
+        assert_eq!(matcher_shared::matcher_magic(matcher_shared::MatcherKind::Privacy), PRIVACY_MATCHER_MAGIC);
+        // A context this program initialized is identified as its own
+        let mut data = vec![0u8; CTX_SIZE];
+        matcher_common::write_header(&mut data, PRIVACY_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(matcher_shared::identify(&data), Some(matcher_shared::MatcherKind::Privacy));
+    }
+
+    #[test]
+    fn test_view_roundtrip_from_header() {
+        let lp = Pubkey::new_unique();
+        let solver = Pubkey::new_unique();
+        let mut data = vec![0u8; CTX_SIZE];
+        write_header(&mut data, PRIVACY_MATCHER_MAGIC, 0, &lp);
+        data[SOLVER_PUBKEY_OFFSET..SOLVER_PUBKEY_OFFSET + 32].copy_from_slice(solver.as_ref());
+        data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&15u32.to_le_bytes());
+        data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&100u32.to_le_bytes());
+        data[SOLVER_FEE_OFFSET..SOLVER_FEE_OFFSET + 4].copy_from_slice(&10u32.to_le_bytes());
+        data[ORACLE_PRICE_OFFSET..ORACLE_PRICE_OFFSET + 8].copy_from_slice(&100_000_000u64.to_le_bytes());
+        data[TOTAL_ORDERS_OFFSET..TOTAL_ORDERS_OFFSET + 8].copy_from_slice(&7u64.to_le_bytes());
+        data[SOLVER_ENCRYPTION_KEY_OFFSET..SOLVER_ENCRYPTION_KEY_OFFSET + 32].copy_from_slice(&[9u8; 32]);
+
+        let view = PrivacyContextView::from_bytes(&data).unwrap();
+        assert_eq!(view.lp_pda(), lp);
+        assert_eq!(view.solver(), solver);
+        assert_eq!(view.base_spread(), 15);
+        assert_eq!(view.max_spread(), 100);
+        assert_eq!(view.solver_fee(), 10);
+        assert_eq!(view.oracle_price(), 100_000_000);
+        assert_eq!(view.total_orders(), 7);
+        assert_eq!(view.total_volume(), 0);
+        assert_eq!(view.solver_encryption_key(), [9u8; 32]);
+        assert!(!view.paused());
+    }
+
+    #[test]
+    fn test_view_rejects_bad_magic_and_length() {
+        let mut data = vec![0u8; CTX_SIZE];
+        assert_eq!(PrivacyContextView::from_bytes(&data), Err(ProgramError::UninitializedAccount));
+        write_header(&mut data, PRIVACY_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(
+            PrivacyContextView::from_bytes(&data[..CTX_SIZE - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
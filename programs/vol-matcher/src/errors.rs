@@ -1,3 +1,4 @@
+use matcher_shared::SidePriceError;
 use solana_program::program_error::ProgramError;
 
 #[derive(Debug, Clone, Copy)]
@@ -7,6 +8,16 @@ pub enum VolMatcherError {
     OracleAccountMismatch = 0x22,
     InvalidRegime = 0x23,
     ArithmeticOverflow = 0x24,
+    InvalidLiquidityConfig = 0x25,
+    UnauthorizedKeeper = 0x26,
+    MatchReentered = 0x27,
+    SlippageExceeded = 0x28,
+    InvalidMode = 0x29,
+    UnsupportedVersion = 0x2A,
+    StaleUpdate = 0x2B,
+    UnauthorizedConfigAuthority = 0x2C,
+    SyncHeartbeatRequired = 0x2D,
+    SpreadTooWide = 0x2E,
 }
 
 impl From<VolMatcherError> for ProgramError {
@@ -14,3 +25,12 @@ impl From<VolMatcherError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+
+impl From<SidePriceError> for VolMatcherError {
+    fn from(e: SidePriceError) -> Self {
+        match e {
+            SidePriceError::Overflow => VolMatcherError::ArithmeticOverflow,
+            SidePriceError::SpreadTooWide => VolMatcherError::SpreadTooWide,
+        }
+    }
+}
@@ -6,16 +6,67 @@ pub enum VolMatcherInstruction {
     /// Execute match - compute vol-adjusted execution price
     #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes)")]
+    #[account(2, optional, name = "global_config", desc = "Global config PDA (staleness default when the context leaves it at 0)")]
+    #[account(3, optional, name = "fallback_pda", desc = "Fallback PDA, seeds [\"fallback\", context] (priced off while the primary is stale)")]
     Match,
 
     /// Initialize vol matcher context
     #[account(0, name = "lp_pda", desc = "LP PDA to store")]
     #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes, writable)")]
+    #[account(2, optional, signer, writable, name = "payer", desc = "Pays for the keeper and fallback PDAs (only when creating one)")]
+    #[account(3, optional, writable, name = "keeper_pda", desc = "Keeper PDA, seeds [\"keeper\", context] (only with a keeper authority)")]
+    #[account(4, optional, name = "system_program", desc = "System program (only when creating a PDA)")]
+    #[account(5, optional, writable, name = "fallback_pda", desc = "Fallback PDA, seeds [\"fallback\", context] (only with a fallback index)")]
     Init,
 
     /// Sync oracle - keeper updates vol data from Sigma oracle
     #[account(0, writable, name = "matcher_context", desc = "Matcher context account")]
     #[account(1, name = "variance_tracker", desc = "Sigma VarianceTracker account")]
     #[account(2, name = "vol_index", desc = "Sigma VolatilityIndex account")]
+    #[account(3, optional, signer, name = "keeper", desc = "Keeper authority (credits keeper rewards)")]
+    #[account(4, optional, writable, name = "keeper_pda", desc = "Keeper PDA, seeds [\"keeper\", context] (required with the keeper)")]
     OracleSync,
+
+    /// Withdraw accrued keeper rewards - zeroes the accumulator
+    #[account(0, signer, name = "keeper", desc = "Keeper authority (must match stored keeper)")]
+    #[account(1, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "keeper_pda", desc = "Keeper PDA, seeds [\"keeper\", context]")]
+    WithdrawKeeperRewards,
+
+    /// Fallback sync - keeper refreshes the fallback mark from the fallback VolatilityIndex
+    #[account(0, name = "matcher_context", desc = "Matcher context account")]
+    #[account(1, writable, name = "fallback_pda", desc = "Fallback PDA, seeds [\"fallback\", context]")]
+    #[account(2, name = "fallback_vol_index", desc = "Fallback VolatilityIndex account (must match stored)")]
+    FallbackSync,
+
+    /// Batch match - price up to 8 sized, directed basket legs in order with a single LP PDA check
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account (320 bytes)")]
+    #[account(2, optional, name = "global_config", desc = "Global config PDA (staleness default when the context leaves it at 0)")]
+    #[account(3, optional, name = "fallback_pda", desc = "Fallback PDA, seeds [\"fallback\", context] (priced off while the primary is stale)")]
+    MatchBatch,
+
+    /// Quote both sides - bid, ask and mid into the return buffer, no state changes
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account (return buffer only)")]
+    #[account(2, optional, name = "global_config", desc = "Global config PDA (staleness default when the context leaves it at 0)")]
+    #[account(3, optional, name = "fallback_pda", desc = "Fallback PDA, seeds [\"fallback\", context] (priced off while the primary is stale)")]
+    QuoteBoth,
+
+    /// Query spread - the pre-skew spread at the stored regime as return data; signer-free, no state changes
+    #[account(0, name = "matcher_context", desc = "Matcher context account")]
+    QuerySpread,
+
+    /// Sweep excess - move lamports above the rent-exempt minimum to a destination
+    #[account(0, signer, name = "lp_pda", desc = "LP PDA (must be signer)")]
+    #[account(1, writable, name = "matcher_context", desc = "Matcher context account")]
+    #[account(2, writable, name = "destination", desc = "Receives the excess lamports")]
+    SweepExcess,
+
+    /// Set global config - fleet-wide defaults, upgrade-authority only
+    #[account(0, writable, signer, name = "upgrade_authority", desc = "Program upgrade authority (pays on first set)")]
+    #[account(1, writable, name = "global_config", desc = "Global config PDA, seeds [\"global_config\"]")]
+    #[account(2, name = "program_data", desc = "This program's ProgramData account")]
+    #[account(3, optional, name = "system_program", desc = "System program (first set only)")]
+    SetGlobalConfig,
 }
@@ -3,12 +3,18 @@ use solana_program::{
     program_error::ProgramError, pubkey::Pubkey,
 };
 
+#[macro_use]
+extern crate matcher_shared;
+
 mod errors;
 mod instructions;
-mod state;
+pub mod state;
 mod vol_pricing;
 
-use vol_pricing::{process_init, process_match, process_oracle_sync};
+use vol_pricing::{
+    process_fallback_sync, process_init, process_match, process_match_batch, process_oracle_sync,
+    process_query_spread, process_quote_both, process_set_global_config, process_withdraw_keeper_rewards,
+};
 
 entrypoint!(process_instruction);
 
@@ -23,17 +29,45 @@ pub fn process_instruction(
 
     match instruction_data[0] {
         0x00 => {
-            msg!("VOL-MATCHER: Match instruction");
+            matcher_log!("VOL-MATCHER: Match instruction");
             process_match(program_id, accounts, instruction_data)
         }
         0x02 => {
-            msg!("VOL-MATCHER: Init instruction");
+            matcher_log!("VOL-MATCHER: Init instruction");
             process_init(program_id, accounts, instruction_data)
         }
         0x03 => {
-            msg!("VOL-MATCHER: Oracle sync instruction");
+            matcher_log!("VOL-MATCHER: Oracle sync instruction");
             process_oracle_sync(program_id, accounts, instruction_data)
         }
+        0x04 => {
+            matcher_log!("VOL-MATCHER: Withdraw keeper rewards instruction");
+            process_withdraw_keeper_rewards(program_id, accounts, instruction_data)
+        }
+        0x05 => {
+            matcher_log!("VOL-MATCHER: Fallback sync instruction");
+            process_fallback_sync(program_id, accounts, instruction_data)
+        }
+        0x0A => {
+            matcher_log!("VOL-MATCHER: Match batch instruction");
+            process_match_batch(program_id, accounts, instruction_data)
+        }
+        0x0C => {
+            matcher_log!("VOL-MATCHER: Quote both instruction");
+            process_quote_both(program_id, accounts, instruction_data)
+        }
+        0x0D => {
+            matcher_log!("VOL-MATCHER: Query spread instruction");
+            process_query_spread(program_id, accounts, instruction_data)
+        }
+        0x0E => {
+            matcher_log!("VOL-MATCHER: Sweep excess instruction");
+            matcher_shared::process_sweep_excess(program_id, accounts, state::VOL_MATCHER_MAGIC, "VOL-MATCHER")
+        }
+        0x0F => {
+            matcher_log!("VOL-MATCHER: Set global config instruction");
+            process_set_global_config(program_id, accounts, instruction_data)
+        }
         _ => {
             msg!("VOL-MATCHER: Unknown instruction tag {}", instruction_data[0]);
             Err(ProgramError::InvalidInstructionData)
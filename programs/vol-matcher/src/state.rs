@@ -1,14 +1,21 @@
+use solana_program::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::errors::VolMatcherError;
+
 // Re-export shared constants and functions from matcher-common
-pub use matcher_common::{CTX_SIZE, verify_magic as verify_magic_generic};
+pub use matcher_common::{CTX_SIZE, LP_PDA_OFFSET, verify_magic as verify_magic_generic};
+#[cfg(test)]
+pub use matcher_common::MAGIC_OFFSET;
 
 /// Magic bytes: "VOLMATCH" as u64 LE
 pub const VOL_MATCHER_MAGIC: u64 = 0x564F_4c4d_4154_4348;
 
 // Vol-matcher-specific field offsets
-#[allow(dead_code)]
 pub const VERSION_OFFSET: usize = 72;            // u32
 #[allow(dead_code)]
 pub const MODE_OFFSET: usize = 76;               // u8: 0=RealizedVol, 1=ImpliedVol
+pub const MATCH_GUARD_OFFSET: usize = 77;        // u8: 1 while a match is in flight (reentrancy guard)
+pub const MAX_MATCHES_PER_SYNC_OFFSET: usize = 78; // u16: matches allowed between syncs (0 = unlimited)
 pub const BASE_SPREAD_OFFSET: usize = 112;       // u32
 pub const VOV_SPREAD_OFFSET: usize = 116;        // u32 vol-of-vol spread
 pub const MAX_SPREAD_OFFSET: usize = 120;        // u32
@@ -17,13 +24,86 @@ pub const CURRENT_VOL_OFFSET: usize = 128;       // u64 current vol in bps
 pub const VOL_MARK_PRICE_OFFSET: usize = 136;    // u64 mark price in e6
 pub const LAST_UPDATE_SLOT_OFFSET: usize = 144;  // u64
 pub const REGIME_OFFSET: usize = 152;            // u8 (0=VeryLow..4=Extreme)
+pub const MAX_STALENESS_OFFSET: usize = 153;     // u8: staleness base in slots at Normal (0 = default)
+pub const SKEW_K_OFFSET: usize = 154;            // u16: inventory skew coefficient (bps)
+pub const MAX_SKEW_OFFSET: usize = 156;          // u16: skew clamp (bps)
+pub const MATCHES_SINCE_SYNC_OFFSET: usize = 158; // u16: matches priced since the last written sync (saturating)
 pub const VOL_7D_AVG_OFFSET: usize = 160;        // u64
 pub const VOL_30D_AVG_OFFSET: usize = 168;       // u64
 pub const LIQUIDITY_OFFSET: usize = 176;         // u128 (16 bytes)
 pub const MAX_FILL_OFFSET: usize = 192;          // u128 (16 bytes)
 pub const VARIANCE_TRACKER_OFFSET: usize = 208;  // Pubkey (32)
 pub const VOL_INDEX_OFFSET: usize = 240;         // Pubkey (32)
-// 272..320 = reserved
+pub const NET_INVENTORY_OFFSET: usize = 272;     // i64: LP signed inventory (+ = net long)
+pub const TOTAL_VOLUME_OFFSET: usize = 280;      // u128: cumulative trade_size_abs (saturating)
+pub const TOTAL_TRADES_OFFSET: usize = 296;      // u64: matches priced (saturating)
+pub const REGIME_MULTIPLIERS_OFFSET: usize = 304; // [u16; 5]: vov multiplier % per regime (all zero = built-in)
+// 314..320 = reserved
+
+/// Keeper: one PDA per context holding the keeper authority and its reward
+/// accumulator, which don't fit in the context. Init creates it when a keeper
+/// authority is supplied; a context without one has no keeper PDA.
+pub const KEEPER_SEED: &[u8] = b"keeper";
+/// Magic bytes: "VOLKEEPR" as u64 LE
+pub const KEEPER_MAGIC: u64 = 0x564F_4C4B_4545_5052;
+pub const KEEPER_SIZE: usize = 56;
+pub const KEEPER_MAGIC_OFFSET: usize = 0;             // u64
+pub const KEEPER_BUMP_OFFSET: usize = 8;              // u8: PDA bump, so readers skip the seed search
+pub const KEEPER_REWARD_PER_SYNC_OFFSET: usize = 10;  // u16: reward credited per sync (e6)
+pub const KEEPER_REWARDS_OFFSET: usize = 16;          // u64: accrued keeper rewards (e6)
+pub const KEEPER_AUTHORITY_OFFSET: usize = 24;        // Pubkey (32): keeper allowed to accrue/withdraw
+
+/// Fallback oracle: one PDA per context naming a second VolatilityIndex that
+/// Match prices off, at a penalty, while the primary is stale. Init creates it
+/// when a fallback index is supplied; Fallback Sync keeps its mark fresh.
+pub const FALLBACK_SEED: &[u8] = b"fallback";
+/// Magic bytes: "VOLFALBK" as u64 LE
+pub const FALLBACK_MAGIC: u64 = 0x564F_4C46_414C_424B;
+pub const FALLBACK_SIZE: usize = 64;
+pub const FALLBACK_MAGIC_OFFSET: usize = 0;         // u64
+pub const FALLBACK_BUMP_OFFSET: usize = 8;          // u8: PDA bump, so readers skip the seed search
+pub const FALLBACK_PENALTY_OFFSET: usize = 10;      // u16: bps added to the spread when pricing off the fallback
+pub const FALLBACK_VOL_MARK_OFFSET: usize = 16;     // u64: fallback mark price in e6 (0 = never synced)
+pub const FALLBACK_UPDATE_SLOT_OFFSET: usize = 24;  // u64: slot the fallback mark was read at
+pub const FALLBACK_VOL_INDEX_OFFSET: usize = 32;    // Pubkey (32): the fallback VolatilityIndex
+
+/// MatchBatch writes one u64 price per leg into the 64-byte return region
+pub const MAX_BATCH_LEGS: usize = matcher_common::RETURN_DATA_SIZE / 8;
+/// MatchBatch leg: trade_size_abs (u64 LE) then direction (u8)
+pub const BATCH_LEG_LEN: usize = 9;
+
+/// QuoteBoth return slots
+pub const QUOTE_BID_RETURN_OFFSET: usize = 0;    // u64
+pub const QUOTE_ASK_RETURN_OFFSET: usize = 8;    // u64
+pub const QUOTE_MID_RETURN_OFFSET: usize = 16;   // u64: vol mark price
+
+/// Staleness base used when none is configured (the historical fixed limit)
+pub const DEFAULT_MAX_STALENESS_SLOTS: u64 = 100;
+
+/// Global config: one PDA per program holding fleet-wide defaults that apply
+/// to any context leaving its own value at 0. Written only by the program's
+/// upgrade authority.
+pub const GLOBAL_CONFIG_SEED: &[u8] = b"global_config";
+/// Magic bytes: "VOLGLOBL" as u64 LE
+pub const GLOBAL_CONFIG_MAGIC: u64 = 0x564F_4C47_4C4F_424C;
+pub const GLOBAL_CONFIG_SIZE: usize = 16;
+pub const GLOBAL_MAGIC_OFFSET: usize = 0;          // u64
+pub const GLOBAL_BUMP_OFFSET: usize = 8;           // u8: PDA bump, so readers skip the seed search
+pub const GLOBAL_MAX_STALENESS_OFFSET: usize = 9;  // u8: staleness base default (0 = DEFAULT_MAX_STALENESS_SLOTS)
+
+/// Upgradeable loader ProgramData header: u32 state tag (3), u64 deploy slot,
+/// then `Option<Pubkey>` upgrade authority
+pub const PROGRAM_DATA_STATE_TAG: u32 = 3;
+pub const PROGRAM_DATA_AUTHORITY_OFFSET: usize = 12; // u8 option tag, then Pubkey (32)
+
+/// Trade direction (match data byte 9)
+pub use matcher_shared::{DIRECTION_BUY, DIRECTION_SELL};
+
+/// Highest valid mode byte (0=RealizedVol, 1=ImpliedVol)
+pub const MAX_MODE: u8 = 1;
+
+/// Context layout version written by init; matches reject any other
+pub const CTX_VERSION: u32 = 1;
 
 /// Volatility regime enum
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -57,9 +137,359 @@ impl VolatilityRegime {
             Self::Extreme => 250,  // 2.5x
         }
     }
+
+    /// Staleness scale: calm regimes tolerate an older oracle, turbulent ones don't
+    pub fn staleness_pct(&self) -> u64 {
+        match self {
+            Self::VeryLow => 150,  // 1.5x
+            Self::Low => 125,      // 1.25x
+            Self::Normal => 100,   // 1.0x
+            Self::High => 60,      // 0.6x
+            Self::Extreme => 30,   // 0.3x
+        }
+    }
+}
+
+/// Reject a global config that isn't this program's PDA. Owner and magic
+/// alone would accept any program-owned account that happens to start with
+/// the magic; the PDA can only hold what Set Global Config wrote.
+pub fn verify_global_config(config: &AccountInfo, config_data: &[u8], program_id: &Pubkey) -> Result<(), ProgramError> {
+    if config.owner != program_id {
+        msg!("VOL-MATCHER: Global config owned by {}, expected this program", config.owner);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if config_data.len() < GLOBAL_CONFIG_SIZE
+        || config_data[GLOBAL_MAGIC_OFFSET..GLOBAL_MAGIC_OFFSET + 8] != GLOBAL_CONFIG_MAGIC.to_le_bytes()
+    {
+        msg!("VOL-MATCHER: Global config not initialized");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let expected = Pubkey::create_program_address(&[GLOBAL_CONFIG_SEED, &[config_data[GLOBAL_BUMP_OFFSET]]], program_id)
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+    if *config.key != expected {
+        msg!("VOL-MATCHER: Global config {} is not the program's config PDA", config.key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
 }
 
 /// Local convenience wrapper that checks magic against VOL_MATCHER_MAGIC
 pub fn verify_magic(ctx_data: &[u8]) -> bool {
     verify_magic_generic(ctx_data, VOL_MATCHER_MAGIC)
 }
+
+/// Read the context layout version stamped at init
+pub fn read_version(ctx_data: &[u8]) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+/// Reject a keeper account that isn't `ctx_key`'s keeper PDA. Owner and magic
+/// alone would accept another context's keeper, whose authority signs for it.
+pub fn verify_keeper_pda(
+    keeper_pda: &AccountInfo,
+    keeper_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if keeper_pda.owner != program_id
+        || keeper_data.len() < KEEPER_SIZE
+        || keeper_data[KEEPER_MAGIC_OFFSET..KEEPER_MAGIC_OFFSET + 8] != KEEPER_MAGIC.to_le_bytes()
+    {
+        msg!("VOL-MATCHER: No keeper configured for {}", ctx_key);
+        return Err(VolMatcherError::UnauthorizedKeeper.into());
+    }
+    let seeds: &[&[u8]] = &[KEEPER_SEED, ctx_key.as_ref(), &[keeper_data[KEEPER_BUMP_OFFSET]]];
+    let expected = Pubkey::create_program_address(seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)?;
+    if *keeper_pda.key != expected {
+        msg!("VOL-MATCHER: {} is not the keeper PDA of {}", keeper_pda.key, ctx_key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+pub fn read_keeper_authority(keeper_data: &[u8]) -> Result<Pubkey, ProgramError> {
+    read_pubkey(keeper_data, KEEPER_AUTHORITY_OFFSET)
+}
+
+pub fn read_keeper_rewards(keeper_data: &[u8]) -> Result<u64, ProgramError> {
+    read_u64(keeper_data, KEEPER_REWARDS_OFFSET)
+}
+
+/// Reject a fallback account that isn't `ctx_key`'s fallback PDA, as
+/// `verify_keeper_pda` does for the keeper
+pub fn verify_fallback_pda(
+    fallback_pda: &AccountInfo,
+    fallback_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if fallback_pda.owner != program_id
+        || fallback_data.len() < FALLBACK_SIZE
+        || fallback_data[FALLBACK_MAGIC_OFFSET..FALLBACK_MAGIC_OFFSET + 8] != FALLBACK_MAGIC.to_le_bytes()
+    {
+        msg!("VOL-MATCHER: No fallback oracle configured for {}", ctx_key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let seeds: &[&[u8]] = &[FALLBACK_SEED, ctx_key.as_ref(), &[fallback_data[FALLBACK_BUMP_OFFSET]]];
+    let expected = Pubkey::create_program_address(seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)?;
+    if *fallback_pda.key != expected {
+        msg!("VOL-MATCHER: {} is not the fallback PDA of {}", fallback_pda.key, ctx_key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// The fallback as pricing reads it: `(vol_mark, update_slot, penalty_bps)`
+pub fn read_fallback_quote(fallback_data: &[u8]) -> Result<(u64, u64, u16), ProgramError> {
+    let penalty = u16::from_le_bytes(
+        fallback_data[FALLBACK_PENALTY_OFFSET..FALLBACK_PENALTY_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok((
+        read_u64(fallback_data, FALLBACK_VOL_MARK_OFFSET)?,
+        read_u64(fallback_data, FALLBACK_UPDATE_SLOT_OFFSET)?,
+        penalty,
+    ))
+}
+
+pub fn read_fallback_vol_index(fallback_data: &[u8]) -> Result<Pubkey, ProgramError> {
+    read_pubkey(fallback_data, FALLBACK_VOL_INDEX_OFFSET)
+}
+
+/// Spread in bps a match charges at the stored regime, before inventory skew:
+/// base plus regime-scaled vol-of-vol, capped at max. Reads nothing but the
+/// spread config and regime, so it holds whatever the mark or its age.
+pub fn compute_current_spread(ctx_data: &[u8]) -> Result<u64, ProgramError> {
+    let base_spread = read_u32(ctx_data, BASE_SPREAD_OFFSET)?;
+    let vov_spread = read_u32(ctx_data, VOV_SPREAD_OFFSET)?;
+    let max_spread = read_u32(ctx_data, MAX_SPREAD_OFFSET)?;
+    let regime = VolatilityRegime::from_u8(ctx_data[REGIME_OFFSET]);
+
+    let adjusted_vov = (vov_spread as u64)
+        .checked_mul(regime_multiplier(ctx_data, regime))
+        .ok_or(VolMatcherError::ArithmeticOverflow)?
+        / 100;
+    Ok(std::cmp::min((base_spread as u64).saturating_add(adjusted_vov), max_spread as u64))
+}
+
+/// Vol-of-vol multiplier (percent): the operator's stored table, or the
+/// built-in `VolatilityRegime::spread_multiplier` when no table was configured
+pub fn regime_multiplier(ctx_data: &[u8], regime: VolatilityRegime) -> u64 {
+    let table = &ctx_data[REGIME_MULTIPLIERS_OFFSET..REGIME_MULTIPLIERS_OFFSET + 10];
+    if table.iter().all(|&b| b == 0) {
+        regime.spread_multiplier()
+    } else {
+        let offset = regime as usize * 2;
+        u16::from_le_bytes([table[offset], table[offset + 1]]) as u64
+    }
+}
+
+/// Typed, validated snapshot of a vol-matcher context
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolContextView {
+    lp_pda: Pubkey,
+    base_spread: u32,
+    vov_spread: u32,
+    max_spread: u32,
+    impact_k: u32,
+    current_vol: u64,
+    vol_mark_price: u64,
+    last_update_slot: u64,
+    regime: VolatilityRegime,
+    max_staleness: u8,
+    vol_7d_avg: u64,
+    vol_30d_avg: u64,
+    total_trades: u64,
+    total_volume: u128,
+    liquidity: u128,
+    max_fill: u128,
+    variance_tracker: Pubkey,
+    vol_index: Pubkey,
+    net_inventory: i64,
+}
+
+impl VolContextView {
+    /// Parse a context account, rejecting short buffers and foreign/uninitialized magic
+    pub fn from_bytes(ctx_data: &[u8]) -> Result<Self, ProgramError> {
+        if ctx_data.len() < CTX_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !verify_magic(ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(Self {
+            lp_pda: read_pubkey(ctx_data, LP_PDA_OFFSET)?,
+            base_spread: read_u32(ctx_data, BASE_SPREAD_OFFSET)?,
+            vov_spread: read_u32(ctx_data, VOV_SPREAD_OFFSET)?,
+            max_spread: read_u32(ctx_data, MAX_SPREAD_OFFSET)?,
+            impact_k: read_u32(ctx_data, IMPACT_K_OFFSET)?,
+            current_vol: read_u64(ctx_data, CURRENT_VOL_OFFSET)?,
+            vol_mark_price: read_u64(ctx_data, VOL_MARK_PRICE_OFFSET)?,
+            last_update_slot: read_u64(ctx_data, LAST_UPDATE_SLOT_OFFSET)?,
+            regime: VolatilityRegime::from_u8(ctx_data[REGIME_OFFSET]),
+            max_staleness: ctx_data[MAX_STALENESS_OFFSET],
+            vol_7d_avg: read_u64(ctx_data, VOL_7D_AVG_OFFSET)?,
+            vol_30d_avg: read_u64(ctx_data, VOL_30D_AVG_OFFSET)?,
+            total_trades: read_u64(ctx_data, TOTAL_TRADES_OFFSET)?,
+            total_volume: read_u128(ctx_data, TOTAL_VOLUME_OFFSET)?,
+            liquidity: read_u128(ctx_data, LIQUIDITY_OFFSET)?,
+            max_fill: read_u128(ctx_data, MAX_FILL_OFFSET)?,
+            variance_tracker: read_pubkey(ctx_data, VARIANCE_TRACKER_OFFSET)?,
+            vol_index: read_pubkey(ctx_data, VOL_INDEX_OFFSET)?,
+            net_inventory: read_u64(ctx_data, NET_INVENTORY_OFFSET)? as i64,
+        })
+    }
+
+    pub fn lp_pda(&self) -> Pubkey {
+        self.lp_pda
+    }
+
+    pub fn base_spread(&self) -> u32 {
+        self.base_spread
+    }
+
+    pub fn vov_spread(&self) -> u32 {
+        self.vov_spread
+    }
+
+    pub fn max_spread(&self) -> u32 {
+        self.max_spread
+    }
+
+    pub fn impact_k(&self) -> u32 {
+        self.impact_k
+    }
+
+    pub fn current_vol(&self) -> u64 {
+        self.current_vol
+    }
+
+    pub fn vol_mark_price(&self) -> u64 {
+        self.vol_mark_price
+    }
+
+    pub fn last_update_slot(&self) -> u64 {
+        self.last_update_slot
+    }
+
+    pub fn regime(&self) -> VolatilityRegime {
+        self.regime
+    }
+
+    pub fn max_staleness(&self) -> u8 {
+        self.max_staleness
+    }
+
+    pub fn vol_7d_avg(&self) -> u64 {
+        self.vol_7d_avg
+    }
+
+    pub fn vol_30d_avg(&self) -> u64 {
+        self.vol_30d_avg
+    }
+
+    pub fn total_trades(&self) -> u64 {
+        self.total_trades
+    }
+
+    pub fn total_volume(&self) -> u128 {
+        self.total_volume
+    }
+
+    pub fn liquidity(&self) -> u128 {
+        self.liquidity
+    }
+
+    pub fn max_fill(&self) -> u128 {
+        self.max_fill
+    }
+
+    pub fn variance_tracker(&self) -> Pubkey {
+        self.variance_tracker
+    }
+
+    pub fn vol_index(&self) -> Pubkey {
+        self.vol_index
+    }
+
+    pub fn net_inventory(&self) -> i64 {
+        self.net_inventory
+    }
+}
+
+fn read_pubkey(ctx_data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    Ok(Pubkey::new_from_array(
+        ctx_data[offset..offset + 32].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_u32(ctx_data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    Ok(u32::from_le_bytes(
+        ctx_data[offset..offset + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_u64(ctx_data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    Ok(u64::from_le_bytes(
+        ctx_data[offset..offset + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+fn read_u128(ctx_data: &[u8], offset: usize) -> Result<u128, ProgramError> {
+    Ok(u128::from_le_bytes(
+        ctx_data[offset..offset + 16].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matcher_common::write_header;
+
+    #[test]
+    fn test_magic_matches_shared_layout() {
+        assert_eq!(matcher_shared::matcher_magic(matcher_shared::MatcherKind::Vol), VOL_MATCHER_MAGIC);
+        // A context this program initialized is identified as its own
+        let mut data = vec![0u8; CTX_SIZE];
+        matcher_common::write_header(&mut data, VOL_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(matcher_shared::identify(&data), Some(matcher_shared::MatcherKind::Vol));
+    }
+
+    #[test]
+    fn test_view_roundtrip_from_header() {
+        let lp = Pubkey::new_unique();
+        let tracker = Pubkey::new_unique();
+        let mut data = vec![0u8; CTX_SIZE];
+        write_header(&mut data, VOL_MATCHER_MAGIC, 0, &lp);
+        data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&20u32.to_le_bytes());
+        data[VOV_SPREAD_OFFSET..VOV_SPREAD_OFFSET + 4].copy_from_slice(&30u32.to_le_bytes());
+        data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&200u32.to_le_bytes());
+        data[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        data[REGIME_OFFSET] = VolatilityRegime::High as u8;
+        data[VARIANCE_TRACKER_OFFSET..VARIANCE_TRACKER_OFFSET + 32].copy_from_slice(tracker.as_ref());
+        data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&(-250i64).to_le_bytes());
+
+        let view = VolContextView::from_bytes(&data).unwrap();
+        assert_eq!(view.lp_pda(), lp);
+        assert_eq!(view.base_spread(), 20);
+        assert_eq!(view.vov_spread(), 30);
+        assert_eq!(view.max_spread(), 200);
+        assert_eq!(view.vol_mark_price(), 4_500_000_000);
+        assert_eq!(view.regime(), VolatilityRegime::High);
+        assert_eq!(view.variance_tracker(), tracker);
+        assert_eq!(view.net_inventory(), -250);
+    }
+
+    #[test]
+    fn test_view_rejects_bad_magic_and_length() {
+        let mut data = vec![0u8; CTX_SIZE];
+        assert_eq!(VolContextView::from_bytes(&data), Err(ProgramError::UninitializedAccount));
+        write_header(&mut data, VOL_MATCHER_MAGIC, 0, &Pubkey::new_unique());
+        assert_eq!(VolContextView::from_bytes(&data[..64]), Err(ProgramError::InvalidAccountData));
+    }
+}
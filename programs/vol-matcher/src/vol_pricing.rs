@@ -1,9 +1,12 @@
 use solana_program::{
-    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
-    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+    account_info::AccountInfo, bpf_loader_upgradeable, clock::Clock, entrypoint::ProgramResult, msg,
+    program::set_return_data, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
 
-use matcher_common::{verify_lp_pda as verify_lp_pda_common, verify_init_preconditions, write_header, write_exec_price, compute_exec_price};
+use matcher_common::{verify_lp_pda as verify_lp_pda_common, verify_init_preconditions, write_header, write_exec_price};
+use matcher_shared::{
+    bump_stats, create_pda_account, side_price, verify_ctx_owner, verify_ctx_size, verify_lp_pda_key,
+};
 
 use crate::errors::VolMatcherError;
 use crate::state::*;
@@ -12,6 +15,11 @@ use crate::state::*;
 /// Accounts:
 ///   [0] LP PDA (signer)
 ///   [1] Matcher context account (writable, 320 bytes)
+///   [2] Payer (signer, writable; only with a keeper authority)
+///   [3] Keeper PDA (writable, seeds `[KEEPER_SEED, context]`; only with a keeper authority)
+///   [4] System program (only with a keeper authority or fallback index)
+///   [5] Fallback PDA (writable, seeds `[FALLBACK_SEED, context]`; only with a fallback index)
+/// [2] and [4] are needed whenever either PDA is created; [3] is read only with a keeper.
 /// Data layout:
 ///   [0]    tag (0x02)
 ///   [1]    mode (u8: 0=RealizedVol, 1=ImpliedVol)
@@ -23,43 +31,86 @@ use crate::state::*;
 ///   [34..50] max_fill_abs (u128 LE)
 ///   [50..82] variance_tracker pubkey (32 bytes)
 ///   [82..114] vol_index pubkey (32 bytes)
+///   [114..116] skew_k_bps (u16 LE, optional, 0 = no inventory skew)
+///   [116..118] max_skew_bps (u16 LE, optional)
+///   [118..150] keeper_authority pubkey (32 bytes, optional, zero = no keeper rewards)
+///   [150..152] keeper_reward_per_sync_e6 (u16 LE, optional)
+///   [152]      max_staleness_slots (u8, optional, 0 = default 100; scaled by regime)
+///   [153..155] max_matches_per_sync (u16 LE, optional, 0 = unlimited)
+///   [155..165] regime_multipliers (5 x u16 LE %, VeryLow..Extreme, optional; all zero = built-in)
+///   [165..197] fallback_vol_index pubkey (32 bytes, optional, zero = no fallback oracle)
+///   [197..199] fallback_penalty_bps (u16 LE, optional: added to the spread while on the fallback)
 pub fn process_init(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 114 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
+    check_init_len(data.len())?;
 
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
     // Verify writable, sized, and not already initialized
+    verify_ctx_owner(ctx_account, program_id, "VOL-MATCHER")?;
     verify_init_preconditions(ctx_account, VOL_MATCHER_MAGIC, "VOL-MATCHER")?;
+    verify_ctx_size(ctx_account, "VOL-MATCHER")?;
+    verify_lp_pda_key(lp_pda.key, "VOL-MATCHER")?;
+
+    if data[1] > MAX_MODE {
+        msg!("VOL-MATCHER: Invalid mode {}, expected 0..={}", data[1], MAX_MODE);
+        return Err(VolMatcherError::InvalidMode.into());
+    }
+
+    let impact_k = u32::from_le_bytes(data[14..18].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let liquidity = u128::from_le_bytes(data[18..34].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    validate_liquidity_config(impact_k, liquidity)?;
+    let multipliers = if data.len() >= 165 { parse_regime_multipliers(&data[155..165])? } else { [0; 5] };
+
+    // Keeper rewards (optional trailing config) live in the keeper PDA
+    if data.len() >= 152 {
+        let keeper = Pubkey::new_from_array(data[118..150].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+        if keeper != Pubkey::default() {
+            create_keeper(program_id, accounts, &keeper, &data[150..152])?;
+        }
+    }
+
+    // Fallback oracle (optional trailing config) lives in the fallback PDA
+    if data.len() >= 199 {
+        let fallback = Pubkey::new_from_array(data[165..197].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+        if fallback != Pubkey::default() {
+            create_fallback(program_id, accounts, &fallback, &data[197..199])?;
+        }
+    }
 
     let mut ctx_data = ctx_account.try_borrow_mut_data()?;
 
     // Write standard header (return data, magic, version, mode, padding, LP PDA)
     write_header(&mut ctx_data, VOL_MATCHER_MAGIC, data[1], lp_pda.key);
+    ctx_data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&CTX_VERSION.to_le_bytes());
 
     // Spread params
     ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&data[2..6]);
     ctx_data[VOV_SPREAD_OFFSET..VOV_SPREAD_OFFSET + 4].copy_from_slice(&data[6..10]);
     ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&data[10..14]);
     ctx_data[IMPACT_K_OFFSET..IMPACT_K_OFFSET + 4].copy_from_slice(&data[14..18]);
+    for (i, multiplier) in multipliers.iter().enumerate() {
+        let offset = REGIME_MULTIPLIERS_OFFSET + i * 2;
+        ctx_data[offset..offset + 2].copy_from_slice(&multiplier.to_le_bytes());
+    }
 
     // Initialize vol data to zero
     ctx_data[CURRENT_VOL_OFFSET..CURRENT_VOL_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
     ctx_data[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
     ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
     ctx_data[REGIME_OFFSET] = 2; // Normal
-    ctx_data[REGIME_OFFSET + 1..REGIME_OFFSET + 8].fill(0); // padding
     ctx_data[VOL_7D_AVG_OFFSET..VOL_7D_AVG_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
     ctx_data[VOL_30D_AVG_OFFSET..VOL_30D_AVG_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    ctx_data[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2].copy_from_slice(&0u16.to_le_bytes());
+    ctx_data[TOTAL_TRADES_OFFSET..TOTAL_TRADES_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    ctx_data[TOTAL_VOLUME_OFFSET..TOTAL_VOLUME_OFFSET + 16].copy_from_slice(&0u128.to_le_bytes());
 
     // Liquidity + max fill
     ctx_data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&data[18..34]);
@@ -69,10 +120,27 @@ pub fn process_init(
     ctx_data[VARIANCE_TRACKER_OFFSET..VARIANCE_TRACKER_OFFSET + 32].copy_from_slice(&data[50..82]);
     ctx_data[VOL_INDEX_OFFSET..VOL_INDEX_OFFSET + 32].copy_from_slice(&data[82..114]);
 
-    // Zero reserved
-    ctx_data[272..CTX_SIZE].fill(0);
+    // Inventory skew (optional trailing config)
+    ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&0i64.to_le_bytes());
+    if data.len() >= 118 {
+        ctx_data[SKEW_K_OFFSET..SKEW_K_OFFSET + 2].copy_from_slice(&data[114..116]);
+        ctx_data[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2].copy_from_slice(&data[116..118]);
+    } else {
+        ctx_data[SKEW_K_OFFSET..MAX_SKEW_OFFSET + 2].fill(0);
+    }
+
+    // Regime-scaled staleness base (optional trailing config)
+    ctx_data[MAX_STALENESS_OFFSET] = if data.len() >= 153 { data[152] } else { 0 };
 
-    msg!(
+    // Sync heartbeat (optional trailing config)
+    if data.len() >= 155 {
+        ctx_data[MAX_MATCHES_PER_SYNC_OFFSET..MAX_MATCHES_PER_SYNC_OFFSET + 2].copy_from_slice(&data[153..155]);
+    } else {
+        ctx_data[MAX_MATCHES_PER_SYNC_OFFSET..MAX_MATCHES_PER_SYNC_OFFSET + 2].fill(0);
+    }
+    ctx_data[REGIME_MULTIPLIERS_OFFSET + 10..CTX_SIZE].fill(0);
+
+    matcher_log!(
         "INIT: lp_pda={} mode={} base_spread={} vov_spread={} max_spread={}",
         lp_pda.key,
         data[1],
@@ -84,14 +152,119 @@ pub fn process_init(
     Ok(())
 }
 
+/// Create the keeper PDA for a keeper authority supplied at init, with its
+/// reward rate and an empty accumulator
+fn create_keeper(program_id: &Pubkey, accounts: &[AccountInfo], keeper: &Pubkey, reward_per_sync: &[u8]) -> ProgramResult {
+    let ctx_account = &accounts[1];
+    let keeper_pda = accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let bump = create_ctx_pda(program_id, accounts, keeper_pda, KEEPER_SEED, KEEPER_SIZE, "Keeper")?;
+
+    let mut keeper_data = keeper_pda.try_borrow_mut_data()?;
+    keeper_data[..KEEPER_SIZE].fill(0);
+    keeper_data[KEEPER_MAGIC_OFFSET..KEEPER_MAGIC_OFFSET + 8].copy_from_slice(&KEEPER_MAGIC.to_le_bytes());
+    keeper_data[KEEPER_BUMP_OFFSET] = bump;
+    keeper_data[KEEPER_REWARD_PER_SYNC_OFFSET..KEEPER_REWARD_PER_SYNC_OFFSET + 2].copy_from_slice(reward_per_sync);
+    keeper_data[KEEPER_AUTHORITY_OFFSET..KEEPER_AUTHORITY_OFFSET + 32].copy_from_slice(keeper.as_ref());
+
+    matcher_log!("INIT_KEEPER: ctx={} keeper={}", ctx_account.key, keeper);
+
+    Ok(())
+}
+
+/// Create the fallback PDA for a fallback index supplied at init, with its
+/// penalty and no mark yet: Match can't use it until the first Fallback Sync
+fn create_fallback(program_id: &Pubkey, accounts: &[AccountInfo], fallback_index: &Pubkey, penalty_bps: &[u8]) -> ProgramResult {
+    let ctx_account = &accounts[1];
+    let fallback_pda = accounts.get(5).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let bump = create_ctx_pda(program_id, accounts, fallback_pda, FALLBACK_SEED, FALLBACK_SIZE, "Fallback")?;
+
+    let mut fallback_data = fallback_pda.try_borrow_mut_data()?;
+    fallback_data[..FALLBACK_SIZE].fill(0);
+    fallback_data[FALLBACK_MAGIC_OFFSET..FALLBACK_MAGIC_OFFSET + 8].copy_from_slice(&FALLBACK_MAGIC.to_le_bytes());
+    fallback_data[FALLBACK_BUMP_OFFSET] = bump;
+    fallback_data[FALLBACK_PENALTY_OFFSET..FALLBACK_PENALTY_OFFSET + 2].copy_from_slice(penalty_bps);
+    fallback_data[FALLBACK_VOL_INDEX_OFFSET..FALLBACK_VOL_INDEX_OFFSET + 32].copy_from_slice(fallback_index.as_ref());
+
+    matcher_log!("INIT_FALLBACK: ctx={} fallback_index={}", ctx_account.key, fallback_index);
+
+    Ok(())
+}
+
+/// Create the context's `seed` PDA at `pda`, paid by init's payer [2] through
+/// the system program [4]; returns the bump for the caller to store
+fn create_ctx_pda<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    pda: &AccountInfo<'a>,
+    seed: &[u8],
+    size: usize,
+    name: &str,
+) -> Result<u8, ProgramError> {
+    if accounts.len() < 5 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let ctx_account = &accounts[1];
+    let payer = &accounts[2];
+    let system_program = &accounts[4];
+
+    if !payer.is_signer {
+        msg!("VOL-MATCHER: Payer must be a signer to create the {}", name);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected, bump) = Pubkey::find_program_address(&[seed, ctx_account.key.as_ref()], program_id);
+    if *pda.key != expected {
+        msg!("VOL-MATCHER: {} must be the PDA {}", name, expected);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !pda.data_is_empty() {
+        msg!("VOL-MATCHER: {} already set for {}", name, ctx_account.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(program_id, payer, pda, system_program, size, &[seed, ctx_account.key.as_ref(), &[bump]])?;
+    Ok(bump)
+}
+
+/// Operator multiplier table: all zero keeps the built-in one; otherwise every
+/// regime needs a multiplier, since a 0 would silently drop the vol-of-vol spread
+fn parse_regime_multipliers(data: &[u8]) -> Result<[u16; 5], ProgramError> {
+    let mut multipliers = [0u16; 5];
+    for (multiplier, bytes) in multipliers.iter_mut().zip(data.chunks_exact(2)) {
+        *multiplier = u16::from_le_bytes([bytes[0], bytes[1]]);
+    }
+    if multipliers.contains(&0) && multipliers != [0; 5] {
+        msg!("VOL-MATCHER: Regime multiplier table {:?} has a zero entry", multipliers);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(multipliers)
+}
+
+/// Impact math divides by liquidity: refuse an enabled impact model without a pool.
+fn validate_liquidity_config(impact_k_bps: u32, liquidity_notional_e6: u128) -> Result<(), ProgramError> {
+    if impact_k_bps > 0 && liquidity_notional_e6 == 0 {
+        msg!("VOL-MATCHER: impact_k_bps={} requires nonzero liquidity", impact_k_bps);
+        return Err(VolMatcherError::InvalidLiquidityConfig.into());
+    }
+    Ok(())
+}
+
 /// Tag 0x00: Execute match — compute vol-adjusted execution price
 /// Accounts:
 ///   [0] LP PDA (signer)
 ///   [1] Matcher context account (writable)
+///   [2] Global config PDA (optional: staleness default when the context's is 0;
+///       not read when the context sets its own, so any account holds the slot for [3])
+///   [3] Fallback PDA (optional: priced off, with its penalty, while the primary is stale)
+/// Data layout:
+///   [0]    tag (0x00)
+///   [1..9] trade_size_abs (u64 LE, optional)
+///   [9]    direction (u8, optional: 0=buy from LP, 1=sell to LP)
+///   [10..18] limit_price (u64 LE, optional: worst acceptable exec price for the direction)
 pub fn process_match(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 2 {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -100,20 +273,281 @@ pub fn process_match(
     let lp_pda = &accounts[0];
     let ctx_account = &accounts[1];
 
-    // Verify LP PDA signature, magic, and PDA match
-    verify_lp_pda_common(lp_pda, ctx_account, VOL_MATCHER_MAGIC, "VOL-MATCHER")?;
+    // Verify LP PDA signature, magic, PDA match, and context version
+    guard_match(program_id, lp_pda, ctx_account)?;
 
-    // Read pricing parameters
+    // Reentrancy guard: held until this match completes
+    enter_match_guard(&mut ctx_account.try_borrow_mut_data()?)?;
+
+    let trade = parse_trade(data)?;
+    let direction = trade.map_or(DIRECTION_BUY, |(_, d)| d);
+
+    let clock = Clock::get()?;
     let ctx_data = ctx_account.try_borrow_data()?;
-    let base_spread = u32::from_le_bytes(
-        ctx_data[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
-    );
-    let vov_spread = u32::from_le_bytes(
-        ctx_data[VOV_SPREAD_OFFSET..VOV_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    let regime = VolatilityRegime::from_u8(ctx_data[REGIME_OFFSET]);
+    let staleness = staleness_base(&ctx_data, accounts.get(2), program_id)?;
+    let fallback = read_fallback(accounts.get(3), ctx_account.key, program_id)?;
+    let (exec_price, side_spread, skew) = quote(&ctx_data, clock.slot, direction, staleness, fallback)?;
+
+    drop(ctx_data);
+
+    check_limit_price(exec_price, direction, parse_limit_price(data)?)?;
+
+    // Write execution price to return buffer using shared utility
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    record_matches_since_sync(&mut ctx_data, 1)?;
+    write_exec_price(&mut ctx_data, exec_price);
+    bump_stats(&mut ctx_data, TOTAL_TRADES_OFFSET, TOTAL_VOLUME_OFFSET, 1, trade.map_or(0, |(size, _)| size as u128));
+
+    if let Some((size, direction)) = trade {
+        apply_fill_to_inventory(&mut ctx_data, size, direction)?;
+    }
+
+    // Release the reentrancy guard
+    ctx_data[MATCH_GUARD_OFFSET] = 0;
+
+    matcher_log!(
+        "MATCH: price={} spread={} skew={} direction={} regime={:?}",
+        exec_price,
+        side_spread,
+        skew,
+        direction,
+        regime
     );
-    let max_spread = u32::from_le_bytes(
-        ctx_data[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+
+    Ok(())
+}
+
+/// Every check Match and MatchBatch make before pricing, in one place:
+/// context owner, LP PDA signer, magic, PDA match, and context version
+fn guard_match(program_id: &Pubkey, lp_pda: &AccountInfo, ctx_account: &AccountInfo) -> ProgramResult {
+    verify_ctx_owner(ctx_account, program_id, "VOL-MATCHER")?;
+    verify_lp_pda_common(lp_pda, ctx_account, VOL_MATCHER_MAGIC, "VOL-MATCHER")?;
+    check_ctx_version(&ctx_account.try_borrow_data()?)
+}
+
+/// Pricing reads this layout only; an older context's fields sit at other offsets
+fn check_ctx_version(ctx_data: &[u8]) -> ProgramResult {
+    let version = read_version(ctx_data)?;
+    if version != CTX_VERSION {
+        msg!("VOL-MATCHER: Context version {}, expected {}", version, CTX_VERSION);
+        return Err(VolMatcherError::UnsupportedVersion.into());
+    }
+    Ok(())
+}
+
+/// Dead-man's switch: count `legs` more matches against the last sync, refusing
+/// any that would go past `max_matches_per_sync` until a keeper syncs again.
+/// The count keeps running with the limit off so enabling it later bites at once.
+fn record_matches_since_sync(ctx_data: &mut [u8], legs: u16) -> ProgramResult {
+    let limit = u16::from_le_bytes(
+        ctx_data[MAX_MATCHES_PER_SYNC_OFFSET..MAX_MATCHES_PER_SYNC_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let count = u16::from_le_bytes(
+        ctx_data[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    )
+    .saturating_add(legs);
+    if limit != 0 && count > limit {
+        msg!("VOL-MATCHER: {} matches since the last sync exceeds {}; keeper sync required", count, limit);
+        return Err(VolMatcherError::SyncHeartbeatRequired.into());
+    }
+    ctx_data[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2].copy_from_slice(&count.to_le_bytes());
+    Ok(())
+}
+
+/// Take the reentrancy guard for the duration of a match. A nested match on
+/// the same context finds it held and is rejected before touching any stats.
+fn enter_match_guard(ctx_data: &mut [u8]) -> ProgramResult {
+    if ctx_data[MATCH_GUARD_OFFSET] != 0 {
+        msg!("VOL-MATCHER: Re-entrant match rejected");
+        return Err(VolMatcherError::MatchReentered.into());
+    }
+    ctx_data[MATCH_GUARD_OFFSET] = 1;
+    Ok(())
+}
+
+/// Tag 0x0A: Batch match — price several basket legs under one LP PDA check
+/// Accounts:
+///   [0] LP PDA (signer)
+///   [1] Matcher context account (writable)
+///   [2] Global config PDA (optional, as in Match)
+///   [3] Fallback PDA (optional, as in Match)
+/// Data layout:
+///   [0]    tag (0x0A)
+///   [1]    count (u8, 0..=MAX_BATCH_LEGS)
+///   [2..]  count x leg: trade_size_abs (u64 LE) then direction (u8: 0=buy from LP, 1=sell to LP)
+/// Legs are priced in order, each after the previous legs' fills have moved
+/// the inventory, so a batch prices exactly as the same legs matched one by one.
+/// Exec prices are written to the return region as consecutive u64s (leg i at i*8).
+pub fn process_match_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    guard_match(program_id, lp_pda, ctx_account)?;
+
+    let legs = parse_batch(data)?;
+    if legs.is_empty() {
+        matcher_log!("MATCH_BATCH: empty batch");
+        return Ok(());
+    }
+
+    let clock = Clock::get()?;
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    let staleness = staleness_base(&ctx_data, accounts.get(2), program_id)?;
+    let fallback = read_fallback(accounts.get(3), ctx_account.key, program_id)?;
+    let mut prices = Vec::with_capacity(legs.len());
+    for &(size, direction) in &legs {
+        let (exec_price, _, _) = quote(&ctx_data, clock.slot, direction, staleness, fallback)?;
+        apply_fill_to_inventory(&mut ctx_data, size, direction)?;
+        prices.push(exec_price);
+    }
+    record_matches_since_sync(&mut ctx_data, prices.len() as u16)?;
+    write_batch_prices(&mut ctx_data, &prices);
+    let volume = legs.iter().map(|&(size, _)| size as u128).sum();
+    bump_stats(&mut ctx_data, TOTAL_TRADES_OFFSET, TOTAL_VOLUME_OFFSET, prices.len() as u64, volume);
+
+    matcher_log!("MATCH_BATCH: legs={} first_price={}", prices.len(), prices[0]);
+
+    Ok(())
+}
+
+/// Tag 0x0C: Quote both sides -- bid, ask and mid for a two-sided spread display.
+/// Read-only apart from the return buffer: no inventory, volume or reward changes.
+/// Vol pricing has no size impact, so the quote holds for any trade size.
+/// Accounts:
+///   [0] LP PDA (signer)
+///   [1] Matcher context account (writable -- return buffer only)
+///   [2] Global config PDA (optional, as in Match)
+///   [3] Fallback PDA (optional, as in Match)
+/// Data layout:
+///   [0] tag (0x0C)
+/// Return: bid at bytes 0..8, ask at 8..16, mid (the mark priced off) at 16..24
+pub fn process_quote_both(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let lp_pda = &accounts[0];
+    let ctx_account = &accounts[1];
+
+    guard_match(program_id, lp_pda, ctx_account)?;
+
+    let clock = Clock::get()?;
+    let (bid, ask, mid) = {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        let staleness = staleness_base(&ctx_data, accounts.get(2), program_id)?;
+        quote_both(&ctx_data, clock.slot, staleness, read_fallback(accounts.get(3), ctx_account.key, program_id)?)?
+    };
+
+    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
+    write_quote_both(&mut ctx_data, bid, ask, mid);
+
+    matcher_log!("QUOTE_BOTH: bid={} ask={} mid={}", bid, ask, mid);
+
+    Ok(())
+}
+
+/// `(bid, ask, mid)`: each side through the same skew-aware pricing as Match
+fn quote_both(
+    ctx_data: &[u8],
+    slot: u64,
+    staleness_base: u8,
+    fallback: Option<FallbackQuote>,
+) -> Result<(u64, u64, u64), ProgramError> {
+    let (bid, _, _) = quote(ctx_data, slot, DIRECTION_SELL, staleness_base, fallback)?;
+    let (ask, _, _) = quote(ctx_data, slot, DIRECTION_BUY, staleness_base, fallback)?;
+    let (mid, _) = pricing_mark(ctx_data, slot, staleness_base, fallback)?;
+    Ok((bid, ask, mid))
+}
+
+fn write_quote_both(ctx_data: &mut [u8], bid: u64, ask: u64, mid: u64) {
+    ctx_data[QUOTE_BID_RETURN_OFFSET..QUOTE_BID_RETURN_OFFSET + 8].copy_from_slice(&bid.to_le_bytes());
+    ctx_data[QUOTE_ASK_RETURN_OFFSET..QUOTE_ASK_RETURN_OFFSET + 8].copy_from_slice(&ask.to_le_bytes());
+    ctx_data[QUOTE_MID_RETURN_OFFSET..QUOTE_MID_RETURN_OFFSET + 8].copy_from_slice(&mid.to_le_bytes());
+}
+
+/// Tag 0x0D: Query spread -- the spread Match would charge at the stored
+/// regime, for analytics. Unlike Quote Both it needs no synced or fresh mark.
+/// Read-only and signer-free: the context's return region is left alone.
+/// Accounts:
+///   [0] Matcher context account
+/// Data layout:
+///   [0] tag (0x0D)
+/// Return data (`set_return_data`): spread bps (before inventory skew) as u64 LE
+pub fn process_query_spread(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let ctx_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    verify_ctx_owner(ctx_account, program_id, "VOL-MATCHER")?;
+
+    let ctx_data = ctx_account.try_borrow_data()?;
+    if !verify_magic(&ctx_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_ctx_version(&ctx_data)?;
+    let spread = compute_current_spread(&ctx_data)?;
+    set_return_data(&spread.to_le_bytes());
+
+    matcher_log!("QUERY_SPREAD: spread={} regime={:?}", spread, VolatilityRegime::from_u8(ctx_data[REGIME_OFFSET]));
+
+    Ok(())
+}
+
+/// Compute one side's exec price from the context: picks the mark (see
+/// `pricing_mark()`), then applies regime spread, any fallback penalty, and
+/// inventory skew.
+/// Returns `(exec_price, side_spread, skew)`.
+fn quote(
+    ctx_data: &[u8],
+    slot: u64,
+    direction: u8,
+    staleness_base: u8,
+    fallback: Option<FallbackQuote>,
+) -> Result<(u64, u64, i64), ProgramError> {
+    let (vol_mark, penalty) = pricing_mark(ctx_data, slot, staleness_base, fallback)?;
+
+    // Dynamic spread based on vol regime, widened while on the fallback
+    let total_spread = compute_current_spread(ctx_data)?.saturating_add(penalty as u64);
+
+    // Inventory skew: LP net long tightens the ask and widens the bid
+    let skew = read_inventory_skew(ctx_data)?;
+    let side_spread = skewed_spread(total_spread, skew, direction);
+
+    let exec_price = side_price(vol_mark, side_spread, direction, false).map_err(VolMatcherError::from)?;
+    Ok((exec_price, side_spread, skew))
+}
+
+/// The fallback as Match prices it: `(vol_mark, update_slot, penalty_bps)`
+type FallbackQuote = (u64, u64, u16);
+
+/// The mark to price off and the penalty (bps) it carries: the primary's when
+/// fresh (against `staleness_base`, see `staleness_base()`); else the
+/// fallback's, penalized, when one is passed and fresh by the same
+/// regime-scaled limit; else the primary is stale and pricing halts.
+fn pricing_mark(
+    ctx_data: &[u8],
+    slot: u64,
+    staleness_base: u8,
+    fallback: Option<FallbackQuote>,
+) -> Result<(u64, u16), ProgramError> {
     let vol_mark = u64::from_le_bytes(
         ctx_data[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
@@ -125,45 +559,163 @@ pub fn process_match(
         return Err(VolMatcherError::OracleNotSynced.into());
     }
 
-    // Check oracle staleness (reject if > 100 slots old)
+    // Check oracle staleness against the regime-scaled limit
     let last_update = u64::from_le_bytes(
         ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
-    let clock = Clock::get()?;
-    if clock.slot.saturating_sub(last_update) > 100 {
-        msg!("VOL-MATCHER: Oracle stale — last update slot {}, current {}", last_update, clock.slot);
-        return Err(VolMatcherError::OracleStale.into());
+    let max_staleness = max_staleness_slots(staleness_base, regime);
+    if slot.saturating_sub(last_update) <= max_staleness {
+        return Ok((vol_mark, 0));
+    }
+    if let Some((fallback_mark, fallback_update, penalty)) = fallback {
+        if fallback_mark != 0 && slot.saturating_sub(fallback_update) <= max_staleness {
+            msg!("VOL-MATCHER: Primary stale since slot {}; pricing off the fallback (+{} bps)", last_update, penalty);
+            return Ok((fallback_mark, penalty));
+        }
     }
+    msg!(
+        "VOL-MATCHER: Oracle stale — last update slot {}, current {}, limit {}",
+        last_update,
+        slot,
+        max_staleness
+    );
+    Err(VolMatcherError::OracleStale.into())
+}
 
-    // Dynamic spread based on vol regime
-    let regime_multiplier = regime.spread_multiplier();
-    let adjusted_vov = (vov_spread as u64)
-        .checked_mul(regime_multiplier)
-        .ok_or(VolMatcherError::ArithmeticOverflow)?
-        / 100;
+/// The context's fallback, when Match was passed its PDA
+fn read_fallback(
+    fallback_pda: Option<&AccountInfo>,
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Option<FallbackQuote>, ProgramError> {
+    let Some(fallback_pda) = fallback_pda else {
+        return Ok(None);
+    };
+    let fallback_data = fallback_pda.try_borrow_data()?;
+    verify_fallback_pda(fallback_pda, &fallback_data, ctx_key, program_id)?;
+    Ok(Some(read_fallback_quote(&fallback_data)?))
+}
 
-    let total_spread = std::cmp::min(
-        (base_spread as u64).saturating_add(adjusted_vov),
-        max_spread as u64,
-    );
+/// Parse a count-prefixed list of trade sizes; rejects counts the return region can't hold
+fn parse_batch(data: &[u8]) -> Result<Vec<(u64, u8)>, ProgramError> {
+    let count = *data.get(1).ok_or(ProgramError::InvalidInstructionData)? as usize;
+    if count > MAX_BATCH_LEGS {
+        msg!("VOL-MATCHER: Batch of {} exceeds {} legs", count, MAX_BATCH_LEGS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if data.len() < 2 + count * BATCH_LEG_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    data[2..2 + count * BATCH_LEG_LEN]
+        .chunks_exact(BATCH_LEG_LEN)
+        .map(|leg| {
+            let size = u64::from_le_bytes(leg[..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+            if leg[8] > DIRECTION_SELL {
+                msg!("VOL-MATCHER: Invalid direction {}", leg[8]);
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            Ok((size, leg[8]))
+        })
+        .collect()
+}
 
-    // Compute execution price using shared utility
-    let exec_price = compute_exec_price(vol_mark, total_spread)?;
+/// Leg i's price goes to return bytes `i*8..i*8+8`
+fn write_batch_prices(ctx_data: &mut [u8], prices: &[u64]) {
+    for (i, price) in prices.iter().enumerate() {
+        ctx_data[i * 8..i * 8 + 8].copy_from_slice(&price.to_le_bytes());
+    }
+}
 
-    drop(ctx_data);
+/// Parse the optional limit price following the direction byte; None when
+/// omitted. A partial limit is rejected rather than read as no protection.
+fn parse_limit_price(data: &[u8]) -> Result<Option<u64>, ProgramError> {
+    if data.len() <= 10 {
+        return Ok(None);
+    }
+    if data.len() < 18 {
+        msg!("VOL-MATCHER: Truncated limit price ({} of 8 bytes)", data.len() - 10);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(Some(u64::from_le_bytes(data[10..18].try_into().map_err(|_| ProgramError::InvalidInstructionData)?)))
+}
 
-    // Write execution price to return buffer using shared utility
-    let mut ctx_data = ctx_account.try_borrow_mut_data()?;
-    write_exec_price(&mut ctx_data, exec_price);
+/// Reject a fill worse than the trader's limit: a buy above it, a sell below it.
+/// The limit itself is acceptable.
+fn check_limit_price(exec_price: u64, direction: u8, limit_price: Option<u64>) -> ProgramResult {
+    let Some(limit) = limit_price else {
+        return Ok(());
+    };
+    let exceeded = if direction == DIRECTION_BUY { exec_price > limit } else { exec_price < limit };
+    if exceeded {
+        msg!("VOL-MATCHER: Exec price {} past limit {} (direction {})", exec_price, limit, direction);
+        return Err(VolMatcherError::SlippageExceeded.into());
+    }
+    Ok(())
+}
 
-    msg!(
-        "MATCH: price={} spread={} regime={:?} vol_mark={}",
-        exec_price,
-        total_spread,
-        regime,
-        vol_mark
+/// Parse optional `(trade_size_abs, direction)` from match data; None when omitted
+fn parse_trade(data: &[u8]) -> Result<Option<(u64, u8)>, ProgramError> {
+    if data.len() < 10 {
+        return Ok(None);
+    }
+    let size = u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let direction = data[9];
+    if direction > DIRECTION_SELL {
+        msg!("VOL-MATCHER: Invalid direction {}", direction);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(Some((size, direction)))
+}
+
+/// Signed inventory skew (bps) from stored state: `net_inventory * skew_k / liquidity`,
+/// clamped to ±max_skew. Zero when skew is unconfigured or liquidity is zero.
+fn read_inventory_skew(ctx_data: &[u8]) -> Result<i64, ProgramError> {
+    let net_inventory = i64::from_le_bytes(
+        ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let skew_k = u16::from_le_bytes(
+        ctx_data[SKEW_K_OFFSET..SKEW_K_OFFSET + 2].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let max_skew = u16::from_le_bytes(
+        ctx_data[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let liquidity = u128::from_le_bytes(
+        ctx_data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok(inventory_skew_bps(net_inventory, skew_k, max_skew, liquidity))
+}
+
+fn inventory_skew_bps(net_inventory: i64, skew_k_bps: u16, max_skew_bps: u16, liquidity: u128) -> i64 {
+    if skew_k_bps == 0 || liquidity == 0 {
+        return 0;
+    }
+    let liquidity = i128::try_from(liquidity).unwrap_or(i128::MAX);
+    let raw = (net_inventory as i128) * (skew_k_bps as i128) / liquidity;
+    raw.clamp(-(max_skew_bps as i128), max_skew_bps as i128) as i64
+}
+
+/// Apply skew to one side: positive skew (LP long) subtracts from the ask, adds to the bid
+fn skewed_spread(spread: u64, skew_bps: i64, direction: u8) -> u64 {
+    let adj = if direction == DIRECTION_BUY { -skew_bps } else { skew_bps };
+    if adj >= 0 {
+        spread.saturating_add(adj as u64)
+    } else {
+        spread.saturating_sub(adj.unsigned_abs())
+    }
+}
 
+/// LP inventory moves opposite the taker: a taker buy makes the LP shorter
+fn apply_fill_to_inventory(ctx_data: &mut [u8], size: u64, direction: u8) -> Result<(), ProgramError> {
+    let delta = i64::try_from(size).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let net_inventory = i64::from_le_bytes(
+        ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    let updated = if direction == DIRECTION_BUY {
+        net_inventory.saturating_sub(delta)
+    } else {
+        net_inventory.saturating_add(delta)
+    };
+    ctx_data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&updated.to_le_bytes());
     Ok(())
 }
 
@@ -172,6 +724,8 @@ pub fn process_match(
 ///   [0] Matcher context account (writable)
 ///   [1] Sigma VarianceTracker account (read)
 ///   [2] Sigma VolatilityIndex account (read)
+///   [3] Keeper authority (signer, optional — credits keeper rewards)
+///   [4] Keeper PDA (writable, required with the keeper)
 /// Data layout:
 ///   [0]    tag (0x03)
 ///   [1..9] current_vol_bps (u64 LE) — from keeper reading Sigma oracle
@@ -179,15 +733,17 @@ pub fn process_match(
 ///   [17]   regime (u8)
 ///   [18..26] vol_7d_avg_bps (u64 LE)
 ///   [26..34] vol_30d_avg_bps (u64 LE)
+///   [34..42] update_slot (u64 LE): the slot the keeper read Sigma at; must be
+///            after the stored last_update_slot and not past the current slot
 pub fn process_oracle_sync(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
     if accounts.len() < 3 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
-    if data.len() < 34 {
+    if data.len() < 42 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
@@ -205,6 +761,7 @@ pub fn process_oracle_sync(
         if !verify_magic(&ctx_data) {
             return Err(ProgramError::UninitializedAccount);
         }
+        check_ctx_version(&ctx_data)?;
 
         // Verify passed accounts match stored oracle accounts
         let stored_vt = Pubkey::new_from_array(
@@ -228,6 +785,7 @@ pub fn process_oracle_sync(
     let regime = data[17];
     let vol_7d = u64::from_le_bytes(data[18..26].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
     let vol_30d = u64::from_le_bytes(data[26..34].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let update_slot = u64::from_le_bytes(data[34..42].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
 
     // Validate regime
     if regime > 4 {
@@ -240,60 +798,401 @@ pub fn process_oracle_sync(
     let old_vol = u64::from_le_bytes(
         ctx_data[CURRENT_VOL_OFFSET..CURRENT_VOL_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
     );
+    let last_update = u64::from_le_bytes(
+        ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    check_sync_slot(last_update, update_slot, clock.slot)?;
+
+    // A re-signed copy of fresh data changes nothing; writing it would only
+    // restart the staleness clock (and pay a keeper reward) for no new information.
+    // It is still a keeper heartbeat, so the match counter resets.
+    let incoming = (current_vol, vol_mark, regime, vol_7d, vol_30d);
+    if is_noop_resync(&ctx_data, incoming, last_update, update_slot)? {
+        ctx_data[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2].copy_from_slice(&0u16.to_le_bytes());
+        matcher_log!("ORACLE_SYNC: no-op sync read at slot {} (unchanged since {})", update_slot, last_update);
+        return Ok(());
+    }
+
+    // Keeper reward: only when the configured keeper co-signs the sync
+    let mut reward = 0u64;
+    if let Some(keeper) = accounts.get(3) {
+        let keeper_pda = accounts.get(4).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let mut keeper_data = keeper_pda.try_borrow_mut_data()?;
+        verify_keeper(keeper, keeper_pda, &keeper_data, ctx_account.key, program_id)?;
+        reward = accrue_keeper_reward(&mut keeper_data, last_update, update_slot)?;
+    }
 
     ctx_data[CURRENT_VOL_OFFSET..CURRENT_VOL_OFFSET + 8].copy_from_slice(&current_vol.to_le_bytes());
     ctx_data[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&vol_mark.to_le_bytes());
-    ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&clock.slot.to_le_bytes());
+    ctx_data[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&update_slot.to_le_bytes());
     ctx_data[REGIME_OFFSET] = regime;
     ctx_data[VOL_7D_AVG_OFFSET..VOL_7D_AVG_OFFSET + 8].copy_from_slice(&vol_7d.to_le_bytes());
     ctx_data[VOL_30D_AVG_OFFSET..VOL_30D_AVG_OFFSET + 8].copy_from_slice(&vol_30d.to_le_bytes());
+    ctx_data[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2].copy_from_slice(&0u16.to_le_bytes());
 
-    msg!(
-        "ORACLE_SYNC: old_vol={} new_vol={} mark={} regime={}",
+    matcher_log!(
+        "ORACLE_SYNC: old_vol={} new_vol={} mark={} regime={} keeper_reward={}",
         old_vol,
         current_vol,
         vol_mark,
-        regime
+        regime,
+        reward
     );
 
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::state::*;
-    use matcher_common::compute_exec_price;
-
-    // ---------------------------------------------------------------------------
-    // Helper: replicate the pricing math from process_match for unit-testing
-    // without needing Solana account scaffolding.
-    // ---------------------------------------------------------------------------
-    fn calc_exec_price(
-        base_spread: u32,
-        vov_spread: u32,
-        max_spread: u32,
-        regime: VolatilityRegime,
-        vol_mark: u64,
-    ) -> u64 {
-        let regime_multiplier = regime.spread_multiplier();
-        let adjusted_vov = (vov_spread as u64) * regime_multiplier / 100;
-        let total_spread = std::cmp::min(
-            (base_spread as u64).saturating_add(adjusted_vov),
-            max_spread as u64,
-        );
-        compute_exec_price(vol_mark, total_spread).unwrap()
+/// Syncs are ordered by the slot the keeper read Sigma at: one not read after
+/// the stored update is a replay or arrived out of order, in whatever slot it
+/// lands, and must not overwrite newer vol data. A slot from the future is no
+/// reading at all.
+fn check_sync_slot(last_update_slot: u64, update_slot: u64, slot: u64) -> ProgramResult {
+    if update_slot <= last_update_slot {
+        msg!("VOL-MATCHER: Stale sync read at slot {} (last update read at {})", update_slot, last_update_slot);
+        return Err(VolMatcherError::StaleUpdate.into());
     }
-
-    // -----------------------------------------------------------------------
-    // 1. Normal regime
-    // -----------------------------------------------------------------------
-    #[test]
-    fn test_normal_regime_pricing() {
-        let price = calc_exec_price(20, 30, 200, VolatilityRegime::Normal, 4_500_000_000);
-        // adjusted_vov = 30 * 100 / 100 = 30
-        // total_spread = min(20 + 30, 200) = 50
-        // exec_price   = 4_500_000_000 * 10050 / 10000 = 4_522_500_000
-        assert_eq!(price, 4_522_500_000);
+    if update_slot > slot {
+        msg!("VOL-MATCHER: Sync read at slot {} is past the current slot {}", update_slot, slot);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+/// True when a sync carries exactly the stored `(vol, mark, regime, 7d, 30d)`
+/// and the stored data is still inside its regime-scaled staleness limit.
+/// Past the limit an identical sync is a real confirmation and is written.
+fn is_noop_resync(
+    ctx_data: &[u8],
+    incoming: (u64, u64, u8, u64, u64),
+    last_update_slot: u64,
+    slot: u64,
+) -> Result<bool, ProgramError> {
+    let read_u64 = |offset: usize| -> Result<u64, ProgramError> {
+        Ok(u64::from_le_bytes(
+            ctx_data[offset..offset + 8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        ))
+    };
+    let stored = (
+        read_u64(CURRENT_VOL_OFFSET)?,
+        read_u64(VOL_MARK_PRICE_OFFSET)?,
+        ctx_data[REGIME_OFFSET],
+        read_u64(VOL_7D_AVG_OFFSET)?,
+        read_u64(VOL_30D_AVG_OFFSET)?,
+    );
+    if incoming != stored {
+        return Ok(false);
+    }
+    let max_staleness = max_staleness_slots(ctx_data[MAX_STALENESS_OFFSET], VolatilityRegime::from_u8(stored.2));
+    Ok(slot.saturating_sub(last_update_slot) <= max_staleness)
+}
+
+/// Tag 0x04: Withdraw keeper rewards — zero the accumulator for the configured keeper
+/// Accounts:
+///   [0] Keeper authority (signer)
+///   [1] Matcher context account
+///   [2] Keeper PDA (writable)
+pub fn process_withdraw_keeper_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let keeper = &accounts[0];
+    let ctx_account = &accounts[1];
+    let keeper_pda = &accounts[2];
+
+    if !keeper_pda.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if !verify_magic(&ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        check_ctx_version(&ctx_data)?;
+    }
+
+    let mut keeper_data = keeper_pda.try_borrow_mut_data()?;
+    verify_keeper(keeper, keeper_pda, &keeper_data, ctx_account.key, program_id)?;
+    let withdrawn = withdraw_keeper_rewards(&mut keeper_data)?;
+
+    matcher_log!("WITHDRAW_KEEPER_REWARDS: keeper={} amount={}", keeper.key, withdrawn);
+
+    Ok(())
+}
+
+/// Tag 0x05: Fallback sync — keeper reads the fallback VolatilityIndex and
+/// refreshes the mark Match falls back to while the primary is stale
+/// Accounts:
+///   [0] Matcher context account
+///   [1] Fallback PDA (writable)
+///   [2] Fallback VolatilityIndex account (read)
+/// Data layout:
+///   [0]    tag (0x05)
+///   [1..9] fallback_vol_mark_e6 (u64 LE)
+///   [9..17] update_slot (u64 LE): the slot the keeper read the fallback at,
+///           ordered against the stored one as Oracle Sync's is
+pub fn process_fallback_sync(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let ctx_account = &accounts[0];
+    let fallback_pda = &accounts[1];
+    let fallback_index = &accounts[2];
+
+    if !fallback_pda.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    {
+        let ctx_data = ctx_account.try_borrow_data()?;
+        if !verify_magic(&ctx_data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        check_ctx_version(&ctx_data)?;
+    }
+
+    let vol_mark = u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    let update_slot = u64::from_le_bytes(data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+
+    let mut fallback_data = fallback_pda.try_borrow_mut_data()?;
+    verify_fallback_pda(fallback_pda, &fallback_data, ctx_account.key, program_id)?;
+    let stored = read_fallback_vol_index(&fallback_data)?;
+    if *fallback_index.key != stored {
+        msg!("VOL-MATCHER: Fallback VolatilityIndex mismatch: expected {}, got {}", stored, fallback_index.key);
+        return Err(VolMatcherError::OracleAccountMismatch.into());
+    }
+
+    let (_, last_update, _) = read_fallback_quote(&fallback_data)?;
+    check_sync_slot(last_update, update_slot, Clock::get()?.slot)?;
+
+    fallback_data[FALLBACK_VOL_MARK_OFFSET..FALLBACK_VOL_MARK_OFFSET + 8].copy_from_slice(&vol_mark.to_le_bytes());
+    fallback_data[FALLBACK_UPDATE_SLOT_OFFSET..FALLBACK_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&update_slot.to_le_bytes());
+
+    matcher_log!("FALLBACK_SYNC: mark={} read_slot={}", vol_mark, update_slot);
+
+    Ok(())
+}
+
+/// Keeper must sign and match the authority in the context's keeper PDA
+fn verify_keeper(
+    keeper: &AccountInfo,
+    keeper_pda: &AccountInfo,
+    keeper_data: &[u8],
+    ctx_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if !keeper.is_signer {
+        msg!("VOL-MATCHER: Keeper must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    verify_keeper_pda(keeper_pda, keeper_data, ctx_key, program_id)?;
+    let stored = read_keeper_authority(keeper_data)?;
+    if *keeper.key != stored {
+        msg!("VOL-MATCHER: Unauthorized keeper: expected {}, got {}", stored, keeper.key);
+        return Err(VolMatcherError::UnauthorizedKeeper.into());
+    }
+    Ok(())
+}
+
+/// Credit one sync's reward. Repeat syncs within the same slot earn nothing,
+/// so a keeper cannot farm rewards by spamming updates.
+fn accrue_keeper_reward(keeper_data: &mut [u8], last_update_slot: u64, slot: u64) -> Result<u64, ProgramError> {
+    if slot <= last_update_slot {
+        return Ok(0);
+    }
+    let reward = u16::from_le_bytes(
+        keeper_data[KEEPER_REWARD_PER_SYNC_OFFSET..KEEPER_REWARD_PER_SYNC_OFFSET + 2]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as u64;
+    let accrued = read_keeper_rewards(keeper_data)?.saturating_add(reward);
+    keeper_data[KEEPER_REWARDS_OFFSET..KEEPER_REWARDS_OFFSET + 8].copy_from_slice(&accrued.to_le_bytes());
+    Ok(reward)
+}
+
+/// Zero the accumulator, returning the amount owed. The caller has already
+/// checked the keeper with `verify_keeper`.
+fn withdraw_keeper_rewards(keeper_data: &mut [u8]) -> Result<u64, ProgramError> {
+    let withdrawn = read_keeper_rewards(keeper_data)?;
+    keeper_data[KEEPER_REWARDS_OFFSET..KEEPER_REWARDS_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    Ok(withdrawn)
+}
+
+/// Staleness base a match prices against: the context's own when set, else the
+/// global config's when one is passed, else 0 (the built-in default)
+fn staleness_base(ctx_data: &[u8], global_config: Option<&AccountInfo>, program_id: &Pubkey) -> Result<u8, ProgramError> {
+    let local = ctx_data[MAX_STALENESS_OFFSET];
+    let Some(config) = global_config.filter(|_| local == 0) else {
+        return Ok(local);
+    };
+    let config_data = config.try_borrow_data()?;
+    verify_global_config(config, &config_data, program_id)?;
+    Ok(config_data[GLOBAL_MAX_STALENESS_OFFSET])
+}
+
+/// Effective staleness limit: the stored base (or the default) scaled by regime
+fn max_staleness_slots(base: u8, regime: VolatilityRegime) -> u64 {
+    let base = if base == 0 { DEFAULT_MAX_STALENESS_SLOTS } else { base as u64 };
+    base * regime.staleness_pct() / 100
+}
+
+/// Accepted init payload lengths: the base layout, then each optional
+/// trailing group (skew, keeper rewards, staleness base, heartbeat, regime
+/// multipliers, fallback oracle) in full.
+const INIT_DATA_LENS: [usize; 7] = [114, 118, 152, 153, 155, 165, 199];
+
+/// Reject truncated optional groups and trailing garbage on init
+fn check_init_len(len: usize) -> Result<(), ProgramError> {
+    if !INIT_DATA_LENS.contains(&len) {
+        msg!("VOL-MATCHER: Init data length {}, expected one of {:?}", len, INIT_DATA_LENS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+/// Tag 0x0F: Set global config — fleet-wide defaults for every context that
+/// leaves its own value at 0. Creates the PDA on first use.
+/// Accounts:
+///   [0] Upgrade authority (signer, writable: pays for the config on first set)
+///   [1] Global config PDA (writable, seeds `[GLOBAL_CONFIG_SEED]`)
+///   [2] This program's ProgramData account
+///   [3] System program (first set only)
+/// Data layout:
+///   [0] tag (0x0F)
+///   [1] max_staleness base (u8, 0 = DEFAULT_MAX_STALENESS_SLOTS)
+pub fn process_set_global_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let authority = &accounts[0];
+    let config = &accounts[1];
+    let program_data = &accounts[2];
+
+    verify_upgrade_authority(program_id, authority, program_data)?;
+
+    let (expected, bump) = Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], program_id);
+    if *config.key != expected {
+        msg!("VOL-MATCHER: Global config must be the PDA {}", expected);
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if config.data_is_empty() {
+        let system_program = accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        create_pda_account(program_id, authority, config, system_program, GLOBAL_CONFIG_SIZE, &[GLOBAL_CONFIG_SEED, &[bump]])?;
+    } else if config.owner != program_id {
+        msg!("VOL-MATCHER: Global config owned by {}, expected this program", config.owner);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut config_data = config.try_borrow_mut_data()?;
+    config_data[..GLOBAL_CONFIG_SIZE].fill(0);
+    config_data[GLOBAL_MAGIC_OFFSET..GLOBAL_MAGIC_OFFSET + 8].copy_from_slice(&GLOBAL_CONFIG_MAGIC.to_le_bytes());
+    config_data[GLOBAL_BUMP_OFFSET] = bump;
+    config_data[GLOBAL_MAX_STALENESS_OFFSET] = data[1];
+
+    matcher_log!("SET_GLOBAL_CONFIG: max_staleness={}", data[1]);
+
+    Ok(())
+}
+
+/// The signer must be the upgrade authority recorded in this program's
+/// ProgramData account. A program deployed immutable has none, so its global
+/// config can never be written.
+fn verify_upgrade_authority(program_id: &Pubkey, authority: &AccountInfo, program_data: &AccountInfo) -> ProgramResult {
+    if !authority.is_signer {
+        msg!("VOL-MATCHER: Upgrade authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (expected, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if *program_data.key != expected || *program_data.owner != bpf_loader_upgradeable::id() {
+        msg!("VOL-MATCHER: {} is not this program's ProgramData account", program_data.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let data = program_data.try_borrow_data()?;
+    let recorded = data
+        .get(PROGRAM_DATA_AUTHORITY_OFFSET..PROGRAM_DATA_AUTHORITY_OFFSET + 33)
+        .filter(|_| data[..4] == PROGRAM_DATA_STATE_TAG.to_le_bytes())
+        .filter(|auth| auth[0] == 1)
+        .map(|auth| &auth[1..]);
+    if recorded != Some(authority.key.as_ref()) {
+        msg!("VOL-MATCHER: {} is not the program's upgrade authority", authority.key);
+        return Err(VolMatcherError::UnauthorizedConfigAuthority.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        accrue_keeper_reward, apply_fill_to_inventory, check_init_len, check_limit_price, check_sync_slot,
+        enter_match_guard, guard_match, max_staleness_slots, parse_batch, parse_limit_price, process_init,
+        process_match, process_match_batch, process_oracle_sync, process_query_spread, quote, quote_both,
+        read_inventory_skew, skewed_spread, validate_liquidity_config,
+        process_set_global_config, staleness_base, process_withdraw_keeper_rewards,
+        write_quote_both, process_fallback_sync, INIT_DATA_LENS,
+    };
+    use crate::errors::VolMatcherError;
+    use crate::state::*;
+    use matcher_common::compute_exec_price;
+    use matcher_shared::side_price;
+    use solana_program::{
+        account_info::AccountInfo, bpf_loader_upgradeable, clock::Clock, entrypoint::ProgramResult,
+        instruction::Instruction, program_error::ProgramError, program_stubs::{set_syscall_stubs, SyscallStubs},
+        pubkey::Pubkey, rent::Rent, system_instruction, system_program,
+    };
+    use std::cell::RefCell;
+
+    // ---------------------------------------------------------------------------
+    // Helper: replicate the pricing math from process_match for unit-testing
+    // without needing Solana account scaffolding.
+    // ---------------------------------------------------------------------------
+    fn calc_exec_price(
+        base_spread: u32,
+        vov_spread: u32,
+        max_spread: u32,
+        regime: VolatilityRegime,
+        vol_mark: u64,
+    ) -> u64 {
+        let regime_multiplier = regime.spread_multiplier();
+        let adjusted_vov = (vov_spread as u64) * regime_multiplier / 100;
+        let total_spread = std::cmp::min(
+            (base_spread as u64).saturating_add(adjusted_vov),
+            max_spread as u64,
+        );
+        compute_exec_price(vol_mark, total_spread).unwrap()
+    }
+
+    // -----------------------------------------------------------------------
+    // 1. Normal regime
+    // -----------------------------------------------------------------------
+    #[test]
+    fn test_normal_regime_pricing() {
+        let price = calc_exec_price(20, 30, 200, VolatilityRegime::Normal, 4_500_000_000);
+        // adjusted_vov = 30 * 100 / 100 = 30
+        // total_spread = min(20 + 30, 200) = 50
+        // exec_price   = 4_500_000_000 * 10050 / 10000 = 4_522_500_000
+        assert_eq!(price, 4_522_500_000);
     }
 
     // -----------------------------------------------------------------------
@@ -357,4 +1256,1135 @@ mod tests {
         assert_eq!(VolatilityRegime::High.spread_multiplier(), 150);
         assert_eq!(VolatilityRegime::Extreme.spread_multiplier(), 250);
     }
+
+    #[test]
+    fn test_stored_multiplier_table_overrides_builtin() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&20u32.to_le_bytes());
+        ctx[VOV_SPREAD_OFFSET..VOV_SPREAD_OFFSET + 4].copy_from_slice(&30u32.to_le_bytes());
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&500u32.to_le_bytes());
+        ctx[REGIME_OFFSET] = VolatilityRegime::Extreme as u8;
+        // No table: built-in 250% -> 20 + 75
+        assert_eq!(compute_current_spread(&ctx).unwrap(), 95);
+
+        for (i, multiplier) in [40u16, 60, 100, 200, 1_000].iter().enumerate() {
+            let offset = REGIME_MULTIPLIERS_OFFSET + i * 2;
+            ctx[offset..offset + 2].copy_from_slice(&multiplier.to_le_bytes());
+        }
+        // 1000% -> 20 + 300
+        assert_eq!(compute_current_spread(&ctx).unwrap(), 320);
+        ctx[REGIME_OFFSET] = VolatilityRegime::VeryLow as u8;
+        // 40% -> 20 + 12
+        assert_eq!(compute_current_spread(&ctx).unwrap(), 32);
+    }
+
+    #[test]
+    fn test_init_rejects_a_zero_multiplier_in_a_set_table() {
+        let mut data = full_init_data();
+        data[155 + 2 * 4..165].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(try_init_over(0, &data), Err(ProgramError::InvalidInstructionData));
+
+        // An all-zero table keeps the built-in one
+        data[155..165].fill(0);
+        let ctx = init_over(0, &data);
+        assert!(ctx[REGIME_MULTIPLIERS_OFFSET..REGIME_MULTIPLIERS_OFFSET + 10].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_init_stores_spreads_and_amounts_at_full_width() {
+        let mut data = full_init_data();
+        data[2..6].copy_from_slice(&70_000u32.to_le_bytes()); // base spread
+        data[18..34].copy_from_slice(&(u64::MAX as u128 + 1).to_le_bytes()); // liquidity
+        let ctx = init_over(0, &data);
+        assert_eq!(read_version(&ctx).unwrap(), CTX_VERSION);
+
+        let view = VolContextView::from_bytes(&ctx).unwrap();
+        assert_eq!(view.base_spread(), 70_000);
+        assert_eq!(view.max_spread(), 200);
+        assert_eq!(view.liquidity(), u64::MAX as u128 + 1);
+        assert_eq!(view.max_fill(), 1_000_000_000);
+    }
+
+    // -----------------------------------------------------------------------
+    // 7. Inventory skew
+    // -----------------------------------------------------------------------
+    fn skew_ctx(net_inventory: i64) -> Vec<u8> {
+        let mut data = vec![0u8; CTX_SIZE];
+        data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].copy_from_slice(&net_inventory.to_le_bytes());
+        data[SKEW_K_OFFSET..SKEW_K_OFFSET + 2].copy_from_slice(&20u16.to_le_bytes());
+        data[MAX_SKEW_OFFSET..MAX_SKEW_OFFSET + 2].copy_from_slice(&15u16.to_le_bytes());
+        data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&1_000_000u128.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_zero_inventory_no_skew() {
+        let data = skew_ctx(0);
+        let skew = read_inventory_skew(&data).unwrap();
+        assert_eq!(skew, 0);
+        assert_eq!(skewed_spread(50, skew, DIRECTION_BUY), 50);
+        assert_eq!(skewed_spread(50, skew, DIRECTION_SELL), 50);
+    }
+
+    #[test]
+    fn test_long_inventory_tightens_ask() {
+        // skew = 500_000 * 20 / 1_000_000 = 10 bps
+        let data = skew_ctx(500_000);
+        let skew = read_inventory_skew(&data).unwrap();
+        assert_eq!(skew, 10);
+        assert_eq!(skewed_spread(50, skew, DIRECTION_BUY), 40);
+        assert_eq!(skewed_spread(50, skew, DIRECTION_SELL), 60);
+        // ask = 4_500_000_000 * 10040 / 10000, bid = 4_500_000_000 * 9940 / 10000
+        assert_eq!(side_price(4_500_000_000, 40, DIRECTION_BUY, false).unwrap(), 4_518_000_000);
+        assert_eq!(side_price(4_500_000_000, 60, DIRECTION_SELL, false).unwrap(), 4_473_000_000);
+    }
+
+    #[test]
+    fn test_short_inventory_tightens_bid() {
+        // skew = -2_000_000 * 20 / 1_000_000 = -40, clamped to -15
+        let data = skew_ctx(-2_000_000);
+        let skew = read_inventory_skew(&data).unwrap();
+        assert_eq!(skew, -15);
+        assert_eq!(skewed_spread(50, skew, DIRECTION_BUY), 65);
+        assert_eq!(skewed_spread(50, skew, DIRECTION_SELL), 35);
+    }
+
+    #[test]
+    fn test_fills_update_inventory() {
+        let mut data = skew_ctx(0);
+        apply_fill_to_inventory(&mut data, 300, DIRECTION_SELL).unwrap();
+        apply_fill_to_inventory(&mut data, 100, DIRECTION_BUY).unwrap();
+        let inv = i64::from_le_bytes(data[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8].try_into().unwrap());
+        assert_eq!(inv, 200);
+    }
+
+    #[test]
+    fn test_impact_without_liquidity_rejected() {
+        assert_eq!(
+            validate_liquidity_config(10, 0),
+            Err(ProgramError::Custom(VolMatcherError::InvalidLiquidityConfig as u32))
+        );
+        assert!(validate_liquidity_config(10, 1_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_zero_impact_zero_liquidity_accepted() {
+        assert!(validate_liquidity_config(0, 0).is_ok());
+    }
+
+    // -----------------------------------------------------------------------
+    // 8. Keeper rewards
+    // -----------------------------------------------------------------------
+    /// The test context's keeper PDA holding `keeper` and its reward rate
+    fn keeper_pda(keeper: &Pubkey, reward_per_sync: u16) -> (Pubkey, Vec<u8>) {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (key, bump) = Pubkey::find_program_address(&[KEEPER_SEED, ctx_key.as_ref()], &program_id);
+        let mut data = vec![0u8; KEEPER_SIZE];
+        data[KEEPER_MAGIC_OFFSET..KEEPER_MAGIC_OFFSET + 8].copy_from_slice(&KEEPER_MAGIC.to_le_bytes());
+        data[KEEPER_BUMP_OFFSET] = bump;
+        data[KEEPER_AUTHORITY_OFFSET..KEEPER_AUTHORITY_OFFSET + 32].copy_from_slice(keeper.as_ref());
+        data[KEEPER_REWARD_PER_SYNC_OFFSET..KEEPER_REWARD_PER_SYNC_OFFSET + 2]
+            .copy_from_slice(&reward_per_sync.to_le_bytes());
+        (key, data)
+    }
+
+    #[test]
+    fn test_syncs_accrue_keeper_rewards() {
+        let keeper = Pubkey::new_unique();
+        let (_, mut data) = keeper_pda(&keeper, 250);
+        assert_eq!(accrue_keeper_reward(&mut data, 0, 100).unwrap(), 250);
+        assert_eq!(accrue_keeper_reward(&mut data, 100, 101).unwrap(), 250);
+        // Same-slot resync earns nothing
+        assert_eq!(accrue_keeper_reward(&mut data, 101, 101).unwrap(), 0);
+        assert_eq!(read_keeper_rewards(&data).unwrap(), 500);
+    }
+
+    #[test]
+    fn test_withdraw_zeroes_for_authorized_keeper() {
+        let keeper = Pubkey::new_unique();
+        let (pda, mut data) = keeper_pda(&keeper, 250);
+        accrue_keeper_reward(&mut data, 0, 1).unwrap();
+        accrue_keeper_reward(&mut data, 1, 2).unwrap();
+        assert_eq!(run_withdraw(&pda, &mut data, &keeper, true), Ok(()));
+        assert_eq!(read_keeper_rewards(&data).unwrap(), 0);
+    }
+
+    /// Run Withdraw Keeper Rewards as `keeper` against the test context, with
+    /// `keeper_data` at `pda`
+    fn run_withdraw(pda: &Pubkey, keeper_data: &mut [u8], keeper: &Pubkey, is_signer: bool) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut keeper_lamports, mut ctx_lamports, mut pda_lamports) = (0u64, 0u64, 0u64);
+        let mut ctx = vec![0u8; CTX_SIZE];
+        matcher_common::write_header(&mut ctx, VOL_MATCHER_MAGIC, 0, &Pubkey::new_from_array([1u8; 32]));
+        let mut signer_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(keeper, is_signer, false, &mut keeper_lamports, &mut signer_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, &mut ctx, &program_id, false, 0),
+            AccountInfo::new(pda, false, true, &mut pda_lamports, keeper_data, &program_id, false, 0),
+        ];
+        process_withdraw_keeper_rewards(&program_id, &accounts, &[0x04])
+    }
+
+    #[test]
+    fn test_withdraw_rejects_other_keeper() {
+        let keeper = Pubkey::new_unique();
+        let (pda, mut data) = keeper_pda(&keeper, 250);
+        accrue_keeper_reward(&mut data, 0, 1).unwrap();
+        assert_eq!(
+            run_withdraw(&pda, &mut data, &Pubkey::new_unique(), true),
+            Err(ProgramError::Custom(VolMatcherError::UnauthorizedKeeper as u32))
+        );
+        // The right key, but not signing
+        assert_eq!(run_withdraw(&pda, &mut data, &keeper, false), Err(ProgramError::MissingRequiredSignature));
+        // Another context's keeper PDA, even one naming the same keeper
+        let other = Pubkey::new_unique();
+        assert_eq!(run_withdraw(&other, &mut data, &keeper, true), Err(ProgramError::InvalidSeeds));
+        assert_eq!(read_keeper_rewards(&data).unwrap(), 250);
+
+        // No keeper configured: nobody can withdraw
+        assert_eq!(
+            run_withdraw(&pda, &mut [], &keeper, true),
+            Err(ProgramError::Custom(VolMatcherError::UnauthorizedKeeper as u32))
+        );
+    }
+
+    #[test]
+    fn test_init_creates_the_keeper_pda_that_syncs_credit() {
+        let keeper = Pubkey::new_from_array([8u8; 32]);
+        let (ctx, data, _) = try_init_with_pdas(0, &full_init_data()).unwrap();
+        let (pda, expected) = keeper_pda(&keeper, 100);
+        assert_eq!(data, expected);
+
+        // A keeper-signed sync credits the PDA, not the context
+        let (mut ctx, mut data) = (ctx, data);
+        let before = ctx.clone();
+        run_keeper_sync(&mut ctx, &pda, &mut data, &keeper).unwrap();
+        assert_eq!(read_keeper_rewards(&data).unwrap(), 100);
+        assert_eq!(ctx[REGIME_MULTIPLIERS_OFFSET + 10..], before[REGIME_MULTIPLIERS_OFFSET + 10..]);
+
+        // No keeper in the payload: no PDA, and the accounts past [1] aren't needed
+        let mut data = full_init_data();
+        data[118..150].fill(0);
+        assert!(try_init_with_pdas(0, &data).unwrap().1.is_empty());
+    }
+
+    /// Oracle Sync read at slot 1_000, co-signed by `keeper` with its PDA
+    fn run_keeper_sync(ctx: &mut [u8], pda: &Pubkey, keeper_data: &mut [u8], keeper: &Pubkey) -> ProgramResult {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (ctx_key, vt_key, vi_key) =
+            (Pubkey::new_from_array([2u8; 32]), Pubkey::new_from_array([5u8; 32]), Pubkey::new_from_array([6u8; 32]));
+        let mut lamports = [0u64; 5];
+        let [ctx_lamports, vt_lamports, vi_lamports, keeper_lamports, pda_lamports] = &mut lamports;
+        let (mut vt_data, mut vi_data, mut signer_data) = (Vec::new(), Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&vt_key, false, false, vt_lamports, &mut vt_data, &program_id, false, 0),
+            AccountInfo::new(&vi_key, false, false, vi_lamports, &mut vi_data, &program_id, false, 0),
+            AccountInfo::new(keeper, true, false, keeper_lamports, &mut signer_data, &program_id, false, 0),
+            AccountInfo::new(pda, false, true, pda_lamports, keeper_data, &program_id, false, 0),
+        ];
+        let mut data = vec![0x03];
+        for value in [4_500u64, 45_000_000] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(2);
+        for value in [4_400u64, 4_300, 1_000] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        process_oracle_sync(&program_id, &accounts, &data)
+    }
+
+    // -----------------------------------------------------------------------
+    // 9. Batch match
+    // -----------------------------------------------------------------------
+    fn batch_data(legs: &[(u64, u8)]) -> Vec<u8> {
+        let mut data = vec![0x0A, legs.len() as u8];
+        for (size, direction) in legs {
+            data.extend_from_slice(&size.to_le_bytes());
+            data.push(*direction);
+        }
+        data
+    }
+
+    #[test]
+    fn test_three_leg_batch() {
+        let mut ctx = init_over(0xFF, &full_init_data());
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        set_syscall_stubs(Box::new(FixedClock));
+
+        let legs = [(2_000_000_000u64, DIRECTION_BUY), (3_000_000_000, DIRECTION_SELL), (1_000_000_000, DIRECTION_BUY)];
+        assert_eq!(parse_batch(&batch_data(&legs)).unwrap(), legs.to_vec());
+
+        // The same legs matched one by one, each moving the inventory the next is skewed by
+        let mut one_by_one = ctx.clone();
+        let mut expected = Vec::new();
+        for (size, direction) in legs {
+            let mut data = vec![0x00];
+            data.extend_from_slice(&size.to_le_bytes());
+            data.push(direction);
+            run_lp_ix(process_match, &mut one_by_one, &data).unwrap();
+            expected.push(u64::from_le_bytes(one_by_one[0..8].try_into().unwrap()));
+        }
+
+        run_lp_ix(process_match_batch, &mut ctx, &batch_data(&legs)).unwrap();
+        for (i, price) in expected.iter().enumerate() {
+            assert_eq!(u64::from_le_bytes(ctx[i * 8..i * 8 + 8].try_into().unwrap()), *price, "leg {}", i);
+        }
+        assert_eq!(&ctx[24..64], &[0u8; 40]);
+        assert_eq!(&ctx[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8], &0i64.to_le_bytes());
+        assert_eq!(ctx[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8], one_by_one[NET_INVENTORY_OFFSET..NET_INVENTORY_OFFSET + 8]);
+
+        // Normal regime, flat book: the first ask sits 20 + 30 = 50 bps over the mark.
+        // The sell leg is priced off the short the buy left, not off a flat book.
+        assert_eq!(expected[0], 4_522_500_000);
+        let flat_bid = side_price(4_500_000_000, 50, DIRECTION_SELL, false).unwrap();
+        assert!(expected[1] > flat_bid, "bid {} not tightened by the LP's short", expected[1]);
+    }
+
+    #[test]
+    fn test_quote_both_brackets_mid() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[BASE_SPREAD_OFFSET..BASE_SPREAD_OFFSET + 4].copy_from_slice(&20u32.to_le_bytes());
+        ctx[VOV_SPREAD_OFFSET..VOV_SPREAD_OFFSET + 4].copy_from_slice(&30u32.to_le_bytes());
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&200u32.to_le_bytes());
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        ctx[REGIME_OFFSET] = VolatilityRegime::Normal as u8;
+
+        let (bid, ask, mid) = quote_both(&ctx, 1_050, 0, None).unwrap();
+        assert!(ask > mid && mid > bid);
+        // Flat inventory: each side sits the Normal spread (20 + 30 = 50 bps) from the mark
+        assert_eq!(mid, 4_500_000_000);
+        assert_eq!(ask - mid, 22_500_000);
+        assert_eq!(mid - bid, 22_500_000);
+
+        write_quote_both(&mut ctx, bid, ask, mid);
+        let read = |at: usize| u64::from_le_bytes(ctx[at..at + 8].try_into().unwrap());
+        assert_eq!(
+            (read(QUOTE_BID_RETURN_OFFSET), read(QUOTE_ASK_RETURN_OFFSET), read(QUOTE_MID_RETURN_OFFSET)),
+            (bid, ask, mid)
+        );
+    }
+
+    #[test]
+    fn test_batch_over_capacity_rejected() {
+        let legs = [(1u64, DIRECTION_BUY); MAX_BATCH_LEGS + 1];
+        assert_eq!(parse_batch(&batch_data(&legs)), Err(ProgramError::InvalidInstructionData));
+        // Exactly at capacity is fine; empty batch is a no-op
+        assert_eq!(parse_batch(&batch_data(&legs[..MAX_BATCH_LEGS])).unwrap().len(), MAX_BATCH_LEGS);
+        assert!(parse_batch(&batch_data(&[])).unwrap().is_empty());
+        // Truncated leg data, and a leg with no valid direction
+        assert_eq!(parse_batch(&[0x0A, 2, 0, 0, 0]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(parse_batch(&batch_data(&[(1, 2)])), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_extreme_regime_tightens_staleness() {
+        for base in [0u8, 120] {
+            let calm = max_staleness_slots(base, VolatilityRegime::VeryLow);
+            let extreme = max_staleness_slots(base, VolatilityRegime::Extreme);
+            assert!(extreme < calm, "base {}: extreme {} vs calm {}", base, extreme, calm);
+        }
+        // Unconfigured base keeps the historical 100-slot limit at Normal
+        assert_eq!(max_staleness_slots(0, VolatilityRegime::Normal), DEFAULT_MAX_STALENESS_SLOTS);
+        assert_eq!(max_staleness_slots(120, VolatilityRegime::Extreme), 36);
+
+        // A 60-slot-old oracle quotes in VeryLow but is stale in Extreme
+        let mut ctx = vec![0u8; CTX_SIZE];
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        ctx[REGIME_OFFSET] = VolatilityRegime::VeryLow as u8;
+        assert!(quote(&ctx, 1_060, DIRECTION_BUY, 0, None).is_ok());
+        ctx[REGIME_OFFSET] = VolatilityRegime::Extreme as u8;
+        assert_eq!(
+            quote(&ctx, 1_060, DIRECTION_BUY, 0, None),
+            Err(ProgramError::Custom(VolMatcherError::OracleStale as u32))
+        );
+    }
+
+    #[test]
+    fn test_init_len_exact_only() {
+        for len in [114, 118, 152, 153, 155, 165, 199] {
+            assert!(check_init_len(len).is_ok(), "len {}", len);
+        }
+        // Short, partial optional group, or trailing garbage
+        for len in [113, 116, 151, 154, 156, 164, 166, 198, 200] {
+            assert_eq!(check_init_len(len), Err(ProgramError::InvalidInstructionData), "len {}", len);
+        }
+    }
+
+    /// Init payload with every optional trailing group present
+    fn full_init_data() -> Vec<u8> {
+        let mut data = vec![0x02, 0];
+        data.extend_from_slice(&20u32.to_le_bytes()); // base spread
+        data.extend_from_slice(&30u32.to_le_bytes()); // vov spread
+        data.extend_from_slice(&200u32.to_le_bytes()); // max spread
+        data.extend_from_slice(&10u32.to_le_bytes()); // impact k
+        data.extend_from_slice(&10_000_000_000u128.to_le_bytes()); // liquidity
+        data.extend_from_slice(&1_000_000_000u128.to_le_bytes()); // max fill
+        data.extend_from_slice(&[5u8; 32]); // variance tracker
+        data.extend_from_slice(&[6u8; 32]); // vol index
+        data.extend_from_slice(&10u16.to_le_bytes()); // skew k
+        data.extend_from_slice(&50u16.to_le_bytes()); // max skew
+        data.extend_from_slice(&[8u8; 32]); // keeper authority
+        data.extend_from_slice(&100u16.to_le_bytes()); // reward per sync
+        data.push(120); // staleness base
+        data.extend_from_slice(&500u16.to_le_bytes()); // max matches per sync
+        for multiplier in [50u16, 75, 100, 150, 250] {
+            data.extend_from_slice(&multiplier.to_le_bytes()); // regime multipliers
+        }
+        data.extend_from_slice(&[9u8; 32]); // fallback vol index
+        data.extend_from_slice(&25u16.to_le_bytes()); // fallback penalty
+        data
+    }
+
+    fn init_over(fill: u8, data: &[u8]) -> Vec<u8> {
+        try_init_over(fill, data).unwrap()
+    }
+
+    /// Run process_init over a context buffer pre-filled with `fill`
+    fn try_init_over(fill: u8, data: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        try_init_with_pdas(fill, data).map(|(ctx, _, _)| ctx)
+    }
+
+    /// Context, keeper PDA and fallback PDA data after an init
+    type InitOutput = (Vec<u8>, Vec<u8>, Vec<u8>);
+
+    /// Run process_init with the payer, system program and PDAs a keeper
+    /// authority and fallback index need
+    fn try_init_with_pdas(fill: u8, data: &[u8]) -> Result<InitOutput, ProgramError> {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let loader = bpf_loader_upgradeable::id();
+        let (lp_key, ctx_key, payer_key) =
+            (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]), Pubkey::new_from_array([4u8; 32]));
+        let (keeper_key, _) = Pubkey::find_program_address(&[KEEPER_SEED, ctx_key.as_ref()], &program_id);
+        let (fallback_key, _) = Pubkey::find_program_address(&[FALLBACK_SEED, ctx_key.as_ref()], &program_id);
+        let system_program = system_program::id();
+        let (mut lp_lamports, mut ctx_lamports, mut payer_lamports, mut keeper_lamports, mut system_lamports) =
+            (0u64, 0u64, 10_000_000_000u64, 0u64, 0u64);
+        let (mut lp_data, mut ctx_data, mut payer_data, mut keeper_data, mut system_data) =
+            (Vec::new(), vec![fill; CTX_SIZE], Vec::new(), Vec::new(), Vec::new());
+        let (mut fallback_lamports, mut fallback_data) = (0u64, Vec::new());
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, &mut ctx_data, &program_id, false, 0),
+            AccountInfo::new(&payer_key, true, true, &mut payer_lamports, &mut payer_data, &system_program, false, 0),
+            AccountInfo::new(&keeper_key, false, true, &mut keeper_lamports, &mut keeper_data, &system_program, false, 0),
+            AccountInfo::new(&system_program, false, false, &mut system_lamports, &mut system_data, &loader, true, 0),
+            AccountInfo::new(&fallback_key, false, true, &mut fallback_lamports, &mut fallback_data, &system_program, false, 0),
+        ];
+        process_init(&program_id, &accounts, data)?;
+        let ctx = accounts[1].try_borrow_data()?.to_vec();
+        let keeper = accounts[3].try_borrow_data()?.to_vec();
+        let fallback = accounts[5].try_borrow_data()?.to_vec();
+        Ok((ctx, keeper, fallback))
+    }
+
+    #[test]
+    fn test_init_overwrites_every_byte() {
+        // Any byte that differs between a zero- and 0xFF-prefilled account is
+        // one the init path never wrote -- stale data leaking into a new market
+        let data = full_init_data();
+        for len in INIT_DATA_LENS {
+            let (zeros, ones) = (init_over(0x00, &data[..len]), init_over(0xFF, &data[..len]));
+            let leaked: Vec<usize> = (0..CTX_SIZE).filter(|&i| zeros[i] != ones[i]).collect();
+            assert!(leaked.is_empty(), "len {}: init left bytes {:?} unwritten", len, leaked);
+        }
+    }
+
+    #[test]
+    fn test_init_rejects_unknown_mode() {
+        let mut data = full_init_data();
+        for mode in 0..=MAX_MODE {
+            data[1] = mode;
+            assert_eq!(init_over(0, &data)[MODE_OFFSET], mode);
+        }
+        // An unknown mode would otherwise be stored and silently priced as mode 0
+        data[1] = 200;
+        assert_eq!(
+            try_init_over(0, &data),
+            Err(ProgramError::Custom(VolMatcherError::InvalidMode as u32))
+        );
+    }
+
+    /// guard_match with the LP account keyed `lp_key`, signed or not
+    fn guard_with(ctx: &mut [u8], lp_key: Pubkey, signed: bool) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let lp = AccountInfo::new(&lp_key, signed, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0);
+        let ctx_account = AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0);
+        guard_match(&program_id, &lp, &ctx_account)
+    }
+
+    #[test]
+    fn test_guard_match_failure_modes() {
+        let lp_key = Pubkey::new_from_array([1u8; 32]);
+        let ctx = init_over(0, &full_init_data());
+        assert!(guard_with(&mut ctx.clone(), lp_key, true).is_ok());
+
+        assert_eq!(guard_with(&mut ctx.clone(), lp_key, false), Err(ProgramError::MissingRequiredSignature));
+        let mut bad_magic = ctx.clone();
+        bad_magic[MAGIC_OFFSET] ^= 0xFF;
+        assert_eq!(guard_with(&mut bad_magic, lp_key, true), Err(ProgramError::UninitializedAccount));
+        assert_eq!(
+            guard_with(&mut ctx.clone(), Pubkey::new_from_array([9u8; 32]), true),
+            Err(ProgramError::InvalidAccountData)
+        );
+        let mut future = ctx.clone();
+        future[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&(CTX_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            guard_with(&mut future, lp_key, true),
+            Err(ProgramError::Custom(VolMatcherError::UnsupportedVersion as u32))
+        );
+    }
+
+    #[test]
+    fn test_match_guard_rejects_nested_match() {
+        let mut ctx = vec![0u8; CTX_SIZE];
+        enter_match_guard(&mut ctx).unwrap();
+        assert_eq!(ctx[MATCH_GUARD_OFFSET], 1);
+        // A re-entrant match lands while the outer one still holds the guard
+        assert_eq!(
+            enter_match_guard(&mut ctx),
+            Err(ProgramError::Custom(VolMatcherError::MatchReentered as u32))
+        );
+        ctx[MATCH_GUARD_OFFSET] = 0;
+        assert!(enter_match_guard(&mut ctx).is_ok());
+    }
+
+    #[test]
+    fn test_limit_price_slippage_protection() {
+        let exceeded = Err(ProgramError::Custom(VolMatcherError::SlippageExceeded as u32));
+        // Buy: exec at or under the limit passes, above is rejected
+        assert_eq!(check_limit_price(1_000, DIRECTION_BUY, Some(1_010)), Ok(()));
+        assert_eq!(check_limit_price(1_010, DIRECTION_BUY, Some(1_010)), Ok(()));
+        assert_eq!(check_limit_price(1_011, DIRECTION_BUY, Some(1_010)), exceeded);
+        // Sell: exec at or over the limit passes, below is rejected
+        assert_eq!(check_limit_price(1_000, DIRECTION_SELL, Some(990)), Ok(()));
+        assert_eq!(check_limit_price(990, DIRECTION_SELL, Some(990)), Ok(()));
+        assert_eq!(check_limit_price(989, DIRECTION_SELL, Some(990)), exceeded);
+        // No limit, no protection
+        assert_eq!(check_limit_price(u64::MAX, DIRECTION_BUY, None), Ok(()));
+
+        let mut data = vec![0x00];
+        data.extend_from_slice(&5_000_000u64.to_le_bytes());
+        data.push(DIRECTION_SELL);
+        assert_eq!(parse_limit_price(&data), Ok(None));
+        data.extend_from_slice(&990u64.to_le_bytes());
+        assert_eq!(parse_limit_price(&data), Ok(Some(990)));
+    }
+
+    #[test]
+    fn test_match_enforces_limit_price() {
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&990u64.to_le_bytes());
+        set_syscall_stubs(Box::new(FixedClock));
+
+        // Learn the buy price without a limit, then quote the limit around it
+        let mut probe = ctx.clone();
+        run_lp_ix(process_match, &mut probe, &[0x00]).unwrap();
+        let exec = u64::from_le_bytes(probe[0..8].try_into().unwrap());
+        let with_limit = |limit: &[u8]| {
+            let mut data = vec![0x00];
+            data.extend_from_slice(&1_000u64.to_le_bytes());
+            data.push(DIRECTION_BUY);
+            data.extend_from_slice(limit);
+            run_lp_ix(process_match, &mut ctx.clone(), &data)
+        };
+        assert_eq!(with_limit(&exec.to_le_bytes()), Ok(()));
+        assert_eq!(
+            with_limit(&(exec - 1).to_le_bytes()),
+            Err(ProgramError::Custom(VolMatcherError::SlippageExceeded as u32))
+        );
+        // A limit cut short is malformed, not "no limit"
+        assert_eq!(with_limit(&exec.to_le_bytes()[..4]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(with_limit(&[]), Ok(()));
+    }
+
+    #[test]
+    fn test_sync_must_advance_slot() {
+        let stale = Err(ProgramError::Custom(VolMatcherError::StaleUpdate as u32));
+        // Never synced: init leaves the stamp at 0
+        assert_eq!(check_sync_slot(0, 1, 1), Ok(()));
+        assert_eq!(check_sync_slot(1_000, 1_001, 1_001), Ok(()));
+        // Read at the stored update's slot, or behind it
+        assert_eq!(check_sync_slot(1_000, 1_000, 1_001), stale);
+        assert_eq!(check_sync_slot(1_000, 999, 1_001), stale);
+        // Read in a slot that hasn't happened yet
+        assert_eq!(check_sync_slot(1_000, 1_002, 1_001), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_sync_replay_in_later_slot_rejected() {
+        let stale = Err(ProgramError::Custom(VolMatcherError::StaleUpdate as u32));
+        let mut ctx = init_over(0, &full_init_data());
+        // Reads at 950 then 960 land; the stored slot is the read slot, not the clock's
+        run_sync_at(&mut ctx, 4_500, 45_000_000, 2, 4_400, 4_300, 950).unwrap();
+        run_sync_at(&mut ctx, 4_600, 46_000_000, 2, 4_400, 4_300, 960).unwrap();
+        assert_eq!(&ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8], &960u64.to_le_bytes());
+
+        // The 950 sync replayed now, 40 slots on: rejected, the newer mark stands
+        assert_eq!(run_sync_at(&mut ctx, 4_500, 45_000_000, 2, 4_400, 4_300, 950), stale);
+        assert_eq!(VolContextView::from_bytes(&ctx).unwrap().current_vol(), 4_600);
+        // Without the read slot there is nothing to order by
+        let mut data = vec![0x03];
+        data.extend_from_slice(&[0u8; 33]);
+        assert_eq!(run_sync_data(&mut ctx, &data), Err(ProgramError::InvalidInstructionData));
+    }
+
+    thread_local! {
+        static INVOKED: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+        static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Sync reads the clock; serve a fixed slot off-chain. Also stands in for
+    /// as much of the System Program as creating the global config and keeper
+    /// PDAs needs: records every CPI on this thread, moves lamports for
+    /// create/transfer and sizes the account on create/allocate. Keeps this thread's
+    /// `set_return_data` for the Query tests. One stub for every test, as
+    /// swapping stubs mid-test would race the others.
+    struct FixedClock;
+
+    impl SyscallStubs for FixedClock {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Clock) = Clock { slot: 1_000, ..Clock::default() } };
+            0
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+
+        fn sol_invoke_signed(&self, ix: &Instruction, infos: &[AccountInfo], _seeds: &[&[&[u8]]]) -> ProgramResult {
+            INVOKED.with(|i| i.borrow_mut().push(ix.clone()));
+            let info = |n: usize| infos.iter().find(|a| *a.key == ix.accounts[n].pubkey).unwrap();
+            let size = |a: &AccountInfo, space: &[u8]| {
+                let space = u64::from_le_bytes(space.try_into().unwrap()) as usize;
+                *a.data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+            };
+            let tag = u32::from_le_bytes(ix.data[..4].try_into().unwrap());
+            if tag == 0 || tag == 2 {
+                // CreateAccount / Transfer: lamports at [4..12]
+                let amount = u64::from_le_bytes(ix.data[4..12].try_into().unwrap());
+                **info(0).try_borrow_mut_lamports()? -= amount;
+                **info(1).try_borrow_mut_lamports()? += amount;
+            }
+            match tag {
+                0 => size(info(1), &ix.data[12..20]),
+                8 => size(info(0), &ix.data[4..12]),
+                _ => {}
+            }
+            Ok(())
+        }
+
+        fn sol_set_return_data(&self, data: &[u8]) {
+            RETURN_DATA.with(|r| *r.borrow_mut() = data.to_vec());
+        }
+
+        fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+            Some((Pubkey::default(), RETURN_DATA.with(|r| r.borrow().clone())))
+        }
+    }
+
+    /// Run signer-free Query Spread over a read-only `ctx`; the spread from return data
+    fn run_query_spread(ctx: &[u8]) -> Result<u64, ProgramError> {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let mut ctx_lamports = 0u64;
+        let mut ctx_data = ctx.to_vec();
+        let accounts = [AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, &mut ctx_data, &program_id, false, 0)];
+        RETURN_DATA.with(|r| r.borrow_mut().clear());
+        process_query_spread(&program_id, &accounts, &[0x0D])?;
+        assert_eq!(ctx_data, ctx, "Query Spread wrote to the context");
+        let (_, data) = solana_program::program::get_return_data().unwrap();
+        Ok(u64::from_le_bytes(data.try_into().unwrap()))
+    }
+
+    /// Run OracleSync at slot 1_000 with `(vol, mark, regime, 7d, 30d)`, no keeper
+    /// Run Oracle Sync read at the clock stub's current slot
+    fn run_sync(ctx: &mut [u8], vol: u64, mark: u64, regime: u8, vol_7d: u64, vol_30d: u64) -> ProgramResult {
+        run_sync_at(ctx, vol, mark, regime, vol_7d, vol_30d, 1_000)
+    }
+
+    fn run_sync_at(
+        ctx: &mut [u8],
+        vol: u64,
+        mark: u64,
+        regime: u8,
+        vol_7d: u64,
+        vol_30d: u64,
+        update_slot: u64,
+    ) -> ProgramResult {
+        let mut data = vec![0x03];
+        data.extend_from_slice(&vol.to_le_bytes());
+        data.extend_from_slice(&mark.to_le_bytes());
+        data.push(regime);
+        data.extend_from_slice(&vol_7d.to_le_bytes());
+        data.extend_from_slice(&vol_30d.to_le_bytes());
+        data.extend_from_slice(&update_slot.to_le_bytes());
+        run_sync_data(ctx, &data)
+    }
+
+    fn run_sync_data(ctx: &mut [u8], data: &[u8]) -> ProgramResult {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (ctx_key, vt_key, vi_key) =
+            (Pubkey::new_from_array([2u8; 32]), Pubkey::new_from_array([5u8; 32]), Pubkey::new_from_array([6u8; 32]));
+        let (mut ctx_lamports, mut vt_lamports, mut vi_lamports) = (0u64, 0u64, 0u64);
+        let (mut vt_data, mut vi_data) = (Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(&vt_key, false, false, &mut vt_lamports, &mut vt_data, &program_id, false, 0),
+            AccountInfo::new(&vi_key, false, false, &mut vi_lamports, &mut vi_data, &program_id, false, 0),
+        ];
+        process_oracle_sync(&program_id, &accounts, data)
+    }
+
+    #[test]
+    fn test_sync_stores_vol_at_full_width() {
+        let mut ctx = init_over(0, &full_init_data());
+        let wide = u32::MAX as u64 + 1;
+        run_sync(&mut ctx, wide, 45_000_000, 2, wide + 1, wide + 2).unwrap();
+        let view = VolContextView::from_bytes(&ctx).unwrap();
+        assert_eq!((view.current_vol(), view.vol_7d_avg(), view.vol_30d_avg()), (wide, wide + 1, wide + 2));
+    }
+
+    #[test]
+    fn test_identical_resync_is_a_noop() {
+        let mut ctx = init_over(0, &full_init_data());
+        // Stored sync 50 slots ago, Normal regime: staleness limit is the 120 base
+        for (offset, value) in [
+            (CURRENT_VOL_OFFSET, 4_500u64),
+            (VOL_7D_AVG_OFFSET, 4_400),
+            (VOL_30D_AVG_OFFSET, 4_300),
+            (VOL_MARK_PRICE_OFFSET, 45_000_000),
+            (LAST_UPDATE_SLOT_OFFSET, 950),
+        ] {
+            ctx[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        }
+        ctx[REGIME_OFFSET] = 2;
+
+        // Same payload while still fresh: nothing moves, the clock keeps aging
+        let before = ctx.clone();
+        run_sync(&mut ctx, 4_500, 45_000_000, 2, 4_400, 4_300).unwrap();
+        assert_eq!(ctx, before);
+
+        // Any changed field is a real sync
+        run_sync(&mut ctx, 4_500, 45_000_000, 2, 4_400, 4_301).unwrap();
+        assert_eq!(&ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8], &1_000u64.to_le_bytes());
+
+        // Identical but past the staleness limit is written
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&850u64.to_le_bytes());
+        run_sync(&mut ctx, 4_500, 45_000_000, 2, 4_400, 4_301).unwrap();
+        assert_eq!(&ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8], &1_000u64.to_le_bytes());
+    }
+
+    /// A program-owned global config at the canonical PDA holding `max_staleness`
+    fn global_config_data(program_id: &Pubkey, max_staleness: u8) -> (Pubkey, Vec<u8>) {
+        let (key, bump) = Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], program_id);
+        let mut data = vec![0u8; GLOBAL_CONFIG_SIZE];
+        data[GLOBAL_MAGIC_OFFSET..GLOBAL_MAGIC_OFFSET + 8].copy_from_slice(&GLOBAL_CONFIG_MAGIC.to_le_bytes());
+        data[GLOBAL_BUMP_OFFSET] = bump;
+        data[GLOBAL_MAX_STALENESS_OFFSET] = max_staleness;
+        (key, data)
+    }
+
+    #[test]
+    fn test_global_config_fills_unset_staleness() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let stale = ProgramError::Custom(VolMatcherError::OracleStale as u32);
+        // No staleness base in the init payload: the context's own is 0
+        let mut ctx = init_over(0, &full_init_data()[..152]);
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&850u64.to_le_bytes());
+        ctx[REGIME_OFFSET] = VolatilityRegime::Normal as u8;
+
+        let (config_key, mut config_data) = global_config_data(&program_id, 200);
+        let mut config_lamports = 0u64;
+        let config =
+            AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0);
+
+        // No global account: the 100-slot default leaves a 150-slot-old mark stale
+        assert_eq!(staleness_base(&ctx, None, &program_id), Ok(0));
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, 0, None).unwrap_err(), stale);
+
+        // Global fallback: 200 slots covers it
+        let base = staleness_base(&ctx, Some(&config), &program_id).unwrap();
+        assert_eq!(base, 200);
+        assert!(quote(&ctx, 1_000, DIRECTION_BUY, base, None).is_ok());
+
+        // A context's own value wins over the global one
+        ctx[MAX_STALENESS_OFFSET] = 120;
+        let base = staleness_base(&ctx, Some(&config), &program_id).unwrap();
+        assert_eq!(base, 120);
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, base, None).unwrap_err(), stale);
+
+        // A look-alike not owned by the program is refused
+        ctx[MAX_STALENESS_OFFSET] = 0;
+        let other = Pubkey::new_from_array([3u8; 32]);
+        let (mut other_lamports, mut other_data) = (0u64, config.try_borrow_data().unwrap().to_vec());
+        let forged = AccountInfo::new(&config_key, false, false, &mut other_lamports, &mut other_data, &other, false, 0);
+        assert_eq!(staleness_base(&ctx, Some(&forged), &program_id), Err(ProgramError::IncorrectProgramId));
+    }
+
+    /// ProgramData account naming `[8; 32]` as upgrade authority of program `[7; 32]`
+    fn upgrade_program_data() -> (Pubkey, Vec<u8>) {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (key, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        let mut data = vec![0u8; PROGRAM_DATA_AUTHORITY_OFFSET + 33];
+        data[..4].copy_from_slice(&PROGRAM_DATA_STATE_TAG.to_le_bytes());
+        data[PROGRAM_DATA_AUTHORITY_OFFSET] = 1;
+        data[PROGRAM_DATA_AUTHORITY_OFFSET + 1..].copy_from_slice(&[8u8; 32]);
+        (key, data)
+    }
+
+    /// Run SetGlobalConfig signed by `signer` against an existing config
+    fn run_set_global_config(config_data: &mut [u8], signer: Pubkey, max_staleness: u8) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let loader = bpf_loader_upgradeable::id();
+        let (config_key, _) = Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], &program_id);
+        let (program_data_key, mut program_data) = upgrade_program_data();
+        let system_program = Pubkey::default();
+        let (mut signer_lamports, mut config_lamports, mut program_data_lamports) = (0u64, 0u64, 0u64);
+        let mut signer_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&signer, true, true, &mut signer_lamports, &mut signer_data, &system_program, false, 0),
+            AccountInfo::new(&config_key, false, true, &mut config_lamports, config_data, &program_id, false, 0),
+            AccountInfo::new(
+                &program_data_key, false, false, &mut program_data_lamports, &mut program_data, &loader, false, 0,
+            ),
+        ];
+        process_set_global_config(&program_id, &accounts, &[0x0F, max_staleness])
+    }
+
+    #[test]
+    fn test_set_global_config_requires_upgrade_authority() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (_, expected) = global_config_data(&program_id, 200);
+        let mut config_data = vec![0u8; GLOBAL_CONFIG_SIZE];
+
+        let err = run_set_global_config(&mut config_data.clone(), Pubkey::new_from_array([1u8; 32]), 200);
+        assert_eq!(err, Err(ProgramError::Custom(VolMatcherError::UnauthorizedConfigAuthority as u32)));
+
+        run_set_global_config(&mut config_data, Pubkey::new_from_array([8u8; 32]), 200).unwrap();
+        assert_eq!(config_data, expected);
+    }
+
+    /// First SetGlobalConfig against an empty PDA holding `config_lamports`.
+    /// Returns the config's final lamports and data, and the CPIs made.
+    fn run_first_set_global_config(config_lamports: u64) -> (u64, Vec<u8>, Vec<Instruction>) {
+        set_syscall_stubs(Box::new(FixedClock));
+        INVOKED.with(|i| i.borrow_mut().clear());
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let loader = bpf_loader_upgradeable::id();
+        let (authority, system_program) = (Pubkey::new_from_array([8u8; 32]), system_program::id());
+        let (config_key, _) = Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], &program_id);
+        let (program_data_key, mut program_data) = upgrade_program_data();
+        let (mut authority_lamports, mut config_lamports, mut program_data_lamports, mut system_lamports) =
+            (10_000_000_000u64, config_lamports, 0u64, 0u64);
+        let (mut authority_data, mut config_data, mut system_data) = (Vec::new(), Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&authority, true, true, &mut authority_lamports, &mut authority_data, &system_program, false, 0),
+            AccountInfo::new(&config_key, false, true, &mut config_lamports, &mut config_data, &system_program, false, 0),
+            AccountInfo::new(
+                &program_data_key, false, false, &mut program_data_lamports, &mut program_data, &loader, false, 0,
+            ),
+            AccountInfo::new(&system_program, false, false, &mut system_lamports, &mut system_data, &loader, true, 0),
+        ];
+        process_set_global_config(&program_id, &accounts, &[0x0F, 200]).unwrap();
+        let (lamports, data) = (accounts[1].lamports(), accounts[1].try_borrow_data().unwrap().to_vec());
+        (lamports, data, INVOKED.with(|i| i.take()))
+    }
+
+    #[test]
+    fn test_set_global_config_creates_the_pda() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let authority = Pubkey::new_from_array([8u8; 32]);
+        let (config_key, expected) = global_config_data(&program_id, 200);
+        let rent = Rent::default().minimum_balance(GLOBAL_CONFIG_SIZE);
+        let allocate = system_instruction::allocate(&config_key, GLOBAL_CONFIG_SIZE as u64);
+        let assign = system_instruction::assign(&config_key, &program_id);
+
+        // Fresh address: a single create_account
+        let (lamports, data, cpis) = run_first_set_global_config(0);
+        let create = system_instruction::create_account(&authority, &config_key, rent, GLOBAL_CONFIG_SIZE as u64, &program_id);
+        assert_eq!(cpis, vec![create]);
+        assert_eq!((lamports, data), (rent, expected.clone()));
+
+        // Pre-funded by a third party: create_account would fail, so top up,
+        // allocate and assign
+        let (lamports, data, cpis) = run_first_set_global_config(1_000);
+        let top_up = system_instruction::transfer(&authority, &config_key, rent - 1_000);
+        assert_eq!(cpis, vec![top_up, allocate.clone(), assign.clone()]);
+        assert_eq!((lamports, data), (rent, expected.clone()));
+
+        // Already rent-exempt: no transfer
+        let (lamports, data, cpis) = run_first_set_global_config(rent + 1);
+        assert_eq!(cpis, vec![allocate, assign]);
+        assert_eq!((lamports, data), (rent + 1, expected));
+    }
+
+    /// Run an LP-signed instruction against `ctx`
+    fn run_lp_ix(ix: fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult, ctx: &mut [u8], data: &[u8]) -> ProgramResult {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let (mut lp_lamports, mut ctx_lamports) = (0u64, 0u64);
+        let mut lp_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, &mut lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, &mut ctx_lamports, ctx, &program_id, false, 0),
+        ];
+        ix(&program_id, &accounts, data)
+    }
+
+    #[test]
+    fn test_query_spread_matches_match_pricing() {
+        set_syscall_stubs(Box::new(FixedClock));
+        let mark = 4_500_000_000u64;
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&mark.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+
+        let mut spreads = Vec::new();
+        for regime in 0..=4u8 {
+            ctx[REGIME_OFFSET] = regime;
+            run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+            let exec = u64::from_le_bytes(ctx[0..8].try_into().unwrap());
+            let spread = run_query_spread(&ctx).unwrap();
+            assert_eq!(spread, compute_current_spread(&ctx).unwrap());
+            // Flat inventory: the buy-side price is exactly the mark widened by the spread
+            assert_eq!(exec, compute_exec_price(mark, spread).unwrap(), "regime {}", regime);
+            spreads.push(spread);
+        }
+        assert!(spreads.windows(2).all(|w| w[0] < w[1]), "{:?}", spreads);
+
+        // The cap applies as it does in Match
+        ctx[MAX_SPREAD_OFFSET..MAX_SPREAD_OFFSET + 4].copy_from_slice(&40u32.to_le_bytes());
+        assert_eq!(compute_current_spread(&ctx), Ok(40));
+
+        // No mark needed: the spread is defined before the first sync
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].fill(0);
+        assert_eq!(run_query_spread(&ctx), Ok(40));
+
+        // Uninitialized or another layout version is refused
+        assert_eq!(run_query_spread(&vec![0u8; CTX_SIZE]), Err(ProgramError::UninitializedAccount));
+        ctx[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&(CTX_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            run_query_spread(&ctx),
+            Err(ProgramError::Custom(VolMatcherError::UnsupportedVersion as u32))
+        );
+    }
+
+    #[test]
+    fn test_sync_heartbeat_blocks_match_past_limit() {
+        let heartbeat = Err(ProgramError::Custom(VolMatcherError::SyncHeartbeatRequired as u32));
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&990u64.to_le_bytes());
+        ctx[MAX_MATCHES_PER_SYNC_OFFSET..MAX_MATCHES_PER_SYNC_OFFSET + 2].copy_from_slice(&2u16.to_le_bytes());
+        set_syscall_stubs(Box::new(FixedClock));
+
+        // Two matches fit, the third is refused though the mark is still fresh
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(run_lp_ix(process_match, &mut ctx.clone(), &[0x00]), heartbeat);
+        assert_eq!(&ctx[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2], &2u16.to_le_bytes());
+
+        // A written sync resets the count
+        run_sync(&mut ctx, 4_600, 46_000_000, 2, 4_400, 4_300).unwrap();
+        assert_eq!(&ctx[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2], &0u16.to_le_bytes());
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+
+        // A batch counts every leg
+        assert_eq!(run_lp_ix(process_match_batch, &mut ctx.clone(), &batch_data(&[(1, DIRECTION_BUY), (1, DIRECTION_SELL)])), heartbeat);
+
+        // Zero disables the limit
+        ctx[MAX_MATCHES_PER_SYNC_OFFSET..MAX_MATCHES_PER_SYNC_OFFSET + 2].fill(0);
+        run_lp_ix(process_match_batch, &mut ctx, &batch_data(&[(1, DIRECTION_BUY), (1, DIRECTION_SELL)])).unwrap();
+        assert_eq!(&ctx[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2], &3u16.to_le_bytes());
+    }
+
+    #[test]
+    fn test_matches_accumulate_trade_stats() {
+        let mut ctx = init_over(0xFF, &full_init_data());
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        set_syscall_stubs(Box::new(FixedClock));
+        let trade = |size: u64, direction: u8| {
+            let mut data = vec![0x00];
+            data.extend_from_slice(&size.to_le_bytes());
+            data.push(direction);
+            data
+        };
+
+        // Buy then sell the largest inventory-safe size, a sizeless match, and
+        // a batch doing the same, its legs alone overflowing u64
+        let big = i64::MAX as u64;
+        run_lp_ix(process_match, &mut ctx, &trade(big, DIRECTION_BUY)).unwrap();
+        run_lp_ix(process_match, &mut ctx, &trade(big, DIRECTION_SELL)).unwrap();
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        run_lp_ix(process_match_batch, &mut ctx, &batch_data(&[(big, DIRECTION_BUY), (big, DIRECTION_SELL)])).unwrap();
+
+        let view = VolContextView::from_bytes(&ctx).unwrap();
+        assert_eq!(view.total_trades(), 5);
+        assert_eq!(view.total_volume(), 4 * big as u128);
+    }
+
+    #[test]
+    fn test_noop_resync_still_resets_heartbeat() {
+        let heartbeat = Err(ProgramError::Custom(VolMatcherError::SyncHeartbeatRequired as u32));
+        let mut ctx = init_over(0, &full_init_data());
+        set_syscall_stubs(Box::new(FixedClock));
+        ctx[MAX_MATCHES_PER_SYNC_OFFSET..MAX_MATCHES_PER_SYNC_OFFSET + 2].copy_from_slice(&1u16.to_le_bytes());
+        run_sync(&mut ctx, 4_500, 4_500_000_000, 2, 4_400, 4_300).unwrap();
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&990u64.to_le_bytes());
+
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+        assert_eq!(run_lp_ix(process_match, &mut ctx.clone(), &[0x00]), heartbeat);
+
+        // The keeper re-sends the same fresh data: nothing is rewritten but
+        // the heartbeat counts, so matching resumes
+        run_sync(&mut ctx, 4_500, 4_500_000_000, 2, 4_400, 4_300).unwrap();
+        assert_eq!(&ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8], &990u64.to_le_bytes());
+        assert_eq!(&ctx[MATCHES_SINCE_SYNC_OFFSET..MATCHES_SINCE_SYNC_OFFSET + 2], &0u16.to_le_bytes());
+        run_lp_ix(process_match, &mut ctx, &[0x00]).unwrap();
+    }
+
+    // -----------------------------------------------------------------------
+    // 10. Fallback oracle
+    // -----------------------------------------------------------------------
+    /// The test context's fallback PDA over index `[9; 32]`
+    fn fallback_pda(mark: u64, update_slot: u64, penalty_bps: u16) -> (Pubkey, Vec<u8>) {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (key, bump) = Pubkey::find_program_address(&[FALLBACK_SEED, ctx_key.as_ref()], &program_id);
+        let mut data = vec![0u8; FALLBACK_SIZE];
+        data[FALLBACK_MAGIC_OFFSET..FALLBACK_MAGIC_OFFSET + 8].copy_from_slice(&FALLBACK_MAGIC.to_le_bytes());
+        data[FALLBACK_BUMP_OFFSET] = bump;
+        data[FALLBACK_PENALTY_OFFSET..FALLBACK_PENALTY_OFFSET + 2].copy_from_slice(&penalty_bps.to_le_bytes());
+        data[FALLBACK_VOL_MARK_OFFSET..FALLBACK_VOL_MARK_OFFSET + 8].copy_from_slice(&mark.to_le_bytes());
+        data[FALLBACK_UPDATE_SLOT_OFFSET..FALLBACK_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&update_slot.to_le_bytes());
+        data[FALLBACK_VOL_INDEX_OFFSET..FALLBACK_VOL_INDEX_OFFSET + 32].copy_from_slice(&[9u8; 32]);
+        (key, data)
+    }
+
+    /// Initialized test context with its primary mark read at `primary_slot`
+    fn ctx_with_primary(primary_slot: u64) -> Vec<u8> {
+        let mut ctx = init_over(0, &full_init_data());
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&primary_slot.to_le_bytes());
+        ctx[REGIME_OFFSET] = VolatilityRegime::Normal as u8;
+        ctx[MAX_STALENESS_OFFSET] = 0;
+        ctx
+    }
+
+    #[test]
+    fn test_fresh_primary_ignores_the_fallback() {
+        let ctx = ctx_with_primary(990);
+        let fallback = Some((4_800_000_000, 995, 25));
+        // Normal regime, flat book: 20 + 30 = 50 bps off the primary, no penalty
+        let expected = side_price(4_500_000_000, 50, DIRECTION_BUY, false).unwrap();
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, 0, fallback).unwrap(), (expected, 50, 0));
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, 0, None).unwrap(), (expected, 50, 0));
+    }
+
+    #[test]
+    fn test_stale_primary_prices_off_a_fresh_fallback_with_penalty() {
+        // The primary was read 150 slots ago, past the 100-slot Normal limit
+        let ctx = ctx_with_primary(850);
+        let fallback = Some((4_800_000_000, 990, 25));
+        let expected = side_price(4_800_000_000, 75, DIRECTION_BUY, false).unwrap();
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, 0, fallback).unwrap(), (expected, 75, 0));
+        let bid = side_price(4_800_000_000, 75, DIRECTION_SELL, false).unwrap();
+        assert_eq!(quote_both(&ctx, 1_000, 0, fallback).unwrap(), (bid, expected, 4_800_000_000));
+    }
+
+    #[test]
+    fn test_both_oracles_stale_rejected() {
+        let stale = ProgramError::Custom(VolMatcherError::OracleStale as u32);
+        let ctx = ctx_with_primary(850);
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, 0, Some((4_800_000_000, 880, 25))).unwrap_err(), stale);
+        // A fallback never synced is no price at all, whatever its slot
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, 0, Some((0, 1_000, 25))).unwrap_err(), stale);
+        assert_eq!(quote(&ctx, 1_000, DIRECTION_BUY, 0, None).unwrap_err(), stale);
+    }
+
+    /// Fallback Sync of `mark` read at `update_slot` through `index`
+    fn run_fallback_sync(
+        ctx: &mut [u8],
+        pda: &Pubkey,
+        fallback_data: &mut [u8],
+        index: &Pubkey,
+        mark: u64,
+        update_slot: u64,
+    ) -> ProgramResult {
+        set_syscall_stubs(Box::new(FixedClock));
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let ctx_key = Pubkey::new_from_array([2u8; 32]);
+        let (mut ctx_lamports, mut pda_lamports, mut index_lamports) = (0u64, 0u64, 0u64);
+        let mut index_data = Vec::new();
+        let accounts = [
+            AccountInfo::new(&ctx_key, false, false, &mut ctx_lamports, ctx, &program_id, false, 0),
+            AccountInfo::new(pda, false, true, &mut pda_lamports, fallback_data, &program_id, false, 0),
+            AccountInfo::new(index, false, false, &mut index_lamports, &mut index_data, &program_id, false, 0),
+        ];
+        let mut data = vec![0x05];
+        data.extend_from_slice(&mark.to_le_bytes());
+        data.extend_from_slice(&update_slot.to_le_bytes());
+        process_fallback_sync(&program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_init_creates_the_fallback_pda_that_match_falls_back_to() {
+        let (ctx, _, data) = try_init_with_pdas(0, &full_init_data()).unwrap();
+        let (pda, expected) = fallback_pda(0, 0, 25);
+        assert_eq!(data, expected);
+
+        let (mut ctx, mut data) = (ctx, data);
+        let index = Pubkey::new_from_array([9u8; 32]);
+        assert_eq!(
+            run_fallback_sync(&mut ctx, &pda, &mut data, &Pubkey::new_unique(), 4_800_000_000, 990),
+            Err(ProgramError::Custom(VolMatcherError::OracleAccountMismatch as u32))
+        );
+        run_fallback_sync(&mut ctx, &pda, &mut data, &index, 4_800_000_000, 990).unwrap();
+        assert_eq!(data, fallback_pda(4_800_000_000, 990, 25).1);
+        // Ordered by read slot, as the primary's syncs are
+        assert_eq!(
+            run_fallback_sync(&mut ctx, &pda, &mut data, &index, 4_700_000_000, 990),
+            Err(ProgramError::Custom(VolMatcherError::StaleUpdate as u32))
+        );
+
+        // Match with the primary stale: the fallback PDA at [3] prices it
+        ctx[VOL_MARK_PRICE_OFFSET..VOL_MARK_PRICE_OFFSET + 8].copy_from_slice(&4_500_000_000u64.to_le_bytes());
+        ctx[LAST_UPDATE_SLOT_OFFSET..LAST_UPDATE_SLOT_OFFSET + 8].copy_from_slice(&850u64.to_le_bytes());
+        ctx[MAX_STALENESS_OFFSET] = 100;
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let (lp_key, ctx_key) = (Pubkey::new_from_array([1u8; 32]), Pubkey::new_from_array([2u8; 32]));
+        let mut lamports = [0u64; 4];
+        let [lp_lamports, ctx_lamports, config_lamports, pda_lamports] = &mut lamports;
+        let (mut lp_data, mut config_data) = (Vec::new(), Vec::new());
+        let accounts = [
+            AccountInfo::new(&lp_key, true, false, lp_lamports, &mut lp_data, &program_id, false, 0),
+            AccountInfo::new(&ctx_key, false, true, ctx_lamports, &mut ctx, &program_id, false, 0),
+            AccountInfo::new(&lp_key, false, false, config_lamports, &mut config_data, &program_id, false, 0),
+            AccountInfo::new(&pda, false, false, pda_lamports, &mut data, &program_id, false, 0),
+        ];
+        process_match(&program_id, &accounts, &[0x00]).unwrap();
+        let ask = u64::from_le_bytes(accounts[1].try_borrow_data().unwrap()[0..8].try_into().unwrap());
+        assert_eq!(ask, side_price(4_800_000_000, 75, DIRECTION_BUY, false).unwrap());
+
+        // No fallback in the payload: no PDA
+        let mut payload = full_init_data();
+        payload[165..197].fill(0);
+        assert!(try_init_with_pdas(0, &payload).unwrap().2.is_empty());
+    }
 }
@@ -0,0 +1,37 @@
+import { describe, it } from "mocha";
+import { expect } from "chai";
+import { checkPriceConsistency } from "../app/shared/price-consistency";
+
+// Cross-matcher monitor: flag matchers on the same underlying that disagree
+describe("Price consistency", () => {
+  it("should accept prices within the divergence bound", () => {
+    // 100.00 / 100.25 / 100.40 -> (100_400_000 - 100_000_000) * 10_000 / 100_000_000 = 40 bps
+    const result = checkPriceConsistency([100_250_000n, 100_000_000n, 100_400_000n], 50);
+    expect(result.consistent).to.equal(true);
+    expect(result.divergenceBps).to.equal(40n);
+    expect(result.minPrice).to.equal(100_000_000n);
+    expect(result.maxPrice).to.equal(100_400_000n);
+  });
+
+  it("should accept divergence exactly at the bound", () => {
+    const result = checkPriceConsistency([100_000_000n, 100_500_000n], 50);
+    expect(result.consistent).to.equal(true);
+    expect(result.divergenceBps).to.equal(50n);
+  });
+
+  it("should flag prices past the divergence bound", () => {
+    // One matcher quoting 1% away from the rest
+    const result = checkPriceConsistency([100_000_000n, 100_100_000n, 101_000_000n], 50);
+    expect(result.consistent).to.equal(false);
+    expect(result.divergenceBps).to.equal(100n);
+  });
+
+  it("should flag an unset (zero) price alongside live ones", () => {
+    expect(checkPriceConsistency([0n, 100_000_000n], 10_000).consistent).to.equal(false);
+  });
+
+  it("should treat empty and single-price sets as consistent", () => {
+    expect(checkPriceConsistency([], 0).consistent).to.equal(true);
+    expect(checkPriceConsistency([150_000_000n], 0).consistent).to.equal(true);
+  });
+});